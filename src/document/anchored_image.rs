@@ -0,0 +1,1011 @@
+//! Floating images (w:drawing > wp:anchor)
+//!
+//! An anchored image is positioned relative to the page, margins, column or
+//! paragraph and text wraps around it (or not). Parsed anchors keep their
+//! raw XML; the model is written back over it so that extensions Word adds
+//! (effect extents, relative sizes, graphic frame locks) survive.
+
+use crate::document::drawing::{self, attr, children, empty, local_name, set_attr};
+use crate::document::lazy::Lazy;
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, RunContent, SdtContent, Table,
+};
+use crate::error::Result;
+use crate::opc::Package;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use quick_xml::Writer;
+
+/// Distance Word leaves between a new floating picture and text on the left and right
+const DEFAULT_DIST_SIDE: i64 = 114300;
+
+/// Base z-order Word gives floating objects
+const DEFAULT_Z_ORDER: u32 = 251659264;
+
+/// What a floating position is measured from (`relativeFrom`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeFrom {
+    Page,
+    Margin,
+    Column,
+    Paragraph,
+    Character,
+    Line,
+    LeftMargin,
+    RightMargin,
+    TopMargin,
+    BottomMargin,
+    InsideMargin,
+    OutsideMargin,
+}
+
+impl RelativeFrom {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "page" => Some(Self::Page),
+            "margin" => Some(Self::Margin),
+            "column" => Some(Self::Column),
+            "paragraph" => Some(Self::Paragraph),
+            "character" => Some(Self::Character),
+            "line" => Some(Self::Line),
+            "leftMargin" => Some(Self::LeftMargin),
+            "rightMargin" => Some(Self::RightMargin),
+            "topMargin" => Some(Self::TopMargin),
+            "bottomMargin" => Some(Self::BottomMargin),
+            "insideMargin" => Some(Self::InsideMargin),
+            "outsideMargin" => Some(Self::OutsideMargin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Page => "page",
+            Self::Margin => "margin",
+            Self::Column => "column",
+            Self::Paragraph => "paragraph",
+            Self::Character => "character",
+            Self::Line => "line",
+            Self::LeftMargin => "leftMargin",
+            Self::RightMargin => "rightMargin",
+            Self::TopMargin => "topMargin",
+            Self::BottomMargin => "bottomMargin",
+            Self::InsideMargin => "insideMargin",
+            Self::OutsideMargin => "outsideMargin",
+        }
+    }
+}
+
+/// Relative alignment of a floating object (`wp:align`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionAlign {
+    Left,
+    Center,
+    Right,
+    Top,
+    Bottom,
+    Inside,
+    Outside,
+}
+
+impl PositionAlign {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "left" => Some(Self::Left),
+            "center" => Some(Self::Center),
+            "right" => Some(Self::Right),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "inside" => Some(Self::Inside),
+            "outside" => Some(Self::Outside),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Inside => "inside",
+            Self::Outside => "outside",
+        }
+    }
+}
+
+/// Where a floating object sits along one axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Offset in EMU from the base (`wp:posOffset`)
+    Offset(i64),
+    /// Aligned to the base (`wp:align`)
+    Align(PositionAlign),
+}
+
+/// Horizontal or vertical position of a floating object (`wp:positionH`/`wp:positionV`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnchorPosition {
+    pub relative_from: RelativeFrom,
+    pub placement: Placement,
+}
+
+impl AnchorPosition {
+    /// Offset in EMU from a base
+    pub fn offset(relative_from: RelativeFrom, emu: i64) -> Self {
+        Self {
+            relative_from,
+            placement: Placement::Offset(emu),
+        }
+    }
+
+    /// Aligned to a base
+    pub fn align(relative_from: RelativeFrom, align: PositionAlign) -> Self {
+        Self {
+            relative_from,
+            placement: Placement::Align(align),
+        }
+    }
+
+    fn from_raw(e: &RawXmlElement) -> Option<Self> {
+        let relative_from = RelativeFrom::parse(attr(e, "relativeFrom")?)?;
+        let placement = children(e).find_map(|(_, c)| match local_name(c) {
+            "posOffset" => text(c).trim().parse().ok().map(Placement::Offset),
+            "align" => PositionAlign::parse(text(c).trim()).map(Placement::Align),
+            _ => None,
+        });
+        Some(Self {
+            relative_from,
+            placement: placement.unwrap_or(Placement::Offset(0)),
+        })
+    }
+
+    fn to_raw(self, name: &str) -> RawXmlElement {
+        let child = match self.placement {
+            Placement::Offset(emu) => RawXmlElement::new("wp:posOffset").with_text(emu.to_string()),
+            Placement::Align(align) => RawXmlElement::new("wp:align").with_text(align.as_str()),
+        };
+        RawXmlElement::new(name)
+            .with_attr("relativeFrom", self.relative_from.as_str())
+            .with_child(child)
+    }
+}
+
+/// How text wraps around a floating object
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    /// In front of or behind text, no wrapping (`wp:wrapNone`)
+    None,
+    /// Around the bounding box (`wp:wrapSquare`)
+    #[default]
+    Square,
+    /// Around the outline (`wp:wrapTight`)
+    Tight,
+    /// Around the outline and through open areas (`wp:wrapThrough`)
+    Through,
+    /// Above and below only (`wp:wrapTopAndBottom`)
+    TopAndBottom,
+}
+
+impl WrapMode {
+    fn from_element(name: &str) -> Option<Self> {
+        match name {
+            "wrapNone" => Some(Self::None),
+            "wrapSquare" => Some(Self::Square),
+            "wrapTight" => Some(Self::Tight),
+            "wrapThrough" => Some(Self::Through),
+            "wrapTopAndBottom" => Some(Self::TopAndBottom),
+            _ => None,
+        }
+    }
+
+    fn element(&self) -> &'static str {
+        match self {
+            Self::None => "wp:wrapNone",
+            Self::Square => "wp:wrapSquare",
+            Self::Tight => "wp:wrapTight",
+            Self::Through => "wp:wrapThrough",
+            Self::TopAndBottom => "wp:wrapTopAndBottom",
+        }
+    }
+
+    /// Whether the mode takes a `wrapText` side
+    fn has_side(&self) -> bool {
+        matches!(self, Self::Square | Self::Tight | Self::Through)
+    }
+}
+
+/// Sides of a floating object text may flow along (`wrapText`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapSide {
+    #[default]
+    BothSides,
+    Left,
+    Right,
+    Largest,
+}
+
+impl WrapSide {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bothSides" => Some(Self::BothSides),
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "largest" => Some(Self::Largest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BothSides => "bothSides",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Largest => "largest",
+        }
+    }
+}
+
+/// A floating image in the document
+#[derive(Clone, Debug)]
+pub struct AnchoredImage {
+    /// Relationship ID referencing the image part
+    pub r_id: String,
+    /// Relationship ID of an SVG version of the image; `r_id` is then its raster fallback
+    pub svg_r_id: Option<String>,
+    /// Image width in EMU
+    pub width_emu: i64,
+    /// Image height in EMU
+    pub height_emu: i64,
+    /// Description / alt text
+    pub description: String,
+    /// Title of the alt text
+    pub title: String,
+    /// Name
+    pub name: String,
+    /// Drawing object ID (`wp:docPr id`); 0 until the document gives the
+    /// image an unused one when it is saved
+    pub id: u32,
+    /// Horizontal position
+    pub position_h: AnchorPosition,
+    /// Vertical position
+    pub position_v: AnchorPosition,
+    /// Text wrapping
+    pub wrap: WrapMode,
+    /// Sides text wraps along (square, tight and through wrapping)
+    pub wrap_side: WrapSide,
+    /// Behind the text rather than in front of it (`behindDoc`)
+    pub behind_text: bool,
+    /// May overlap other floating objects
+    pub allow_overlap: bool,
+    /// Anchor locked to its paragraph
+    pub locked: bool,
+    /// Laid out inside the table cell holding it
+    pub layout_in_cell: bool,
+    /// Stacking order among floating objects; higher is in front (`relativeHeight`)
+    pub z_order: u32,
+    /// Distance from text above, in EMU
+    pub dist_top: i64,
+    /// Distance from text below, in EMU
+    pub dist_bottom: i64,
+    /// Distance from text on the left, in EMU
+    pub dist_left: i64,
+    /// Distance from text on the right, in EMU
+    pub dist_right: i64,
+    /// The full raw XML of the drawing element (for round-trip preservation)
+    pub raw_xml: Option<RawXmlNode>,
+}
+
+impl AnchoredImage {
+    /// Create a floating image at the top left of its column and paragraph
+    pub fn new(r_id: impl Into<String>, width_emu: i64, height_emu: i64) -> Self {
+        AnchoredImage {
+            r_id: r_id.into(),
+            svg_r_id: None,
+            width_emu,
+            height_emu,
+            description: String::new(),
+            title: String::new(),
+            name: String::new(),
+            id: 0,
+            position_h: AnchorPosition::offset(RelativeFrom::Column, 0),
+            position_v: AnchorPosition::offset(RelativeFrom::Paragraph, 0),
+            wrap: WrapMode::Square,
+            wrap_side: WrapSide::BothSides,
+            behind_text: false,
+            allow_overlap: true,
+            locked: false,
+            layout_in_cell: true,
+            z_order: DEFAULT_Z_ORDER,
+            dist_top: 0,
+            dist_bottom: 0,
+            dist_left: DEFAULT_DIST_SIDE,
+            dist_right: DEFAULT_DIST_SIDE,
+            raw_xml: None,
+        }
+    }
+
+    /// Create an anchored image builder
+    pub fn builder(
+        r_id: impl Into<String>,
+        width_emu: i64,
+        height_emu: i64,
+    ) -> AnchoredImageBuilder {
+        AnchoredImageBuilder {
+            image: Self::new(r_id, width_emu, height_emu),
+        }
+    }
+
+    /// Read a parsed `w:drawing` holding a single anchored picture
+    ///
+    /// Other drawings (inline, groups, shapes, text boxes) are handed back unchanged.
+    pub(crate) fn from_raw(raw: RawXmlElement) -> std::result::Result<Self, RawXmlElement> {
+        let found = drawing::pictures(&raw);
+        let picture = match found.as_slice() {
+            [picture] if picture.floating && !picture.in_text_box => picture,
+            _ => return Err(raw),
+        };
+        let Some(frame) = picture.frame.as_ref().filter(|path| path.len() == 1) else {
+            return Err(raw);
+        };
+
+        let anchor = drawing::element_at(&raw, frame);
+        let flag = |key: &str, default: bool| {
+            attr(anchor, key).map_or(default, |v| matches!(v, "1" | "true" | "on"))
+        };
+        let dist = |key: &str| attr(anchor, key).and_then(|v| v.parse().ok()).unwrap_or(0);
+        let position = |local: &str| {
+            children(anchor)
+                .find(|(_, c)| local_name(c) == local)
+                .and_then(|(_, c)| AnchorPosition::from_raw(c))
+        };
+        let wrap = children(anchor)
+            .find_map(|(_, c)| WrapMode::from_element(local_name(c)).map(|mode| (mode, c)));
+
+        let mut image = Self::new(picture.r_id.clone(), picture.width_emu, picture.height_emu);
+        image.svg_r_id = picture.svg_r_id.clone();
+        image.description = picture.description.clone();
+        image.title = picture.title.clone();
+        image.name = picture.name.clone();
+        image.id = children(anchor)
+            .find(|(_, c)| local_name(c) == "docPr")
+            .and_then(|(_, c)| attr(c, "id"))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if let Some(position) = position("positionH") {
+            image.position_h = position;
+        }
+        if let Some(position) = position("positionV") {
+            image.position_v = position;
+        }
+        image.wrap = wrap.map_or(WrapMode::None, |(mode, _)| mode);
+        image.wrap_side = wrap
+            .and_then(|(_, e)| attr(e, "wrapText"))
+            .and_then(WrapSide::parse)
+            .unwrap_or_default();
+        image.behind_text = flag("behindDoc", false);
+        image.allow_overlap = flag("allowOverlap", true);
+        image.locked = flag("locked", false);
+        image.layout_in_cell = flag("layoutInCell", true);
+        image.z_order = attr(anchor, "relativeHeight")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        image.dist_top = dist("distT");
+        image.dist_bottom = dist("distB");
+        image.dist_left = dist("distL");
+        image.dist_right = dist("distR");
+        image.raw_xml = Some(RawXmlNode::Element(raw));
+        Ok(image)
+    }
+
+    /// Set the displayed size in EMU
+    pub fn set_size(&mut self, width_emu: i64, height_emu: i64) {
+        self.width_emu = width_emu;
+        self.height_emu = height_emu;
+    }
+
+    /// Point the image at another relationship
+    pub fn set_r_id(&mut self, r_id: impl Into<String>) {
+        self.r_id = r_id.into();
+    }
+
+    /// Set or remove the SVG version of the image
+    pub fn set_svg_r_id(&mut self, svg_r_id: Option<String>) {
+        self.svg_r_id = svg_r_id;
+    }
+
+    /// The `w:drawing` element, with the model applied over any parsed XML
+    pub fn to_raw(&self) -> RawXmlElement {
+        let mut root = match &self.raw_xml {
+            Some(RawXmlNode::Element(raw)) => raw.clone(),
+            _ => self.template(),
+        };
+        for picture in drawing::pictures(&root) {
+            drawing::set_rel_id(&mut root, &picture, &self.r_id);
+            drawing::set_svg_rel_id(&mut root, &picture, self.svg_r_id.as_deref());
+            drawing::set_size(&mut root, &picture, self.width_emu, self.height_emu);
+        }
+        if let Some(RawXmlNode::Element(anchor)) = root
+            .children
+            .iter_mut()
+            .find(|c| matches!(c, RawXmlNode::Element(e) if local_name(e) == "anchor"))
+        {
+            self.apply(anchor);
+        }
+        root
+    }
+
+    /// Generate the DrawingML XML for this image
+    pub fn to_drawing_xml<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        self.to_raw().write_to(writer)
+    }
+
+    /// Write the anchor's attributes, position, wrapping and alt text
+    fn apply(&self, anchor: &mut RawXmlElement) {
+        let flag = |b: bool| if b { "1" } else { "0" };
+        set_attr(anchor, "distT", &self.dist_top.to_string());
+        set_attr(anchor, "distB", &self.dist_bottom.to_string());
+        set_attr(anchor, "distL", &self.dist_left.to_string());
+        set_attr(anchor, "distR", &self.dist_right.to_string());
+        set_attr(anchor, "simplePos", "0");
+        set_attr(anchor, "relativeHeight", &self.z_order.to_string());
+        set_attr(anchor, "behindDoc", flag(self.behind_text));
+        set_attr(anchor, "locked", flag(self.locked));
+        set_attr(anchor, "layoutInCell", flag(self.layout_in_cell));
+        set_attr(anchor, "allowOverlap", flag(self.allow_overlap));
+
+        replace_child(anchor, "positionH", self.position_h.to_raw("wp:positionH"));
+        replace_child(anchor, "positionV", self.position_v.to_raw("wp:positionV"));
+
+        // Keep an existing wrap element of the same mode, which may carry a polygon
+        let wrap_index = children(anchor)
+            .find(|(_, c)| WrapMode::from_element(local_name(c)).is_some())
+            .map(|(i, c)| (i, WrapMode::from_element(local_name(c)) == Some(self.wrap)));
+        match wrap_index {
+            Some((i, true)) => {
+                if let RawXmlNode::Element(wrap) = &mut anchor.children[i] {
+                    if self.wrap.has_side() {
+                        set_attr(wrap, "wrapText", self.wrap_side.as_str());
+                    }
+                }
+            }
+            Some((i, false)) => anchor.children[i] = RawXmlNode::Element(self.wrap_element()),
+            None => {
+                let after = children(anchor)
+                    .filter(|(_, c)| {
+                        matches!(
+                            local_name(c),
+                            "simplePos" | "positionH" | "positionV" | "extent" | "effectExtent"
+                        )
+                    })
+                    .map(|(i, _)| i + 1)
+                    .last()
+                    .unwrap_or(0);
+                anchor
+                    .children
+                    .insert(after, RawXmlNode::Element(self.wrap_element()));
+            }
+        }
+
+        if let Some(RawXmlNode::Element(doc_pr)) = anchor
+            .children
+            .iter_mut()
+            .find(|c| matches!(c, RawXmlNode::Element(e) if local_name(e) == "docPr"))
+        {
+            set_attr(doc_pr, "id", &self.id.to_string());
+            set_attr(doc_pr, "name", &self.name);
+            set_attr(doc_pr, "descr", &self.description);
+            if !self.title.is_empty() || attr(doc_pr, "title").is_some() {
+                set_attr(doc_pr, "title", &self.title);
+            }
+        }
+    }
+
+    fn wrap_element(&self) -> RawXmlElement {
+        let mut wrap = empty(self.wrap.element());
+        if self.wrap.has_side() {
+            wrap = wrap.with_attr("wrapText", self.wrap_side.as_str());
+        }
+        if matches!(self.wrap, WrapMode::Tight | WrapMode::Through) {
+            // The bounding box, in the 21600ths Word uses for wrap polygons
+            let point = |name: &str, x: u32, y: u32| {
+                empty(name)
+                    .with_attr("x", x.to_string())
+                    .with_attr("y", y.to_string())
+            };
+            wrap = wrap.with_child(
+                RawXmlElement::new("wp:wrapPolygon")
+                    .with_attr("edited", "0")
+                    .with_child(point("wp:start", 0, 0))
+                    .with_child(point("wp:lineTo", 0, 21600))
+                    .with_child(point("wp:lineTo", 21600, 21600))
+                    .with_child(point("wp:lineTo", 21600, 0))
+                    .with_child(point("wp:lineTo", 0, 0)),
+            );
+        }
+        wrap
+    }
+
+    /// A `w:drawing` anchoring another kind of graphic (such as a text box
+    /// shape) with this image's size, position and wrapping
+    pub(crate) fn anchor_graphic(&self, uri: &str, graphic: RawXmlElement) -> RawXmlElement {
+        let mut root = self.frame(uri, graphic, false);
+        if let Some(RawXmlNode::Element(anchor)) = root.children.first_mut() {
+            self.apply(anchor);
+        }
+        root
+    }
+
+    /// A minimal anchored picture; [`AnchoredImage::apply`] fills in the model
+    fn template(&self) -> RawXmlElement {
+        let size = |name: &str| {
+            empty(name)
+                .with_attr("cx", self.width_emu.to_string())
+                .with_attr("cy", self.height_emu.to_string())
+        };
+        let picture = RawXmlElement::new("pic:pic")
+            .with_attr("xmlns:pic", crate::xml::PIC)
+            .with_child(
+                RawXmlElement::new("pic:nvPicPr")
+                    .with_child(
+                        empty("pic:cNvPr")
+                            .with_attr("id", "0")
+                            .with_attr("name", self.name.as_str()),
+                    )
+                    .with_child(empty("pic:cNvPicPr")),
+            )
+            .with_child(
+                RawXmlElement::new("pic:blipFill")
+                    .with_child(empty("a:blip").with_attr("r:embed", self.r_id.as_str()))
+                    .with_child(RawXmlElement::new("a:stretch").with_child(empty("a:fillRect"))),
+            )
+            .with_child(
+                RawXmlElement::new("pic:spPr")
+                    .with_child(
+                        RawXmlElement::new("a:xfrm")
+                            .with_child(empty("a:off").with_attr("x", "0").with_attr("y", "0"))
+                            .with_child(size("a:ext")),
+                    )
+                    .with_child(
+                        RawXmlElement::new("a:prstGeom")
+                            .with_attr("prst", "rect")
+                            .with_child(empty("a:avLst")),
+                    ),
+            );
+        self.frame(crate::xml::PIC, picture, true)
+    }
+
+    /// A `w:drawing` with an anchor around a graphic
+    fn frame(&self, uri: &str, graphic: RawXmlElement, lock_aspect: bool) -> RawXmlElement {
+        let mut frame_pr = empty("wp:cNvGraphicFramePr");
+        if lock_aspect {
+            frame_pr = frame_pr.with_child(
+                empty("a:graphicFrameLocks")
+                    .with_attr("xmlns:a", crate::xml::A)
+                    .with_attr("noChangeAspect", "1"),
+            );
+        }
+        let anchor = RawXmlElement::new("wp:anchor")
+            .with_child(
+                empty("wp:simplePos")
+                    .with_attr("x", "0")
+                    .with_attr("y", "0"),
+            )
+            .with_child(self.position_h.to_raw("wp:positionH"))
+            .with_child(self.position_v.to_raw("wp:positionV"))
+            .with_child(
+                empty("wp:extent")
+                    .with_attr("cx", self.width_emu.to_string())
+                    .with_attr("cy", self.height_emu.to_string()),
+            )
+            .with_child(
+                empty("wp:effectExtent")
+                    .with_attr("l", "0")
+                    .with_attr("t", "0")
+                    .with_attr("r", "0")
+                    .with_attr("b", "0"),
+            )
+            .with_child(self.wrap_element())
+            .with_child(empty("wp:docPr").with_attr("id", self.id.to_string()))
+            .with_child(frame_pr)
+            .with_child(
+                RawXmlElement::new("a:graphic")
+                    .with_attr("xmlns:a", crate::xml::A)
+                    .with_child(
+                        RawXmlElement::new("a:graphicData")
+                            .with_attr("uri", uri)
+                            .with_child(graphic),
+                    ),
+            );
+        RawXmlElement::new("w:drawing").with_child(anchor)
+    }
+}
+
+/// Builder for floating images
+pub struct AnchoredImageBuilder {
+    image: AnchoredImage,
+}
+
+impl AnchoredImageBuilder {
+    /// Show an SVG image, keeping the `r_id` as its raster fallback
+    pub fn svg(mut self, svg_r_id: impl Into<String>) -> Self {
+        self.image.svg_r_id = Some(svg_r_id.into());
+        self
+    }
+
+    /// Set the horizontal position
+    pub fn position_h(mut self, position: AnchorPosition) -> Self {
+        self.image.position_h = position;
+        self
+    }
+
+    /// Set the vertical position
+    pub fn position_v(mut self, position: AnchorPosition) -> Self {
+        self.image.position_v = position;
+        self
+    }
+
+    /// Set text wrapping
+    pub fn wrap(mut self, wrap: WrapMode) -> Self {
+        self.image.wrap = wrap;
+        self
+    }
+
+    /// Set the sides text wraps along
+    pub fn wrap_side(mut self, side: WrapSide) -> Self {
+        self.image.wrap_side = side;
+        self
+    }
+
+    /// Place the image behind the text
+    pub fn behind_text(mut self, behind: bool) -> Self {
+        self.image.behind_text = behind;
+        self
+    }
+
+    /// Allow overlapping other floating objects
+    pub fn allow_overlap(mut self, allow: bool) -> Self {
+        self.image.allow_overlap = allow;
+        self
+    }
+
+    /// Lock the anchor to its paragraph
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.image.locked = locked;
+        self
+    }
+
+    /// Set the distance from surrounding text (in EMU)
+    pub fn distance(mut self, top: i64, bottom: i64, left: i64, right: i64) -> Self {
+        self.image.dist_top = top;
+        self.image.dist_bottom = bottom;
+        self.image.dist_left = left;
+        self.image.dist_right = right;
+        self
+    }
+
+    /// Set the stacking order; higher is in front
+    pub fn z_order(mut self, z_order: u32) -> Self {
+        self.image.z_order = z_order;
+        self
+    }
+
+    /// Set alt text
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.image.description = desc.into();
+        self
+    }
+
+    /// Set the alt text title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.image.title = title.into();
+        self
+    }
+
+    /// Set name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.image.name = name.into();
+        self
+    }
+
+    /// Build the image
+    pub fn build(self) -> AnchoredImage {
+        self.image
+    }
+}
+
+impl Document {
+    /// Give floating images and text boxes without a drawing object ID
+    /// (`wp:docPr id`) one that is unused in every story
+    pub(crate) fn allocate_drawing_ids(&mut self) {
+        // New drawings can only be in stories that have been parsed
+        let mut ids = DrawingIds::default();
+        self.visit_drawing_ids(&mut ids, false);
+        if ids.pending == 0 {
+            return;
+        }
+        self.visit_drawing_ids(&mut ids, true);
+        ids.next = Some(ids.max.saturating_add(1));
+        self.visit_drawing_ids(&mut ids, false);
+    }
+
+    /// Walk the drawings of every story, parsing the unloaded ones if `load`
+    fn visit_drawing_ids(&mut self, ids: &mut DrawingIds, load: bool) {
+        fn story<'a, T>(lazy: &'a mut Lazy<T>, package: &Package, load: bool) -> Option<&'a mut T> {
+            if load {
+                Some(lazy.get_mut(package))
+            } else {
+                lazy.loaded_mut()
+            }
+        }
+
+        ids.blocks(&mut self.body.content);
+        if let Some((headers, footers)) = story(&mut self.headers_footers, &self.package, load) {
+            for (_, part) in headers.iter_mut().chain(footers.iter_mut()) {
+                ids.paragraphs(&mut part.paragraphs, &part.unknown_children);
+            }
+        }
+        for notes in [&mut self.footnotes, &mut self.endnotes] {
+            if let Some(Some(notes)) = story(notes, &self.package, load) {
+                for note in &mut notes.notes {
+                    ids.paragraphs(&mut note.paragraphs, &note.unknown_children);
+                }
+            }
+        }
+        if let Some(Some(comments)) = story(&mut self.comments, &self.package, load) {
+            for comment in &mut comments.comments {
+                ids.paragraphs(&mut comment.paragraphs, &comment.unknown_children);
+            }
+        }
+    }
+}
+
+/// Finds the drawing object IDs in use and hands out new ones to drawings
+/// that have none (ID 0)
+#[derive(Default)]
+pub(crate) struct DrawingIds {
+    /// Highest ID seen
+    max: u32,
+    /// Drawings seen without an ID
+    pending: usize,
+    /// Next ID to hand out; while `None`, drawings without one are only counted
+    next: Option<u32>,
+}
+
+impl DrawingIds {
+    /// Hand out IDs from `first` on
+    pub fn starting_at(first: u32) -> Self {
+        Self {
+            next: Some(first),
+            ..Default::default()
+        }
+    }
+
+    /// Record a drawing's ID, returning the one to give it if it has none
+    fn visit(&mut self, id: u32) -> Option<u32> {
+        if id != 0 {
+            self.max = self.max.max(id);
+            if let Some(next) = &mut self.next {
+                *next = (*next).max(id.saturating_add(1));
+            }
+            return None;
+        }
+        match &mut self.next {
+            Some(next) => {
+                let id = *next;
+                *next = next.saturating_add(1);
+                Some(id)
+            }
+            None => {
+                self.pending += 1;
+                None
+            }
+        }
+    }
+
+    pub fn blocks(&mut self, blocks: &mut [BlockContent]) {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.content(&mut p.content),
+                BlockContent::Table(t) => self.table(t),
+                BlockContent::Sdt(sdt) => match &mut sdt.content {
+                    SdtContent::Blocks(inner) => self.blocks(inner),
+                    SdtContent::Inline(inner) => self.content(inner),
+                    _ => {}
+                },
+                BlockContent::Unknown(node) => self.raw(node),
+            }
+        }
+    }
+
+    pub fn table(&mut self, table: &mut Table) {
+        for cell in table.rows.iter_mut().flat_map(|r| r.cells.iter_mut()) {
+            self.paragraphs(&mut cell.paragraphs, &cell.unknown_children);
+        }
+    }
+
+    fn paragraphs(&mut self, paragraphs: &mut [Paragraph], unknown: &[RawXmlNode]) {
+        for para in paragraphs {
+            self.content(&mut para.content);
+        }
+        for node in unknown {
+            self.raw(node);
+        }
+    }
+
+    pub fn content(&mut self, content: &mut [ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::Run(run) => {
+                    for item in &mut run.content {
+                        match item {
+                            RunContent::Drawing(image) => match &image.raw_xml {
+                                Some(node) => self.raw(node),
+                                // New inline images are written with ID 1
+                                None => {
+                                    self.visit(1);
+                                }
+                            },
+                            RunContent::AnchoredDrawing(image) => {
+                                if let Some(id) = self.visit(image.id) {
+                                    image.id = id;
+                                }
+                            }
+                            RunContent::TextBox(text_box) => {
+                                if let Some(id) = text_box.id().and_then(|id| self.visit(id)) {
+                                    text_box.set_id(id);
+                                }
+                                self.blocks(&mut text_box.content);
+                            }
+                            RunContent::Unknown(node) => self.raw(node),
+                            _ => {}
+                        }
+                    }
+                }
+                ParagraphContent::Hyperlink(link) => self.content(&mut link.content),
+                ParagraphContent::Revision(rev) => self.content(&mut rev.content),
+                ParagraphContent::SimpleField(field) => self.content(&mut field.content),
+                ParagraphContent::Sdt(sdt) => match &mut sdt.content {
+                    SdtContent::Blocks(inner) => self.blocks(inner),
+                    SdtContent::Inline(inner) => self.content(inner),
+                    _ => {}
+                },
+                ParagraphContent::Unknown(node) => self.raw(node),
+                _ => {}
+            }
+        }
+    }
+
+    fn raw(&mut self, node: &RawXmlNode) {
+        if let RawXmlNode::Element(e) = node {
+            let mut ids = Vec::new();
+            drawing::doc_pr_ids(e, &mut ids);
+            for id in ids.into_iter().filter(|&id| id != 0) {
+                self.visit(id);
+            }
+        }
+    }
+}
+
+fn text(e: &RawXmlElement) -> String {
+    e.children
+        .iter()
+        .filter_map(|c| match c {
+            RawXmlNode::Text(t) => Some(t.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replace the first child with a local name, or append
+fn replace_child(parent: &mut RawXmlElement, local: &str, child: RawXmlElement) {
+    let index = children(parent)
+        .find(|(_, c)| local_name(c) == local)
+        .map(|(i, _)| i);
+    match index {
+        Some(i) => parent.children[i] = RawXmlNode::Element(child),
+        None => parent.children.push(RawXmlNode::Element(child)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    fn parse(xml: &str) -> RawXmlElement {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            if let Event::Start(e) = reader.read_event().unwrap() {
+                return RawXmlElement::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    fn write(image: &AnchoredImage) -> String {
+        let mut buf = Vec::new();
+        image.to_drawing_xml(&mut Writer::new(&mut buf)).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    const ANCHOR: &str = r#"<w:drawing><wp:anchor distT="0" distB="0" distL="114300" distR="114300" simplePos="0" relativeHeight="251660288" behindDoc="1" locked="0" layoutInCell="1" allowOverlap="1"><wp:simplePos x="0" y="0"/><wp:positionH relativeFrom="margin"><wp:align>right</wp:align></wp:positionH><wp:positionV relativeFrom="page"><wp:posOffset>457200</wp:posOffset></wp:positionV><wp:extent cx="1828800" cy="914400"/><wp:effectExtent l="0" t="0" r="0" b="0"/><wp:wrapTight wrapText="left"><wp:wrapPolygon edited="1"><wp:start x="0" y="0"/><wp:lineTo x="10" y="10"/></wp:wrapPolygon></wp:wrapTight><wp:docPr id="3" name="Logo" descr="Acme logo"/><wp:cNvGraphicFramePr/><a:graphic><a:graphicData><pic:pic><pic:blipFill><a:blip r:embed="rId5"/></pic:blipFill><pic:spPr><a:xfrm><a:ext cx="1828800" cy="914400"/></a:xfrm></pic:spPr></pic:pic></a:graphicData></a:graphic><wp14:sizeRelH relativeFrom="page"/></wp:anchor></w:drawing>"#;
+
+    #[test]
+    fn test_parse_anchored_image() {
+        let image = AnchoredImage::from_raw(parse(ANCHOR)).unwrap();
+        assert_eq!(image.r_id, "rId5");
+        assert_eq!((image.width_emu, image.height_emu), (1828800, 914400));
+        assert_eq!(image.name, "Logo");
+        assert_eq!(image.description, "Acme logo");
+        assert_eq!(
+            image.position_h,
+            AnchorPosition::align(RelativeFrom::Margin, PositionAlign::Right)
+        );
+        assert_eq!(
+            image.position_v,
+            AnchorPosition::offset(RelativeFrom::Page, 457200)
+        );
+        assert_eq!(image.wrap, WrapMode::Tight);
+        assert_eq!(image.wrap_side, WrapSide::Left);
+        assert!(image.behind_text && image.allow_overlap && !image.locked);
+        assert_eq!(image.z_order, 251660288);
+        assert_eq!(image.dist_left, 114300);
+
+        // Inline drawings are not anchored images
+        let inline = ANCHOR.replace("wp:anchor", "wp:inline");
+        assert!(AnchoredImage::from_raw(parse(&inline)).is_err());
+    }
+
+    #[test]
+    fn test_edit_parsed_anchored_image() {
+        let mut image = AnchoredImage::from_raw(parse(ANCHOR)).unwrap();
+        image.wrap_side = WrapSide::BothSides;
+        image.position_v = AnchorPosition::align(RelativeFrom::TopMargin, PositionAlign::Center);
+        image.set_size(914400, 457200);
+        let xml = write(&image);
+        // The edited polygon and Word's extensions survive
+        assert!(xml.contains(r#"<wp:wrapTight wrapText="bothSides"><wp:wrapPolygon edited="1">"#));
+        assert!(xml.contains("<wp14:sizeRelH"));
+        assert!(xml.contains(
+            r#"<wp:positionV relativeFrom="topMargin"><wp:align>center</wp:align></wp:positionV>"#
+        ));
+        assert!(xml.contains(r#"<a:ext cx="914400" cy="457200"/>"#));
+
+        image.wrap = WrapMode::TopAndBottom;
+        let xml = write(&image);
+        assert!(xml.contains("<wp:wrapTopAndBottom/><wp:docPr"));
+        assert!(!xml.contains("wrapTight"));
+    }
+
+    #[test]
+    fn test_build_anchored_image() {
+        let image = AnchoredImage::builder("rId7", 1828800, 914400)
+            .position_h(AnchorPosition::align(
+                RelativeFrom::Margin,
+                PositionAlign::Right,
+            ))
+            .position_v(AnchorPosition::align(
+                RelativeFrom::Page,
+                PositionAlign::Top,
+            ))
+            .wrap(WrapMode::None)
+            .behind_text(true)
+            .distance(0, 0, 0, 0)
+            .z_order(5)
+            .name("Letterhead logo")
+            .build();
+        let xml = write(&image);
+        assert!(xml.starts_with(r#"<w:drawing><wp:anchor distT="0" distB="0" distL="0" distR="0" simplePos="0" relativeHeight="5" behindDoc="1""#));
+        assert!(xml.contains("<wp:wrapNone/>"));
+        assert!(xml.contains(r#"<a:blip r:embed="rId7"/>"#));
+
+        let parsed = AnchoredImage::from_raw(parse(&xml)).unwrap();
+        assert_eq!(parsed.position_h, image.position_h);
+        assert_eq!(parsed.position_v, image.position_v);
+        assert_eq!(parsed.wrap, WrapMode::None);
+        assert!(parsed.behind_text);
+        assert_eq!(parsed.z_order, 5);
+        assert_eq!(parsed.name, "Letterhead logo");
+    }
+}
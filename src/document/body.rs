@@ -1,8 +1,8 @@
 //! Document body and block-level content
 
-use crate::document::{Paragraph, SectionProperties, Table};
+use crate::document::{Paragraph, Sdt, SectionProperties, Table};
 use crate::error::Result;
-use crate::xml::RawXmlNode;
+use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -14,6 +14,8 @@ pub enum BlockContent {
     Paragraph(Box<Paragraph>),
     /// Table
     Table(Box<Table>),
+    /// Content control (w:sdt)
+    Sdt(Box<Sdt>),
     /// Unknown element (preserved for round-trip)
     Unknown(RawXmlNode),
 }
@@ -35,67 +37,17 @@ impl Body {
 
         loop {
             match reader.read_event_into(&mut buf)? {
-                Event::Start(e) => {
-                    let name = e.name();
-                    let local = name.local_name();
-
-                    match local.as_ref() {
-                        b"p" => {
-                            let para = Paragraph::from_reader(reader, &e)?;
-                            body.content.push(BlockContent::Paragraph(Box::new(para)));
-                        }
-                        b"tbl" => {
-                            let table = Table::from_reader(reader, &e)?;
-                            body.content.push(BlockContent::Table(Box::new(table)));
-                        }
-                        b"sectPr" => {
-                            body.section_properties =
-                                Some(SectionProperties::from_reader(reader, &e)?);
-                        }
-                        _ => {
-                            // Unknown element - preserve for round-trip
-                            let raw = crate::xml::RawXmlElement::from_reader(reader, &e)?;
-                            body.content
-                                .push(BlockContent::Unknown(RawXmlNode::Element(raw)));
-                        }
-                    }
+                Event::Start(e) if e.name().local_name().as_ref() == b"sectPr" => {
+                    body.section_properties = Some(SectionProperties::from_reader(reader, &e)?);
                 }
-                Event::Empty(e) => {
-                    let name = e.name();
-                    let local = name.local_name();
-
-                    match local.as_ref() {
-                        b"p" => {
-                            // Empty paragraph
-                            let para = Paragraph::from_empty(&e)?;
-                            body.content.push(BlockContent::Paragraph(Box::new(para)));
-                        }
-                        _ => {
-                            // Preserve unknown empty elements
-                            let raw = crate::xml::RawXmlElement {
-                                name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
-                                attributes: e
-                                    .attributes()
-                                    .filter_map(|a| a.ok())
-                                    .map(|a| {
-                                        (
-                                            String::from_utf8_lossy(a.key.as_ref()).to_string(),
-                                            String::from_utf8_lossy(&a.value).to_string(),
-                                        )
-                                    })
-                                    .collect(),
-                                children: Vec::new(),
-                                self_closing: true,
-                            };
-                            body.content
-                                .push(BlockContent::Unknown(RawXmlNode::Element(raw)));
-                        }
-                    }
-                }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"body" {
-                        break;
-                    }
+                Event::Start(e) => body
+                    .content
+                    .push(BlockContent::from_element(reader, &e, false)?),
+                Event::Empty(e) => body
+                    .content
+                    .push(BlockContent::from_element(reader, &e, true)?),
+                Event::End(e) if e.name().local_name().as_ref() == b"body" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -169,11 +121,33 @@ impl Body {
 }
 
 impl BlockContent {
+    /// Parse block content from a start tag, or an empty tag when `empty` is set
+    pub fn from_element<R: BufRead>(
+        reader: &mut Reader<R>,
+        e: &BytesStart,
+        empty: bool,
+    ) -> Result<Self> {
+        let content = match e.name().local_name().as_ref() {
+            b"p" if empty => BlockContent::Paragraph(Box::new(Paragraph::from_empty(e)?)),
+            b"p" => BlockContent::Paragraph(Box::new(Paragraph::from_reader(reader, e)?)),
+            b"tbl" if !empty => BlockContent::Table(Box::new(Table::from_reader(reader, e)?)),
+            b"sdt" if !empty => BlockContent::Sdt(Box::new(Sdt::from_reader_block(reader, e)?)),
+            _ if empty => BlockContent::Unknown(RawXmlNode::Element(RawXmlElement::from_empty(e))),
+            _ => {
+                // Unknown element - preserve for round-trip
+                let raw = RawXmlElement::from_reader(reader, e)?;
+                BlockContent::Unknown(RawXmlNode::Element(raw))
+            }
+        };
+        Ok(content)
+    }
+
     /// Write to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         match self {
             BlockContent::Paragraph(para) => para.write_to(writer),
             BlockContent::Table(table) => table.write_to(writer),
+            BlockContent::Sdt(sdt) => sdt.write_to(writer),
             BlockContent::Unknown(node) => node.write_to(writer),
         }
     }
@@ -0,0 +1,499 @@
+//! Comment threads (commentsExtended.xml, commentsIds.xml, people.xml)
+
+use crate::document::comments::{body_paragraphs, body_paragraphs_mut, Comment, Comments};
+use crate::document::{store_part, Document, ParagraphContent, Run, RunContent};
+use crate::error::{Error, Result};
+use crate::opc::PartUri;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashSet;
+use std::io::Cursor;
+
+/// Paragraph attribute identifying a comment paragraph
+const PARA_ID: &str = "w14:paraId";
+
+/// Serializer of one of the comment parts
+type PartWriter = fn(&Comments) -> Result<String>;
+
+/// Largest value allowed for paragraph and durable IDs
+const MAX_ID: u32 = 0x7FFF_FFFF;
+
+/// Author of comments (w15:person in people.xml)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Person {
+    /// Author name as used on comments
+    pub author: String,
+    /// Identity provider (e.g. "AD", or "None")
+    pub provider_id: Option<String>,
+    /// User ID at the provider
+    pub user_id: Option<String>,
+}
+
+impl Person {
+    /// Create a person not tied to an identity provider
+    pub fn new(author: impl Into<String>) -> Self {
+        let author = author.into();
+        Person {
+            provider_id: Some("None".into()),
+            user_id: Some(author.clone()),
+            author,
+        }
+    }
+}
+
+impl Comment {
+    /// ID of the comment this one replies to
+    pub fn parent(&self) -> Option<u32> {
+        self.parent
+    }
+
+    /// Replies to this comment, in order
+    pub fn replies<'a>(&self, comments: &'a Comments) -> Vec<&'a Comment> {
+        comments
+            .comments
+            .iter()
+            .filter(|c| c.parent == Some(self.id))
+            .collect()
+    }
+
+    /// Check if the discussion is marked done
+    pub fn is_resolved(&self) -> bool {
+        self.resolved
+    }
+
+    /// Mark the discussion as done or reopen it
+    pub fn set_resolved(&mut self, resolved: bool) {
+        self.resolved = resolved;
+    }
+
+    /// Paragraph ID of the last comment paragraph, which identifies the comment
+    fn para_id(&self) -> Option<&str> {
+        let para = self.paragraphs.last()?;
+        para.unknown_attrs
+            .iter()
+            .find(|(key, _)| key == PARA_ID)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Comments {
+    /// Add a reply to a comment, returns the assigned ID
+    ///
+    /// Replies to a reply join the thread of the comment that started it.
+    pub fn reply(
+        &mut self,
+        parent: u32,
+        author: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Result<u32> {
+        let parent = self
+            .get(parent)
+            .ok_or_else(|| Error::NotFound(format!("comment {}", parent)))?;
+        let root = parent.parent.unwrap_or(parent.id);
+
+        let id = self.next_id();
+        let mut comment = Comment::new(id, author, text);
+        comment.parent = Some(root);
+        self.comments.push(comment);
+        Ok(id)
+    }
+
+    /// Apply thread state from commentsExtended.xml
+    pub(crate) fn read_extended(&mut self, xml: &str) -> Result<()> {
+        for e in empty_elements(xml, b"commentEx")? {
+            let Some(para_id) = local_attr(&e, b"paraId") else {
+                continue;
+            };
+            let parent = local_attr(&e, b"paraIdParent").and_then(|parent_id| {
+                self.comments
+                    .iter()
+                    .find(|c| c.para_id() == Some(parent_id.as_str()))
+                    .map(|c| c.id)
+            });
+            let done = local_attr(&e, b"done").is_some_and(|v| v == "1" || v == "true");
+            if let Some(comment) = self
+                .comments
+                .iter_mut()
+                .find(|c| c.para_id() == Some(para_id.as_str()))
+            {
+                comment.parent = parent;
+                comment.resolved = done;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply durable IDs from commentsIds.xml
+    pub(crate) fn read_ids(&mut self, xml: &str) -> Result<()> {
+        for e in empty_elements(xml, b"commentId")? {
+            let (Some(para_id), Some(durable_id)) =
+                (local_attr(&e, b"paraId"), local_attr(&e, b"durableId"))
+            else {
+                continue;
+            };
+            if let Some(comment) = self
+                .comments
+                .iter_mut()
+                .find(|c| c.para_id() == Some(para_id.as_str()))
+            {
+                comment.durable_id = Some(durable_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read comment authors from people.xml
+    pub(crate) fn read_people(&mut self, xml: &str) -> Result<()> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                    b"person" => {
+                        if let Some(author) = local_attr(&e, b"author") {
+                            self.people.push(Person {
+                                author,
+                                provider_id: None,
+                                user_id: None,
+                            });
+                        }
+                    }
+                    b"presenceInfo" => {
+                        if let Some(person) = self.people.last_mut() {
+                            person.provider_id = local_attr(&e, b"providerId");
+                            person.user_id = local_attr(&e, b"userId");
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(())
+    }
+
+    /// Give every comment paragraph a paragraph ID, every comment a durable
+    /// ID, and every author an entry in people.xml
+    ///
+    /// `taken` holds paragraph IDs already used elsewhere in the document.
+    fn prepare_threads(&mut self, mut taken: HashSet<u32>) {
+        let mut durable_taken = HashSet::new();
+        for comment in &self.comments {
+            for para in &comment.paragraphs {
+                taken.extend(para_id_value(&para.unknown_attrs));
+            }
+            durable_taken.extend(
+                comment
+                    .durable_id
+                    .as_deref()
+                    .and_then(|id| u32::from_str_radix(id, 16).ok()),
+            );
+        }
+
+        let mut next_para_id = 0;
+        let mut next_durable_id = 0;
+        for comment in &mut self.comments {
+            for para in &mut comment.paragraphs {
+                if para_id_value(&para.unknown_attrs).is_none() {
+                    next_para_id = free_id(&taken, next_para_id);
+                    taken.insert(next_para_id);
+                    para.unknown_attrs.retain(|(key, _)| key != PARA_ID);
+                    para.unknown_attrs
+                        .push((PARA_ID.into(), format!("{:08X}", next_para_id)));
+                }
+            }
+            if comment.durable_id.is_none() {
+                next_durable_id = free_id(&durable_taken, next_durable_id);
+                durable_taken.insert(next_durable_id);
+                comment.durable_id = Some(format!("{:08X}", next_durable_id));
+            }
+            if !self.people.iter().any(|p| p.author == comment.author) {
+                self.people.push(Person::new(comment.author.clone()));
+            }
+        }
+    }
+
+    /// Serialize thread state to commentsExtended.xml
+    pub(crate) fn extended_to_xml(&self) -> Result<String> {
+        write_part("w15:commentsEx", ("xmlns:w15", crate::xml::W15), |writer| {
+            for comment in &self.comments {
+                let Some(para_id) = comment.para_id() else {
+                    continue;
+                };
+                let mut e = BytesStart::new("w15:commentEx");
+                e.push_attribute(("w15:paraId", para_id));
+                let parent = comment.parent.and_then(|id| self.get(id));
+                if let Some(parent_id) = parent.and_then(|p| p.para_id()) {
+                    e.push_attribute(("w15:paraIdParent", parent_id));
+                }
+                e.push_attribute(("w15:done", if comment.resolved { "1" } else { "0" }));
+                writer.write_event(Event::Empty(e))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Serialize durable IDs to commentsIds.xml
+    pub(crate) fn ids_to_xml(&self) -> Result<String> {
+        write_part(
+            "w16cid:commentsIds",
+            ("xmlns:w16cid", crate::xml::W16CID),
+            |writer| {
+                for comment in &self.comments {
+                    let (Some(para_id), Some(durable_id)) =
+                        (comment.para_id(), comment.durable_id.as_deref())
+                    else {
+                        continue;
+                    };
+                    let mut e = BytesStart::new("w16cid:commentId");
+                    e.push_attribute(("w16cid:paraId", para_id));
+                    e.push_attribute(("w16cid:durableId", durable_id));
+                    writer.write_event(Event::Empty(e))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Serialize comment authors to people.xml
+    pub(crate) fn people_to_xml(&self) -> Result<String> {
+        write_part("w15:people", ("xmlns:w15", crate::xml::W15), |writer| {
+            for person in &self.people {
+                let mut e = BytesStart::new("w15:person");
+                e.push_attribute(("w15:author", person.author.as_str()));
+                if person.provider_id.is_none() && person.user_id.is_none() {
+                    writer.write_event(Event::Empty(e))?;
+                    continue;
+                }
+                writer.write_event(Event::Start(e))?;
+                let mut info = BytesStart::new("w15:presenceInfo");
+                if let Some(ref provider_id) = person.provider_id {
+                    info.push_attribute(("w15:providerId", provider_id.as_str()));
+                }
+                if let Some(ref user_id) = person.user_id {
+                    info.push_attribute(("w15:userId", user_id.as_str()));
+                }
+                writer.write_event(Event::Empty(info))?;
+                writer.write_event(Event::End(BytesEnd::new("w15:person")))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Document {
+    /// Reply to a comment; returns the reply's ID
+    ///
+    /// The reply is anchored next to the range of the comment it answers.
+    pub fn reply_to_comment(
+        &mut self,
+        parent: u32,
+        author: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Result<u32> {
+        if self.comments().and_then(|c| c.get(parent)).is_none() {
+            return Err(Error::NotFound(format!("comment {}", parent)));
+        }
+        let id = self.comments_mut().reply(parent, author, text)?;
+
+        let mut anchored = false;
+        for para in body_paragraphs_mut(&mut self.body.content) {
+            let start = para.content.iter().position(
+                |c| matches!(c, ParagraphContent::CommentRangeStart { id } if *id == parent),
+            );
+            if let Some(i) = start {
+                para.content
+                    .insert(i + 1, ParagraphContent::CommentRangeStart { id });
+                anchored = true;
+            }
+            let end = para.content.iter().position(
+                |c| matches!(c, ParagraphContent::CommentRangeEnd { id } if *id == parent),
+            );
+            if let Some(i) = end {
+                let mut reference = Run::default();
+                reference.content.push(RunContent::CommentReference(id));
+                para.content
+                    .insert(i + 1, ParagraphContent::CommentRangeEnd { id });
+                para.content.insert(i + 2, ParagraphContent::Run(reference));
+                anchored = true;
+            }
+        }
+        if anchored {
+            self.mark_body_modified();
+        }
+        Ok(id)
+    }
+
+    /// Write comments.xml together with its thread and people parts
+    pub(crate) fn store_comment_parts(&mut self, uri: &PartUri) -> Result<()> {
+        use crate::opc::rel_types;
+
+        let taken = body_paragraphs(&self.body.content)
+            .filter_map(|p| para_id_value(&p.unknown_attrs))
+            .collect();
+        let Some(comments) = self.comments.get_mut(&self.package) else {
+            return Ok(());
+        };
+        comments.prepare_threads(taken);
+        let comments = &*comments;
+
+        store_part(&mut self.package, uri, crate::opc::COMMENTS, || {
+            comments.to_xml()
+        })?;
+
+        let parts: [(&str, &str, &str, PartWriter); 3] = [
+            (
+                rel_types::COMMENTS_EXTENDED,
+                "commentsExtended.xml",
+                crate::opc::COMMENTS_EXTENDED,
+                Comments::extended_to_xml,
+            ),
+            (
+                rel_types::COMMENTS_IDS,
+                "commentsIds.xml",
+                crate::opc::COMMENTS_IDS,
+                Comments::ids_to_xml,
+            ),
+            (
+                rel_types::PEOPLE,
+                "people.xml",
+                crate::opc::PEOPLE,
+                Comments::people_to_xml,
+            ),
+        ];
+        for (rel_type, target, content_type, to_xml) in parts {
+            let uri = super::ensure_doc_rel(&mut self.package, rel_type, target)?;
+            if let Some(part) = self.package.part_mut(&uri) {
+                part.mark_modified();
+            }
+            store_part(&mut self.package, &uri, content_type, || to_xml(comments))?;
+        }
+        Ok(())
+    }
+}
+
+/// Numeric value of a paragraph's w14:paraId
+fn para_id_value(attrs: &[(String, String)]) -> Option<u32> {
+    let (_, value) = attrs.iter().find(|(key, _)| key == PARA_ID)?;
+    u32::from_str_radix(value, 16).ok()
+}
+
+/// Smallest unused ID after `after`
+fn free_id(taken: &HashSet<u32>, after: u32) -> u32 {
+    (after + 1..=MAX_ID)
+        .find(|id| !taken.contains(id))
+        .unwrap_or(MAX_ID)
+}
+
+/// Attribute value by local name, whatever the namespace prefix
+fn local_attr(e: &BytesStart, local: &[u8]) -> Option<String> {
+    e.attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.local_name().as_ref() == local)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// All elements with the given local name
+fn empty_elements(xml: &str, local: &[u8]) -> Result<Vec<BytesStart<'static>>> {
+    let mut reader = Reader::from_str(xml);
+    let mut elements = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == local => {
+                elements.push(e.into_owned());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(elements)
+}
+
+/// Write a part with a single root element
+fn write_part<F>(root: &str, namespace: (&str, &str), body: F) -> Result<String>
+where
+    F: FnOnce(&mut Writer<&mut Cursor<Vec<u8>>>) -> Result<()>,
+{
+    let mut buffer = Cursor::new(Vec::new());
+    let mut writer = Writer::new(&mut buffer);
+
+    writer.write_event(Event::Decl(BytesDecl::new(
+        "1.0",
+        Some("UTF-8"),
+        Some("yes"),
+    )))?;
+    let mut start = BytesStart::new(root);
+    start.push_attribute(namespace);
+    writer.write_event(Event::Start(start))?;
+    body(&mut writer)?;
+    writer.write_event(Event::End(BytesEnd::new(root)))?;
+
+    String::from_utf8(buffer.into_inner()).map_err(|e| Error::InvalidDocument(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENTS: &str = r#"<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml">
+<w:comment w:id="0" w:author="Ann"><w:p w14:paraId="1A2B3C4D"><w:r><w:t>Why?</w:t></w:r></w:p></w:comment>
+<w:comment w:id="1" w:author="Bob"><w:p w14:paraId="0000000A"><w:r><w:t>Because.</w:t></w:r></w:p></w:comment>
+</w:comments>"#;
+
+    #[test]
+    fn test_read_threads() {
+        let mut comments = Comments::from_xml(COMMENTS).unwrap();
+        comments
+            .read_extended(
+                r#"<w15:commentsEx xmlns:w15="http://schemas.microsoft.com/office/word/2012/wordml">
+<w15:commentEx w15:paraId="1A2B3C4D" w15:done="1"/>
+<w15:commentEx w15:paraId="0000000A" w15:paraIdParent="1A2B3C4D" w15:done="0"/>
+</w15:commentsEx>"#,
+            )
+            .unwrap();
+        comments
+            .read_people(
+                r#"<w15:people xmlns:w15="http://schemas.microsoft.com/office/word/2012/wordml">
+<w15:person w15:author="Ann"><w15:presenceInfo w15:providerId="AD" w15:userId="S-1-5"/></w15:person>
+</w15:people>"#,
+            )
+            .unwrap();
+
+        let first = comments.get(0).unwrap();
+        assert!(first.is_resolved());
+        assert_eq!(first.parent(), None);
+        let replies = first.replies(&comments);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].text(), "Because.");
+        assert!(!replies[0].is_resolved());
+        assert_eq!(comments.people[0].user_id.as_deref(), Some("S-1-5"));
+    }
+
+    #[test]
+    fn test_prepare_threads() {
+        let mut comments = Comments::from_xml(COMMENTS).unwrap();
+        let reply = comments.reply(1, "Cy", "Agreed").unwrap();
+        assert_eq!(comments.get(reply).unwrap().parent(), Some(1));
+        assert!(comments.reply(9, "Cy", "Lost").is_err());
+
+        comments.prepare_threads(HashSet::from([1]));
+        // New paragraph IDs skip those already in use
+        assert_eq!(comments.get(reply).unwrap().para_id(), Some("00000002"));
+        let authors: Vec<_> = comments.people.iter().map(|p| p.author.as_str()).collect();
+        assert_eq!(authors, ["Ann", "Bob", "Cy"]);
+
+        let extended = comments.extended_to_xml().unwrap();
+        assert!(extended.contains(
+            r#"<w15:commentEx w15:paraId="00000002" w15:paraIdParent="0000000A" w15:done="0"/>"#
+        ));
+        let ids = comments.ids_to_xml().unwrap();
+        assert_eq!(ids.matches("w16cid:durableId").count(), 3);
+    }
+}
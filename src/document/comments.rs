@@ -1,7 +1,11 @@
 //! Comments (comments.xml)
 
-use crate::document::Paragraph;
-use crate::error::Result;
+use crate::document::comment_threads::Person;
+use crate::document::{
+    BlockContent, BreakType, Document, Paragraph, ParagraphContent, Run, RunContent, SdtContent,
+    TextLocation,
+};
+use crate::error::{Error, Result};
 use crate::xml::{get_attr, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
@@ -22,6 +26,12 @@ pub struct Comment {
     pub paragraphs: Vec<Paragraph>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
+    /// ID of the comment this one replies to (from commentsExtended.xml)
+    pub(crate) parent: Option<u32>,
+    /// Whether the discussion is marked done (from commentsExtended.xml)
+    pub(crate) resolved: bool,
+    /// Durable ID (from commentsIds.xml)
+    pub(crate) durable_id: Option<String>,
 }
 
 impl Comment {
@@ -34,6 +44,9 @@ impl Comment {
             date: None,
             paragraphs: vec![Paragraph::new(text)],
             unknown_children: Vec::new(),
+            parent: None,
+            resolved: false,
+            durable_id: None,
         }
     }
 
@@ -45,6 +58,47 @@ impl Comment {
             .collect::<Vec<_>>()
             .join("\n")
     }
+
+    /// Text of the document the comment annotates, or None if it has no range
+    pub fn anchored_text(&self, doc: &Document) -> Option<String> {
+        let mut text: Option<String> = None;
+        for para in body_paragraphs(&doc.body.content) {
+            if let Some(ref mut text) = text {
+                text.push('\n');
+            }
+            for item in &para.content {
+                match item {
+                    ParagraphContent::CommentRangeStart { id } if *id == self.id => {
+                        text = Some(String::new());
+                    }
+                    ParagraphContent::CommentRangeEnd { id } if *id == self.id => return text,
+                    _ => {
+                        if let Some(ref mut text) = text {
+                            text.push_str(&crate::document::paragraph::content_text(
+                                std::slice::from_ref(item),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Span of the document a new comment annotates
+#[derive(Clone, Debug)]
+pub enum CommentRange {
+    /// A whole body paragraph
+    Paragraph(usize),
+    /// Runs `start..end` (indices among the paragraph's runs) of a body paragraph
+    Runs {
+        paragraph: usize,
+        start: usize,
+        end: usize,
+    },
+    /// `len` bytes of text at a location found with [`Document::find_text`]
+    Text { location: TextLocation, len: usize },
 }
 
 /// Collection of comments from comments.xml
@@ -52,13 +106,16 @@ impl Comment {
 pub struct Comments {
     pub comments: Vec<Comment>,
     pub unknown_children: Vec<RawXmlNode>,
+    /// Comment authors (from people.xml)
+    pub people: Vec<Person>,
 }
 
 impl Comments {
     /// Parse from XML string
     pub fn from_xml(xml: &str) -> Result<Self> {
         let mut reader = Reader::from_str(xml);
-        reader.config_mut().trim_text(true);
+        // Whitespace in w:t is significant, so text is not trimmed
+        reader.config_mut().trim_text(false);
 
         let mut comments = Comments::default();
         let mut buf = Vec::new();
@@ -97,6 +154,9 @@ impl Comments {
         let mut start = BytesStart::new("w:comments");
         start.push_attribute(("xmlns:w", crate::xml::W));
         start.push_attribute(("xmlns:r", crate::xml::R));
+        start.push_attribute(("xmlns:w14", crate::xml::W14));
+        start.push_attribute(("xmlns:mc", crate::xml::MC));
+        start.push_attribute(("mc:Ignorable", "w14"));
         writer.write_event(Event::Start(start))?;
 
         for comment in &self.comments {
@@ -137,6 +197,11 @@ impl Comments {
         self.comments.iter().find(|c| c.id == id)
     }
 
+    /// Get a mutable comment by ID
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Comment> {
+        self.comments.iter_mut().find(|c| c.id == id)
+    }
+
     /// Next available ID
     pub fn next_id(&self) -> u32 {
         self.comments.iter().map(|c| c.id).max().unwrap_or(0) + 1
@@ -150,6 +215,251 @@ impl Comments {
     }
 }
 
+impl Document {
+    /// Add a comment annotating a range of the body; returns the comment ID
+    ///
+    /// Inserts the range markers and the comment reference run.
+    pub fn add_comment(
+        &mut self,
+        range: CommentRange,
+        author: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Result<u32> {
+        let index = match range {
+            CommentRange::Paragraph(index)
+            | CommentRange::Runs {
+                paragraph: index, ..
+            } => index,
+            CommentRange::Text { ref location, .. } => location.paragraph_index,
+        };
+        let max = self.paragraph_count();
+        let para = self
+            .body
+            .paragraphs_mut()
+            .nth(index)
+            .ok_or(Error::IndexOutOfBounds { index, max })?;
+
+        let (start, end) = match range {
+            CommentRange::Paragraph(_) => (0, para.content.len()),
+            CommentRange::Runs { start, end, .. } => {
+                let runs: Vec<usize> = para
+                    .content
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| matches!(c, ParagraphContent::Run(_)))
+                    .map(|(i, _)| i)
+                    .collect();
+                if start >= end || end > runs.len() {
+                    return Err(Error::IndexOutOfBounds {
+                        index: end,
+                        max: runs.len(),
+                    });
+                }
+                (runs[start], runs[end - 1] + 1)
+            }
+            CommentRange::Text { location, len } => {
+                let text_len = para.text().len();
+                let end = location.char_offset + len;
+                if end > text_len {
+                    return Err(Error::IndexOutOfBounds {
+                        index: end,
+                        max: text_len,
+                    });
+                }
+                // Splitting runs leaves the text unchanged, so offsets stay valid
+                let start = split_at_text_offset(para, location.char_offset, false);
+                let end = split_at_text_offset(para, end, true);
+                (start, end)
+            }
+        };
+
+        let id = match self.comments() {
+            Some(comments) => comments.next_id(),
+            None => 1,
+        };
+        let para = self
+            .body
+            .paragraphs_mut()
+            .nth(index)
+            .expect("paragraph checked above");
+        para.content
+            .insert(end, ParagraphContent::CommentRangeEnd { id });
+        let mut reference = Run::default();
+        reference.content.push(RunContent::CommentReference(id));
+        para.content
+            .insert(end + 1, ParagraphContent::Run(reference));
+        para.content
+            .insert(start, ParagraphContent::CommentRangeStart { id });
+        self.mark_body_modified();
+
+        self.comments_mut()
+            .comments
+            .push(Comment::new(id, author, text));
+        Ok(id)
+    }
+
+    /// Delete a comment together with its range markers and reference
+    ///
+    /// Replies to the comment are deleted with it.
+    pub fn remove_comment(&mut self, id: u32) -> bool {
+        let mut ids = vec![id];
+        if let Some(comments) = self.comments() {
+            ids.extend(
+                comments
+                    .comments
+                    .iter()
+                    .filter(|c| c.parent == Some(id))
+                    .map(|c| c.id),
+            );
+        }
+
+        let mut found = false;
+        for para in body_paragraphs_mut(&mut self.body.content) {
+            let before = para.content.len();
+            para.content.retain(|c| {
+                !matches!(c, ParagraphContent::CommentRangeStart { id }
+                    | ParagraphContent::CommentRangeEnd { id } if ids.contains(id))
+            });
+            para.content.retain_mut(|c| {
+                let ParagraphContent::Run(run) = c else {
+                    return true;
+                };
+                let len = run.content.len();
+                run.content
+                    .retain(|c| !matches!(c, RunContent::CommentReference(id) if ids.contains(id)));
+                // Drop the reference run if nothing else is left in it
+                len == run.content.len() || !run.content.is_empty()
+            });
+            found |= para.content.len() != before;
+        }
+        if found {
+            self.mark_body_modified();
+        }
+
+        let in_part = self
+            .comments()
+            .is_some_and(|c| c.comments.iter().any(|c| c.id == id));
+        if in_part {
+            self.comments_mut()
+                .comments
+                .retain(|c| !ids.contains(&c.id));
+        }
+        found || in_part
+    }
+}
+
+/// Split runs so a content boundary falls at a byte offset of the paragraph text
+///
+/// Returns the index of that boundary. Offsets inside items that cannot be
+/// split (hyperlinks, revisions) round down, or up if `after` is set.
+fn split_at_text_offset(para: &mut Paragraph, offset: usize, after: bool) -> usize {
+    let mut pos = 0;
+    for i in 0..para.content.len() {
+        if pos >= offset {
+            return i;
+        }
+        let len = crate::document::paragraph::content_text(&para.content[i..=i]).len();
+        if pos + len > offset {
+            if let ParagraphContent::Run(run) = &mut para.content[i] {
+                if let Some(tail) = split_run(run, offset - pos) {
+                    para.content.insert(i + 1, ParagraphContent::Run(tail));
+                    return i + 1;
+                }
+            }
+            return if after { i + 1 } else { i };
+        }
+        pos += len;
+    }
+    para.content.len()
+}
+
+/// Split a run at a byte offset of its text, returning the second half
+fn split_run(run: &mut Run, offset: usize) -> Option<Run> {
+    let mut pos = 0;
+    for i in 0..run.content.len() {
+        if pos == offset {
+            return Some(run_tail(run, i));
+        }
+        let len = match &run.content[i] {
+            RunContent::Text(t) => t.len(),
+            RunContent::Tab
+            | RunContent::Break(BreakType::TextWrapping)
+            | RunContent::CarriageReturn => 1,
+            _ => 0,
+        };
+        if pos + len > offset {
+            let RunContent::Text(text) = &mut run.content[i] else {
+                return None;
+            };
+            let at = offset - pos;
+            if !text.is_char_boundary(at) {
+                return None;
+            }
+            let rest = text.split_off(at);
+            run.content.insert(i + 1, RunContent::Text(rest));
+            return Some(run_tail(run, i + 1));
+        }
+        pos += len;
+    }
+    None
+}
+
+/// Move a run's content from `index` on into a new run with the same formatting
+fn run_tail(run: &mut Run, index: usize) -> Run {
+    Run {
+        properties: run.properties.clone(),
+        content: run.content.split_off(index),
+        unknown_attrs: run.unknown_attrs.clone(),
+        unknown_children: Vec::new(),
+    }
+}
+
+/// Body paragraphs in document order, including those in table cells and
+/// block-level content controls
+pub(crate) fn body_paragraphs(blocks: &[BlockContent]) -> impl Iterator<Item = &Paragraph> {
+    blocks
+        .iter()
+        .flat_map(|block| -> Box<dyn Iterator<Item = &Paragraph>> {
+            match block {
+                BlockContent::Paragraph(p) => Box::new(std::iter::once(p.as_ref())),
+                BlockContent::Table(t) => Box::new(
+                    t.rows
+                        .iter()
+                        .flat_map(|r| r.cells.iter())
+                        .flat_map(|c| c.paragraphs.iter()),
+                ),
+                BlockContent::Sdt(sdt) => match &sdt.content {
+                    SdtContent::Blocks(inner) => Box::new(body_paragraphs(inner)),
+                    _ => Box::new(std::iter::empty()),
+                },
+                BlockContent::Unknown(_) => Box::new(std::iter::empty()),
+            }
+        })
+}
+
+pub(crate) fn body_paragraphs_mut(
+    blocks: &mut [BlockContent],
+) -> impl Iterator<Item = &mut Paragraph> {
+    blocks
+        .iter_mut()
+        .flat_map(|block| -> Box<dyn Iterator<Item = &mut Paragraph>> {
+            match block {
+                BlockContent::Paragraph(p) => Box::new(std::iter::once(p.as_mut())),
+                BlockContent::Table(t) => Box::new(
+                    t.rows
+                        .iter_mut()
+                        .flat_map(|r| r.cells.iter_mut())
+                        .flat_map(|c| c.paragraphs.iter_mut()),
+                ),
+                BlockContent::Sdt(sdt) => match &mut sdt.content {
+                    SdtContent::Blocks(inner) => Box::new(body_paragraphs_mut(inner)),
+                    _ => Box::new(std::iter::empty()),
+                },
+                BlockContent::Unknown(_) => Box::new(std::iter::empty()),
+            }
+        })
+}
+
 fn parse_comment<R: std::io::BufRead>(
     reader: &mut Reader<R>,
     start: &BytesStart,
@@ -168,6 +478,9 @@ fn parse_comment<R: std::io::BufRead>(
         date,
         paragraphs: Vec::new(),
         unknown_children: Vec::new(),
+        parent: None,
+        resolved: false,
+        durable_id: None,
     };
 
     let mut buf = Vec::new();
@@ -182,10 +495,8 @@ fn parse_comment<R: std::io::BufRead>(
                     comment.unknown_children.push(RawXmlNode::Element(raw));
                 }
             }
-            Event::Empty(e) => {
-                if e.name().local_name().as_ref() == b"p" {
-                    comment.paragraphs.push(Paragraph::from_empty(&e)?);
-                }
+            Event::Empty(e) if e.name().local_name().as_ref() == b"p" => {
+                comment.paragraphs.push(Paragraph::from_empty(&e)?);
             }
             Event::End(e) if e.name().local_name().as_ref() == b"comment" => break,
             Event::Eof => break,
@@ -240,4 +551,20 @@ mod tests {
         assert_eq!(comments2.get(1).unwrap().text(), "First comment");
         assert_eq!(comments2.get(2).unwrap().author, "Bob");
     }
+
+    #[test]
+    fn test_split_at_text_offset() {
+        let mut para = Paragraph::new("Hello");
+        let mut run = Run::new("world");
+        run.content.insert(0, RunContent::Tab);
+        para.add_run(run);
+
+        assert_eq!(split_at_text_offset(&mut para, 2, false), 1);
+        assert_eq!(split_at_text_offset(&mut para, 5, false), 2);
+        assert_eq!(split_at_text_offset(&mut para, 6, false), 3);
+        assert_eq!(split_at_text_offset(&mut para, 8, true), 4);
+
+        let texts: Vec<_> = para.runs().map(|r| r.text()).collect();
+        assert_eq!(texts, ["He", "llo", "\t", "wo", "rld"]);
+    }
 }
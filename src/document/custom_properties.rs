@@ -0,0 +1,404 @@
+//! Custom properties (custom.xml) - user-defined typed metadata
+
+use crate::error::Result;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::io::Cursor;
+
+/// Format ID Word uses for user-defined custom properties
+pub const FMTID_USER_DEFINED: &str = "{D5CDD505-2E9C-101B-9397-08002B2CF9AE}";
+
+/// First property ID available to custom properties (0 and 1 are reserved)
+const FIRST_PID: u32 = 2;
+
+/// Typed value of a custom property
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    /// `vt:lpwstr`
+    Text(String),
+    /// `vt:i4`
+    Integer(i32),
+    /// `vt:r8`
+    Number(f64),
+    /// `vt:bool`
+    Bool(bool),
+    /// `vt:filetime` (W3CDTF date string, e.g. `2024-01-15T10:30:00Z`)
+    FileTime(String),
+    /// Any other variant type (preserved for round-trip)
+    Other(RawXmlElement),
+}
+
+/// A single custom property
+#[derive(Clone, Debug)]
+pub struct CustomProperty {
+    /// Property name (unique, compared case-insensitively)
+    pub name: String,
+    /// Property ID
+    pub pid: u32,
+    /// Format ID
+    pub fmtid: String,
+    /// Name of a bookmark the value is linked to
+    pub link_target: Option<String>,
+    /// Property value
+    pub value: PropertyValue,
+}
+
+/// Custom properties from custom.xml
+#[derive(Clone, Debug, Default)]
+pub struct CustomProperties {
+    /// Properties in document order
+    pub properties: Vec<CustomProperty>,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+impl CustomProperties {
+    /// Create empty custom properties
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse from XML string
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut props = CustomProperties::default();
+        let mut buf = Vec::new();
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    let local = e.name().local_name();
+                    match local.as_ref() {
+                        b"Properties" => {
+                            // Root element, continue
+                        }
+                        b"property" => {
+                            let attrs = PropertyAttrs::from_start(&e)?;
+                            let raw = RawXmlElement::from_reader(&mut reader, &e)?;
+                            match raw.children.iter().find_map(as_element) {
+                                Some(value) => props.properties.push(CustomProperty {
+                                    name: attrs.name,
+                                    pid: attrs.pid,
+                                    fmtid: attrs.fmtid,
+                                    link_target: attrs.link_target,
+                                    value: PropertyValue::from_raw(value),
+                                }),
+                                None => props.unknown_children.push(RawXmlNode::Element(raw)),
+                            }
+                        }
+                        _ => {
+                            let raw = RawXmlElement::from_reader(&mut reader, &e)?;
+                            props.unknown_children.push(RawXmlNode::Element(raw));
+                        }
+                    }
+                }
+                Event::Empty(e) if e.name().local_name().as_ref() != b"Properties" => {
+                    let raw = RawXmlElement::from_empty(&e);
+                    props.unknown_children.push(RawXmlNode::Element(raw));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(props)
+    }
+
+    /// Serialize to XML string
+    pub fn to_xml(&self) -> Result<String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut buffer);
+
+        writer.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+
+        let mut start = BytesStart::new("Properties");
+        start.push_attribute(("xmlns", crate::xml::CUSTOM_PROPS));
+        start.push_attribute(("xmlns:vt", crate::xml::VT));
+        writer.write_event(Event::Start(start))?;
+
+        for prop in &self.properties {
+            prop.write_to(&mut writer)?;
+        }
+        for child in &self.unknown_children {
+            child.write_to(&mut writer)?;
+        }
+
+        writer.write_event(Event::End(BytesEnd::new("Properties")))?;
+
+        let xml_bytes = buffer.into_inner();
+        String::from_utf8(xml_bytes)
+            .map_err(|e| crate::error::Error::InvalidDocument(e.to_string()))
+    }
+
+    /// Get a property value by name
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.property(name).map(|p| &p.value)
+    }
+
+    /// Get a property by name
+    pub fn property(&self, name: &str) -> Option<&CustomProperty> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Set a property, replacing the value if it already exists
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<PropertyValue>) {
+        let name = name.into();
+        let value = value.into();
+        if let Some(prop) = self
+            .properties
+            .iter_mut()
+            .find(|p| p.name.eq_ignore_ascii_case(&name))
+        {
+            prop.value = value;
+            return;
+        }
+
+        let pid = self
+            .properties
+            .iter()
+            .map(|p| p.pid + 1)
+            .max()
+            .unwrap_or(FIRST_PID)
+            .max(FIRST_PID);
+        self.properties.push(CustomProperty {
+            name,
+            pid,
+            fmtid: FMTID_USER_DEFINED.into(),
+            link_target: None,
+            value,
+        });
+    }
+
+    /// Remove a property by name
+    pub fn remove(&mut self, name: &str) -> Option<CustomProperty> {
+        let idx = self
+            .properties
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name))?;
+        Some(self.properties.remove(idx))
+    }
+
+    /// Iterate over all properties
+    pub fn iter(&self) -> impl Iterator<Item = &CustomProperty> {
+        self.properties.iter()
+    }
+
+    /// Number of properties
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Whether there are no properties
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+}
+
+impl CustomProperty {
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut start = BytesStart::new("property");
+        start.push_attribute(("fmtid", self.fmtid.as_str()));
+        start.push_attribute(("pid", self.pid.to_string().as_str()));
+        start.push_attribute(("name", self.name.as_str()));
+        if let Some(ref target) = self.link_target {
+            start.push_attribute(("linkTarget", target.as_str()));
+        }
+        writer.write_event(Event::Start(start))?;
+        self.value.write_to(writer)?;
+        writer.write_event(Event::End(BytesEnd::new("property")))?;
+        Ok(())
+    }
+}
+
+impl PropertyValue {
+    /// Convert a parsed `vt:*` element, keeping unsupported or malformed values raw
+    fn from_raw(raw: &RawXmlElement) -> Self {
+        let text: String = raw
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                RawXmlNode::Text(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect();
+        let local = raw.name.rsplit(':').next().unwrap_or(&raw.name);
+        let parsed = match local {
+            "lpwstr" => Some(PropertyValue::Text(text)),
+            "i4" => text.trim().parse().ok().map(PropertyValue::Integer),
+            "r8" => text.trim().parse().ok().map(PropertyValue::Number),
+            "bool" => match text.trim() {
+                "true" | "1" => Some(PropertyValue::Bool(true)),
+                "false" | "0" => Some(PropertyValue::Bool(false)),
+                _ => None,
+            },
+            "filetime" => Some(PropertyValue::FileTime(text)),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| PropertyValue::Other(raw.clone()))
+    }
+
+    /// Get the value as text, if it is `Text`
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            PropertyValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Write the `vt:*` value element
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let (name, text) = match self {
+            PropertyValue::Text(s) => ("vt:lpwstr", s.clone()),
+            PropertyValue::Integer(v) => ("vt:i4", v.to_string()),
+            PropertyValue::Number(v) => ("vt:r8", v.to_string()),
+            PropertyValue::Bool(v) => ("vt:bool", v.to_string()),
+            PropertyValue::FileTime(s) => ("vt:filetime", s.clone()),
+            PropertyValue::Other(raw) => return raw.write_to(writer),
+        };
+        writer.write_event(Event::Start(BytesStart::new(name)))?;
+        writer.write_event(Event::Text(BytesText::new(&text)))?;
+        writer.write_event(Event::End(BytesEnd::new(name)))?;
+        Ok(())
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(s: &str) -> Self {
+        PropertyValue::Text(s.to_string())
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(s: String) -> Self {
+        PropertyValue::Text(s)
+    }
+}
+
+impl From<i32> for PropertyValue {
+    fn from(v: i32) -> Self {
+        PropertyValue::Integer(v)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(v: f64) -> Self {
+        PropertyValue::Number(v)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(v: bool) -> Self {
+        PropertyValue::Bool(v)
+    }
+}
+
+/// Attributes of a `property` element
+struct PropertyAttrs {
+    name: String,
+    pid: u32,
+    fmtid: String,
+    link_target: Option<String>,
+}
+
+impl PropertyAttrs {
+    fn from_start(e: &BytesStart) -> Result<Self> {
+        let mut attrs = PropertyAttrs {
+            name: String::new(),
+            pid: FIRST_PID,
+            fmtid: FMTID_USER_DEFINED.into(),
+            link_target: None,
+        };
+        for attr in e.attributes() {
+            let attr = attr?;
+            let value = attr.unescape_value()?.to_string();
+            match attr.key.as_ref() {
+                b"name" => attrs.name = value,
+                b"pid" => attrs.pid = value.parse().unwrap_or(FIRST_PID),
+                b"fmtid" => attrs.fmtid = value,
+                b"linkTarget" => attrs.link_target = Some(value),
+                _ => {}
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+fn as_element(node: &RawXmlNode) -> Option<&RawXmlElement> {
+    match node {
+        RawXmlNode::Element(e) => Some(e),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOM_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/custom-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="2" name="CaseNumber"><vt:lpwstr>C-2024-0042</vt:lpwstr></property>
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="3" name="Priority"><vt:i4>2</vt:i4></property>
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="4" name="Score"><vt:r8>0.75</vt:r8></property>
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="5" name="Confidential"><vt:bool>true</vt:bool></property>
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="6" name="Filed"><vt:filetime>2024-01-15T10:30:00Z</vt:filetime></property>
+  <property fmtid="{D5CDD505-2E9C-101B-9397-08002B2CF9AE}" pid="7" name="Big"><vt:i8>9000000000</vt:i8></property>
+</Properties>"#;
+
+    #[test]
+    fn test_parse_custom_properties() {
+        let props = CustomProperties::from_xml(CUSTOM_XML).unwrap();
+        assert_eq!(props.len(), 6);
+        assert_eq!(
+            props.get("casenumber").and_then(|v| v.as_text()),
+            Some("C-2024-0042")
+        );
+        assert_eq!(props.get("Priority"), Some(&PropertyValue::Integer(2)));
+        assert_eq!(props.get("Score"), Some(&PropertyValue::Number(0.75)));
+        assert_eq!(props.get("Confidential"), Some(&PropertyValue::Bool(true)));
+        assert_eq!(
+            props.get("Filed"),
+            Some(&PropertyValue::FileTime("2024-01-15T10:30:00Z".into()))
+        );
+        assert!(matches!(props.get("Big"), Some(PropertyValue::Other(_))));
+    }
+
+    #[test]
+    fn test_set_and_roundtrip() {
+        let mut props = CustomProperties::from_xml(CUSTOM_XML).unwrap();
+        props.set("Priority", 5);
+        props.set("Classification", "Internal & Restricted");
+        assert_eq!(props.property("Classification").unwrap().pid, 8);
+        assert!(props.remove("Score").is_some());
+
+        let xml = props.to_xml().unwrap();
+        assert!(xml.contains("<vt:i8>9000000000</vt:i8>"));
+
+        let props2 = CustomProperties::from_xml(&xml).unwrap();
+        assert_eq!(props2.len(), 6);
+        assert_eq!(props2.get("Priority"), Some(&PropertyValue::Integer(5)));
+        assert_eq!(
+            props2.get("Classification").and_then(|v| v.as_text()),
+            Some("Internal & Restricted")
+        );
+        assert!(props2.get("Score").is_none());
+    }
+
+    #[test]
+    fn test_first_pid() {
+        let mut props = CustomProperties::new();
+        props.set("A", true);
+        assert_eq!(props.property("A").unwrap().pid, FIRST_PID);
+        assert_eq!(props.property("A").unwrap().fmtid, FMTID_USER_DEFINED);
+    }
+}
@@ -0,0 +1,573 @@
+//! Content control data binding (w:dataBinding)
+//!
+//! A bound control shows the value of a node in a custom XML part, chosen by
+//! the part's datastore item ID and an XPath. Paths are resolved with the
+//! subset Word writes: absolute element steps with optional positions and an
+//! optional final attribute or `text()` step, e.g.
+//! `/ns0:order[1]/ns0:customer[1]/@id`.
+
+use crate::document::sdt::ValueSetter;
+use crate::document::{DataBinding, Document, SdtKind, SdtProperties};
+use crate::error::{Error, Result};
+use crate::opc::CustomXmlPart;
+use crate::xml::{read_attributes, RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesDecl, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// Namespace declarations in scope, innermost last; the default namespace has an empty prefix
+type Scope = Vec<(String, String)>;
+
+/// An element or attribute name with its namespace resolved
+#[derive(Clone, Debug, PartialEq)]
+struct Name {
+    namespace: Option<String>,
+    /// Local name, or `*` for any element
+    local: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Step {
+    name: Name,
+    /// 1-based position among matching siblings
+    position: Option<usize>,
+}
+
+/// A parsed data binding XPath
+#[derive(Clone, Debug, PartialEq)]
+struct XmlPath {
+    steps: Vec<Step>,
+    attribute: Option<Name>,
+}
+
+impl XmlPath {
+    /// Parse an XPath, resolving prefixes through `w:prefixMappings`
+    ///
+    /// Returns `None` for paths outside the supported subset.
+    fn parse(xpath: &str, prefix_mappings: Option<&str>) -> Option<Self> {
+        let prefixes = parse_prefix_mappings(prefix_mappings.unwrap_or_default());
+        let resolve = |qname: &str| -> Option<Name> {
+            match qname.split_once(':') {
+                Some((prefix, local)) => Some(Name {
+                    namespace: Some(lookup(&prefixes, prefix)?.to_string()),
+                    local: local.to_string(),
+                }),
+                None => Some(Name {
+                    namespace: None,
+                    local: qname.to_string(),
+                }),
+            }
+        };
+
+        let mut segments = xpath.trim().strip_prefix('/')?.split('/').peekable();
+        let mut path = XmlPath {
+            steps: Vec::new(),
+            attribute: None,
+        };
+        while let Some(segment) = segments.next() {
+            let last = segments.peek().is_none();
+            if let Some(attr) = segment.strip_prefix('@') {
+                if !last {
+                    return None;
+                }
+                path.attribute = Some(resolve(attr)?);
+                break;
+            }
+            if segment == "text()" && last {
+                break;
+            }
+            let (qname, mut predicates) = match segment.find('[') {
+                Some(i) => (&segment[..i], &segment[i..]),
+                None => (segment, ""),
+            };
+            if qname.is_empty() {
+                return None;
+            }
+            let mut position = None;
+            while !predicates.is_empty() {
+                let end = predicates.find(']')?;
+                let n: usize = predicates[1..end].trim().parse().ok()?;
+                position = Some(n);
+                predicates = &predicates[end + 1..];
+            }
+            let name = match qname {
+                "*" => Name {
+                    namespace: None,
+                    local: "*".into(),
+                },
+                _ => resolve(qname)?,
+            };
+            path.steps.push(Step { name, position });
+        }
+        (!path.steps.is_empty()).then_some(path)
+    }
+
+    /// Child index path from the root to the selected element, with its scope
+    fn find(&self, root: &RawXmlElement) -> Option<(Vec<usize>, Scope)> {
+        let scope = scope_of(&Vec::new(), root);
+        let first = &self.steps[0];
+        if !matches(&first.name, root, &scope) || first.position.is_some_and(|p| p != 1) {
+            return None;
+        }
+        let (mut path, scope) = descend(root, scope, &self.steps[1..])?;
+        path.reverse();
+        Some((path, scope))
+    }
+
+    /// Value of the selected node
+    fn value(&self, root: &RawXmlElement) -> Option<String> {
+        let (path, scope) = self.find(root)?;
+        let element = element_at(root, &path);
+        match &self.attribute {
+            Some(name) => {
+                attribute_index(element, name, &scope).map(|i| element.attributes[i].1.clone())
+            }
+            None => Some(string_value(element)),
+        }
+    }
+
+    /// Set the selected node; returns whether it changed
+    fn set_value(&self, root: &mut RawXmlElement, value: &str) -> bool {
+        let Some((path, scope)) = self.find(root) else {
+            return false;
+        };
+        let element = element_at_mut(root, &path);
+        match &self.attribute {
+            Some(name) => match attribute_index(element, name, &scope) {
+                Some(i) if element.attributes[i].1 == value => false,
+                Some(i) => {
+                    element.attributes[i].1 = value.to_string();
+                    true
+                }
+                None if name.namespace.is_none() => {
+                    element
+                        .attributes
+                        .push((name.local.clone(), value.to_string()));
+                    true
+                }
+                None => false,
+            },
+            None if string_value(element) == value => false,
+            None => {
+                element.children.clear();
+                if !value.is_empty() {
+                    element.children.push(RawXmlNode::Text(value.to_string()));
+                }
+                element.self_closing = value.is_empty();
+                true
+            }
+        }
+    }
+}
+
+/// Find the remaining steps below `element`; the index path comes back reversed
+fn descend(element: &RawXmlElement, scope: Scope, steps: &[Step]) -> Option<(Vec<usize>, Scope)> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Some((Vec::new(), scope));
+    };
+    let mut seen = 0;
+    for (i, child) in element.children.iter().enumerate() {
+        let RawXmlNode::Element(child) = child else {
+            continue;
+        };
+        let child_scope = scope_of(&scope, child);
+        if !matches(&step.name, child, &child_scope) {
+            continue;
+        }
+        seen += 1;
+        if step.position.is_some_and(|p| p != seen) {
+            continue;
+        }
+        if let Some((mut path, found)) = descend(child, child_scope, rest) {
+            path.push(i);
+            return Some((path, found));
+        }
+    }
+    None
+}
+
+/// Extend a scope with an element's namespace declarations
+fn scope_of(parent: &Scope, element: &RawXmlElement) -> Scope {
+    let mut scope = parent.clone();
+    for (key, value) in &element.attributes {
+        if key == "xmlns" {
+            scope.push((String::new(), value.clone()));
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            scope.push((prefix.to_string(), value.clone()));
+        }
+    }
+    scope
+}
+
+fn lookup<'a>(scope: &'a [(String, String)], prefix: &str) -> Option<&'a str> {
+    scope
+        .iter()
+        .rev()
+        .find(|(p, _)| p == prefix)
+        .map(|(_, uri)| uri.as_str())
+}
+
+/// Check an element against a step name
+fn matches(name: &Name, element: &RawXmlElement, scope: &Scope) -> bool {
+    if name.local == "*" {
+        return true;
+    }
+    let (prefix, local) = element.name.split_once(':').unwrap_or(("", &element.name));
+    let namespace = lookup(scope, prefix).filter(|uri| !uri.is_empty());
+    local == name.local && namespace == name.namespace.as_deref()
+}
+
+/// Index of an attribute; unprefixed attributes are in no namespace
+fn attribute_index(element: &RawXmlElement, name: &Name, scope: &Scope) -> Option<usize> {
+    element.attributes.iter().position(|(key, _)| {
+        let (namespace, local) = match key.split_once(':') {
+            Some(("xmlns", _)) => return false,
+            Some((prefix, local)) => (lookup(scope, prefix), local),
+            None => (None, key.as_str()),
+        };
+        key != "xmlns" && local == name.local && namespace == name.namespace.as_deref()
+    })
+}
+
+fn element_at<'a>(root: &'a RawXmlElement, path: &[usize]) -> &'a RawXmlElement {
+    path.iter()
+        .fold(root, |element, &i| match &element.children[i] {
+            RawXmlNode::Element(child) => child,
+            _ => unreachable!("paths only select elements"),
+        })
+}
+
+fn element_at_mut<'a>(root: &'a mut RawXmlElement, path: &[usize]) -> &'a mut RawXmlElement {
+    path.iter()
+        .fold(root, |element, &i| match &mut element.children[i] {
+            RawXmlNode::Element(child) => child,
+            _ => unreachable!("paths only select elements"),
+        })
+}
+
+/// Concatenated text of an element and its descendants
+fn string_value(element: &RawXmlElement) -> String {
+    let mut text = String::new();
+    for child in &element.children {
+        match child {
+            RawXmlNode::Text(t) => text.push_str(t),
+            RawXmlNode::Element(e) => text.push_str(&string_value(e)),
+            RawXmlNode::Comment(_) => {}
+        }
+    }
+    text
+}
+
+/// Parse `xmlns:ns0='uri' xmlns:ns1="uri"`
+fn parse_prefix_mappings(mappings: &str) -> Vec<(String, String)> {
+    mappings
+        .split_whitespace()
+        .filter_map(|decl| {
+            let (prefix, uri) = decl.strip_prefix("xmlns:")?.split_once('=')?;
+            let uri = uri.trim_matches(|c| c == '\'' || c == '"');
+            Some((prefix.to_string(), uri.to_string()))
+        })
+        .collect()
+}
+
+/// A custom XML part parsed for binding
+struct XmlStore {
+    part: CustomXmlPart,
+    /// `standalone` of the XML declaration, if the part had one
+    declaration: Option<Option<String>>,
+    root: RawXmlElement,
+    modified: bool,
+}
+
+impl XmlStore {
+    fn parse(part: CustomXmlPart) -> Result<Self> {
+        let mut reader = Reader::from_str(&part.xml);
+        let mut buf = Vec::new();
+        let mut declaration = None;
+        let root = loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Decl(decl) => {
+                    let standalone = decl
+                        .standalone()
+                        .and_then(|s| s.ok())
+                        .map(|s| String::from_utf8_lossy(&s).into_owned());
+                    declaration = Some(standalone);
+                }
+                Event::Start(e) => break read_element(&mut reader, &e)?,
+                Event::Empty(e) => break RawXmlElement::from_empty(&e),
+                Event::Eof => {
+                    return Err(Error::InvalidDocument(format!(
+                        "custom XML part {} has no root element",
+                        part.uri
+                    )))
+                }
+                _ => {}
+            }
+            buf.clear();
+        };
+        Ok(Self {
+            part,
+            declaration,
+            root,
+            modified: false,
+        })
+    }
+
+    fn to_xml(&self) -> Result<Vec<u8>> {
+        let mut writer = Writer::new(Vec::new());
+        if let Some(standalone) = &self.declaration {
+            let decl = BytesDecl::new("1.0", Some("UTF-8"), standalone.as_deref());
+            writer.write_event(Event::Decl(decl))?;
+        }
+        self.root.write_to(&mut writer)?;
+        Ok(writer.into_inner())
+    }
+}
+
+/// Read an element of a data store, keeping every text node
+///
+/// Unlike [`RawXmlElement::from_reader`], whitespace-only text is kept (it may be
+/// data, e.g. under `xml:space="preserve"`) and CDATA sections are read as text.
+fn read_element(reader: &mut Reader<&[u8]>, start: &BytesStart) -> Result<RawXmlElement> {
+    let mut element = RawXmlElement::new(String::from_utf8_lossy(start.name().as_ref()));
+    element.attributes = read_attributes(start);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                let child = read_element(reader, &e)?;
+                element.children.push(RawXmlNode::Element(child));
+            }
+            Event::Empty(e) => {
+                let child = RawXmlElement::from_empty(&e);
+                element.children.push(RawXmlNode::Element(child));
+            }
+            Event::Text(t) => push_text(&mut element, &t.unescape()?),
+            Event::CData(t) => push_text(&mut element, &String::from_utf8_lossy(&t)),
+            Event::Comment(c) => {
+                let comment = String::from_utf8_lossy(&c).into_owned();
+                element.children.push(RawXmlNode::Comment(comment));
+            }
+            Event::End(_) => break,
+            Event::Eof => return Err(Error::InvalidDocument("Unexpected EOF".into())),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(element)
+}
+
+/// Append text, merging it with a preceding text node
+fn push_text(element: &mut RawXmlElement, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match element.children.last_mut() {
+        Some(RawXmlNode::Text(last)) => last.push_str(text),
+        _ => element.children.push(RawXmlNode::Text(text.to_string())),
+    }
+}
+
+/// Parse every custom XML part of a document
+fn parse_stores(doc: &Document) -> Result<Vec<XmlStore>> {
+    doc.package
+        .custom_xml_parts()
+        .into_iter()
+        .map(XmlStore::parse)
+        .collect()
+}
+
+/// The store a binding refers to: the part with its item ID, or the first
+/// part where the path resolves when the ID is missing or unknown
+fn find_store(stores: &[XmlStore], binding: &DataBinding, path: &XmlPath) -> Option<usize> {
+    binding
+        .store_item_id
+        .as_deref()
+        .and_then(|id| stores.iter().position(|s| s.part.has_item_id(id)))
+        .or_else(|| stores.iter().position(|s| path.find(&s.root).is_some()))
+}
+
+/// Whether a control kind carries a value a binding can hold
+fn is_bindable(kind: &SdtKind) -> bool {
+    !matches!(
+        kind,
+        SdtKind::Picture | SdtKind::DocPart(_) | SdtKind::Group | SdtKind::Other
+    )
+}
+
+impl Document {
+    /// Get the custom XML data parts (see [`Package::custom_xml_parts`](crate::Package::custom_xml_parts))
+    pub fn custom_xml_parts(&self) -> Vec<CustomXmlPart> {
+        self.package.custom_xml_parts()
+    }
+
+    /// Add a custom XML data part with a new datastore item ID
+    pub fn add_custom_xml_part(
+        &mut self,
+        xml: &str,
+        schema_refs: &[&str],
+    ) -> Result<CustomXmlPart> {
+        self.ensure_main_document_part();
+        self.package.add_custom_xml_part(xml, schema_refs)
+    }
+
+    /// Replace the XML of the custom XML data part with a datastore item ID
+    pub fn replace_custom_xml_part(&mut self, item_id: &str, xml: &str) -> Result<()> {
+        self.package.replace_custom_xml_part(item_id, xml)
+    }
+
+    /// Show the custom XML values in bound content controls
+    ///
+    /// Each control with a `w:dataBinding` takes the value of its node (see
+    /// [`Sdt::set_value`](crate::Sdt::set_value)). Controls whose node is
+    /// missing or whose value does not fit the control are left alone.
+    /// Returns the number of controls changed.
+    pub fn refresh_bindings(&mut self) -> Result<usize> {
+        let stores = parse_stores(self)?;
+        if stores.is_empty() {
+            return Ok(0);
+        }
+        self.set_content_control_values(ValueSetter {
+            value_for: |props: &SdtProperties, current: &str| {
+                let binding = props.data_binding.as_ref()?;
+                if !is_bindable(&props.kind) {
+                    return None;
+                }
+                let path = XmlPath::parse(&binding.xpath, binding.prefix_mappings.as_deref())?;
+                let store = &stores[find_store(&stores, binding, &path)?];
+                let value = path.value(&store.root)?;
+                props.would_change(current, &value).then_some(value)
+            },
+            strict: false,
+            count: 0,
+        })
+    }
+
+    /// Write the values of bound content controls into their custom XML nodes
+    ///
+    /// The reverse of [`Document::refresh_bindings`]. Checkboxes store
+    /// `true`/`false`, date pickers their ISO date and lists the item value.
+    /// Nodes that do not exist are not created. Returns the number of nodes
+    /// changed.
+    pub fn collect_bindings(&mut self) -> Result<usize> {
+        let mut stores = parse_stores(self)?;
+        let mut count = 0;
+        for control in self.content_controls() {
+            let Some(binding) = &control.properties.data_binding else {
+                continue;
+            };
+            if !is_bindable(&control.properties.kind) {
+                continue;
+            }
+            let Some(path) = XmlPath::parse(&binding.xpath, binding.prefix_mappings.as_deref())
+            else {
+                continue;
+            };
+            let Some(index) = find_store(&stores, binding, &path) else {
+                continue;
+            };
+            let store = &mut stores[index];
+            let value = control.value();
+            // Keep nodes that already hold the value in another spelling, e.g. `1` for `true`
+            let stored = path.value(&store.root);
+            if stored.is_some_and(|v| !control.properties.would_change(&value, &v)) {
+                continue;
+            }
+            if path.set_value(&mut store.root, &value) {
+                store.modified = true;
+                count += 1;
+            }
+        }
+        for store in stores.iter().filter(|s| s.modified) {
+            let xml = store.to_xml()?;
+            if let Some(part) = self.package.part_mut(&store.part.uri) {
+                part.set_data(xml);
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(xml: &str) -> RawXmlElement {
+        let part = CustomXmlPart {
+            uri: crate::opc::PartUri::new("/customXml/item1.xml").unwrap(),
+            props_uri: None,
+            item_id: None,
+            schema_refs: Vec::new(),
+            xml: xml.to_string(),
+        };
+        XmlStore::parse(part).unwrap().root
+    }
+
+    #[test]
+    fn test_parse_xpath() {
+        let path = XmlPath::parse(
+            "/ns0:order[1]/ns0:line[2]/@sku",
+            Some("xmlns:ns0='urn:orders'"),
+        )
+        .unwrap();
+        assert_eq!(path.steps.len(), 2);
+        assert_eq!(path.steps[1].position, Some(2));
+        assert_eq!(path.steps[1].name.namespace.as_deref(), Some("urn:orders"));
+        assert_eq!(path.attribute.as_ref().unwrap().local, "sku");
+
+        assert!(XmlPath::parse("/ns1:order", Some("xmlns:ns0='urn:orders'")).is_none());
+        assert!(XmlPath::parse("//order", None).is_none());
+        assert!(XmlPath::parse("/order[@id='1']", None).is_none());
+        assert!(XmlPath::parse("order", None).is_none());
+    }
+
+    #[test]
+    fn test_select_and_set() {
+        let mut xml = root(
+            r#"<order xmlns="urn:orders" xmlns:x="urn:extra"><line sku="A">Bolts</line><line sku="B">Nuts</line><x:note>n</x:note></order>"#,
+        );
+        let ns = Some("xmlns:o='urn:orders' xmlns:e='urn:extra'");
+        let second = XmlPath::parse("/o:order[1]/o:line[2]", ns).unwrap();
+        assert_eq!(second.value(&xml).as_deref(), Some("Nuts"));
+        let sku = XmlPath::parse("/o:order/o:line[2]/@sku", ns).unwrap();
+        assert_eq!(sku.value(&xml).as_deref(), Some("B"));
+        let note = XmlPath::parse("/o:order/e:note/text()", ns).unwrap();
+        assert_eq!(note.value(&xml).as_deref(), Some("n"));
+        // Unprefixed steps only match elements in no namespace
+        assert_eq!(XmlPath::parse("/order", None).unwrap().value(&xml), None);
+
+        assert!(second.set_value(&mut xml, "Washers"));
+        assert!(!second.set_value(&mut xml, "Washers"));
+        assert!(sku.set_value(&mut xml, "C&D"));
+        assert_eq!(second.value(&xml).as_deref(), Some("Washers"));
+        assert_eq!(sku.value(&xml).as_deref(), Some("C&D"));
+        let missing = XmlPath::parse("/o:order/o:line[3]", ns).unwrap();
+        assert!(!missing.set_value(&mut xml, "x"));
+    }
+
+    #[test]
+    fn test_store_keeps_whitespace() {
+        let xml = "<ns0:crm xmlns:ns0=\"urn:crm\">\n  <ns0:name>Ada</ns0:name>\n  <ns0:note xml:space=\"preserve\">  </ns0:note>\n  <ns0:code><![CDATA[a<b]]></ns0:code>\n</ns0:crm>";
+        let part = CustomXmlPart {
+            uri: crate::opc::PartUri::new("/customXml/item1.xml").unwrap(),
+            props_uri: None,
+            item_id: None,
+            schema_refs: Vec::new(),
+            xml: xml.to_string(),
+        };
+        let mut store = XmlStore::parse(part).unwrap();
+        let ns = Some("xmlns:c='urn:crm'");
+        let note = XmlPath::parse("/c:crm/c:note", ns).unwrap();
+        assert_eq!(note.value(&store.root).as_deref(), Some("  "));
+        let code = XmlPath::parse("/c:crm/c:code", ns).unwrap();
+        assert_eq!(code.value(&store.root).as_deref(), Some("a<b"));
+
+        let name = XmlPath::parse("/c:crm/c:name", ns).unwrap();
+        assert!(name.set_value(&mut store.root, "Grace"));
+        let written = String::from_utf8(store.to_xml().unwrap()).unwrap();
+        assert_eq!(
+            written,
+            xml.replace("Ada", "Grace")
+                .replace("<![CDATA[a<b]]>", "a&lt;b")
+        );
+    }
+}
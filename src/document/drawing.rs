@@ -0,0 +1,546 @@
+//! Pictures inside drawing XML
+//!
+//! Drawings are kept as raw XML. These helpers find the pictures in a
+//! `w:drawing` (inline, anchored, in a group or inside a text box) or in a
+//! legacy VML `w:pict`, and edit their relationship IDs and sizes in place.
+//! Only the `mc:Choice` branch of `mc:AlternateContent` is searched, since
+//! the fallback repeats the same pictures.
+
+use crate::xml::{RawXmlElement, RawXmlNode};
+
+/// EMU per point
+const EMU_PER_PT: f64 = 12700.0;
+
+/// `a:ext` URI of the Office 2016 extension holding a picture's SVG version
+const SVG_EXT_URI: &str = "{96DAC541-7B7A-43D3-8B79-37D633B846F1}";
+
+/// A picture found in drawing XML
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Picture {
+    /// Child index path to the element holding the relationship ID
+    pub blip: Vec<usize>,
+    /// Attribute holding the relationship ID (`r:embed`, `r:link`, `r:id`)
+    pub rel_attr: String,
+    /// Path to the wp:inline, wp:anchor or v:shape that sizes the picture
+    pub frame: Option<Vec<usize>>,
+    /// Path to the picture's own a:xfrm/a:ext
+    pub xfrm_ext: Option<Vec<usize>>,
+    /// Relationship ID of the image
+    pub r_id: String,
+    /// Relationship ID of the SVG version (`asvg:svgBlip`); `r_id` is then its raster fallback
+    pub svg_r_id: Option<String>,
+    /// The image is linked (`r:link`) rather than embedded
+    pub linked: bool,
+    /// Legacy VML picture
+    pub vml: bool,
+    /// Floating (wp:anchor) rather than inline
+    pub floating: bool,
+    /// Inside a text box
+    pub in_text_box: bool,
+    pub width_emu: i64,
+    pub height_emu: i64,
+    pub description: String,
+    pub title: String,
+    pub name: String,
+}
+
+/// The wp:inline or wp:anchor a picture sits in
+struct Frame<'a> {
+    path: Vec<usize>,
+    floating: bool,
+    element: &'a RawXmlElement,
+}
+
+/// Find all pictures in a drawing, in document order
+pub(crate) fn pictures(root: &RawXmlElement) -> Vec<Picture> {
+    let mut out = Vec::new();
+    scan(root, &mut Vec::new(), None, false, &mut out);
+    out
+}
+
+fn scan<'a>(
+    e: &'a RawXmlElement,
+    path: &mut Vec<usize>,
+    frame: Option<&Frame<'a>>,
+    in_text_box: bool,
+    out: &mut Vec<Picture>,
+) {
+    match local_name(e) {
+        "AlternateContent" => {
+            if let Some((i, choice)) = children(e).find(|(_, c)| local_name(c) == "Choice") {
+                path.push(i);
+                scan(choice, path, frame, in_text_box, out);
+                path.pop();
+            }
+            return;
+        }
+        "inline" | "anchor" => {
+            let frame = Frame {
+                path: path.clone(),
+                floating: local_name(e) == "anchor",
+                element: e,
+            };
+            scan_children(e, path, Some(&frame), in_text_box, out);
+            return;
+        }
+        "pic" => {
+            out.extend(drawingml_picture(e, path, frame, in_text_box));
+            return;
+        }
+        "txbxContent" => {
+            scan_children(e, path, None, true, out);
+            return;
+        }
+        _ => {}
+    }
+    if let Some((i, data)) = children(e).find(|(_, c)| local_name(c) == "imagedata") {
+        out.extend(vml_picture(e, path, i, data, in_text_box));
+    }
+    scan_children(e, path, frame, in_text_box, out);
+}
+
+fn scan_children<'a>(
+    e: &'a RawXmlElement,
+    path: &mut Vec<usize>,
+    frame: Option<&Frame<'a>>,
+    in_text_box: bool,
+    out: &mut Vec<Picture>,
+) {
+    for (i, child) in children(e) {
+        path.push(i);
+        scan(child, path, frame, in_text_box, out);
+        path.pop();
+    }
+}
+
+/// A pic:pic element
+fn drawingml_picture(
+    pic: &RawXmlElement,
+    path: &[usize],
+    frame: Option<&Frame>,
+    in_text_box: bool,
+) -> Option<Picture> {
+    let (fill_index, fill) = children(pic).find(|(_, c)| local_name(c) == "blipFill")?;
+    let (blip_index, blip) = children(fill).find(|(_, c)| local_name(c) == "blip")?;
+    let (rel_attr, r_id) = rel_id(blip, &["embed", "link"])?;
+
+    let mut picture = Picture {
+        blip: [path, &[fill_index, blip_index]].concat(),
+        linked: rel_attr.ends_with(":link"),
+        rel_attr,
+        r_id,
+        svg_r_id: svg_blip(blip).and_then(|svg| rel_id(svg, &["embed", "link"]).map(|(_, id)| id)),
+        in_text_box,
+        ..Default::default()
+    };
+    let ext = child_path(pic, &["spPr", "xfrm", "ext"]);
+    if let Some(ext_path) = &ext {
+        let ext = element_at(pic, ext_path);
+        picture.width_emu = emu(ext, "cx");
+        picture.height_emu = emu(ext, "cy");
+        picture.xfrm_ext = Some([path, ext_path].concat());
+    }
+    if let Some((_, c_nv_pr)) = children(pic)
+        .find(|(_, c)| local_name(c) == "nvPicPr")
+        .and_then(|(_, nv)| children(nv).find(|(_, c)| local_name(c) == "cNvPr"))
+    {
+        set_alt_text(&mut picture, c_nv_pr);
+    }
+
+    // A picture that is the whole graphic (graphic/graphicData/pic) takes the
+    // frame's size and alt text; pictures in groups keep their own
+    if let Some(frame) = frame.filter(|f| path.len() == f.path.len() + 3) {
+        picture.floating = frame.floating;
+        if let Some((_, extent)) = children(frame.element).find(|(_, c)| local_name(c) == "extent")
+        {
+            picture.width_emu = emu(extent, "cx");
+            picture.height_emu = emu(extent, "cy");
+        }
+        if let Some((_, doc_pr)) = children(frame.element).find(|(_, c)| local_name(c) == "docPr") {
+            set_alt_text(&mut picture, doc_pr);
+        }
+        picture.frame = Some(frame.path.clone());
+    } else if let Some(frame) = frame {
+        picture.floating = frame.floating;
+    }
+    Some(picture)
+}
+
+/// A VML shape with a v:imagedata child
+fn vml_picture(
+    shape: &RawXmlElement,
+    path: &[usize],
+    index: usize,
+    data: &RawXmlElement,
+    in_text_box: bool,
+) -> Option<Picture> {
+    let (rel_attr, r_id) = rel_id(data, &["id", "relid", "pict"])?;
+    let style = attr(shape, "style").unwrap_or_default();
+    let floating = css_value(style, "position").is_some_and(|p| p == "absolute");
+    Some(Picture {
+        blip: [path, &[index]].concat(),
+        rel_attr,
+        frame: Some(path.to_vec()),
+        r_id,
+        vml: true,
+        floating,
+        in_text_box,
+        width_emu: css_value(style, "width")
+            .and_then(css_length_emu)
+            .unwrap_or(0),
+        height_emu: css_value(style, "height")
+            .and_then(css_length_emu)
+            .unwrap_or(0),
+        description: attr(shape, "alt").unwrap_or_default().to_string(),
+        title: attr(data, "o:title").unwrap_or_default().to_string(),
+        name: attr(shape, "id").unwrap_or_default().to_string(),
+        ..Default::default()
+    })
+}
+
+/// Point a picture at another relationship
+pub(crate) fn set_rel_id(root: &mut RawXmlElement, picture: &Picture, r_id: &str) {
+    let blip = element_at_mut(root, &picture.blip);
+    set_attr(blip, &picture.rel_attr, r_id);
+}
+
+/// Set or remove the SVG version of a DrawingML picture
+///
+/// The SVG goes in an `asvg:svgBlip` extension of the picture's `a:blip`,
+/// which then serves as the raster fallback for older readers.
+pub(crate) fn set_svg_rel_id(root: &mut RawXmlElement, picture: &Picture, r_id: Option<&str>) {
+    if picture.vml {
+        return;
+    }
+    let blip = element_at_mut(root, &picture.blip);
+    let list_index = children(blip)
+        .find(|(_, c)| local_name(c) == "extLst")
+        .map(|(i, _)| i);
+    match (r_id, list_index) {
+        (Some(r_id), Some(i)) => {
+            let RawXmlNode::Element(list) = &mut blip.children[i] else {
+                unreachable!("extLst is an element");
+            };
+            let svg = list.children.iter_mut().find_map(|c| match c {
+                RawXmlNode::Element(ext) if is_svg_ext(ext) => {
+                    ext.children.iter_mut().find_map(|c| match c {
+                        RawXmlNode::Element(e) if local_name(e) == "svgBlip" => Some(e),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            });
+            match svg {
+                Some(svg) => set_attr(svg, "r:embed", r_id),
+                None => list.children.push(RawXmlNode::Element(svg_extension(r_id))),
+            }
+        }
+        (Some(r_id), None) => blip.children.push(RawXmlNode::Element(
+            RawXmlElement::new("a:extLst").with_child(svg_extension(r_id)),
+        )),
+        (None, Some(i)) => {
+            let RawXmlNode::Element(list) = &mut blip.children[i] else {
+                unreachable!("extLst is an element");
+            };
+            list.children
+                .retain(|c| !matches!(c, RawXmlNode::Element(ext) if is_svg_ext(ext)));
+            if children(list).next().is_none() {
+                blip.children.remove(i);
+                blip.self_closing = blip.children.is_empty();
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+/// The `asvg:svgBlip` in a blip's extension list
+fn svg_blip(blip: &RawXmlElement) -> Option<&RawXmlElement> {
+    let (_, list) = children(blip).find(|(_, c)| local_name(c) == "extLst")?;
+    let (_, ext) = children(list).find(|(_, c)| is_svg_ext(c))?;
+    children(ext)
+        .find(|(_, c)| local_name(c) == "svgBlip")
+        .map(|(_, svg)| svg)
+}
+
+fn is_svg_ext(e: &RawXmlElement) -> bool {
+    local_name(e) == "ext" && attr(e, "uri") == Some(SVG_EXT_URI)
+}
+
+/// An `a:ext` holding an `asvg:svgBlip`
+pub(crate) fn svg_extension(r_id: &str) -> RawXmlElement {
+    let svg = empty("asvg:svgBlip")
+        .with_attr("xmlns:asvg", crate::xml::ASVG)
+        .with_attr("r:embed", r_id);
+    RawXmlElement::new("a:ext")
+        .with_attr("uri", SVG_EXT_URI)
+        .with_child(svg)
+}
+
+/// Resize a picture, keeping the frame and the picture's own extent in step
+pub(crate) fn set_size(root: &mut RawXmlElement, picture: &Picture, width: i64, height: i64) {
+    if picture.vml {
+        if let Some(frame) = &picture.frame {
+            let shape = element_at_mut(root, frame);
+            let style = attr(shape, "style").unwrap_or_default();
+            let style = set_css_value(style, "width", &format_pt(width));
+            let style = set_css_value(&style, "height", &format_pt(height));
+            set_attr(shape, "style", &style);
+        }
+        return;
+    }
+    if let Some(frame) = &picture.frame {
+        let frame = element_at_mut(root, frame);
+        if let Some(extent) = frame.children.iter_mut().find_map(|c| match c {
+            RawXmlNode::Element(e) if local_name(e) == "extent" => Some(e),
+            _ => None,
+        }) {
+            set_attr(extent, "cx", &width.to_string());
+            set_attr(extent, "cy", &height.to_string());
+        }
+    }
+    if let Some(ext) = &picture.xfrm_ext {
+        let ext = element_at_mut(root, ext);
+        set_attr(ext, "cx", &width.to_string());
+        set_attr(ext, "cy", &height.to_string());
+    }
+}
+
+/// IDs of the `wp:docPr` elements in drawing XML, in document order
+pub(crate) fn doc_pr_ids(e: &RawXmlElement, out: &mut Vec<u32>) {
+    for (_, child) in children(e) {
+        if local_name(child) == "docPr" {
+            out.extend(attr(child, "id").and_then(|v| v.parse::<u32>().ok()));
+        } else {
+            doc_pr_ids(child, out);
+        }
+    }
+}
+
+/// Set the ID of every `wp:docPr` element in drawing XML
+pub(crate) fn set_doc_pr_id(e: &mut RawXmlElement, id: u32) {
+    for child in &mut e.children {
+        if let RawXmlNode::Element(child) = child {
+            if local_name(child) == "docPr" {
+                set_attr(child, "id", &id.to_string());
+            } else {
+                set_doc_pr_id(child, id);
+            }
+        }
+    }
+}
+
+fn set_alt_text(picture: &mut Picture, e: &RawXmlElement) {
+    picture.description = attr(e, "descr").unwrap_or_default().to_string();
+    picture.title = attr(e, "title").unwrap_or_default().to_string();
+    picture.name = attr(e, "name").unwrap_or_default().to_string();
+}
+
+/// First relationship attribute present, by local name in order of preference
+fn rel_id(e: &RawXmlElement, locals: &[&str]) -> Option<(String, String)> {
+    locals.iter().find_map(|local| {
+        e.attributes
+            .iter()
+            .find(|(key, _)| key.split_once(':').is_some_and(|(_, l)| l == *local))
+            .map(|(key, value)| (key.clone(), value.clone()))
+    })
+}
+
+/// A self-closing element
+pub(crate) fn empty(name: &str) -> RawXmlElement {
+    let mut e = RawXmlElement::new(name);
+    e.self_closing = true;
+    e
+}
+
+pub(crate) fn local_name(e: &RawXmlElement) -> &str {
+    e.name.rsplit(':').next().unwrap_or(&e.name)
+}
+
+pub(crate) fn children(e: &RawXmlElement) -> impl Iterator<Item = (usize, &RawXmlElement)> {
+    e.children.iter().enumerate().filter_map(|(i, c)| match c {
+        RawXmlNode::Element(e) => Some((i, e)),
+        _ => None,
+    })
+}
+
+/// Path to a chain of descendants by local name
+fn child_path(e: &RawXmlElement, locals: &[&str]) -> Option<Vec<usize>> {
+    let mut path = Vec::new();
+    let mut current = e;
+    for local in locals {
+        let (i, child) = children(current).find(|(_, c)| local_name(c) == *local)?;
+        path.push(i);
+        current = child;
+    }
+    Some(path)
+}
+
+pub(crate) fn element_at<'a>(root: &'a RawXmlElement, path: &[usize]) -> &'a RawXmlElement {
+    path.iter().fold(root, |e, &i| match &e.children[i] {
+        RawXmlNode::Element(child) => child,
+        _ => unreachable!("picture paths only select elements"),
+    })
+}
+
+pub(crate) fn element_at_mut<'a>(
+    root: &'a mut RawXmlElement,
+    path: &[usize],
+) -> &'a mut RawXmlElement {
+    path.iter().fold(root, |e, &i| match &mut e.children[i] {
+        RawXmlNode::Element(child) => child,
+        _ => unreachable!("picture paths only select elements"),
+    })
+}
+
+pub(crate) fn attr<'a>(e: &'a RawXmlElement, key: &str) -> Option<&'a str> {
+    e.attributes
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+pub(crate) fn set_attr(e: &mut RawXmlElement, key: &str, value: &str) {
+    match e.attributes.iter_mut().find(|(k, _)| k == key) {
+        Some((_, v)) => *v = value.to_string(),
+        None => e.attributes.push((key.to_string(), value.to_string())),
+    }
+}
+
+fn emu(e: &RawXmlElement, key: &str) -> i64 {
+    attr(e, key).and_then(|v| v.parse().ok()).unwrap_or(0)
+}
+
+/// Value of a property in a CSS style string
+fn css_value<'a>(style: &'a str, name: &str) -> Option<&'a str> {
+    style.split(';').find_map(|decl| {
+        let (key, value) = decl.split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+/// Replace or append a property in a CSS style string
+fn set_css_value(style: &str, name: &str, value: &str) -> String {
+    let mut found = false;
+    let mut decls: Vec<String> = style
+        .split(';')
+        .filter(|d| !d.trim().is_empty())
+        .map(|decl| match decl.split_once(':') {
+            Some((key, _)) if key.trim() == name => {
+                found = true;
+                format!("{}:{}", name, value)
+            }
+            _ => decl.to_string(),
+        })
+        .collect();
+    if !found {
+        decls.push(format!("{}:{}", name, value));
+    }
+    decls.join(";")
+}
+
+/// A CSS length (pt, in, cm, mm, px or unitless EMU) in EMU
+fn css_length_emu(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().ok()?;
+    let pt = match unit {
+        "pt" => number,
+        "in" => number * 72.0,
+        "cm" => number * 72.0 / 2.54,
+        "mm" => number * 72.0 / 25.4,
+        "px" => number * 0.75,
+        "" => return Some(number as i64),
+        _ => return None,
+    };
+    Some((pt * EMU_PER_PT).round() as i64)
+}
+
+fn format_pt(emu: i64) -> String {
+    let pt = (emu as f64 / EMU_PER_PT * 100.0).round() / 100.0;
+    format!("{}pt", pt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    fn parse(xml: &str) -> RawXmlElement {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            if let Event::Start(e) = reader.read_event().unwrap() {
+                return RawXmlElement::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    const INLINE: &str = r#"<w:drawing><wp:inline><wp:extent cx="200" cy="100"/><wp:docPr id="1" name="Logo" descr="Company logo" title="Logo"/><a:graphic><a:graphicData><pic:pic><pic:nvPicPr><pic:cNvPr id="0" name="logo.png"/></pic:nvPicPr><pic:blipFill><a:blip r:embed="rId4"/></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="200" cy="100"/></a:xfrm></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing>"#;
+
+    #[test]
+    fn test_inline_picture() {
+        let mut root = parse(INLINE);
+        let found = pictures(&root);
+        assert_eq!(found.len(), 1);
+        let pic = &found[0];
+        assert_eq!(pic.r_id, "rId4");
+        assert_eq!((pic.width_emu, pic.height_emu), (200, 100));
+        assert_eq!(pic.description, "Company logo");
+        assert_eq!(pic.name, "Logo");
+        assert!(!pic.floating && !pic.vml && !pic.in_text_box);
+
+        set_rel_id(&mut root, pic, "rId9");
+        set_size(&mut root, pic, 400, 150);
+        let pic = &pictures(&root)[0];
+        assert_eq!(pic.r_id, "rId9");
+        assert_eq!((pic.width_emu, pic.height_emu), (400, 150));
+        let ext = element_at(&root, pic.xfrm_ext.as_ref().unwrap());
+        assert_eq!(attr(ext, "cx"), Some("400"));
+    }
+
+    #[test]
+    fn test_svg_blip() {
+        let mut root = parse(INLINE);
+        let pic = pictures(&root).remove(0);
+        assert_eq!(pic.svg_r_id, None);
+
+        set_svg_rel_id(&mut root, &pic, Some("rId5"));
+        set_svg_rel_id(&mut root, &pic, Some("rId6"));
+        let pic = pictures(&root).remove(0);
+        assert_eq!(pic.r_id, "rId4");
+        assert_eq!(pic.svg_r_id.as_deref(), Some("rId6"));
+        let blip = element_at(&root, &pic.blip);
+        assert_eq!(blip.children.len(), 1);
+
+        set_svg_rel_id(&mut root, &pic, None);
+        let pic = pictures(&root).remove(0);
+        assert_eq!(pic.svg_r_id, None);
+        assert!(element_at(&root, &pic.blip).children.is_empty());
+    }
+
+    #[test]
+    fn test_vml_picture_and_text_box() {
+        let xml = r#"<w:r><mc:AlternateContent><mc:Choice Requires="wps"><w:drawing><wp:anchor><wp:extent cx="9" cy="9"/><a:graphic><a:graphicData><wps:wsp><wps:txbx><w:txbxContent><w:p><w:r>"#.to_string()
+            + INLINE
+            + r#"</w:r></w:p></w:txbxContent></wps:txbx></wps:wsp></a:graphicData></a:graphic></wp:anchor></w:drawing></mc:Choice><mc:Fallback><w:pict><v:shape style="width:10pt;height:20pt"><v:imagedata r:id="rId4"/></v:shape></w:pict></mc:Fallback></mc:AlternateContent><w:pict><v:shape id="_x0000_i1025" alt="Chart" style="width:72pt;height:0.5in"><v:imagedata r:id="rId7" o:title="chart"/></v:shape></w:pict></w:r>"#;
+        let mut root = parse(&xml);
+        let found = pictures(&root);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].in_text_box && !found[0].floating);
+        assert_eq!(found[0].r_id, "rId4");
+
+        let vml = &found[1];
+        assert!(vml.vml);
+        assert_eq!(vml.r_id, "rId7");
+        assert_eq!((vml.width_emu, vml.height_emu), (914400, 457200));
+        assert_eq!(vml.description, "Chart");
+        assert_eq!(vml.title, "chart");
+
+        set_size(&mut root, vml, 1270000, 635000);
+        let shape = element_at(&root, vml.frame.as_ref().unwrap());
+        assert_eq!(attr(shape, "style"), Some("width:100pt;height:50pt"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Extended properties (app.xml) - application-specific metadata
+
+use crate::error::Result;
+use crate::xml::{schema, RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::io::Cursor;
+
+/// Extended properties from app.xml
+///
+/// Statistics such as `pages` and `words` are written by Word on save; they
+/// are not recomputed by this library.
+#[derive(Clone, Debug, Default)]
+pub struct ExtendedProperties {
+    pub template: Option<String>,
+    /// Total editing time in minutes
+    pub total_time: Option<u32>,
+    pub pages: Option<u32>,
+    pub words: Option<u32>,
+    pub characters: Option<u32>,
+    pub application: Option<String>,
+    pub lines: Option<u32>,
+    pub paragraphs: Option<u32>,
+    pub manager: Option<String>,
+    pub company: Option<String>,
+    pub characters_with_spaces: Option<u32>,
+    pub app_version: Option<String>,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+impl ExtendedProperties {
+    /// Parse from XML string
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut props = ExtendedProperties::default();
+        let mut buf = Vec::new();
+        let mut current_element: Option<String> = None;
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    let local = e.name().local_name();
+                    let local_str = String::from_utf8_lossy(local.as_ref()).to_string();
+
+                    match local_str.as_str() {
+                        "Properties" => {
+                            // Root element, continue
+                        }
+                        name if is_known(name) => {
+                            current_element = Some(local_str);
+                        }
+                        _ => {
+                            let raw = RawXmlElement::from_reader(&mut reader, &e)?;
+                            props.unknown_children.push(RawXmlNode::Element(raw));
+                        }
+                    }
+                }
+                Event::Empty(e) => {
+                    let local = e.name().local_name();
+                    if !is_known(&String::from_utf8_lossy(local.as_ref())) {
+                        let raw = RawXmlElement::from_empty(&e);
+                        props.unknown_children.push(RawXmlNode::Element(raw));
+                    }
+                }
+                Event::Text(t) => {
+                    if let Some(ref elem) = current_element {
+                        let text = t.unescape()?.to_string();
+                        props.set_field(elem, text);
+                    }
+                }
+                Event::End(e) => {
+                    let local = e.name().local_name();
+                    let local_str = String::from_utf8_lossy(local.as_ref()).to_string();
+                    if current_element.as_deref() == Some(&local_str) {
+                        current_element = None;
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(props)
+    }
+
+    /// Serialize to XML string
+    pub fn to_xml(&self) -> Result<String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut buffer);
+
+        writer.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+
+        let mut start = BytesStart::new("Properties");
+        start.push_attribute(("xmlns", crate::xml::EP));
+        start.push_attribute(("xmlns:vt", crate::xml::VT));
+        writer.write_event(Event::Start(start))?;
+
+        schema::write_in_order(
+            &mut writer,
+            schema::APP_PROPERTIES,
+            &self.unknown_children,
+            |writer, name| match self.field_text(name) {
+                Some(text) => write_text_element(writer, name, &text),
+                None => Ok(()),
+            },
+        )?;
+
+        writer.write_event(Event::End(BytesEnd::new("Properties")))?;
+
+        let xml_bytes = buffer.into_inner();
+        String::from_utf8(xml_bytes)
+            .map_err(|e| crate::error::Error::InvalidDocument(e.to_string()))
+    }
+
+    /// Set a modeled field from element text
+    fn set_field(&mut self, name: &str, text: String) {
+        let count = || text.trim().parse().ok();
+        match name {
+            "Template" => self.template = Some(text),
+            "TotalTime" => self.total_time = count(),
+            "Pages" => self.pages = count(),
+            "Words" => self.words = count(),
+            "Characters" => self.characters = count(),
+            "Application" => self.application = Some(text),
+            "Lines" => self.lines = count(),
+            "Paragraphs" => self.paragraphs = count(),
+            "Manager" => self.manager = Some(text),
+            "Company" => self.company = Some(text),
+            "CharactersWithSpaces" => self.characters_with_spaces = count(),
+            "AppVersion" => self.app_version = Some(text),
+            _ => {}
+        }
+    }
+
+    /// Text of a modeled field, if set
+    fn field_text(&self, name: &str) -> Option<String> {
+        match name {
+            "Template" => self.template.clone(),
+            "TotalTime" => self.total_time.map(|v| v.to_string()),
+            "Pages" => self.pages.map(|v| v.to_string()),
+            "Words" => self.words.map(|v| v.to_string()),
+            "Characters" => self.characters.map(|v| v.to_string()),
+            "Application" => self.application.clone(),
+            "Lines" => self.lines.map(|v| v.to_string()),
+            "Paragraphs" => self.paragraphs.map(|v| v.to_string()),
+            "Manager" => self.manager.clone(),
+            "Company" => self.company.clone(),
+            "CharactersWithSpaces" => self.characters_with_spaces.map(|v| v.to_string()),
+            "AppVersion" => self.app_version.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `name` is one of the modeled elements
+fn is_known(name: &str) -> bool {
+    matches!(
+        name,
+        "Template"
+            | "TotalTime"
+            | "Pages"
+            | "Words"
+            | "Characters"
+            | "Application"
+            | "Lines"
+            | "Paragraphs"
+            | "Manager"
+            | "Company"
+            | "CharactersWithSpaces"
+            | "AppVersion"
+    )
+}
+
+fn write_text_element<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    value: &str,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(value)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties" xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">
+  <Template>Normal.dotm</Template>
+  <TotalTime>12</TotalTime>
+  <Pages>3</Pages>
+  <Words>512</Words>
+  <Application>Microsoft Office Word</Application>
+  <DocSecurity>0</DocSecurity>
+  <TitlesOfParts><vt:vector size="1" baseType="lpstr"><vt:lpstr>Report</vt:lpstr></vt:vector></TitlesOfParts>
+  <Company>Acme &amp; Co</Company>
+  <AppVersion>16.0000</AppVersion>
+</Properties>"#;
+
+    #[test]
+    fn test_parse_extended_properties() {
+        let props = ExtendedProperties::from_xml(APP_XML).unwrap();
+        assert_eq!(props.template.as_deref(), Some("Normal.dotm"));
+        assert_eq!(props.total_time, Some(12));
+        assert_eq!(props.pages, Some(3));
+        assert_eq!(props.words, Some(512));
+        assert_eq!(props.company.as_deref(), Some("Acme & Co"));
+        assert_eq!(props.unknown_children.len(), 2);
+        assert!(props.manager.is_none());
+    }
+
+    #[test]
+    fn test_extended_properties_roundtrip() {
+        let mut props = ExtendedProperties::from_xml(APP_XML).unwrap();
+        props.manager = Some("Jane".into());
+
+        let xml = props.to_xml().unwrap();
+        let titles = xml.find("<TitlesOfParts>").unwrap();
+        assert!(xml.find("<DocSecurity>").unwrap() < titles);
+        assert!(titles < xml.find("<Manager>").unwrap());
+        assert!(xml.contains("<vt:lpstr>Report</vt:lpstr>"));
+
+        let props2 = ExtendedProperties::from_xml(&xml).unwrap();
+        assert_eq!(props2.manager.as_deref(), Some("Jane"));
+        assert_eq!(props2.app_version.as_deref(), Some("16.0000"));
+        assert_eq!(props2.unknown_children.len(), 2);
+    }
+}
@@ -0,0 +1,929 @@
+//! Fields (`w:fldSimple` and complex `w:fldChar`/`w:instrText` fields)
+//!
+//! A complex field spans several runs: a `begin` field character, the field
+//! code in `w:instrText`, a `separate` character, the cached result runs and
+//! an `end` character. Fields nest inside the code or result of other fields
+//! and may continue over several paragraphs (a table of contents does).
+//! [`Field`] is a read-only view that groups these runs back together.
+
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, Run, RunContent, SdtContent,
+};
+use crate::error::Result;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::fmt;
+use std::io::BufRead;
+
+/// Type of a field character (`w:fldCharType`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldCharType {
+    /// Start of the field code
+    Begin,
+    /// End of the field code, start of the cached result
+    Separate,
+    /// End of the field
+    End,
+}
+
+impl FieldCharType {
+    /// Parse from the `w:fldCharType` attribute value
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "begin" => Some(Self::Begin),
+            "separate" => Some(Self::Separate),
+            "end" => Some(Self::End),
+            _ => None,
+        }
+    }
+
+    /// Value of the `w:fldCharType` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Begin => "begin",
+            Self::Separate => "separate",
+            Self::End => "end",
+        }
+    }
+}
+
+/// Field character (`w:fldChar`)
+#[derive(Clone, Debug)]
+pub struct FieldChar {
+    pub kind: FieldCharType,
+    /// Result is stale and should be recomputed (`w:dirty`)
+    pub dirty: Option<bool>,
+    /// Result must not be recomputed (`w:fldLock`)
+    pub locked: Option<bool>,
+    /// Unknown attributes (preserved for round-trip)
+    pub unknown_attrs: Vec<(String, String)>,
+    /// Children such as form field data (`w:ffData`), preserved
+    pub children: Vec<RawXmlNode>,
+}
+
+impl FieldChar {
+    /// Create a field character
+    pub fn new(kind: FieldCharType) -> Self {
+        Self {
+            kind,
+            dirty: None,
+            locked: None,
+            unknown_attrs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Read from the attributes of a `w:fldChar` element
+    pub fn from_element(e: &BytesStart) -> Result<Self> {
+        let mut fld_char = Self::new(FieldCharType::Begin);
+        for attr in e.attributes() {
+            let attr = attr?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = attr.unescape_value()?.to_string();
+            match key.as_str() {
+                "w:fldCharType" => {
+                    fld_char.kind = FieldCharType::parse(&value).unwrap_or(FieldCharType::Begin)
+                }
+                "w:dirty" => fld_char.dirty = Some(is_on(&value)),
+                "w:fldLock" => fld_char.locked = Some(is_on(&value)),
+                _ => fld_char.unknown_attrs.push((key, value)),
+            }
+        }
+        Ok(fld_char)
+    }
+
+    /// Parse from reader (after a `w:fldChar` start tag)
+    pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
+        let mut fld_char = Self::from_element(start)?;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    let raw = RawXmlElement::from_reader(reader, &e)?;
+                    fld_char.children.push(RawXmlNode::Element(raw));
+                }
+                Event::Empty(e) => {
+                    let raw = RawXmlElement::from_empty(&e);
+                    fld_char.children.push(RawXmlNode::Element(raw));
+                }
+                Event::End(e) if e.name().local_name().as_ref() == b"fldChar" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(fld_char)
+    }
+
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:fldChar");
+        elem.push_attribute(("w:fldCharType", self.kind.as_str()));
+        if let Some(locked) = self.locked {
+            elem.push_attribute(("w:fldLock", if locked { "1" } else { "0" }));
+        }
+        if let Some(dirty) = self.dirty {
+            elem.push_attribute(("w:dirty", if dirty { "1" } else { "0" }));
+        }
+        for (key, value) in &self.unknown_attrs {
+            elem.push_attribute((key.as_str(), value.as_str()));
+        }
+        if self.children.is_empty() {
+            writer.write_event(Event::Empty(elem))?;
+        } else {
+            writer.write_event(Event::Start(elem))?;
+            for child in &self.children {
+                child.write_to(writer)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("w:fldChar")))?;
+        }
+        Ok(())
+    }
+}
+
+/// Simple field (`w:fldSimple`): the code is an attribute, the content is the result
+#[derive(Clone, Debug, Default)]
+pub struct SimpleField {
+    /// Field code (`w:instr`)
+    pub instruction: String,
+    /// Result must not be recomputed (`w:fldLock`)
+    pub locked: Option<bool>,
+    /// Result is stale and should be recomputed (`w:dirty`)
+    pub dirty: Option<bool>,
+    /// Cached result
+    pub content: Vec<ParagraphContent>,
+    /// Unknown attributes (preserved for round-trip)
+    pub unknown_attrs: Vec<(String, String)>,
+}
+
+impl SimpleField {
+    /// Create a simple field with a cached result
+    pub fn new(instruction: impl Into<String>, result: impl Into<String>) -> Self {
+        Self {
+            instruction: instruction.into(),
+            content: vec![ParagraphContent::Run(Run::new(result))],
+            ..Default::default()
+        }
+    }
+
+    /// Read from the attributes of a `w:fldSimple` element
+    pub fn from_element(e: &BytesStart) -> Result<Self> {
+        let mut field = Self::default();
+        for attr in e.attributes() {
+            let attr = attr?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = attr.unescape_value()?.to_string();
+            match key.as_str() {
+                "w:instr" => field.instruction = value,
+                "w:fldLock" => field.locked = Some(is_on(&value)),
+                "w:dirty" => field.dirty = Some(is_on(&value)),
+                _ => field.unknown_attrs.push((key, value)),
+            }
+        }
+        Ok(field)
+    }
+
+    /// Parse from reader (after a `w:fldSimple` start tag)
+    pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
+        let mut field = Self::from_element(start)?;
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => field
+                    .content
+                    .push(ParagraphContent::from_start(reader, &e)?),
+                Event::Empty(e) => field.content.push(ParagraphContent::from_empty(&e)?),
+                Event::End(e) if e.name().local_name().as_ref() == b"fldSimple" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(field)
+    }
+
+    /// Cached result text
+    pub fn text(&self) -> String {
+        crate::document::paragraph::content_text(&self.content)
+    }
+
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:fldSimple");
+        elem.push_attribute(("w:instr", self.instruction.as_str()));
+        if let Some(locked) = self.locked {
+            elem.push_attribute(("w:fldLock", if locked { "1" } else { "0" }));
+        }
+        if let Some(dirty) = self.dirty {
+            elem.push_attribute(("w:dirty", if dirty { "1" } else { "0" }));
+        }
+        for (key, value) in &self.unknown_attrs {
+            elem.push_attribute((key.as_str(), value.as_str()));
+        }
+        if self.content.is_empty() {
+            writer.write_event(Event::Empty(elem))?;
+        } else {
+            writer.write_event(Event::Start(elem))?;
+            for content in &self.content {
+                content.write_to(writer)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("w:fldSimple")))?;
+        }
+        Ok(())
+    }
+}
+
+/// Switch of a field instruction, e.g. `\* MERGEFORMAT` or `\h`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSwitch {
+    /// Switch name without the backslash (`*`, `@`, `#`, `h`, ...)
+    pub name: String,
+    /// Switch argument, if any
+    pub value: Option<String>,
+}
+
+/// Parsed field instruction: type, positional arguments and switches
+///
+/// `DATE \@ "yyyy-MM-dd"` has type `DATE`, no arguments and an `@` switch
+/// with the value `yyyy-MM-dd`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldInstruction {
+    /// Field type in upper case (`PAGE`, `MERGEFIELD`, `=` for formulas)
+    pub field_type: String,
+    /// Positional arguments, unquoted
+    pub arguments: Vec<String>,
+    /// Switches in order of appearance
+    pub switches: Vec<FieldSwitch>,
+}
+
+impl FieldInstruction {
+    /// Create an instruction of the given field type
+    pub fn new(field_type: impl Into<String>) -> Self {
+        Self {
+            field_type: field_type.into().to_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    /// Parse a field code
+    ///
+    /// A token that follows a switch and is not itself a switch is taken as
+    /// the switch's argument.
+    pub fn parse(code: &str) -> Self {
+        let mut instruction = Self::default();
+        let mut tokens = tokenize(code).into_iter().peekable();
+
+        if let Some((first, _)) = tokens.next() {
+            instruction.field_type = first.to_uppercase();
+        }
+        while let Some((token, quoted)) = tokens.next() {
+            match token.strip_prefix('\\') {
+                Some(name) if !quoted => {
+                    let value = match tokens.peek() {
+                        Some((next, next_quoted)) if *next_quoted || !next.starts_with('\\') => {
+                            tokens.next().map(|(value, _)| value)
+                        }
+                        _ => None,
+                    };
+                    instruction.switches.push(FieldSwitch {
+                        name: name.to_string(),
+                        value,
+                    });
+                }
+                _ => instruction.arguments.push(token),
+            }
+        }
+        instruction
+    }
+
+    /// Add a positional argument
+    pub fn with_argument(mut self, argument: impl Into<String>) -> Self {
+        self.arguments.push(argument.into());
+        self
+    }
+
+    /// Add a switch without an argument, e.g. `h` for `\h`
+    pub fn with_switch(mut self, name: impl Into<String>) -> Self {
+        self.switches.push(FieldSwitch {
+            name: name.into(),
+            value: None,
+        });
+        self
+    }
+
+    /// Add a switch with an argument, e.g. `@` and `yyyy-MM-dd` for `\@ "yyyy-MM-dd"`
+    pub fn with_switch_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.switches.push(FieldSwitch {
+            name: name.into(),
+            value: Some(value.into()),
+        });
+        self
+    }
+
+    /// First switch with the given name
+    pub fn switch(&self, name: &str) -> Option<&FieldSwitch> {
+        self.switches.iter().find(|s| s.name == name)
+    }
+
+    /// Whether the switch is present
+    pub fn has_switch(&self, name: &str) -> bool {
+        self.switch(name).is_some()
+    }
+
+    /// Argument of the first switch with the given name
+    pub fn switch_value(&self, name: &str) -> Option<&str> {
+        self.switch(name)?.value.as_deref()
+    }
+
+    /// Date-time format picture (`\@`)
+    pub fn date_format(&self) -> Option<&str> {
+        self.switch_value("@")
+    }
+
+    /// Numeric format picture (`\#`)
+    pub fn numeric_format(&self) -> Option<&str> {
+        self.switch_value("#")
+    }
+
+    /// General formatting switches (`\*`), e.g. `MERGEFORMAT`, `Upper`
+    pub fn general_formats(&self) -> impl Iterator<Item = &str> {
+        self.switches
+            .iter()
+            .filter(|s| s.name == "*")
+            .filter_map(|s| s.value.as_deref())
+    }
+}
+
+impl fmt::Display for FieldInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.field_type)?;
+        for argument in &self.arguments {
+            write!(f, " {}", quote(argument))?;
+        }
+        for switch in &self.switches {
+            write!(f, " \\{}", switch.name)?;
+            if let Some(ref value) = switch.value {
+                write!(f, " {}", quote(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a field code into tokens, reporting whether each was quoted
+fn tokenize(code: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut chars = code.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' if matches!(chars.peek(), Some('"') | Some('\\')) => {
+                        token.extend(chars.next());
+                    }
+                    _ => token.push(c),
+                }
+            }
+            tokens.push((token, true));
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || (c == '"' && !token.is_empty()) {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push((token, false));
+        }
+    }
+    tokens
+}
+
+/// Quote a token if it would not survive tokenizing on its own
+fn quote(token: &str) -> String {
+    let plain = !token.is_empty()
+        && !token.starts_with('\\')
+        && !token.chars().any(|c| c.is_whitespace() || c == '"');
+    if plain {
+        token.to_string()
+    } else {
+        format!("\"{}\"", token.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn is_on(value: &str) -> bool {
+    matches!(value, "1" | "true" | "on")
+}
+
+/// A field in the document, with its code and cached result
+#[derive(Clone, Debug)]
+pub struct Field {
+    /// Field code, with nested fields replaced by their results
+    pub code: String,
+    /// Parsed field code
+    pub instruction: FieldInstruction,
+    /// Cached result text, as last computed
+    pub result: String,
+    /// Whether this is a simple field (`w:fldSimple`)
+    pub is_simple: bool,
+    /// Whether the result is marked stale
+    pub dirty: bool,
+    /// Whether the result is locked against updates
+    pub locked: bool,
+    /// Fields nested in the code or result of this field
+    pub nested: Vec<Field>,
+}
+
+impl Field {
+    /// Field type in upper case, e.g. `PAGE`
+    pub fn field_type(&self) -> &str {
+        &self.instruction.field_type
+    }
+
+    /// Runs of a new complex field with a cached result
+    pub fn complex_runs(instruction: impl Into<String>, result: impl Into<String>) -> Vec<Run> {
+        let instruction = instruction.into();
+        let code = if instruction.starts_with(' ') {
+            instruction
+        } else {
+            format!(" {} ", instruction)
+        };
+        let field_char = |kind| Run {
+            content: vec![RunContent::FieldChar(FieldChar::new(kind))],
+            ..Default::default()
+        };
+        vec![
+            field_char(FieldCharType::Begin),
+            Run {
+                content: vec![RunContent::InstrText(code)],
+                ..Default::default()
+            },
+            field_char(FieldCharType::Separate),
+            Run::new(result),
+            field_char(FieldCharType::End),
+        ]
+    }
+}
+
+/// Whether paragraph text shows field results or field codes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldDisplay {
+    /// Cached results, as Word shows by default
+    #[default]
+    Results,
+    /// Field codes in braces, as Word shows after Alt+F9
+    Codes,
+}
+
+/// Builds paragraph text, showing either field results or field codes
+pub(crate) struct FieldText {
+    display: FieldDisplay,
+    /// Open complex fields; true once past the separator
+    open: Vec<bool>,
+    pub text: String,
+}
+
+impl FieldText {
+    pub fn new(display: FieldDisplay) -> Self {
+        Self {
+            display,
+            open: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    fn visible(&self) -> bool {
+        match self.display {
+            FieldDisplay::Results => self.open.iter().all(|in_result| *in_result),
+            FieldDisplay::Codes => !self.open.iter().any(|in_result| *in_result),
+        }
+    }
+
+    pub fn content(&mut self, content: &[ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::Run(run) => self.run(run),
+                ParagraphContent::Hyperlink(link) => self.content(&link.content),
+                ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                    self.content(&rev.content)
+                }
+                ParagraphContent::SimpleField(field) => match self.display {
+                    FieldDisplay::Results => self.content(&field.content),
+                    FieldDisplay::Codes if self.visible() => {
+                        self.text.push('{');
+                        self.text.push_str(&field.instruction);
+                        self.text.push('}');
+                    }
+                    FieldDisplay::Codes => {}
+                },
+                ParagraphContent::Sdt(sdt) => {
+                    if let SdtContent::Inline(inner) = &sdt.content {
+                        self.content(inner)
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn run(&mut self, run: &Run) {
+        let codes = self.display == FieldDisplay::Codes;
+        for item in &run.content {
+            match item {
+                RunContent::FieldChar(fld_char) => match fld_char.kind {
+                    FieldCharType::Begin => {
+                        if codes && self.visible() {
+                            self.text.push('{');
+                        }
+                        self.open.push(false);
+                    }
+                    FieldCharType::Separate => {
+                        if let Some(in_result) = self.open.last_mut() {
+                            *in_result = true;
+                        }
+                    }
+                    FieldCharType::End => {
+                        if self.open.pop().is_some() && codes && self.visible() {
+                            self.text.push('}');
+                        }
+                    }
+                },
+                RunContent::InstrText(code) if codes && self.visible() => self.text.push_str(code),
+                RunContent::TextBox(text_box) if self.visible() => {
+                    self.text.push_str(&text_box.text())
+                }
+                other if self.visible() => self.text.extend(other.plain_text()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A complex field whose end has not been seen yet
+struct OpenField {
+    code: String,
+    result: String,
+    in_result: bool,
+    dirty: bool,
+    locked: bool,
+    nested: Vec<Field>,
+}
+
+/// Groups field characters, codes and results into [`Field`]s
+#[derive(Default)]
+pub(crate) struct FieldCollector {
+    open: Vec<OpenField>,
+    pub fields: Vec<Field>,
+}
+
+impl FieldCollector {
+    pub fn blocks(&mut self, blocks: &[BlockContent]) {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.paragraph(p),
+                BlockContent::Table(t) => {
+                    for cell in t.rows.iter().flat_map(|r| r.cells.iter()) {
+                        self.paragraphs(&cell.paragraphs);
+                    }
+                }
+                BlockContent::Sdt(sdt) => {
+                    if let SdtContent::Blocks(inner) = &sdt.content {
+                        self.blocks(inner)
+                    }
+                }
+                BlockContent::Unknown(_) => {}
+            }
+        }
+    }
+
+    pub fn paragraphs(&mut self, paras: &[Paragraph]) {
+        for para in paras {
+            self.paragraph(para);
+        }
+    }
+
+    /// Collect the fields of a paragraph; a field left open continues in the next one
+    pub fn paragraph(&mut self, para: &Paragraph) {
+        self.content(&para.content);
+        self.push_text("\n");
+    }
+
+    /// Close fields left open at the end of a story
+    pub fn finish(mut self) -> Vec<Field> {
+        while !self.open.is_empty() {
+            self.close();
+        }
+        self.fields
+    }
+
+    fn content(&mut self, content: &[ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::Run(run) => self.run(run),
+                ParagraphContent::Hyperlink(link) => self.content(&link.content),
+                ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                    self.content(&rev.content)
+                }
+                ParagraphContent::SimpleField(simple) => {
+                    let mut inner = FieldCollector::default();
+                    inner.content(&simple.content);
+                    let field = Field {
+                        code: simple.instruction.clone(),
+                        instruction: FieldInstruction::parse(&simple.instruction),
+                        result: simple.text(),
+                        is_simple: true,
+                        dirty: simple.dirty.unwrap_or(false),
+                        locked: simple.locked.unwrap_or(false),
+                        nested: inner.finish(),
+                    };
+                    self.push_field(field);
+                }
+                ParagraphContent::Sdt(sdt) => {
+                    if let SdtContent::Inline(inner) = &sdt.content {
+                        self.content(inner)
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn run(&mut self, run: &Run) {
+        for item in &run.content {
+            match item {
+                RunContent::FieldChar(fld_char) => match fld_char.kind {
+                    FieldCharType::Begin => self.open.push(OpenField {
+                        code: String::new(),
+                        result: String::new(),
+                        in_result: false,
+                        dirty: fld_char.dirty.unwrap_or(false),
+                        locked: fld_char.locked.unwrap_or(false),
+                        nested: Vec::new(),
+                    }),
+                    FieldCharType::Separate => {
+                        if let Some(field) = self.open.last_mut() {
+                            field.in_result = true;
+                        }
+                    }
+                    FieldCharType::End => self.close(),
+                },
+                RunContent::InstrText(code) => {
+                    if let Some(field) = self.open.last_mut().filter(|f| !f.in_result) {
+                        field.code.push_str(code);
+                    }
+                }
+                other => {
+                    if let Some(text) = other.plain_text() {
+                        self.push_text(text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(field) = self.open.last_mut() {
+            if field.in_result {
+                field.result.push_str(text);
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(open) = self.open.pop() {
+            let field = Field {
+                instruction: FieldInstruction::parse(&open.code),
+                code: open.code,
+                result: open.result,
+                is_simple: false,
+                dirty: open.dirty,
+                locked: open.locked,
+                nested: open.nested,
+            };
+            self.push_field(field);
+        }
+    }
+
+    /// Add a finished field to the enclosing field, or to the top level
+    fn push_field(&mut self, field: Field) {
+        match self.open.last_mut() {
+            Some(parent) => {
+                if parent.in_result {
+                    parent.result.push_str(&field.result);
+                } else {
+                    parent.code.push_str(&field.result);
+                }
+                parent.nested.push(field);
+            }
+            None => self.fields.push(field),
+        }
+    }
+}
+
+impl Paragraph {
+    /// Fields in this paragraph (nested fields are in [`Field::nested`])
+    pub fn fields(&self) -> Vec<Field> {
+        let mut collector = FieldCollector::default();
+        collector.content(&self.content);
+        collector.finish()
+    }
+
+    /// Append a complex field with a cached result, e.g. `add_field("PAGE", "1")`
+    pub fn add_field(&mut self, instruction: impl Into<String>, result: impl Into<String>) {
+        for run in Field::complex_runs(instruction, result) {
+            self.add_run(run);
+        }
+    }
+
+    /// Append a simple field (`w:fldSimple`) with a cached result
+    pub fn add_simple_field(&mut self, instruction: impl Into<String>, result: impl Into<String>) {
+        let field = SimpleField::new(instruction, result);
+        self.content.push(ParagraphContent::SimpleField(field));
+    }
+}
+
+impl Document {
+    /// Fields in the body, headers, footers and notes
+    pub fn fields(&self) -> Vec<Field> {
+        let mut collector = FieldCollector::default();
+        collector.blocks(&self.body.content);
+        let mut fields = collector.finish();
+
+        let (headers, footers) = self.headers_footers.get(&self.package);
+        let notes = [self.footnotes(), self.endnotes()];
+        let stories = headers
+            .iter()
+            .chain(footers)
+            .map(|(_, hf)| &hf.paragraphs)
+            .chain(
+                notes
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|n| n.notes.iter().map(|note| &note.paragraphs)),
+            );
+        for paragraphs in stories {
+            let mut collector = FieldCollector::default();
+            collector.paragraphs(paragraphs);
+            fields.extend(collector.finish());
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_paragraph(xml: &str) -> Paragraph {
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        loop {
+            if let Event::Start(e) = reader.read_event_into(&mut buf).unwrap() {
+                return Paragraph::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    fn write_paragraph(para: &Paragraph) -> String {
+        let mut writer = Writer::new(Vec::new());
+        para.write_to(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    const NESTED: &str = concat!(
+        r#"<w:p><w:r><w:t xml:space="preserve">Dear </w:t></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+        r#"<w:r><w:instrText xml:space="preserve"> IF </w:instrText></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+        r#"<w:r><w:instrText xml:space="preserve"> MERGEFIELD Title </w:instrText></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r>"#,
+        r#"<w:r><w:t>Dr</w:t></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+        r#"<w:r><w:instrText xml:space="preserve"> = "Dr" "Doctor" "Sir" </w:instrText></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r>"#,
+        r#"<w:r><w:t>Doctor</w:t></w:r>"#,
+        r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+        r#"<w:fldSimple w:instr=" PAGE \* MERGEFORMAT "><w:r><w:t>3</w:t></w:r></w:fldSimple>"#,
+        r#"</w:p>"#,
+    );
+
+    #[test]
+    fn test_parse_instruction() {
+        let instr = FieldInstruction::parse(r#" DATE \@ "yyyy-MM-dd" \* MERGEFORMAT "#);
+        assert_eq!(instr.field_type, "DATE");
+        assert!(instr.arguments.is_empty());
+        assert_eq!(instr.date_format(), Some("yyyy-MM-dd"));
+        assert_eq!(instr.general_formats().collect::<Vec<_>>(), ["MERGEFORMAT"]);
+
+        let instr = FieldInstruction::parse(r#"TOC \o "1-3" \h \z \u"#);
+        assert_eq!(instr.switch_value("o"), Some("1-3"));
+        assert!(instr.has_switch("h") && instr.has_switch("z") && instr.has_switch("u"));
+        assert_eq!(instr.switch_value("h"), None);
+
+        let instr = FieldInstruction::parse(r#"hyperlink "C:\\docs\\a b.docx" \l "Part_2""#);
+        assert_eq!(instr.field_type, "HYPERLINK");
+        assert_eq!(instr.arguments, [r"C:\docs\a b.docx"]);
+        assert_eq!(instr.switch_value("l"), Some("Part_2"));
+    }
+
+    #[test]
+    fn test_instruction_display() {
+        let instr = FieldInstruction::new("mergefield")
+            .with_argument("First Name")
+            .with_switch_value("*", "Upper");
+        assert_eq!(instr.to_string(), r#"MERGEFIELD "First Name" \* Upper"#);
+        assert_eq!(FieldInstruction::parse(&instr.to_string()), instr);
+    }
+
+    #[test]
+    fn test_nested_fields() {
+        let para = parse_paragraph(NESTED);
+        let fields = para.fields();
+        assert_eq!(fields.len(), 2);
+
+        let if_field = &fields[0];
+        assert_eq!(if_field.field_type(), "IF");
+        assert_eq!(if_field.result, "Doctor");
+        assert_eq!(if_field.nested.len(), 1);
+        assert_eq!(if_field.nested[0].field_type(), "MERGEFIELD");
+        assert_eq!(if_field.nested[0].instruction.arguments, ["Title"]);
+        // The nested result stands in for the nested field in the code
+        assert_eq!(if_field.instruction.arguments[0], "Dr");
+
+        assert!(fields[1].is_simple);
+        assert_eq!(fields[1].field_type(), "PAGE");
+        assert_eq!(fields[1].result, "3");
+    }
+
+    #[test]
+    fn test_text_with_field_codes() {
+        let para = parse_paragraph(NESTED);
+        assert_eq!(para.text(), "Dear Doctor3");
+        assert_eq!(
+            para.text_with(FieldDisplay::Codes),
+            r#"Dear { IF { MERGEFIELD Title } = "Dr" "Doctor" "Sir" }{ PAGE \* MERGEFORMAT }"#
+        );
+    }
+
+    #[test]
+    fn test_fields_roundtrip() {
+        let para = parse_paragraph(NESTED);
+        let xml = write_paragraph(&para);
+        assert!(xml.contains(r#"<w:fldChar w:fldCharType="separate"/>"#));
+        assert!(
+            xml.contains(r#"<w:instrText xml:space="preserve"> MERGEFIELD Title </w:instrText>"#)
+        );
+        assert!(xml.contains(r#"<w:fldSimple w:instr=" PAGE \* MERGEFORMAT ">"#));
+        assert_eq!(parse_paragraph(&xml).text(), para.text());
+    }
+
+    #[test]
+    fn test_form_field_data_preserved() {
+        let xml = concat!(
+            r#"<w:p><w:r><w:fldChar w:fldCharType="begin" w:dirty="1"><w:ffData><w:name w:val="Check1"/></w:ffData></w:fldChar></w:r>"#,
+            r#"<w:r><w:instrText> FORMCHECKBOX </w:instrText></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+        );
+        let para = parse_paragraph(xml);
+        let fields = para.fields();
+        assert_eq!(fields[0].field_type(), "FORMCHECKBOX");
+        assert!(fields[0].dirty);
+        assert!(write_paragraph(&para).contains(r#"<w:ffData><w:name w:val="Check1"/></w:ffData>"#));
+    }
+
+    #[test]
+    fn test_field_char_escaped_attribute() {
+        let xml = r#"<w:p><w:r><w:fldChar w:fldCharType="begin" w14:note="R&amp;D"/></w:r></w:p>"#;
+        let mut para = parse_paragraph(xml);
+        for _ in 0..3 {
+            let written = write_paragraph(&para);
+            assert!(written.contains(r#"w14:note="R&amp;D""#));
+            para = parse_paragraph(&written);
+        }
+    }
+
+    #[test]
+    fn test_add_fields() {
+        let mut para = Paragraph::new("Page ");
+        para.add_field("PAGE", "1");
+        para.add_simple_field(" NUMPAGES ", "4");
+
+        assert_eq!(para.text(), "Page 14");
+        assert_eq!(
+            para.text_with(FieldDisplay::Codes),
+            "Page { PAGE }{ NUMPAGES }"
+        );
+        let types: Vec<_> = para
+            .fields()
+            .iter()
+            .map(|f| f.field_type().to_string())
+            .collect();
+        assert_eq!(types, ["PAGE", "NUMPAGES"]);
+    }
+}
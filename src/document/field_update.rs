@@ -0,0 +1,1004 @@
+//! Offline field update
+//!
+//! Recomputes the cached results of fields that do not depend on page
+//! layout: dates, document properties, mail merge fields, bookmark
+//! references, sequence numbers and IF comparisons. Layout-dependent fields
+//! such as PAGE and NUMPAGES are marked dirty so Word refreshes them when the
+//! document is opened.
+
+use crate::document::comments::{body_paragraphs, body_paragraphs_mut};
+use crate::document::field::{Field, FieldCharType, FieldInstruction, SimpleField};
+use crate::document::paragraph::content_text;
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, PropertyValue, Run, RunContent, SdtContent,
+};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Fields whose result depends on pagination; only Word can compute them
+const LAYOUT_FIELDS: &[&str] = &[
+    "PAGE",
+    "NUMPAGES",
+    "SECTIONPAGES",
+    "SECTION",
+    "PAGEREF",
+    "TOC",
+    "TOA",
+    "INDEX",
+];
+
+/// Data for [`Document::update_fields`]
+#[derive(Clone, Debug, Default)]
+pub struct FieldContext {
+    /// Current date and time for DATE and TIME (ISO 8601, e.g.
+    /// `2024-05-01T09:30:00Z`); the system clock in UTC if `None`
+    pub now: Option<String>,
+    /// Mail merge values for MERGEFIELD, keyed by field name
+    pub merge_data: HashMap<String, String>,
+}
+
+impl FieldContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the date and time used by DATE and TIME
+    pub fn with_now(mut self, now: impl Into<String>) -> Self {
+        self.now = Some(now.into());
+        self
+    }
+
+    /// Add a mail merge value
+    pub fn with_merge_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.merge_data.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl Document {
+    /// Recompute the cached results of fields that don't need page layout
+    ///
+    /// Covers DATE, TIME, CREATEDATE, SAVEDATE, DOCPROPERTY and the core
+    /// property fields (AUTHOR, TITLE, ...), MERGEFIELD, REF, SEQ and IF.
+    /// PAGE, NUMPAGES, PAGEREF, TOC and other layout-dependent fields are
+    /// marked dirty instead. Locked fields are left alone.
+    ///
+    /// Returns the number of fields whose result was recomputed.
+    pub fn update_fields(&mut self, context: &FieldContext) -> usize {
+        let mut evaluator = FieldEvaluator::new(self, context);
+        let mut count = 0;
+
+        // Body content
+        let mut changed = false;
+        for para in body_paragraphs_mut(&mut self.body.content) {
+            changed |= update_paragraph(para, &mut evaluator, &mut count);
+        }
+        if changed {
+            self.mark_body_modified();
+        }
+
+        // Headers and footers
+        let mut touched = Vec::new();
+        let (headers, footers) = self.headers_footers.get_mut(&self.package);
+        for (r_id, hf) in headers.iter_mut().chain(footers) {
+            let mut changed = false;
+            for para in &mut hf.paragraphs {
+                changed |= update_paragraph(para, &mut evaluator, &mut count);
+            }
+            if changed {
+                touched.push(r_id.clone());
+            }
+        }
+        for r_id in touched {
+            self.mark_header_footer_modified(&r_id);
+        }
+
+        // Footnotes
+        let mut changed = false;
+        if let Some(notes) = self.footnotes.get_mut(&self.package) {
+            for para in notes.notes.iter_mut().flat_map(|n| n.paragraphs.iter_mut()) {
+                changed |= update_paragraph(para, &mut evaluator, &mut count);
+            }
+        }
+        if changed {
+            self.mark_doc_part_modified(crate::opc::rel_types::FOOTNOTES);
+        }
+
+        // Endnotes
+        let mut changed = false;
+        if let Some(notes) = self.endnotes.get_mut(&self.package) {
+            for para in notes.notes.iter_mut().flat_map(|n| n.paragraphs.iter_mut()) {
+                changed |= update_paragraph(para, &mut evaluator, &mut count);
+            }
+        }
+        if changed {
+            self.mark_doc_part_modified(crate::opc::rel_types::ENDNOTES);
+        }
+
+        count
+    }
+}
+
+/// What to do with a field
+enum Update {
+    /// Replace the cached result
+    Result(String),
+    /// Flag the field for Word to refresh
+    Dirty,
+    /// Leave the field as it is
+    Keep,
+}
+
+/// Computes field results from document data
+struct FieldEvaluator<'a> {
+    context: &'a FieldContext,
+    now: DateTime,
+    /// Built-in and custom document properties, keyed by lower-case name
+    properties: HashMap<String, PropertyValue>,
+    /// Bookmark text, keyed by lower-case bookmark name
+    bookmarks: HashMap<String, String>,
+    /// SEQ counters, keyed by lower-case identifier
+    sequences: HashMap<String, u32>,
+}
+
+impl<'a> FieldEvaluator<'a> {
+    fn new(doc: &Document, context: &'a FieldContext) -> Self {
+        let now = context
+            .now
+            .as_deref()
+            .and_then(DateTime::parse)
+            .unwrap_or_else(DateTime::now_utc);
+
+        let mut properties = HashMap::new();
+        let mut add = |name: &str, value: Option<&String>| {
+            if let Some(value) = value {
+                properties.insert(name.to_string(), PropertyValue::Text(value.clone()));
+            }
+        };
+        if let Some(core) = doc.core_properties() {
+            add("title", core.title.as_ref());
+            add("subject", core.subject.as_ref());
+            add("author", core.creator.as_ref());
+            add("keywords", core.keywords.as_ref());
+            add("comments", core.description.as_ref());
+            add("lastsavedby", core.last_modified_by.as_ref());
+            add("revisionnumber", core.revision.as_ref());
+            add("category", core.category.as_ref());
+            add("createtime", core.created.as_ref());
+            add("lastsavedtime", core.modified.as_ref());
+        }
+        if let Some(app) = doc.extended_properties() {
+            add("company", app.company.as_ref());
+            add("manager", app.manager.as_ref());
+            add("template", app.template.as_ref());
+        }
+        if let Some(custom) = doc.custom_properties() {
+            for prop in custom.iter() {
+                properties.insert(prop.name.to_lowercase(), prop.value.clone());
+            }
+        }
+
+        let mut bookmarks = BookmarkText::default();
+        bookmarks.blocks(&doc.body.content);
+
+        Self {
+            context,
+            now,
+            properties,
+            bookmarks: bookmarks.finished,
+            sequences: HashMap::new(),
+        }
+    }
+
+    fn evaluate(&mut self, field: &Field) -> Update {
+        let instr = &field.instruction;
+        let field_type = instr.field_type.as_str();
+        if LAYOUT_FIELDS.contains(&field_type) {
+            return Update::Dirty;
+        }
+
+        let result = match field_type {
+            "DATE" => Some(format_date(
+                &self.now,
+                instr.date_format().unwrap_or("M/d/yyyy"),
+            )),
+            "TIME" => Some(format_date(
+                &self.now,
+                instr.date_format().unwrap_or("h:mm AM/PM"),
+            )),
+            "CREATEDATE" | "SAVEDATE" => {
+                let name = if field_type == "CREATEDATE" {
+                    "createtime"
+                } else {
+                    "lastsavedtime"
+                };
+                self.property(name, instr)
+            }
+            "DOCPROPERTY" => match instr.arguments.first() {
+                Some(name) => self.property(&name.to_lowercase(), instr),
+                None => None,
+            },
+            "AUTHOR" => self.property("author", instr),
+            "TITLE" => self.property("title", instr),
+            "SUBJECT" => self.property("subject", instr),
+            "KEYWORDS" => self.property("keywords", instr),
+            "COMMENTS" => self.property("comments", instr),
+            "LASTSAVEDBY" => self.property("lastsavedby", instr),
+            "REVNUM" => self.property("revisionnumber", instr),
+            "MERGEFIELD" => self.merge_field(instr),
+            "REF" => instr
+                .arguments
+                .first()
+                .and_then(|name| self.reference(name, instr)),
+            "SEQ" => self.sequence(instr),
+            "IF" => evaluate_if(&instr.arguments),
+            // A bare bookmark name is a reference to it
+            _ => self.reference(&instr.field_type, instr),
+        };
+
+        match result {
+            Some(text) => Update::Result(apply_general_formats(text, instr)),
+            None => Update::Keep,
+        }
+    }
+
+    fn property(&self, name: &str, instr: &FieldInstruction) -> Option<String> {
+        let value = self.properties.get(name)?;
+        let text = match value {
+            PropertyValue::Text(s) => match (instr.date_format(), DateTime::parse(s)) {
+                (Some(picture), Some(date)) if is_date_property(name) => {
+                    format_date(&date, picture)
+                }
+                _ => s.clone(),
+            },
+            PropertyValue::Integer(n) => format_number(*n as f64, instr.numeric_format()),
+            PropertyValue::Number(n) => format_number(*n, instr.numeric_format()),
+            PropertyValue::Bool(b) => if *b { "Y" } else { "N" }.to_string(),
+            PropertyValue::FileTime(s) => match DateTime::parse(s) {
+                Some(date) => format_date(&date, instr.date_format().unwrap_or("M/d/yyyy")),
+                None => s.clone(),
+            },
+            PropertyValue::Other(_) => return None,
+        };
+        Some(text)
+    }
+
+    fn merge_field(&self, instr: &FieldInstruction) -> Option<String> {
+        let name = instr.arguments.first()?;
+        let data = &self.context.merge_data;
+        let value = data.get(name.as_str()).or_else(|| {
+            data.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })?;
+        if value.is_empty() {
+            return Some(String::new());
+        }
+        let before = instr.switch_value("b").unwrap_or_default();
+        let after = instr.switch_value("f").unwrap_or_default();
+        Some(format!("{}{}{}", before, value, after))
+    }
+
+    fn reference(&self, name: &str, instr: &FieldInstruction) -> Option<String> {
+        // Paragraph number references need list numbering
+        if ["n", "r", "w", "p"].iter().any(|s| instr.has_switch(s)) {
+            return None;
+        }
+        self.bookmarks.get(&name.to_lowercase()).cloned()
+    }
+
+    fn sequence(&mut self, instr: &FieldInstruction) -> Option<String> {
+        let id = instr.arguments.first()?.to_lowercase();
+        let counter = self.sequences.entry(id).or_insert(0);
+        if let Some(reset) = instr.switch_value("r").and_then(|v| v.parse().ok()) {
+            *counter = reset;
+        } else if !instr.has_switch("c") {
+            *counter += 1;
+        }
+        if instr.has_switch("h") {
+            return Some(String::new());
+        }
+        Some(counter.to_string())
+    }
+}
+
+fn is_date_property(name: &str) -> bool {
+    matches!(name, "createtime" | "lastsavedtime")
+}
+
+/// Evaluate `IF expression1 operator expression2 "true text" "false text"`
+fn evaluate_if(args: &[String]) -> Option<String> {
+    let [left, op, right, rest @ ..] = args else {
+        return None;
+    };
+    let ordering = match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b)?,
+        _ => left.cmp(right),
+    };
+    let matches = match op.as_str() {
+        "=" => wildcard_match(right, left),
+        "<>" => !wildcard_match(right, left),
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        ">=" => ordering.is_ge(),
+        _ => return None,
+    };
+    let index = if matches { 0 } else { 1 };
+    Some(rest.get(index).cloned().unwrap_or_default())
+}
+
+/// Compare with `*` and `?` wildcards in the pattern, as IF does for `=` and `<>`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    if let (Ok(a), Ok(b)) = (pattern.trim().parse::<f64>(), text.trim().parse::<f64>()) {
+        return a == b;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Apply `\*` formats: case conversion and number styles
+fn apply_general_formats(mut text: String, instr: &FieldInstruction) -> String {
+    for format in instr.general_formats() {
+        let number = || text.trim().parse::<u32>().ok();
+        text = match format {
+            "Upper" | "UPPER" => text.to_uppercase(),
+            "Lower" | "LOWER" => text.to_lowercase(),
+            "FirstCap" => capitalize_words(&text, true),
+            "Caps" => capitalize_words(&text, false),
+            "roman" => number().map_or(text.clone(), |n| roman(n).to_lowercase()),
+            "ROMAN" => number().map_or(text.clone(), roman),
+            "alphabetic" => number().map_or(text.clone(), |n| alphabetic(n).to_lowercase()),
+            "ALPHABETIC" => number().map_or(text.clone(), alphabetic),
+            _ => text,
+        };
+    }
+    text
+}
+
+/// Capitalize every word, or only the first one
+fn capitalize_words(text: &str, first_only: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut at_word_start = true;
+    let mut capitalized = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            at_word_start = true;
+            result.push(c);
+        } else if at_word_start && !(first_only && capitalized) {
+            result.extend(c.to_uppercase());
+            at_word_start = false;
+            capitalized = true;
+        } else {
+            at_word_start = false;
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn roman(mut n: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            result.push_str(numeral);
+            n -= value;
+        }
+    }
+    result
+}
+
+/// A, B, ..., Z, AA, BB, ... as Word numbers with ALPHABETIC
+fn alphabetic(n: u32) -> String {
+    if n == 0 {
+        return String::new();
+    }
+    let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+    letter.to_string().repeat(((n - 1) / 26 + 1) as usize)
+}
+
+/// Format a number with a `\#` picture such as `#,##0.00` or `$0`
+fn format_number(value: f64, picture: Option<&str>) -> String {
+    let Some(picture) = picture else {
+        return if value.fract() == 0.0 {
+            format!("{}", value as i64)
+        } else {
+            value.to_string()
+        };
+    };
+    let picture = picture.trim_matches('\'');
+    let is_digit = |c: char| c == '0' || c == '#';
+    let (Some(first), Some(last)) = (picture.find(is_digit), picture.rfind(is_digit)) else {
+        return value.to_string();
+    };
+    let (prefix, core, suffix) = (
+        &picture[..first],
+        &picture[first..=last],
+        &picture[last + 1..],
+    );
+    let decimals = core
+        .split_once('.')
+        .map_or(0, |(_, frac)| frac.chars().filter(|c| is_digit(*c)).count());
+
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((int, frac)) => (int.to_string(), Some(frac.to_string())),
+        None => (formatted, None),
+    };
+    let int_part = if core.contains(',') {
+        group_thousands(&int_part)
+    } else {
+        int_part
+    };
+
+    let mut result = String::from(prefix);
+    if value < 0.0 {
+        result.push('-');
+    }
+    result.push_str(&int_part);
+    if let Some(frac) = frac_part {
+        result.push('.');
+        result.push_str(&frac);
+    }
+    result.push_str(suffix);
+    result
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// A calendar date and time, without time zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+impl DateTime {
+    /// Parse an ISO 8601 date (`2024-05-01`) or date-time (`2024-05-01T09:30:00Z`)
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (date, time) = s.split_once('T').unwrap_or((s, ""));
+        let mut date_parts = date.splitn(3, '-');
+        let year = date_parts.next()?.parse().ok()?;
+        let month = date_parts.next()?.parse().ok()?;
+        let day = date_parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let time = time.split(['Z', '+', '.']).next().unwrap_or_default();
+        let time = time.split('-').next().unwrap_or_default();
+        let mut time_parts = time.split(':').map(|p| p.parse::<u32>().ok());
+        let mut next = || time_parts.next().flatten().unwrap_or(0);
+        Some(Self {
+            year,
+            month,
+            day,
+            hour: next(),
+            minute: next(),
+            second: next(),
+        })
+    }
+
+    /// Current time from the system clock, in UTC
+    fn now_utc() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let time = secs.rem_euclid(86_400) as u32;
+        Self {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time / 60 % 60,
+            second: time % 60,
+        }
+    }
+
+    /// Day of the week, 0 = Monday
+    fn weekday(&self) -> usize {
+        // 1970-01-01 was a Thursday
+        (days_from_civil(self.year, self.month, self.day) + 3).rem_euclid(7) as usize
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Format a date with a Word date-time picture such as `dddd, MMMM d, yyyy`
+pub(crate) fn format_date(date: &DateTime, picture: &str) -> String {
+    let chars: Vec<char> = picture.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().take(5).collect();
+        if rest == "AM/PM" || rest == "am/pm" {
+            let pm = date.hour >= 12;
+            let marker = if pm { "PM" } else { "AM" };
+            if rest == "am/pm" {
+                result.push_str(&marker.to_lowercase());
+            } else {
+                result.push_str(marker);
+            }
+            i += 5;
+            continue;
+        }
+        if c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&q| q == '\'')
+                .map_or(chars.len(), |p| i + 1 + p);
+            result.extend(&chars[i + 1..end]);
+            i = end + 1;
+            continue;
+        }
+
+        let count = chars[i..].iter().take_while(|&&x| x == c).count();
+        let hour12 = match date.hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        let padded = |value: u32| {
+            if count >= 2 {
+                format!("{:02}", value)
+            } else {
+                value.to_string()
+            }
+        };
+        match c {
+            'y' | 'Y' if count >= 3 => result.push_str(&format!("{:04}", date.year)),
+            'y' | 'Y' => result.push_str(&format!("{:02}", date.year.rem_euclid(100))),
+            'M' if count >= 4 => result.push_str(MONTHS[date.month as usize - 1]),
+            'M' if count == 3 => result.push_str(&MONTHS[date.month as usize - 1][..3]),
+            'M' => result.push_str(&padded(date.month)),
+            'd' | 'D' if count >= 4 => result.push_str(WEEKDAYS[date.weekday()]),
+            'd' | 'D' if count == 3 => result.push_str(&WEEKDAYS[date.weekday()][..3]),
+            'd' | 'D' => result.push_str(&padded(date.day)),
+            'H' => result.push_str(&padded(date.hour)),
+            'h' => result.push_str(&padded(hour12)),
+            'm' => result.push_str(&padded(date.minute)),
+            's' | 'S' => result.push_str(&padded(date.second)),
+            _ => {
+                result.extend(std::iter::repeat(c).take(count));
+            }
+        }
+        i += count;
+    }
+    result
+}
+
+/// Collects the text of bookmarked ranges
+#[derive(Default)]
+struct BookmarkText {
+    /// Open bookmarks as (id, lower-case name, text so far)
+    open: Vec<(String, String, String)>,
+    finished: HashMap<String, String>,
+}
+
+impl BookmarkText {
+    fn blocks(&mut self, blocks: &[BlockContent]) {
+        body_paragraphs(blocks).for_each(|p| self.paragraph(p));
+    }
+
+    fn paragraph(&mut self, para: &Paragraph) {
+        for item in &para.content {
+            match item {
+                ParagraphContent::BookmarkStart { id, name } => {
+                    self.open
+                        .push((id.clone(), name.to_lowercase(), String::new()));
+                }
+                ParagraphContent::BookmarkEnd { id } => {
+                    if let Some(pos) = self.open.iter().position(|(open, _, _)| open == id) {
+                        let (_, name, text) = self.open.remove(pos);
+                        self.finished.entry(name).or_insert(text);
+                    }
+                }
+                other => {
+                    let text = content_text(std::slice::from_ref(other));
+                    for (_, _, open) in &mut self.open {
+                        open.push_str(&text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A run or simple field of a paragraph, in document order
+enum Slot<'a> {
+    Run(&'a mut Run),
+    Simple(&'a mut SimpleField),
+}
+
+/// Flatten runs out of hyperlinks and tracked insertions
+fn flatten<'a>(content: &'a mut [ParagraphContent], slots: &mut Vec<Slot<'a>>) {
+    for item in content {
+        match item {
+            ParagraphContent::Run(run) => slots.push(Slot::Run(run)),
+            ParagraphContent::Hyperlink(link) => flatten(&mut link.content, slots),
+            ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                flatten(&mut rev.content, slots)
+            }
+            ParagraphContent::SimpleField(field) => slots.push(Slot::Simple(field)),
+            ParagraphContent::Sdt(sdt) => {
+                if let SdtContent::Inline(inner) = &mut sdt.content {
+                    flatten(inner, slots)
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A complex field whose end has not been reached, with run positions
+struct OpenField {
+    begin: (usize, usize),
+    separator: Option<(usize, usize)>,
+    code: String,
+    result: String,
+    locked: bool,
+    dirty: bool,
+    nested: Vec<Field>,
+}
+
+/// Update the fields of a paragraph; returns whether anything changed
+///
+/// Complex fields are updated only if they begin and end in this paragraph.
+fn update_paragraph(
+    para: &mut Paragraph,
+    evaluator: &mut FieldEvaluator,
+    count: &mut usize,
+) -> bool {
+    let mut slots = Vec::new();
+    flatten(&mut para.content, &mut slots);
+
+    let mut changed = false;
+    let mut open: Vec<OpenField> = Vec::new();
+    let mut i = 0;
+    while i < slots.len() {
+        if let Slot::Simple(ref mut simple) = slots[i] {
+            let field = Field {
+                code: simple.instruction.clone(),
+                instruction: FieldInstruction::parse(&simple.instruction),
+                result: simple.text(),
+                is_simple: true,
+                dirty: simple.dirty.unwrap_or(false),
+                locked: simple.locked.unwrap_or(false),
+                nested: Vec::new(),
+            };
+            let update = if field.locked {
+                Update::Keep
+            } else {
+                evaluator.evaluate(&field)
+            };
+            let result = match update {
+                Update::Result(text) => {
+                    let props = simple.content.iter().find_map(|c| match c {
+                        ParagraphContent::Run(r) => r.properties.clone(),
+                        _ => None,
+                    });
+                    let mut run = Run::new(text.clone());
+                    run.properties = props;
+                    simple.content = vec![ParagraphContent::Run(run)];
+                    *count += 1;
+                    changed = true;
+                    text
+                }
+                Update::Dirty => {
+                    changed |= simple.dirty != Some(true);
+                    simple.dirty = Some(true);
+                    field.result.clone()
+                }
+                Update::Keep => field.result.clone(),
+            };
+            push_result(&mut open, result, field);
+            i += 1;
+            continue;
+        }
+
+        let mut j = 0;
+        while j < slot_run(&mut slots[i]).content.len() {
+            let item = &slot_run(&mut slots[i]).content[j];
+            match item {
+                RunContent::FieldChar(fld_char) => match fld_char.kind {
+                    FieldCharType::Begin => open.push(OpenField {
+                        begin: (i, j),
+                        separator: None,
+                        code: String::new(),
+                        result: String::new(),
+                        locked: fld_char.locked.unwrap_or(false),
+                        dirty: fld_char.dirty.unwrap_or(false),
+                        nested: Vec::new(),
+                    }),
+                    FieldCharType::Separate => {
+                        if let Some(field) = open.last_mut() {
+                            field.separator = Some((i, j));
+                        }
+                    }
+                    FieldCharType::End => {
+                        if let Some(field) = open.pop() {
+                            let view = Field {
+                                instruction: FieldInstruction::parse(&field.code),
+                                code: field.code,
+                                result: field.result,
+                                is_simple: false,
+                                dirty: field.dirty,
+                                locked: field.locked,
+                                nested: field.nested,
+                            };
+                            let update = if view.locked {
+                                Update::Keep
+                            } else {
+                                evaluator.evaluate(&view)
+                            };
+                            let result = match update {
+                                Update::Result(text) => {
+                                    j += replace_result(&mut slots, field.separator, (i, j), &text);
+                                    *count += 1;
+                                    changed = true;
+                                    text
+                                }
+                                Update::Dirty => {
+                                    let (bi, bj) = field.begin;
+                                    if let RunContent::FieldChar(ref mut begin) =
+                                        slot_run(&mut slots[bi]).content[bj]
+                                    {
+                                        changed |= begin.dirty != Some(true);
+                                        begin.dirty = Some(true);
+                                    }
+                                    view.result.clone()
+                                }
+                                Update::Keep => view.result.clone(),
+                            };
+                            push_result(&mut open, result, view);
+                        }
+                    }
+                },
+                RunContent::InstrText(code) => {
+                    if let Some(field) = open.last_mut().filter(|f| f.separator.is_none()) {
+                        field.code.push_str(code);
+                    }
+                }
+                other => {
+                    if let (Some(text), Some(field)) = (other.plain_text(), open.last_mut()) {
+                        if field.separator.is_some() {
+                            field.result.push_str(text);
+                        }
+                    }
+                }
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    changed
+}
+
+fn slot_run<'s>(slot: &'s mut Slot<'_>) -> &'s mut Run {
+    match slot {
+        Slot::Run(run) => run,
+        Slot::Simple(_) => unreachable!("simple fields are handled separately"),
+    }
+}
+
+/// Hand a finished field's result to the enclosing field's code or result
+fn push_result(open: &mut [OpenField], result: String, field: Field) {
+    if let Some(parent) = open.last_mut() {
+        if parent.separator.is_some() {
+            parent.result.push_str(&result);
+        } else {
+            parent.code.push_str(&result);
+        }
+        parent.nested.push(field);
+    }
+}
+
+/// Replace the result text between a field's separator and its end
+///
+/// Returns how many items were inserted before the end character.
+fn replace_result(
+    slots: &mut [Slot],
+    separator: Option<(usize, usize)>,
+    end: (usize, usize),
+    text: &str,
+) -> usize {
+    let Some((si, sj)) = separator else {
+        let run = slot_run(&mut slots[end.0]);
+        let separator = crate::document::FieldChar::new(FieldCharType::Separate);
+        run.content.insert(end.1, RunContent::FieldChar(separator));
+        run.content
+            .insert(end.1 + 1, RunContent::Text(text.to_string()));
+        return 2;
+    };
+
+    let mut placed = false;
+    for (i, slot) in slots.iter_mut().enumerate().take(end.0 + 1).skip(si) {
+        let run = slot_run(slot);
+        let from = if i == si { sj + 1 } else { 0 };
+        let to = if i == end.0 { end.1 } else { run.content.len() };
+        for item in &mut run.content[from..to] {
+            if item.plain_text().is_some() {
+                let replacement = if placed {
+                    String::new()
+                } else {
+                    text.to_string()
+                };
+                *item = RunContent::Text(replacement);
+                placed = true;
+            }
+        }
+    }
+    if placed {
+        return 0;
+    }
+
+    slot_run(&mut slots[si])
+        .content
+        .insert(sj + 1, RunContent::Text(text.to_string()));
+    usize::from(si == end.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> DateTime {
+        DateTime::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_format_date() {
+        let d = date("2024-05-01T14:05:09Z");
+        assert_eq!(format_date(&d, "yyyy-MM-dd"), "2024-05-01");
+        assert_eq!(
+            format_date(&d, "dddd, MMMM d, yyyy"),
+            "Wednesday, May 1, 2024"
+        );
+        assert_eq!(format_date(&d, "d MMM yy"), "1 May 24");
+        assert_eq!(format_date(&d, "h:mm AM/PM"), "2:05 PM");
+        assert_eq!(format_date(&d, "HH:mm:ss"), "14:05:09");
+        assert_eq!(format_date(&d, "'Day' d"), "Day 1");
+    }
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(days_from_civil(2000, 2, 29)), (2000, 2, 29));
+        assert_eq!(date("1970-01-01").weekday(), 3);
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234.5, Some("#,##0.00")), "1,234.50");
+        assert_eq!(format_number(-7.0, Some("$0")), "$-7");
+        assert_eq!(format_number(3.0, None), "3");
+    }
+
+    #[test]
+    fn test_evaluate_if() {
+        let args = |s: &str| FieldInstruction::parse(s).arguments;
+        assert_eq!(
+            evaluate_if(&args(r#"IF Dr = "Dr" Doctor Sir"#)).unwrap(),
+            "Doctor"
+        );
+        assert_eq!(
+            evaluate_if(&args(r#"IF 5 > 10 big small"#)).unwrap(),
+            "small"
+        );
+        assert_eq!(
+            evaluate_if(&args(r#"IF Smith = "Sm*" yes no"#)).unwrap(),
+            "yes"
+        );
+        assert_eq!(evaluate_if(&args(r#"IF a <> a yes"#)).unwrap(), "");
+    }
+
+    #[test]
+    fn test_general_formats() {
+        let instr = FieldInstruction::parse(r#"SEQ Figure \* ROMAN"#);
+        assert_eq!(apply_general_formats("4".into(), &instr), "IV");
+        let instr = FieldInstruction::parse(r#"MERGEFIELD Name \* Caps"#);
+        assert_eq!(
+            apply_general_formats("ada lovelace".into(), &instr),
+            "Ada Lovelace"
+        );
+        assert_eq!(alphabetic(28), "BB");
+    }
+}
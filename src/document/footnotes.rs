@@ -1,7 +1,7 @@
 //! Footnotes and endnotes (footnotes.xml / endnotes.xml)
 
-use crate::document::Paragraph;
-use crate::error::Result;
+use crate::document::{Document, Paragraph, ParagraphContent, Run, RunContent, Style, StyleType};
+use crate::error::{Error, Result};
 use crate::xml::{get_attr, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
@@ -58,10 +58,36 @@ pub struct Notes {
 }
 
 impl Notes {
+    /// Create an empty collection with the separator and continuation
+    /// separator notes (IDs -1 and 0) Word expects
+    pub fn new(is_footnotes: bool) -> Self {
+        let separator = |id: i32, kind: &str| Note {
+            id,
+            note_type: Some(kind.to_string()),
+            paragraphs: vec![Paragraph {
+                content: vec![ParagraphContent::Run(Run {
+                    content: vec![RunContent::Unknown(empty_element(&format!("w:{}", kind)))],
+                    ..Default::default()
+                })],
+                ..Default::default()
+            }],
+            unknown_children: Vec::new(),
+        };
+        Notes {
+            notes: vec![
+                separator(-1, "separator"),
+                separator(0, "continuationSeparator"),
+            ],
+            is_footnotes,
+            unknown_children: Vec::new(),
+        }
+    }
+
     /// Parse from XML string
     pub fn from_xml(xml: &str, is_footnotes: bool) -> Result<Self> {
         let mut reader = Reader::from_str(xml);
-        reader.config_mut().trim_text(true);
+        // Whitespace in w:t is significant, so text is not trimmed
+        reader.config_mut().trim_text(false);
 
         let mut notes = Notes {
             is_footnotes,
@@ -176,6 +202,129 @@ impl Notes {
     }
 }
 
+impl Document {
+    /// Add a footnote referenced at the end of a body paragraph; returns its ID
+    pub fn add_footnote(&mut self, paragraph: usize, text: impl Into<String>) -> Result<i32> {
+        self.add_note(paragraph, text.into(), true)
+    }
+
+    /// Add an endnote referenced at the end of a body paragraph; returns its ID
+    pub fn add_endnote(&mut self, paragraph: usize, text: impl Into<String>) -> Result<i32> {
+        self.add_note(paragraph, text.into(), false)
+    }
+
+    /// Footnote references in the body as (paragraph index, note ID) pairs
+    pub fn footnote_references(&self) -> Vec<(usize, i32)> {
+        self.note_references(Run::footnote_id)
+    }
+
+    /// Endnote references in the body as (paragraph index, note ID) pairs
+    pub fn endnote_references(&self) -> Vec<(usize, i32)> {
+        self.note_references(Run::endnote_id)
+    }
+
+    fn note_references(&self, note_id: fn(&Run) -> Option<i32>) -> Vec<(usize, i32)> {
+        self.body
+            .paragraphs()
+            .enumerate()
+            .flat_map(|(index, para)| para.runs().filter_map(note_id).map(move |id| (index, id)))
+            .collect()
+    }
+
+    fn add_note(&mut self, paragraph: usize, text: String, is_footnotes: bool) -> Result<i32> {
+        let max = self.paragraph_count();
+        if paragraph >= max {
+            return Err(Error::IndexOutOfBounds {
+                index: paragraph,
+                max,
+            });
+        }
+
+        let (kind, names) = if is_footnotes {
+            ("Footnote", ["footnote text", "footnote reference"])
+        } else {
+            ("Endnote", ["endnote text", "endnote reference"])
+        };
+        let text_style = format!("{}Text", kind);
+        let reference_style = format!("{}Reference", kind);
+        self.ensure_note_style(&text_style, names[0], StyleType::Paragraph);
+        self.ensure_note_style(&reference_style, names[1], StyleType::Character);
+
+        // Word's layout: the note mark, then a space and the text
+        let mut mark = Run::default();
+        mark.set_style(reference_style);
+        let tag = if is_footnotes {
+            "w:footnoteRef"
+        } else {
+            "w:endnoteRef"
+        };
+        mark.content.push(RunContent::Unknown(empty_element(tag)));
+        let mut para = Paragraph::default();
+        para.set_style(text_style);
+        para.add_run(mark);
+        para.add_run(Run::new(format!(" {}", text)));
+
+        let notes = if is_footnotes {
+            self.footnotes_mut()
+        } else {
+            self.endnotes_mut()
+        };
+        let id = notes.next_id();
+        notes.notes.push(Note {
+            id,
+            note_type: None,
+            paragraphs: vec![para],
+            unknown_children: Vec::new(),
+        });
+
+        let reference = if is_footnotes {
+            Run::footnote_reference(id)
+        } else {
+            Run::endnote_reference(id)
+        };
+        self.body
+            .paragraphs_mut()
+            .nth(paragraph)
+            .expect("paragraph checked above")
+            .add_run(reference);
+        self.mark_body_modified();
+        Ok(id)
+    }
+
+    /// Add a built-in note style unless the document defines it
+    fn ensure_note_style(&mut self, style_id: &str, name: &str, style_type: StyleType) {
+        if self.style(style_id).is_some() {
+            return;
+        }
+        let mut style = Style {
+            style_id: style_id.to_string(),
+            name: Some(name.to_string()),
+            semi_hidden: true,
+            unhide_when_used: true,
+            ..Default::default()
+        };
+        let run_properties = style.run_properties.get_or_insert_with(Default::default);
+        if style_type == StyleType::Character {
+            run_properties.vertical_align = Some("superscript".into());
+        } else {
+            style.based_on = Some("Normal".into());
+            run_properties.size = Some(20);
+        }
+        style.style_type = Some(style_type);
+        self.styles_mut().add(style);
+    }
+}
+
+/// Self-closing element with no attributes
+fn empty_element(name: &str) -> RawXmlNode {
+    RawXmlNode::Element(RawXmlElement {
+        name: name.to_string(),
+        attributes: Vec::new(),
+        children: Vec::new(),
+        self_closing: true,
+    })
+}
+
 fn parse_note<R: std::io::BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Note> {
     let id = get_attr(start, "w:id")
         .or_else(|| get_attr(start, "id"))
@@ -276,4 +425,15 @@ mod tests {
         assert!(xml.contains("w:endnotes"));
         assert!(xml.contains("w:endnote"));
     }
+
+    #[test]
+    fn test_new_notes_have_separators() {
+        let mut notes = Notes::new(true);
+        assert_eq!(notes.regular_notes().count(), 0);
+        assert_eq!(notes.add("First"), 1);
+
+        let xml = notes.to_xml().unwrap();
+        assert!(xml.contains(r#"<w:footnote w:id="-1" w:type="separator"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>"#));
+        assert!(xml.contains("<w:continuationSeparator/>"));
+    }
 }
@@ -1,6 +1,7 @@
 //! Header and Footer elements
 
-use crate::document::Paragraph;
+use crate::document::sdt::{read_paragraph, read_ranged, write_ranged};
+use crate::document::{Paragraph, Sdt, SdtContent};
 use crate::error::Result;
 use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
@@ -12,6 +13,8 @@ use std::io::Cursor;
 pub struct HeaderFooter {
     /// Paragraphs in the header/footer
     pub paragraphs: Vec<Paragraph>,
+    /// Content controls around ranges of paragraphs, outer controls first
+    pub content_controls: Vec<Sdt>,
     /// Unknown children (preserved for round-trip)
     pub unknown_children: Vec<RawXmlNode>,
     /// Whether this is a header (true) or footer (false)
@@ -42,6 +45,14 @@ impl HeaderFooter {
                             let para = Paragraph::from_reader(&mut reader, &e)?;
                             hf.paragraphs.push(para);
                         }
+                        b"sdt" => read_ranged(
+                            &mut reader,
+                            &mut hf.paragraphs,
+                            &mut hf.content_controls,
+                            &mut hf.unknown_children,
+                            SdtContent::Paragraphs,
+                            &mut read_paragraph,
+                        )?,
                         _ => {
                             let raw = RawXmlElement::from_reader(&mut reader, &e)?;
                             hf.unknown_children.push(RawXmlNode::Element(raw));
@@ -96,11 +107,15 @@ impl HeaderFooter {
         let mut start = BytesStart::new(tag);
         start.push_attribute(("xmlns:w", crate::xml::W));
         start.push_attribute(("xmlns:r", crate::xml::R));
+        start.push_attribute(("xmlns:w14", crate::xml::W14));
         writer.write_event(Event::Start(start))?;
 
-        for para in &self.paragraphs {
-            para.write_to(&mut writer)?;
-        }
+        write_ranged(
+            &mut writer,
+            &self.paragraphs,
+            &self.content_controls,
+            |w, para| para.write_to(w),
+        )?;
 
         for child in &self.unknown_children {
             child.write_to(&mut writer)?;
@@ -1,9 +1,13 @@
 //! Image support for DOCX documents
 //!
 //! Handles inline images via DrawingML (w:drawing > wp:inline > a:graphic > pic:pic).
+//! SVG images are embedded the way Office 2016 does it: the `a:blip` points at
+//! a raster fallback and an `asvg:svgBlip` extension points at the SVG.
 
-use crate::error::Result;
-use crate::xml::RawXmlNode;
+use crate::document::drawing;
+use crate::document::image_format::{ImageFormat, ImageHeader};
+use crate::error::{Error, Result};
+use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::Writer;
 
@@ -12,12 +16,16 @@ use quick_xml::Writer;
 pub struct InlineImage {
     /// Relationship ID referencing the image part
     pub r_id: String,
+    /// Relationship ID of an SVG version of the image; `r_id` is then its raster fallback
+    pub svg_r_id: Option<String>,
     /// Image width in EMU (English Metric Units, 914400 per inch)
     pub width_emu: i64,
     /// Image height in EMU
     pub height_emu: i64,
     /// Description / alt text
     pub description: String,
+    /// Title of the alt text
+    pub title: String,
     /// Name
     pub name: String,
     /// The full raw XML of the drawing element (for round-trip preservation)
@@ -29,14 +37,70 @@ impl InlineImage {
     pub fn new(r_id: impl Into<String>, width_emu: i64, height_emu: i64) -> Self {
         InlineImage {
             r_id: r_id.into(),
+            svg_r_id: None,
             width_emu,
             height_emu,
             description: String::new(),
+            title: String::new(),
             name: String::new(),
             raw_xml: None,
         }
     }
 
+    /// Read a parsed `w:drawing` holding a single inline picture
+    ///
+    /// Other drawings (floating, groups, shapes) are handed back unchanged.
+    pub(crate) fn from_raw(raw: RawXmlElement) -> std::result::Result<Self, RawXmlElement> {
+        let found = drawing::pictures(&raw);
+        let [picture] = found.as_slice() else {
+            return Err(raw);
+        };
+        if picture.floating || picture.in_text_box || picture.frame.is_none() {
+            return Err(raw);
+        }
+        Ok(InlineImage {
+            r_id: picture.r_id.clone(),
+            svg_r_id: picture.svg_r_id.clone(),
+            width_emu: picture.width_emu,
+            height_emu: picture.height_emu,
+            description: picture.description.clone(),
+            title: picture.title.clone(),
+            name: picture.name.clone(),
+            raw_xml: Some(RawXmlNode::Element(raw)),
+        })
+    }
+
+    /// Set the displayed size in EMU
+    pub fn set_size(&mut self, width_emu: i64, height_emu: i64) {
+        self.width_emu = width_emu;
+        self.height_emu = height_emu;
+        if let Some(RawXmlNode::Element(raw)) = &mut self.raw_xml {
+            for picture in drawing::pictures(raw) {
+                drawing::set_size(raw, &picture, width_emu, height_emu);
+            }
+        }
+    }
+
+    /// Point the image at another relationship
+    pub fn set_r_id(&mut self, r_id: impl Into<String>) {
+        self.r_id = r_id.into();
+        if let Some(RawXmlNode::Element(raw)) = &mut self.raw_xml {
+            for picture in drawing::pictures(raw) {
+                drawing::set_rel_id(raw, &picture, &self.r_id);
+            }
+        }
+    }
+
+    /// Set or remove the SVG version of the image
+    pub fn set_svg_r_id(&mut self, svg_r_id: Option<String>) {
+        self.svg_r_id = svg_r_id;
+        if let Some(RawXmlNode::Element(raw)) = &mut self.raw_xml {
+            for picture in drawing::pictures(raw) {
+                drawing::set_svg_rel_id(raw, &picture, self.svg_r_id.as_deref());
+            }
+        }
+    }
+
     /// Create with dimensions in centimeters
     pub fn from_cm(r_id: impl Into<String>, width_cm: f64, height_cm: f64) -> Self {
         // 1 cm = 360000 EMU
@@ -57,6 +121,24 @@ impl InlineImage {
         )
     }
 
+    /// Create at the image's natural size (see [`ImageData::natural_size`])
+    pub fn natural_size(r_id: impl Into<String>, image: &ImageData) -> Option<Self> {
+        let (width, height) = image.natural_size()?;
+        Some(Self::new(r_id, width, height))
+    }
+
+    /// Scale down to fit within a box, keeping the aspect ratio
+    ///
+    /// Images that already fit are left at their size.
+    pub fn fit_within(mut self, max_width_emu: i64, max_height_emu: i64) -> Self {
+        let (w, h) = (self.width_emu.max(1) as f64, self.height_emu.max(1) as f64);
+        let scale = (max_width_emu as f64 / w).min(max_height_emu as f64 / h);
+        if scale < 1.0 {
+            self.set_size((w * scale).round() as i64, (h * scale).round() as i64);
+        }
+        self
+    }
+
     /// Set alt text
     pub fn with_description(mut self, desc: impl Into<String>) -> Self {
         self.description = desc.into();
@@ -69,6 +151,13 @@ impl InlineImage {
         self
     }
 
+    /// Show an SVG image, keeping `r_id` as its raster fallback
+    /// (see [`Document::add_svg_image`](crate::Document::add_svg_image))
+    pub fn with_svg(mut self, svg_r_id: impl Into<String>) -> Self {
+        self.set_svg_r_id(Some(svg_r_id.into()));
+        self
+    }
+
     /// Generate the DrawingML XML for this image
     pub fn to_drawing_xml<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         // If we have raw XML from parsing, use it for round-trip
@@ -100,6 +189,9 @@ impl InlineImage {
         doc_pr.push_attribute(("id", "1"));
         doc_pr.push_attribute(("name", self.name.as_str()));
         doc_pr.push_attribute(("descr", self.description.as_str()));
+        if !self.title.is_empty() {
+            doc_pr.push_attribute(("title", self.title.as_str()));
+        }
         writer.write_event(Event::Empty(doc_pr))?;
 
         //     <a:graphic>
@@ -130,7 +222,16 @@ impl InlineImage {
         writer.write_event(Event::Start(BytesStart::new("pic:blipFill")))?;
         let mut blip = BytesStart::new("a:blip");
         blip.push_attribute(("r:embed", self.r_id.as_str()));
-        writer.write_event(Event::Empty(blip))?;
+        match &self.svg_r_id {
+            Some(svg_r_id) => {
+                writer.write_event(Event::Start(blip))?;
+                writer.write_event(Event::Start(BytesStart::new("a:extLst")))?;
+                drawing::svg_extension(svg_r_id).write_to(writer)?;
+                writer.write_event(Event::End(BytesEnd::new("a:extLst")))?;
+                writer.write_event(Event::End(BytesEnd::new("a:blip")))?;
+            }
+            None => writer.write_event(Event::Empty(blip))?,
+        }
         writer.write_event(Event::Start(BytesStart::new("a:stretch")))?;
         writer.write_event(Event::Empty(BytesStart::new("a:fillRect")))?;
         writer.write_event(Event::End(BytesEnd::new("a:stretch")))?;
@@ -178,6 +279,8 @@ pub struct ImageData {
     pub content_type: String,
     /// File extension
     pub extension: String,
+    /// Raster version for readers without SVG support (see [`ImageData::svg`])
+    pub fallback: Option<Box<ImageData>>,
 }
 
 impl ImageData {
@@ -187,6 +290,7 @@ impl ImageData {
             data,
             content_type: "image/png".into(),
             extension: "png".into(),
+            fallback: None,
         }
     }
 
@@ -196,30 +300,94 @@ impl ImageData {
             data,
             content_type: "image/jpeg".into(),
             extension: "jpeg".into(),
+            fallback: None,
+        }
+    }
+
+    /// Create from SVG bytes with a PNG fallback
+    ///
+    /// Word 2016 and later show the SVG; older readers show the PNG.
+    pub fn svg(data: Vec<u8>, fallback_png: Vec<u8>) -> Self {
+        ImageData {
+            fallback: Some(Box::new(Self::png(fallback_png))),
+            ..Self::with_format(data, ImageFormat::Svg)
         }
     }
 
-    /// Create from file path (auto-detects type)
+    /// Create from image bytes, detecting the format from its magic bytes
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let format = ImageFormat::sniff(&data)
+            .ok_or_else(|| Error::Unsupported("unrecognized image format".into()))?;
+        Ok(Self::with_format(data, format))
+    }
+
+    /// Create from file path
+    ///
+    /// The format is sniffed from the file's contents, falling back to its
+    /// extension for headers that cannot be recognized.
     pub fn from_file(path: &std::path::Path) -> std::io::Result<Self> {
         let data = std::fs::read(path)?;
-        let ext = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("png")
-            .to_lowercase();
-        let content_type = match ext.as_str() {
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "bmp" => "image/bmp",
-            "tiff" | "tif" => "image/tiff",
-            _ => "image/png",
-        };
-        Ok(ImageData {
+        let format = ImageFormat::sniff(&data)
+            .or_else(|| {
+                path.extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(ImageFormat::from_extension)
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unrecognized image format: {}", path.display()),
+                )
+            })?;
+        Ok(Self::with_format(data, format))
+    }
+
+    fn with_format(data: Vec<u8>, format: ImageFormat) -> Self {
+        ImageData {
             data,
-            content_type: content_type.into(),
-            extension: ext,
-        })
+            content_type: format.content_type().into(),
+            extension: format.extension().into(),
+            fallback: None,
+        }
+    }
+
+    /// Split into the image for the `a:blip` and, when there is a fallback,
+    /// the SVG for the `asvg:svgBlip`
+    pub(crate) fn into_blips(mut self) -> (ImageData, Option<ImageData>) {
+        match self.fallback.take() {
+            Some(fallback) => (*fallback, Some(self)),
+            None => (self, None),
+        }
+    }
+
+    /// Format detected from the image bytes
+    pub fn format(&self) -> Option<ImageFormat> {
+        ImageFormat::sniff(&self.data)
+    }
+
+    /// Stored pixel width and height
+    ///
+    /// Vector images report their nominal size at 96 DPI.
+    pub fn pixel_size(&self) -> Option<(u32, u32)> {
+        ImageHeader::read(&self.data)?.pixels
+    }
+
+    /// Horizontal and vertical resolution recorded in the image, if any
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        ImageHeader::read(&self.data)?.dpi
+    }
+
+    /// EXIF orientation (1-8; 1 when the image has none)
+    pub fn orientation(&self) -> u16 {
+        ImageHeader::read(&self.data).map_or(1, |h| h.orientation)
+    }
+
+    /// Displayed width and height in EMU
+    ///
+    /// Uses the recorded resolution (96 DPI when absent) and swaps the axes
+    /// for EXIF orientations that rotate the image a quarter turn.
+    pub fn natural_size(&self) -> Option<(i64, i64)> {
+        ImageHeader::read(&self.data)?.natural_size()
     }
 }
 
@@ -256,6 +424,36 @@ mod tests {
         assert_eq!(data.extension, "png");
     }
 
+    #[test]
+    fn test_image_data_from_bytes() {
+        let gif = ImageData::from_bytes(b"GIF89a\x30\0\x18\0".to_vec()).unwrap();
+        assert_eq!(gif.content_type, "image/gif");
+        assert_eq!(gif.extension, "gif");
+        assert_eq!(gif.format(), Some(ImageFormat::Gif));
+        assert_eq!(gif.pixel_size(), Some((48, 24)));
+        assert_eq!(gif.natural_size(), Some((457200, 228600)));
+        assert!(ImageData::from_bytes(b"not an image".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_image_data_svg() {
+        let svg = ImageData::svg(b"<svg width=\"10\" height=\"5\"/>".to_vec(), vec![1]);
+        assert_eq!(svg.content_type, "image/svg+xml");
+        assert_eq!(svg.pixel_size(), Some((10, 5)));
+        let (fallback, svg) = svg.into_blips();
+        assert_eq!(fallback.content_type, "image/png");
+        assert_eq!(svg.unwrap().extension, "svg");
+        assert!(ImageData::png(vec![1]).into_blips().1.is_none());
+    }
+
+    #[test]
+    fn test_fit_within() {
+        let img = InlineImage::new("rId1", 4000, 2000).fit_within(1000, 1000);
+        assert_eq!((img.width_emu, img.height_emu), (1000, 500));
+        let img = InlineImage::new("rId1", 400, 200).fit_within(1000, 1000);
+        assert_eq!((img.width_emu, img.height_emu), (400, 200));
+    }
+
     #[test]
     fn test_generate_drawing_xml() {
         let img = InlineImage::new("rId1", 914400, 914400)
@@ -272,4 +470,13 @@ mod tests {
         assert!(xml.contains("r:embed=\"rId1\""));
         assert!(xml.contains("cx=\"914400\""));
     }
+
+    #[test]
+    fn test_generate_svg_drawing_xml() {
+        let img = InlineImage::new("rId1", 914400, 914400).with_svg("rId2");
+        let mut buf = Vec::new();
+        img.to_drawing_xml(&mut Writer::new(&mut buf)).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains(r#"<a:blip r:embed="rId1"><a:extLst><a:ext uri="{96DAC541-7B7A-43D3-8B79-37D633B846F1}"><asvg:svgBlip xmlns:asvg="http://schemas.microsoft.com/office/drawing/2016/SVG/main" r:embed="rId2"/></a:ext></a:extLst></a:blip>"#));
+    }
 }
@@ -0,0 +1,503 @@
+//! Image format detection and header parsing
+//!
+//! The format is sniffed from magic bytes. Pixel size, resolution and EXIF
+//! orientation are read from the headers only; image data is never decoded.
+//! Vector formats (SVG, EMF, placeable WMF) report their nominal size as
+//! pixels at 96 DPI.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Resolution assumed when an image does not record one
+pub(crate) const DEFAULT_DPI: f64 = 96.0;
+
+/// EMU per inch
+const EMU_PER_INCH: f64 = 914400.0;
+
+/// An image file format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    Tiff,
+    WebP,
+    Svg,
+    Emf,
+    Wmf,
+}
+
+impl ImageFormat {
+    /// Detect the format from the first bytes of an image
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        let format = match data {
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => ImageFormat::Png,
+            [0xFF, 0xD8, 0xFF, ..] => ImageFormat::Jpeg,
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => ImageFormat::Gif,
+            [b'B', b'M', ..] if data.len() >= 26 => ImageFormat::Bmp,
+            [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => ImageFormat::Tiff,
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => ImageFormat::WebP,
+            [0x01, 0x00, 0x00, 0x00, ..] if data.get(40..44) == Some(b" EMF") => ImageFormat::Emf,
+            [0xD7, 0xCD, 0xC6, 0x9A, ..] | [0x01 | 0x02, 0x00, 0x09, 0x00, ..] => ImageFormat::Wmf,
+            _ if svg_root(data).is_some() => ImageFormat::Svg,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Detect the format from a file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let format = match extension.to_ascii_lowercase().as_str() {
+            "png" => ImageFormat::Png,
+            "jpg" | "jpeg" | "jpe" => ImageFormat::Jpeg,
+            "gif" => ImageFormat::Gif,
+            "bmp" | "dib" => ImageFormat::Bmp,
+            "tif" | "tiff" => ImageFormat::Tiff,
+            "webp" => ImageFormat::WebP,
+            "svg" => ImageFormat::Svg,
+            "emf" => ImageFormat::Emf,
+            "wmf" => ImageFormat::Wmf,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// MIME content type
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Tiff => "image/tiff",
+            ImageFormat::WebP => "image/webp",
+            ImageFormat::Svg => "image/svg+xml",
+            ImageFormat::Emf => "image/x-emf",
+            ImageFormat::Wmf => "image/x-wmf",
+        }
+    }
+
+    /// File extension used for the media part
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Tiff => "tiff",
+            ImageFormat::WebP => "webp",
+            ImageFormat::Svg => "svg",
+            ImageFormat::Emf => "emf",
+            ImageFormat::Wmf => "wmf",
+        }
+    }
+}
+
+/// What an image header says about its size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ImageHeader {
+    pub format: ImageFormat,
+    /// Stored pixel width and height
+    pub pixels: Option<(u32, u32)>,
+    /// Horizontal and vertical resolution
+    pub dpi: Option<(f64, f64)>,
+    /// EXIF orientation (1-8, 1 when absent)
+    pub orientation: u16,
+}
+
+impl ImageHeader {
+    /// Read the header of an image
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let format = ImageFormat::sniff(data)?;
+        let mut header = ImageHeader {
+            format,
+            pixels: None,
+            dpi: None,
+            orientation: 1,
+        };
+        match format {
+            ImageFormat::Png => read_png(data, &mut header),
+            ImageFormat::Jpeg => read_jpeg(data, &mut header),
+            ImageFormat::Gif => header.pixels = Some((le16(data, 6)?, le16(data, 8)?)),
+            ImageFormat::Bmp => read_bmp(data, &mut header),
+            ImageFormat::Tiff => read_tiff(data, &mut header, true),
+            ImageFormat::WebP => header.pixels = webp_size(data),
+            ImageFormat::Svg => header.pixels = svg_size(data),
+            ImageFormat::Emf => header.pixels = emf_size(data),
+            ImageFormat::Wmf => header.pixels = wmf_size(data),
+        }
+        Some(header)
+    }
+
+    /// Displayed size in EMU, after EXIF rotation
+    pub fn natural_size(&self) -> Option<(i64, i64)> {
+        let (w, h) = self.pixels.filter(|&(w, h)| w > 0 && h > 0)?;
+        let (dpi_x, dpi_y) = self
+            .dpi
+            .filter(|&(x, y)| x > 0.0 && y > 0.0)
+            .unwrap_or((DEFAULT_DPI, DEFAULT_DPI));
+        let cx = (w as f64 / dpi_x * EMU_PER_INCH).round() as i64;
+        let cy = (h as f64 / dpi_y * EMU_PER_INCH).round() as i64;
+        // Orientations 5-8 turn the image a quarter
+        match self.orientation {
+            5..=8 => Some((cy, cx)),
+            _ => Some((cx, cy)),
+        }
+    }
+}
+
+fn be16(data: &[u8], i: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(data.get(i..i + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(data: &[u8], i: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(i..i + 4)?.try_into().ok()?))
+}
+
+fn le16(data: &[u8], i: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(data.get(i..i + 2)?.try_into().ok()?) as u32)
+}
+
+fn le32(data: &[u8], i: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(data.get(i..i + 4)?.try_into().ok()?))
+}
+
+/// PNG: IHDR size and the pHYs chunk
+fn read_png(data: &[u8], header: &mut ImageHeader) {
+    header.pixels = be32(data, 16).zip(be32(data, 20));
+    let mut i = 8;
+    while let Some(length) = be32(data, i) {
+        let kind = data.get(i + 4..i + 8);
+        if kind == Some(b"pHYs") && data.get(i + 16) == Some(&1) {
+            // Pixels per metre
+            header.dpi = be32(data, i + 8)
+                .zip(be32(data, i + 12))
+                .map(|(x, y)| (x as f64 * 0.0254, y as f64 * 0.0254));
+        }
+        if kind == Some(b"IDAT") || kind == Some(b"IEND") {
+            break;
+        }
+        i += 12 + length as usize;
+    }
+}
+
+/// JPEG: walk the segments for JFIF density, EXIF and the frame size
+fn read_jpeg(data: &[u8], header: &mut ImageHeader) {
+    let mut i = 2;
+    while data.get(i) == Some(&0xFF) {
+        let Some(&marker) = data.get(i + 1) else {
+            return;
+        };
+        let Some(length) = be16(data, i + 2).map(|l| l as usize) else {
+            return;
+        };
+        let segment = data.get(i + 4..i + 2 + length).unwrap_or_default();
+        match marker {
+            0xE0 if segment.starts_with(b"JFIF\0") && header.dpi.is_none() => {
+                let density = be16(segment, 8).zip(be16(segment, 10));
+                header.dpi = match segment.get(7) {
+                    Some(1) => density.map(|(x, y)| (x as f64, y as f64)),
+                    Some(2) => density.map(|(x, y)| (x as f64 * 2.54, y as f64 * 2.54)),
+                    _ => None,
+                };
+            }
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                read_tiff(&segment[6..], header, false);
+            }
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                header.pixels = be16(data, i + 7).zip(be16(data, i + 5));
+                return;
+            }
+            _ => {}
+        }
+        i += 2 + length;
+    }
+}
+
+/// BMP: BITMAPINFOHEADER size and pixels per metre
+fn read_bmp(data: &[u8], header: &mut ImageHeader) {
+    header.pixels = match le32(data, 14) {
+        // BITMAPCOREHEADER has 16-bit sizes
+        Some(12) => le16(data, 18).zip(le16(data, 20)),
+        _ => le32(data, 18)
+            .zip(le32(data, 22))
+            .map(|(w, h)| (w.unsigned_abs(), h.unsigned_abs())),
+    };
+    header.dpi = le32(data, 38)
+        .zip(le32(data, 42))
+        .filter(|&(x, y)| x > 0 && y > 0)
+        .map(|(x, y)| (x as f64 * 0.0254, y as f64 * 0.0254));
+}
+
+/// TIFF (or EXIF) IFD0: size, resolution and orientation
+fn read_tiff(data: &[u8], header: &mut ImageHeader, read_size: bool) {
+    let big_endian = data.starts_with(b"MM");
+    let u16_at = |i: usize| match big_endian {
+        true => be16(data, i),
+        false => le16(data, i),
+    };
+    let u32_at = |i: usize| match big_endian {
+        true => be32(data, i),
+        false => le32(data, i).map(|v| v as u32),
+    };
+    let Some(ifd) = u32_at(4).map(|o| o as usize) else {
+        return;
+    };
+    let Some(count) = u16_at(ifd) else {
+        return;
+    };
+
+    let (mut width, mut height) = (None, None);
+    let (mut x_res, mut y_res, mut unit) = (None, None, 2);
+    for n in 0..count as usize {
+        let entry = ifd + 2 + n * 12;
+        let (Some(tag), Some(kind)) = (u16_at(entry), u16_at(entry + 2)) else {
+            break;
+        };
+        // SHORT values sit in the first half of the value field
+        let value = match kind {
+            3 => u16_at(entry + 8),
+            _ => u32_at(entry + 8),
+        };
+        let rational = || {
+            let offset = u32_at(entry + 8)? as usize;
+            let (num, den) = (u32_at(offset)?, u32_at(offset + 4)?);
+            (den != 0).then(|| num as f64 / den as f64)
+        };
+        match tag {
+            0x0100 => width = value,
+            0x0101 => height = value,
+            0x0112 => header.orientation = value.map_or(1, |v| v as u16),
+            0x011A => x_res = rational(),
+            0x011B => y_res = rational(),
+            0x0128 => unit = value.unwrap_or(2),
+            _ => {}
+        }
+    }
+
+    if read_size {
+        header.pixels = width.zip(height);
+    }
+    let scale = match unit {
+        2 => Some(1.0),
+        3 => Some(2.54),
+        _ => None,
+    };
+    if let (Some(x), Some(y), Some(scale)) = (x_res, y_res, scale) {
+        // A JFIF density is more reliable than an EXIF default of 72
+        if header.dpi.is_none() || read_size {
+            header.dpi = Some((x * scale, y * scale));
+        }
+    }
+}
+
+/// WebP: lossy, lossless or extended canvas size
+fn webp_size(data: &[u8]) -> Option<(u32, u32)> {
+    let chunk = data.get(12..16)?;
+    let le24 = |i: usize| Some(le16(data, i)? | (*data.get(i + 2)? as u32) << 16);
+    match chunk {
+        b"VP8 " => Some((le16(data, 26)? & 0x3FFF, le16(data, 28)? & 0x3FFF)),
+        b"VP8L" => {
+            let bits = le32(data, 21)? as u32;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+        _ => None,
+    }
+}
+
+/// Attributes of the root `svg` element, if the data is an SVG document
+fn svg_root(data: &[u8]) -> Option<Vec<(String, String)>> {
+    let text = std::str::from_utf8(data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data)).ok()?;
+    let mut reader = Reader::from_str(text);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) | Event::Empty(e) => {
+                if e.local_name().as_ref() != b"svg" {
+                    return None;
+                }
+                return Some(
+                    e.attributes()
+                        .filter_map(|a| a.ok())
+                        .map(|a| {
+                            let key = String::from_utf8_lossy(a.key.local_name().as_ref()).into();
+                            let value = a.unescape_value().map(|v| v.into_owned());
+                            (key, value.unwrap_or_default())
+                        })
+                        .collect(),
+                );
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+/// SVG: width and height in CSS pixels, falling back to the viewBox
+fn svg_size(data: &[u8]) -> Option<(u32, u32)> {
+    let attrs = svg_root(data)?;
+    let attr = |name: &str| {
+        attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let view_box: Vec<f64> = attr("viewBox")
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    let view_size = match view_box.as_slice() {
+        [_, _, w, h] if *w > 0.0 && *h > 0.0 => Some((*w, *h)),
+        _ => None,
+    };
+    let width = attr("width").and_then(css_px);
+    let height = attr("height").and_then(css_px);
+    let (w, h) = match (width, height, view_size) {
+        (Some(w), Some(h), _) => (w, h),
+        (Some(w), None, Some((vw, vh))) => (w, w * vh / vw),
+        (None, Some(h), Some((vw, vh))) => (h * vw / vh, h),
+        (None, None, Some(size)) => size,
+        _ => return None,
+    };
+    Some((w.round() as u32, h.round() as u32))
+}
+
+/// A CSS length in pixels (percentages are not lengths)
+fn css_px(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    let scale = match value[split..].trim() {
+        "" | "px" => 1.0,
+        "pt" => 96.0 / 72.0,
+        "pc" => 16.0,
+        "in" => 96.0,
+        "cm" => 96.0 / 2.54,
+        "mm" => 96.0 / 25.4,
+        _ => return None,
+    };
+    Some(number * scale).filter(|px| *px > 0.0)
+}
+
+/// EMF: the header frame, in hundredths of a millimetre
+fn emf_size(data: &[u8]) -> Option<(u32, u32)> {
+    let (left, top) = (le32(data, 24)?, le32(data, 28)?);
+    let (right, bottom) = (le32(data, 32)?, le32(data, 36)?);
+    let to_px = |v: i32| (v as f64 / 2540.0 * DEFAULT_DPI).round() as u32;
+    Some((to_px(right - left), to_px(bottom - top)))
+}
+
+/// WMF: the placeable header's bounding box in units per inch
+fn wmf_size(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(&[0xD7, 0xCD, 0xC6, 0x9A]) {
+        return None;
+    }
+    let signed = |i: usize| le16(data, i).map(|v| v as u16 as i16 as f64);
+    let (left, top, right, bottom) = (signed(6)?, signed(8)?, signed(10)?, signed(12)?);
+    let per_inch = le16(data, 14).filter(|v| *v > 0)? as f64;
+    let to_px = |v: f64| (v.abs() / per_inch * DEFAULT_DPI).round() as u32;
+    Some((to_px(right - left), to_px(bottom - top)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, ppm: Option<u32>) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend([8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        if let Some(ppm) = ppm {
+            data.extend(b"\0\0\0\x09pHYs");
+            data.extend(ppm.to_be_bytes());
+            data.extend(ppm.to_be_bytes());
+            data.extend([1, 0, 0, 0, 0]);
+        }
+        data.extend(b"\0\0\0\0IEND\xAE\x42\x60\x82");
+        data
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(ImageFormat::sniff(&png(1, 1, None)), Some(ImageFormat::Png));
+        assert_eq!(
+            ImageFormat::sniff(b"GIF89a\x01\0\x01\0"),
+            Some(ImageFormat::Gif)
+        );
+        assert_eq!(
+            ImageFormat::sniff(b"II*\0\x08\0\0\0"),
+            Some(ImageFormat::Tiff)
+        );
+        assert_eq!(
+            ImageFormat::sniff(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(
+            ImageFormat::sniff(b"<?xml version=\"1.0\"?>\n<!-- logo -->\n<svg/>"),
+            Some(ImageFormat::Svg)
+        );
+        assert_eq!(ImageFormat::sniff(b"<html/>"), None);
+        assert_eq!(ImageFormat::sniff(b"\0\0\0\0"), None);
+        assert_eq!(ImageFormat::from_extension("JPG"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::Svg.content_type(), "image/svg+xml");
+    }
+
+    #[test]
+    fn test_png_dpi() {
+        // 7874 pixels per metre is 200 DPI
+        let header = ImageHeader::read(&png(400, 200, Some(7874))).unwrap();
+        assert_eq!(header.pixels, Some((400, 200)));
+        let (dpi, _) = header.dpi.unwrap();
+        assert!((dpi - 200.0).abs() < 0.01);
+        assert_eq!(header.natural_size(), Some((1828804, 914402)));
+
+        let header = ImageHeader::read(&png(96, 48, None)).unwrap();
+        assert_eq!(header.natural_size(), Some((914400, 457200)));
+    }
+
+    #[test]
+    fn test_jpeg_jfif_and_exif() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        // JFIF at 300 DPI
+        jpeg.extend([0xFF, 0xE0, 0, 16]);
+        jpeg.extend(b"JFIF\0\x01\x02\x01\x01\x2C\x01\x2C\0\0");
+        // EXIF with orientation 6 (rotated a quarter)
+        let mut exif = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01".to_vec();
+        exif.extend([0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+        jpeg.extend([0xFF, 0xE1, 0, exif.len() as u8 + 2]);
+        jpeg.extend(exif);
+        // SOF0 with height 600 and width 900
+        jpeg.extend([0xFF, 0xC0, 0, 11, 8, 0x02, 0x58, 0x03, 0x84, 3]);
+
+        let header = ImageHeader::read(&jpeg).unwrap();
+        assert_eq!(header.pixels, Some((900, 600)));
+        assert_eq!(header.dpi, Some((300.0, 300.0)));
+        assert_eq!(header.orientation, 6);
+        assert_eq!(header.natural_size(), Some((1828800, 2743200)));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let gif = ImageHeader::read(b"GIF89a\x20\0\x10\0").unwrap();
+        assert_eq!(gif.pixels, Some((32, 16)));
+
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="2in" viewBox="0 0 200 100"/>"#;
+        assert_eq!(ImageHeader::read(svg).unwrap().pixels, Some((192, 96)));
+        let svg = br#"<svg viewBox="0 0 300 150" width="100%"/>"#;
+        assert_eq!(ImageHeader::read(svg).unwrap().pixels, Some((300, 150)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend([0x3F, 0x01, 0x00, 0xC7, 0x00, 0x00]);
+        assert_eq!(ImageHeader::read(&webp).unwrap().pixels, Some((320, 200)));
+
+        // Placeable WMF, 1440 units per inch, 2 x 1 inches
+        let mut wmf = vec![0xD7, 0xCD, 0xC6, 0x9A, 0, 0, 0, 0, 0, 0];
+        wmf.extend([0x40, 0x0B, 0xA0, 0x05, 0xA0, 0x05]);
+        assert_eq!(ImageHeader::read(&wmf).unwrap().pixels, Some((192, 96)));
+    }
+}
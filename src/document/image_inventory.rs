@@ -0,0 +1,693 @@
+//! Image inventory and in-place replacement
+//!
+//! Pictures are collected from every story (body, headers, footers and
+//! notes), including those in tables, content controls and text boxes,
+//! whether inline, floating or legacy VML.
+
+use crate::document::drawing::{self, Picture};
+use crate::document::{
+    loaders, AnchoredImage, BlockContent, Document, ImageData, InlineImage, Paragraph,
+    ParagraphContent, RunContent, SdtContent,
+};
+use crate::error::{Error, Result};
+use crate::opc::{rel_types, Package, Part, PartUri, TargetMode};
+use crate::xml::{RawXmlElement, RawXmlNode};
+
+/// The story an image sits in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageLocation {
+    /// Main document body
+    Body,
+    /// Header, by relationship ID from the main document
+    Header(String),
+    /// Footer, by relationship ID from the main document
+    Footer(String),
+    /// Footnote, by note ID
+    Footnote(i32),
+    /// Endnote, by note ID
+    Endnote(i32),
+}
+
+/// A picture in the document (see [`Document::images`])
+#[derive(Clone, Debug)]
+pub struct ImageInfo {
+    /// Position in document order; pass to [`Document::replace_image`]
+    pub id: usize,
+    /// Story holding the picture
+    pub location: ImageLocation,
+    /// Inside a text box
+    pub in_text_box: bool,
+    /// Floating (wp:anchor or absolutely positioned VML) rather than inline
+    pub floating: bool,
+    /// Relationship ID from the story part
+    pub r_id: String,
+    /// Media part; `None` for linked or missing images
+    pub part_uri: Option<PartUri>,
+    /// Image bytes (empty for linked or missing images)
+    pub data: Vec<u8>,
+    /// Content type of the media part
+    pub content_type: String,
+    /// Displayed width in EMU
+    pub width_emu: i64,
+    /// Displayed height in EMU
+    pub height_emu: i64,
+    /// Alt text description (docPr `descr`)
+    pub description: String,
+    /// Alt text title (docPr `title`)
+    pub title: String,
+    /// Drawing name
+    pub name: String,
+    /// SVG version of the picture; `data` is then its raster fallback
+    pub svg: Option<SvgBlip>,
+}
+
+/// The SVG version of a picture (`asvg:svgBlip`)
+#[derive(Clone, Debug)]
+pub struct SvgBlip {
+    /// Relationship ID from the story part
+    pub r_id: String,
+    /// Media part; `None` when missing
+    pub part_uri: Option<PartUri>,
+    /// SVG bytes (empty when missing)
+    pub data: Vec<u8>,
+}
+
+/// Something that can hold pictures
+enum Holder<'a> {
+    Image(&'a InlineImage),
+    Anchored(&'a AnchoredImage),
+    Raw(&'a RawXmlElement),
+    InTextBox(Box<Holder<'a>>),
+}
+
+enum HolderMut<'a> {
+    Image(&'a mut InlineImage),
+    Anchored(&'a mut AnchoredImage),
+    Raw(&'a mut RawXmlElement),
+    InTextBox(Box<HolderMut<'a>>),
+}
+
+impl Holder<'_> {
+    fn pictures(&self) -> Vec<Picture> {
+        match self {
+            Holder::Image(image) => match &image.raw_xml {
+                Some(RawXmlNode::Element(raw)) => drawing::pictures(raw),
+                _ => vec![Picture {
+                    r_id: image.r_id.clone(),
+                    svg_r_id: image.svg_r_id.clone(),
+                    width_emu: image.width_emu,
+                    height_emu: image.height_emu,
+                    description: image.description.clone(),
+                    title: image.title.clone(),
+                    name: image.name.clone(),
+                    ..Default::default()
+                }],
+            },
+            Holder::Anchored(image) => vec![Picture {
+                r_id: image.r_id.clone(),
+                svg_r_id: image.svg_r_id.clone(),
+                floating: true,
+                width_emu: image.width_emu,
+                height_emu: image.height_emu,
+                description: image.description.clone(),
+                title: image.title.clone(),
+                name: image.name.clone(),
+                ..Default::default()
+            }],
+            Holder::Raw(raw) => drawing::pictures(raw),
+            Holder::InTextBox(holder) => {
+                let mut pictures = holder.pictures();
+                for picture in &mut pictures {
+                    picture.in_text_box = true;
+                }
+                pictures
+            }
+        }
+    }
+}
+
+impl HolderMut<'_> {
+    fn as_ref(&self) -> Holder<'_> {
+        match self {
+            HolderMut::Image(image) => Holder::Image(image),
+            HolderMut::Anchored(image) => Holder::Anchored(image),
+            HolderMut::Raw(raw) => Holder::Raw(raw),
+            HolderMut::InTextBox(holder) => Holder::InTextBox(Box::new(HolderMut::as_ref(holder))),
+        }
+    }
+
+    /// Repoint and resize one of the holder's pictures
+    ///
+    /// `svg` is `None` to leave the SVG version alone, or its new relationship ID.
+    fn update(
+        &mut self,
+        picture: &Picture,
+        r_id: Option<&str>,
+        svg: Option<Option<&str>>,
+        size: (i64, i64),
+    ) {
+        match self {
+            HolderMut::Image(image) => {
+                if let Some(r_id) = r_id {
+                    image.set_r_id(r_id);
+                }
+                if let Some(svg) = svg {
+                    image.set_svg_r_id(svg.map(String::from));
+                }
+                image.set_size(size.0, size.1);
+            }
+            HolderMut::Anchored(image) => {
+                if let Some(r_id) = r_id {
+                    image.set_r_id(r_id);
+                }
+                if let Some(svg) = svg {
+                    image.set_svg_r_id(svg.map(String::from));
+                }
+                image.set_size(size.0, size.1);
+            }
+            HolderMut::Raw(raw) => {
+                if let Some(r_id) = r_id {
+                    drawing::set_rel_id(raw, picture, r_id);
+                }
+                if let Some(svg) = svg {
+                    drawing::set_svg_rel_id(raw, picture, svg);
+                }
+                drawing::set_size(raw, picture, size.0, size.1);
+            }
+            HolderMut::InTextBox(holder) => holder.update(picture, r_id, svg, size),
+        }
+    }
+}
+
+fn blocks<'a>(items: &'a [BlockContent], out: &mut Vec<Holder<'a>>) {
+    for block in items {
+        match block {
+            BlockContent::Paragraph(p) => content(&p.content, out),
+            BlockContent::Table(t) => {
+                for cell in t.rows.iter().flat_map(|r| r.cells.iter()) {
+                    paragraphs(&cell.paragraphs, &cell.unknown_children, out);
+                }
+            }
+            BlockContent::Sdt(sdt) => match &sdt.content {
+                SdtContent::Blocks(inner) => blocks(inner, out),
+                SdtContent::Inline(inner) => content(inner, out),
+                _ => {}
+            },
+            BlockContent::Unknown(node) => raw(node, out),
+        }
+    }
+}
+
+fn paragraphs<'a>(paras: &'a [Paragraph], unknown: &'a [RawXmlNode], out: &mut Vec<Holder<'a>>) {
+    for para in paras {
+        content(&para.content, out);
+    }
+    for node in unknown {
+        raw(node, out);
+    }
+}
+
+fn content<'a>(items: &'a [ParagraphContent], out: &mut Vec<Holder<'a>>) {
+    for item in items {
+        match item {
+            ParagraphContent::Run(run) => runs(std::slice::from_ref(run), out),
+            ParagraphContent::Hyperlink(link) => content(&link.content, out),
+            ParagraphContent::Revision(rev) => content(&rev.content, out),
+            ParagraphContent::SimpleField(field) => content(&field.content, out),
+            ParagraphContent::Sdt(sdt) => match &sdt.content {
+                SdtContent::Blocks(inner) => blocks(inner, out),
+                SdtContent::Inline(inner) => content(inner, out),
+                _ => {}
+            },
+            ParagraphContent::Unknown(node) => raw(node, out),
+            _ => {}
+        }
+    }
+}
+
+fn runs<'a>(items: &'a [crate::document::Run], out: &mut Vec<Holder<'a>>) {
+    for item in items.iter().flat_map(|r| r.content.iter()) {
+        match item {
+            RunContent::Drawing(image) => out.push(Holder::Image(image)),
+            RunContent::AnchoredDrawing(image) => out.push(Holder::Anchored(image)),
+            RunContent::TextBox(text_box) => {
+                let mut inner = Vec::new();
+                blocks(&text_box.content, &mut inner);
+                out.extend(inner.into_iter().map(|h| Holder::InTextBox(Box::new(h))));
+            }
+            RunContent::Unknown(node) => raw(node, out),
+            _ => {}
+        }
+    }
+}
+
+fn raw<'a>(node: &'a RawXmlNode, out: &mut Vec<Holder<'a>>) {
+    if let RawXmlNode::Element(e) = node {
+        out.push(Holder::Raw(e));
+    }
+}
+
+fn blocks_mut<'a>(items: &'a mut [BlockContent], out: &mut Vec<HolderMut<'a>>) {
+    for block in items {
+        match block {
+            BlockContent::Paragraph(p) => content_mut(&mut p.content, out),
+            BlockContent::Table(t) => {
+                for cell in t.rows.iter_mut().flat_map(|r| r.cells.iter_mut()) {
+                    paragraphs_mut(&mut cell.paragraphs, &mut cell.unknown_children, out);
+                }
+            }
+            BlockContent::Sdt(sdt) => match &mut sdt.content {
+                SdtContent::Blocks(inner) => blocks_mut(inner, out),
+                SdtContent::Inline(inner) => content_mut(inner, out),
+                _ => {}
+            },
+            BlockContent::Unknown(node) => raw_mut(node, out),
+        }
+    }
+}
+
+fn paragraphs_mut<'a>(
+    paras: &'a mut [Paragraph],
+    unknown: &'a mut [RawXmlNode],
+    out: &mut Vec<HolderMut<'a>>,
+) {
+    for para in paras {
+        content_mut(&mut para.content, out);
+    }
+    for node in unknown {
+        raw_mut(node, out);
+    }
+}
+
+fn content_mut<'a>(items: &'a mut [ParagraphContent], out: &mut Vec<HolderMut<'a>>) {
+    for item in items {
+        match item {
+            ParagraphContent::Run(run) => runs_mut(std::slice::from_mut(run), out),
+            ParagraphContent::Hyperlink(link) => content_mut(&mut link.content, out),
+            ParagraphContent::Revision(rev) => content_mut(&mut rev.content, out),
+            ParagraphContent::SimpleField(field) => content_mut(&mut field.content, out),
+            ParagraphContent::Sdt(sdt) => match &mut sdt.content {
+                SdtContent::Blocks(inner) => blocks_mut(inner, out),
+                SdtContent::Inline(inner) => content_mut(inner, out),
+                _ => {}
+            },
+            ParagraphContent::Unknown(node) => raw_mut(node, out),
+            _ => {}
+        }
+    }
+}
+
+fn runs_mut<'a>(items: &'a mut [crate::document::Run], out: &mut Vec<HolderMut<'a>>) {
+    for item in items.iter_mut().flat_map(|r| r.content.iter_mut()) {
+        match item {
+            RunContent::Drawing(image) => out.push(HolderMut::Image(image)),
+            RunContent::AnchoredDrawing(image) => out.push(HolderMut::Anchored(image)),
+            RunContent::TextBox(text_box) => {
+                let mut inner = Vec::new();
+                blocks_mut(&mut text_box.content, &mut inner);
+                out.extend(inner.into_iter().map(|h| HolderMut::InTextBox(Box::new(h))));
+            }
+            RunContent::Unknown(node) => raw_mut(node, out),
+            _ => {}
+        }
+    }
+}
+
+fn raw_mut<'a>(node: &'a mut RawXmlNode, out: &mut Vec<HolderMut<'a>>) {
+    if let RawXmlNode::Element(e) = node {
+        out.push(HolderMut::Raw(e));
+    }
+}
+
+/// First unused `/word/media/imageN.ext`
+pub(crate) fn next_media_uri(package: &Package, extension: &str) -> PartUri {
+    let first = package
+        .part_uris()
+        .filter(|u| u.as_str().starts_with("/word/media/"))
+        .count()
+        + 1;
+    (first..)
+        .map(|n| format!("/word/media/image{}.{}", n, extension))
+        .find(|path| package.part_uris().all(|u| u.as_str() != path))
+        .and_then(|path| PartUri::new(&path).ok())
+        .expect("valid media URI")
+}
+
+/// Relationship target of a media part from a story part
+pub(crate) fn media_target(story: &PartUri, media: &PartUri) -> String {
+    let folder = story
+        .parent()
+        .map(|p| format!("{}/", p.as_str()))
+        .unwrap_or_default();
+    match media.as_str().strip_prefix(folder.as_str()) {
+        Some(relative) if !folder.is_empty() => relative.to_string(),
+        _ => media.as_str().to_string(),
+    }
+}
+
+/// Largest size with the aspect ratio of `natural` that fits in `bounds`
+fn fit_aspect(bounds: (i64, i64), natural: (i64, i64)) -> (i64, i64) {
+    let (w, h) = (natural.0.max(1) as f64, natural.1.max(1) as f64);
+    let scale = (bounds.0 as f64 / w).min(bounds.1 as f64 / h);
+    ((w * scale).round() as i64, (h * scale).round() as i64)
+}
+
+/// A picture found in the document, with its media parts but not their data
+struct PictureRef {
+    location: ImageLocation,
+    picture: Picture,
+    /// Embedded media part; `None` for linked or missing images
+    part_uri: Option<PartUri>,
+    content_type: String,
+    /// Media part of the SVG version, if the picture has one
+    svg_part_uri: Option<PartUri>,
+}
+
+impl Document {
+    /// Get every picture in the document, in document order
+    ///
+    /// Covers the body, headers, footers, footnotes and endnotes, including
+    /// pictures in tables, content controls and text boxes.
+    pub fn images(&self) -> Vec<ImageInfo> {
+        let data = |uri: Option<&PartUri>| {
+            uri.and_then(|uri| self.package.part(uri))
+                .map(|part| part.data().to_vec())
+                .unwrap_or_default()
+        };
+        self.picture_refs()
+            .into_iter()
+            .enumerate()
+            .map(|(id, found)| {
+                let picture = found.picture;
+                let svg = picture.svg_r_id.map(|r_id| SvgBlip {
+                    r_id,
+                    data: data(found.svg_part_uri.as_ref()),
+                    part_uri: found.svg_part_uri,
+                });
+                ImageInfo {
+                    id,
+                    location: found.location,
+                    in_text_box: picture.in_text_box,
+                    floating: picture.floating,
+                    r_id: picture.r_id,
+                    data: data(found.part_uri.as_ref()),
+                    part_uri: found.part_uri,
+                    content_type: found.content_type,
+                    width_emu: picture.width_emu,
+                    height_emu: picture.height_emu,
+                    description: picture.description,
+                    title: picture.title,
+                    name: picture.name,
+                    svg,
+                }
+            })
+            .collect()
+    }
+
+    /// Every picture in document order, as numbered by [`Document::images`]
+    ///
+    /// Media parts are looked up without reading their data.
+    fn picture_refs(&self) -> Vec<PictureRef> {
+        let mut found = Vec::new();
+        for (location, holders) in self.image_holders() {
+            let story = self.story_uri(&location);
+            let rels = story
+                .as_ref()
+                .and_then(|uri| self.package.part(uri))
+                .and_then(|part| part.relationships());
+            // Embedded media part of a relationship
+            let media = |r_id: &str| {
+                let rel = rels
+                    .and_then(|rels| rels.get(r_id))
+                    .filter(|rel| rel.target_mode == TargetMode::Internal)?;
+                let uri = story.as_ref()?.resolve(&rel.target).ok()?;
+                self.package.part_entry(&uri)
+            };
+            for picture in holders.iter().flat_map(Holder::pictures) {
+                let part = media(&picture.r_id).filter(|_| !picture.linked);
+                let svg = picture.svg_r_id.as_deref().and_then(media);
+                found.push(PictureRef {
+                    location: location.clone(),
+                    part_uri: part.map(|p| p.uri().clone()),
+                    content_type: part
+                        .map(|p| p.content_type().to_string())
+                        .unwrap_or_default(),
+                    svg_part_uri: svg.map(|p| p.uri().clone()),
+                    picture,
+                });
+            }
+        }
+        found
+    }
+
+    /// Replace the picture with an ID from [`Document::images`], keeping its size
+    ///
+    /// A media part used only by this picture is replaced; otherwise the
+    /// picture gets a new media part and relationship, so other drawings
+    /// keep the old image. An SVG image with a fallback (see
+    /// [`ImageData::svg`]) replaces both blips; any other image drops the
+    /// picture's SVG version.
+    pub fn replace_image(&mut self, id: usize, image: ImageData) -> Result<()> {
+        self.replace_image_sized(id, image, false)
+    }
+
+    /// Replace a picture, rescaling it to the new image's aspect ratio
+    ///
+    /// The new size is the largest that fits in the old one, taking EXIF
+    /// rotation into account. Fails with [`Error::Unsupported`] when the new
+    /// image's size cannot be read.
+    pub fn replace_image_keep_aspect(&mut self, id: usize, image: ImageData) -> Result<()> {
+        self.replace_image_sized(id, image, true)
+    }
+
+    fn replace_image_sized(
+        &mut self,
+        id: usize,
+        image: ImageData,
+        keep_aspect: bool,
+    ) -> Result<()> {
+        let images = self.picture_refs();
+        let info = images
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("image {}", id)))?;
+        let story = self
+            .story_uri(&info.location)
+            .ok_or_else(|| Error::MissingPart(format!("story of image {}", id)))?;
+        let old_size = (info.picture.width_emu, info.picture.height_emu);
+        let size = match keep_aspect {
+            true => fit_aspect(
+                old_size,
+                image.natural_size().ok_or_else(|| {
+                    Error::Unsupported("cannot read the size of the image".into())
+                })?,
+            ),
+            false => old_size,
+        };
+
+        let (image, svg) = image.into_blips();
+        let sole_user = info.part_uri.as_ref().filter(|uri| {
+            images
+                .iter()
+                .filter(|i| i.part_uri.as_ref() == Some(uri))
+                .count()
+                == 1
+        });
+        let new_r_id = match sole_user {
+            Some(old) if info.content_type == image.content_type => {
+                if let Some(part) = self.package.part_entry_mut(old) {
+                    part.set_data(image.data);
+                }
+                None
+            }
+            Some(old) => {
+                // Swap the part for one with a matching extension under the same relationship
+                let old = old.clone();
+                self.package.remove_part(&old);
+                let uri = next_media_uri(&self.package, &image.extension);
+                let target = media_target(&story, &uri);
+                self.package
+                    .add_part(Part::new(uri, image.content_type, image.data));
+                if let Some(rels) = self
+                    .package
+                    .part_mut(&story)
+                    .and_then(|p| p.relationships_mut())
+                {
+                    rels.add_with_id(
+                        &info.picture.r_id,
+                        rel_types::IMAGE,
+                        &target,
+                        TargetMode::Internal,
+                    );
+                }
+                None
+            }
+            None => Some(self.add_story_media(&story, image)?),
+        };
+
+        let old_svg = info.svg_part_uri.as_ref().filter(|uri| {
+            images
+                .iter()
+                .filter(|i| i.svg_part_uri.as_ref() == Some(uri))
+                .count()
+                == 1
+        });
+        let new_svg = match (svg, old_svg) {
+            (Some(svg), Some(old)) => {
+                if let Some(part) = self.package.part_entry_mut(old) {
+                    part.set_data(svg.data);
+                }
+                None
+            }
+            (Some(svg), None) => Some(Some(self.add_story_media(&story, svg)?)),
+            (None, old) => {
+                // An SVG no other picture shows goes with its relationship
+                if let (Some(uri), Some(r_id)) = (old, &info.picture.svg_r_id) {
+                    self.package.remove_part(uri);
+                    if let Some(rels) = self
+                        .package
+                        .part_mut(&story)
+                        .and_then(|p| p.relationships_mut())
+                    {
+                        rels.remove(r_id);
+                    }
+                }
+                info.picture.svg_r_id.as_ref().map(|_| None)
+            }
+        };
+
+        if new_r_id.is_some() || new_svg.is_some() || size != old_size {
+            // Offset of the picture within its story
+            let location = info.location.clone();
+            let mut index = id
+                - images
+                    .iter()
+                    .position(|i| i.location == location)
+                    .unwrap_or(0);
+            let mut stories = self.image_holders_mut();
+            let holders = stories
+                .iter_mut()
+                .find(|(l, _)| *l == location)
+                .map(|(_, holders)| holders);
+            for holder in holders.into_iter().flatten() {
+                let pictures = holder.as_ref().pictures();
+                if let Some(picture) = pictures.get(index) {
+                    let svg = new_svg.as_ref().map(Option::as_deref);
+                    holder.update(picture, new_r_id.as_deref(), svg, size);
+                    break;
+                }
+                index -= pictures.len();
+            }
+            drop(stories);
+            self.mark_story_modified(&location);
+        }
+        Ok(())
+    }
+
+    /// Add a media part and an image relationship to it from a story
+    fn add_story_media(&mut self, story: &PartUri, image: ImageData) -> Result<String> {
+        let uri = next_media_uri(&self.package, &image.extension);
+        let target = media_target(story, &uri);
+        self.package
+            .add_part(Part::new(uri, image.content_type, image.data));
+        let part = self
+            .package
+            .part_mut(story)
+            .ok_or_else(|| Error::MissingPart(story.to_string()))?;
+        Ok(part.ensure_relationships().add(rel_types::IMAGE, &target))
+    }
+
+    /// Picture holders of each story, in document order
+    fn image_holders(&self) -> Vec<(ImageLocation, Vec<Holder<'_>>)> {
+        let mut stories = Vec::new();
+        let mut body = Vec::new();
+        blocks(&self.body.content, &mut body);
+        stories.push((ImageLocation::Body, body));
+
+        let (headers, footers) = self.headers_footers.get(&self.package);
+        for (r_id, hf) in headers.iter().chain(footers) {
+            let mut holders = Vec::new();
+            paragraphs(&hf.paragraphs, &hf.unknown_children, &mut holders);
+            let location = match hf.is_header {
+                true => ImageLocation::Header(r_id.clone()),
+                false => ImageLocation::Footer(r_id.clone()),
+            };
+            stories.push((location, holders));
+        }
+
+        for (notes, is_footnote) in [(self.footnotes(), true), (self.endnotes(), false)] {
+            for note in notes.iter().flat_map(|n| n.notes.iter()) {
+                let mut holders = Vec::new();
+                paragraphs(&note.paragraphs, &note.unknown_children, &mut holders);
+                let location = match is_footnote {
+                    true => ImageLocation::Footnote(note.id),
+                    false => ImageLocation::Endnote(note.id),
+                };
+                stories.push((location, holders));
+            }
+        }
+        stories
+    }
+
+    /// Mutable picture holders, in the same order as [`Document::image_holders`]
+    fn image_holders_mut(&mut self) -> Vec<(ImageLocation, Vec<HolderMut<'_>>)> {
+        let mut stories = Vec::new();
+        let mut body = Vec::new();
+        blocks_mut(&mut self.body.content, &mut body);
+        stories.push((ImageLocation::Body, body));
+
+        let (headers, footers) = self.headers_footers.get_mut(&self.package);
+        for (r_id, hf) in headers.iter_mut().chain(footers) {
+            let mut holders = Vec::new();
+            paragraphs_mut(&mut hf.paragraphs, &mut hf.unknown_children, &mut holders);
+            let location = match hf.is_header {
+                true => ImageLocation::Header(r_id.clone()),
+                false => ImageLocation::Footer(r_id.clone()),
+            };
+            stories.push((location, holders));
+        }
+
+        let footnotes = self.footnotes.get_mut(&self.package);
+        let endnotes = self.endnotes.get_mut(&self.package);
+        for (notes, is_footnote) in [(footnotes, true), (endnotes, false)] {
+            for note in notes.iter_mut().flat_map(|n| n.notes.iter_mut()) {
+                let mut holders = Vec::new();
+                paragraphs_mut(
+                    &mut note.paragraphs,
+                    &mut note.unknown_children,
+                    &mut holders,
+                );
+                let location = match is_footnote {
+                    true => ImageLocation::Footnote(note.id),
+                    false => ImageLocation::Endnote(note.id),
+                };
+                stories.push((location, holders));
+            }
+        }
+        stories
+    }
+
+    /// URI of the part holding a story
+    fn story_uri(&self, location: &ImageLocation) -> Option<PartUri> {
+        match location {
+            ImageLocation::Body => loaders::main_document_uri(&self.package),
+            ImageLocation::Header(r_id) | ImageLocation::Footer(r_id) => {
+                loaders::doc_part_uri_by_id(&self.package, r_id)
+            }
+            ImageLocation::Footnote(_) => {
+                loaders::doc_part_uri(&self.package, rel_types::FOOTNOTES)
+            }
+            ImageLocation::Endnote(_) => loaders::doc_part_uri(&self.package, rel_types::ENDNOTES),
+        }
+    }
+
+    fn mark_story_modified(&mut self, location: &ImageLocation) {
+        match location {
+            ImageLocation::Body => self.mark_body_modified(),
+            ImageLocation::Header(r_id) | ImageLocation::Footer(r_id) => {
+                self.mark_header_footer_modified(r_id)
+            }
+            ImageLocation::Footnote(_) => self.mark_doc_part_modified(rel_types::FOOTNOTES),
+            ImageLocation::Endnote(_) => self.mark_doc_part_modified(rel_types::ENDNOTES),
+        }
+    }
+}
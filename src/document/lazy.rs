@@ -0,0 +1,42 @@
+//! Sub-models parsed from their package part on first access
+
+use crate::opc::Package;
+use std::sync::OnceLock;
+
+/// A document sub-model (styles, numbering, ...) parsed on first access
+#[derive(Debug)]
+pub(crate) struct Lazy<T> {
+    cell: OnceLock<T>,
+    load: fn(&Package) -> T,
+}
+
+impl<T> Lazy<T> {
+    /// Create a model that is parsed from the package with `load`
+    pub fn new(load: fn(&Package) -> T) -> Self {
+        Self {
+            cell: OnceLock::new(),
+            load,
+        }
+    }
+
+    /// Get the model, parsing it on first access
+    pub fn get(&self, package: &Package) -> &T {
+        self.cell.get_or_init(|| (self.load)(package))
+    }
+
+    /// Get the model mutably, parsing it on first access
+    pub fn get_mut(&mut self, package: &Package) -> &mut T {
+        self.get(package);
+        self.cell.get_mut().expect("initialized above")
+    }
+
+    /// Get the model only if it has already been parsed
+    pub fn loaded(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Get the model mutably only if it has already been parsed
+    pub fn loaded_mut(&mut self) -> Option<&mut T> {
+        self.cell.get_mut()
+    }
+}
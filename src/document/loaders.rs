@@ -1,28 +1,52 @@
 //! Package loading helpers for Document parts
 
 use crate::document::comments::Comments;
+use crate::document::custom_properties::CustomProperties;
+use crate::document::extended_properties::ExtendedProperties;
 use crate::document::footnotes::Notes;
 use crate::document::header_footer::HeaderFooter;
 use crate::document::properties::CoreProperties;
+use crate::document::settings::Settings;
 use crate::document::styles::Styles;
 use crate::document::HeaderFooterList;
 use crate::document::Numbering;
 use crate::opc::{Package, PartUri};
 
+/// Resolve the URI of the main document part
+pub fn main_document_uri(package: &Package) -> Option<PartUri> {
+    let rel = package
+        .relationships()
+        .by_type(crate::opc::rel_types::OFFICE_DOCUMENT)?;
+    package_target_uri(&rel.target)
+}
+
+/// Resolve the URI of the core properties part
+pub fn core_properties_uri(package: &Package) -> Option<PartUri> {
+    package_part_uri(package, crate::opc::rel_types::CORE_PROPERTIES)
+}
+
+/// Resolve the URI of a package-level part by relationship type
+pub fn package_part_uri(package: &Package, rel_type: &str) -> Option<PartUri> {
+    let rel = package.relationships().by_type(rel_type)?;
+    package_target_uri(&rel.target)
+}
+
+/// Resolve the URI of a document-level part by relationship type
+pub fn doc_part_uri(package: &Package, rel_type: &str) -> Option<PartUri> {
+    let rels = package.main_document_part()?.relationships()?;
+    doc_target_uri(&rels.by_type(rel_type)?.target)
+}
+
+/// Resolve the URI of a document-level part by relationship ID
+pub fn doc_part_uri_by_id(package: &Package, r_id: &str) -> Option<PartUri> {
+    let rels = package.main_document_part()?.relationships()?;
+    doc_target_uri(&rels.get(r_id)?.target)
+}
+
 /// Load numbering definitions from the package
 pub fn load_numbering(package: &Package) -> Option<Numbering> {
-    let doc_part = package.main_document_part()?;
-    let rels = doc_part.relationships()?;
-    let numbering_rel = rels.by_type(crate::opc::rel_types::NUMBERING)?;
-    let target = &numbering_rel.target;
-    let numbering_uri = if target.starts_with('/') {
-        PartUri::new(target).ok()?
-    } else {
-        PartUri::new(&format!("/word/{}", target)).ok()?
-    };
-    let numbering_part = package.part(&numbering_uri)?;
-    let xml = numbering_part.data_as_str().ok()?;
-    Numbering::from_xml(xml).ok()
+    load_doc_part_by_rel(package, crate::opc::rel_types::NUMBERING)
+        .and_then(|xml| Numbering::from_xml(&xml).ok())
 }
 
 /// Load styles from the package
@@ -31,21 +55,33 @@ pub fn load_styles(package: &Package) -> Option<Styles> {
         .and_then(|xml| Styles::from_xml(&xml).ok())
 }
 
+/// Load document settings from the package
+pub fn load_settings(package: &Package) -> Option<Settings> {
+    load_doc_part_by_rel(package, crate::opc::rel_types::SETTINGS)
+        .and_then(|xml| Settings::from_xml(&xml).ok())
+}
+
 /// Load core properties from the package (package-level relationship)
 pub fn load_core_properties(package: &Package) -> Option<CoreProperties> {
-    let rel = package
-        .relationships()
-        .by_type(crate::opc::rel_types::CORE_PROPERTIES)?;
-    let target = &rel.target;
-    let uri = if target.starts_with('/') {
-        PartUri::new(target).ok()?
-    } else {
-        PartUri::new(&format!("/{}", target)).ok()?
-    };
+    let uri = core_properties_uri(package)?;
     let part = package.part(&uri)?;
     CoreProperties::from_xml(part.data_as_str().ok()?).ok()
 }
 
+/// Load extended properties (app.xml) from the package
+pub fn load_extended_properties(package: &Package) -> Option<ExtendedProperties> {
+    let uri = package_part_uri(package, crate::opc::rel_types::EXTENDED_PROPERTIES)?;
+    let part = package.part(&uri)?;
+    ExtendedProperties::from_xml(part.data_as_str().ok()?).ok()
+}
+
+/// Load custom properties (custom.xml) from the package
+pub fn load_custom_properties(package: &Package) -> Option<CustomProperties> {
+    let uri = package_part_uri(package, crate::opc::rel_types::CUSTOM_PROPERTIES)?;
+    let part = package.part(&uri)?;
+    CustomProperties::from_xml(part.data_as_str().ok()?).ok()
+}
+
 /// Load headers and footers from the package
 pub fn load_headers_footers(package: &Package) -> (HeaderFooterList, HeaderFooterList) {
     let mut headers = Vec::new();
@@ -85,34 +121,53 @@ pub fn load_notes(package: &Package, is_footnotes: bool) -> Option<Notes> {
     Notes::from_xml(&xml, is_footnotes).ok()
 }
 
-/// Load comments
+/// Load comments, with their threads and authors
 pub fn load_comments(package: &Package) -> Option<Comments> {
-    let xml = load_doc_part_by_rel(package, crate::opc::rel_types::COMMENTS)?;
-    Comments::from_xml(&xml).ok()
+    use crate::opc::rel_types;
+
+    let xml = load_doc_part_by_rel(package, rel_types::COMMENTS)?;
+    let mut comments = Comments::from_xml(&xml).ok()?;
+    // Thread data that fails to parse is skipped, keeping the comments
+    if let Some(xml) = load_doc_part_by_rel(package, rel_types::COMMENTS_EXTENDED) {
+        comments.read_extended(&xml).ok();
+    }
+    if let Some(xml) = load_doc_part_by_rel(package, rel_types::COMMENTS_IDS) {
+        comments.read_ids(&xml).ok();
+    }
+    if let Some(xml) = load_doc_part_by_rel(package, rel_types::PEOPLE) {
+        comments.read_people(&xml).ok();
+    }
+    Some(comments)
 }
 
 /// Helper: load a document-level part by relationship type, returning its XML string
 fn load_doc_part_by_rel(package: &Package, rel_type: &str) -> Option<String> {
-    let doc_part = package.main_document_part()?;
-    let rels = doc_part.relationships()?;
-    let rel = rels.by_type(rel_type)?;
-    let target = &rel.target;
-    let uri = if target.starts_with('/') {
-        PartUri::new(target).ok()?
-    } else {
-        PartUri::new(&format!("/word/{}", target)).ok()?
-    };
+    let uri = doc_part_uri(package, rel_type)?;
     let part = package.part(&uri)?;
     Some(part.data_as_str().ok()?.to_string())
 }
 
 /// Helper: load a header/footer part
 fn load_hf_part(package: &Package, target: &str, is_header: bool) -> Option<HeaderFooter> {
-    let uri = if target.starts_with('/') {
-        PartUri::new(target).ok()?
-    } else {
-        PartUri::new(&format!("/word/{}", target)).ok()?
-    };
+    let uri = doc_target_uri(target)?;
     let part = package.part(&uri)?;
     HeaderFooter::from_xml(part.data_as_str().ok()?, is_header).ok()
 }
+
+/// Helper: resolve a package-level relationship target
+fn package_target_uri(target: &str) -> Option<PartUri> {
+    if target.starts_with('/') {
+        PartUri::new(target).ok()
+    } else {
+        PartUri::new(&format!("/{}", target)).ok()
+    }
+}
+
+/// Helper: resolve a relationship target relative to the word/ directory
+fn doc_target_uri(target: &str) -> Option<PartUri> {
+    if target.starts_with('/') {
+        PartUri::new(target).ok()
+    } else {
+        PartUri::new(&format!("/word/{}", target)).ok()
+    }
+}
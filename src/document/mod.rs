@@ -1,51 +1,101 @@
 //! Document model - high-level API for DOCX documents
 
+mod anchored_image;
 mod body;
+mod comment_threads;
 mod comments;
+mod custom_properties;
+mod data_binding;
+mod drawing;
+mod extended_properties;
+mod field;
+mod field_update;
 mod footnotes;
 mod header_footer;
 mod image;
+mod image_format;
+mod image_inventory;
+mod lazy;
 mod loaders;
 mod numbering;
 mod paragraph;
 mod properties;
+mod protection;
+mod revision;
 mod run;
+mod sdt;
 mod section;
+mod settings;
 mod styles;
 mod table;
 mod template;
+mod text_box;
 mod text_ops;
+mod toc;
+mod tracking;
+mod writer;
 mod xml_ops;
 
+pub use anchored_image::{
+    AnchorPosition, AnchoredImage, AnchoredImageBuilder, Placement, PositionAlign, RelativeFrom,
+    WrapMode, WrapSide,
+};
 pub use body::{BlockContent, Body};
-pub use comments::{Comment, Comments};
+pub use comment_threads::Person;
+pub use comments::{Comment, CommentRange, Comments};
+pub use custom_properties::{CustomProperties, CustomProperty, PropertyValue, FMTID_USER_DEFINED};
+pub use extended_properties::ExtendedProperties;
+pub use field::{
+    Field, FieldChar, FieldCharType, FieldDisplay, FieldInstruction, FieldSwitch, SimpleField,
+};
+pub use field_update::FieldContext;
 pub use footnotes::{Note, Notes};
 pub use header_footer::HeaderFooter;
 pub use image::{ImageData, InlineImage};
+pub use image_format::ImageFormat;
+pub use image_inventory::{ImageInfo, ImageLocation, SvgBlip};
 pub use numbering::{AbstractNum, Level, LevelOverride, Num, NumberFormat, Numbering};
 pub use paragraph::{
     Alignment, Hyperlink, Indentation, LineSpacing, Paragraph, ParagraphContent,
     ParagraphProperties,
 };
 pub use properties::CoreProperties;
+pub use protection::{
+    DocumentProtection, EditRestriction, HashAlgorithm, PasswordHash, PermStart, DEFAULT_SPIN_COUNT,
+};
+pub use revision::{
+    ParagraphPropertiesChange, Revision, RevisionInfo, RevisionKind, RunPropertiesChange,
+    TrackedChange,
+};
 pub use run::{BreakType, Run, RunContent, RunProperties};
+pub use sdt::{
+    CheckboxSymbol, ContentControl, DataBinding, ListItem, Sdt, SdtCheckbox, SdtContent, SdtDate,
+    SdtDocPart, SdtKind, SdtLevel, SdtList, SdtLock, SdtProperties,
+};
 pub use section::{
     Columns, HeaderFooterRef, HeaderFooterType, PageMargin, PageOrientation, PageSize,
     SectionProperties,
 };
+pub use settings::{Settings, CURRENT_COMPATIBILITY_MODE};
 pub use styles::{DocDefaults, Style, StyleType, Styles};
 pub use table::{
     GridColumn, Table, TableAlignment, TableBuilder, TableCell, TableCellProperties, TableRow,
     TableWidth, VMerge, VerticalAlignment,
 };
 pub use template::TemplateContext;
+pub use text_box::{TextBox, TextBoxBuilder};
 pub use text_ops::TextLocation;
+pub use toc::TocOptions;
+pub use tracking::RevisionTracker;
+pub use writer::DocumentWriter;
 
 use crate::error::{Error, Result};
-use crate::opc::{Package, Part, PartUri};
+use crate::opc::{LoadOptions, Package, Part, PartUri};
 use std::path::Path;
 
-use xml_ops::{parse_document_xml, serialize_document_xml};
+use lazy::Lazy;
+use revision::{RevisionCollector, RevisionResolver};
+use xml_ops::{parse_document_xml, serialize_document_xml, DocumentRoot};
 
 /// List of headers/footers keyed by relationship ID
 type HeaderFooterList = Vec<(String, HeaderFooter)>;
@@ -57,22 +107,30 @@ pub struct Document {
     package: Package,
     /// Parsed document body
     body: Body,
+    /// Root element attributes and body siblings (preserved for round-trip)
+    root: DocumentRoot,
     /// Numbering definitions (from numbering.xml)
-    numbering: Option<Numbering>,
+    numbering: Lazy<Option<Numbering>>,
     /// Style definitions (from styles.xml)
-    styles: Option<Styles>,
+    styles: Lazy<Option<Styles>>,
+    /// Document settings (from settings.xml)
+    settings: Lazy<Option<Settings>>,
     /// Core properties (from core.xml)
-    core_properties: Option<CoreProperties>,
-    /// Headers (keyed by relationship ID)
-    headers: HeaderFooterList,
-    /// Footers (keyed by relationship ID)
-    footers: HeaderFooterList,
+    core_properties: Lazy<Option<CoreProperties>>,
+    /// Extended properties (from app.xml)
+    extended_properties: Lazy<Option<ExtendedProperties>>,
+    /// Custom properties (from custom.xml)
+    custom_properties: Lazy<Option<CustomProperties>>,
+    /// Headers and footers (keyed by relationship ID)
+    headers_footers: Lazy<(HeaderFooterList, HeaderFooterList)>,
     /// Footnotes
-    footnotes: Option<Notes>,
+    footnotes: Lazy<Option<Notes>>,
     /// Endnotes
-    endnotes: Option<Notes>,
+    endnotes: Lazy<Option<Notes>>,
     /// Comments
-    comments: Option<Comments>,
+    comments: Lazy<Option<Comments>>,
+    /// Author and ID counter while change tracking is on
+    tracker: Option<RevisionTracker>,
 }
 
 impl Document {
@@ -82,58 +140,104 @@ impl Document {
         Self::from_package(package)
     }
 
+    /// Open a document from a file path, enforcing resource limits
+    pub fn open_with<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Self> {
+        let package = Package::open_with(path, options)?;
+        Self::from_package(package)
+    }
+
     /// Open a document from bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
         let package = Package::from_bytes(bytes)?;
         Self::from_package(package)
     }
 
+    /// Open a document from bytes, enforcing resource limits
+    pub fn from_bytes_with(bytes: &[u8], options: &LoadOptions) -> Result<Self> {
+        let package = Package::from_bytes_with(bytes, options)?;
+        Self::from_package(package)
+    }
+
+    /// Open a password-encrypted document from a file path
+    ///
+    /// Fails with [`Error::WrongPassword`] if the password does not match.
+    /// Documents that are not encrypted are opened as usual.
+    pub fn open_with_password<P: AsRef<Path>>(path: P, password: &str) -> Result<Self> {
+        let package = Package::open_with_password(path, password)?;
+        Self::from_package(package)
+    }
+
+    /// Open a password-encrypted document from a file path, enforcing resource limits
+    pub fn open_with_password_and_options<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+        options: &LoadOptions,
+    ) -> Result<Self> {
+        let package = Package::open_with_password_and_options(path, password, options)?;
+        Self::from_package(package)
+    }
+
+    /// Open a password-encrypted document from bytes
+    pub fn from_bytes_with_password(bytes: &[u8], password: &str) -> Result<Self> {
+        let package = Package::from_bytes_with_password(bytes, password)?;
+        Self::from_package(package)
+    }
+
+    /// Open a password-encrypted document from bytes, enforcing resource limits
+    pub fn from_bytes_with_password_and_options(
+        bytes: &[u8],
+        password: &str,
+        options: &LoadOptions,
+    ) -> Result<Self> {
+        let package = Package::from_bytes_with_password_and_options(bytes, password, options)?;
+        Self::from_package(package)
+    }
+
     /// Create document from an OPC package
+    ///
+    /// Only document.xml is parsed here; styles, numbering, notes, comments,
+    /// headers and footers are parsed on first access.
     fn from_package(package: Package) -> Result<Self> {
         // Get main document part
-        let doc_part = package
-            .main_document_part()
-            .ok_or_else(|| Error::MissingPart("Main document part not found".into()))?;
+        let missing = || Error::MissingPart("Main document part not found".into());
+        let doc_uri = loaders::main_document_uri(&package).ok_or_else(missing)?;
+        let doc_part = package.try_part(&doc_uri)?.ok_or_else(missing)?;
 
         // Parse document.xml
         let xml = doc_part.data_as_str()?;
-        let body = parse_document_xml(xml)?;
-
-        let numbering = loaders::load_numbering(&package);
-        let styles = loaders::load_styles(&package);
-        let core_properties = loaders::load_core_properties(&package);
-        let (headers, footers) = loaders::load_headers_footers(&package);
-        let footnotes = loaders::load_notes(&package, true);
-        let endnotes = loaders::load_notes(&package, false);
-        let comments = loaders::load_comments(&package);
+        let (body, root) = parse_document_xml(xml)?;
 
         Ok(Self {
-            package,
             body,
-            numbering,
-            styles,
-            core_properties,
-            headers,
-            footers,
-            footnotes,
-            endnotes,
-            comments,
+            root,
+            ..Self::with_package(package)
         })
     }
 
     /// Create a new empty document
     pub fn new() -> Self {
+        let mut doc = Self::with_package(Package::new());
+        *doc.settings.get_mut(&doc.package) = Some(Settings::new());
+        doc
+    }
+
+    /// Empty document over a package, with sub-models not yet parsed
+    fn with_package(package: Package) -> Self {
         Self {
-            package: Package::new(),
+            package,
             body: Body::default(),
-            numbering: None,
-            styles: None,
-            core_properties: None,
-            headers: Vec::new(),
-            footers: Vec::new(),
-            footnotes: None,
-            endnotes: None,
-            comments: None,
+            root: DocumentRoot::default(),
+            numbering: Lazy::new(loaders::load_numbering),
+            styles: Lazy::new(loaders::load_styles),
+            settings: Lazy::new(loaders::load_settings),
+            core_properties: Lazy::new(loaders::load_core_properties),
+            extended_properties: Lazy::new(loaders::load_extended_properties),
+            custom_properties: Lazy::new(loaders::load_custom_properties),
+            headers_footers: Lazy::new(loaders::load_headers_footers),
+            footnotes: Lazy::new(|package| loaders::load_notes(package, true)),
+            endnotes: Lazy::new(|package| loaders::load_notes(package, false)),
+            comments: Lazy::new(loaders::load_comments),
+            tracker: None,
         }
     }
 
@@ -149,131 +253,165 @@ impl Document {
         self.package.to_bytes()
     }
 
+    /// Save the document to a file, encrypted with a password
+    pub fn save_with_password<P: AsRef<Path>>(&mut self, path: P, password: &str) -> Result<()> {
+        self.update_package()?;
+        self.package.save_with_password(path, password)
+    }
+
+    /// Save the document to bytes, encrypted with a password
+    pub fn to_bytes_with_password(&mut self, password: &str) -> Result<Vec<u8>> {
+        self.update_package()?;
+        self.package.to_bytes_with_password(password)
+    }
+
     /// Update the package with current body content
+    ///
+    /// Only parts that are new or marked modified are re-serialized; all
+    /// other parts keep their original bytes.
     fn update_package(&mut self) -> Result<()> {
         use crate::opc::rel_types;
 
-        let doc_uri = PartUri::new("/word/document.xml")?;
-
-        // 1. Write document.xml
-        let xml = serialize_document_xml(&self.body)?;
-        self.package.add_part(Part::new(
-            doc_uri.clone(),
-            crate::opc::MAIN_DOCUMENT.to_string(),
-            xml.into_bytes(),
-        ));
-
-        // Ensure package-level relationship for document
-        if self
-            .package
-            .relationships()
-            .by_type(rel_types::OFFICE_DOCUMENT)
-            .is_none()
-        {
-            self.package
-                .add_relationship(rel_types::OFFICE_DOCUMENT, doc_uri.as_str());
+        self.allocate_drawing_ids();
+
+        // 1. document.xml
+        let doc_uri = match loaders::main_document_uri(&self.package) {
+            Some(uri) => uri,
+            None => self.ensure_main_document_part(),
+        };
+        store_part(
+            &mut self.package,
+            &doc_uri,
+            crate::opc::MAIN_DOCUMENT,
+            || serialize_document_xml(&self.body, &self.root),
+        )?;
+
+        // 2. Document properties (package-level relationships)
+        if let Some(Some(core_props)) = self.core_properties.loaded() {
+            let uri = ensure_package_rel(
+                &mut self.package,
+                rel_types::CORE_PROPERTIES,
+                "docProps/core.xml",
+            )?;
+            store_part(&mut self.package, &uri, crate::opc::CORE_PROPERTIES, || {
+                core_props.to_xml()
+            })?;
         }
 
-        // Ensure package-level relationship for core properties
-        if self.core_properties.is_some()
-            && self
-                .package
-                .relationships()
-                .by_type(rel_types::CORE_PROPERTIES)
-                .is_none()
-        {
-            self.package
-                .add_relationship(rel_types::CORE_PROPERTIES, "docProps/core.xml");
+        if let Some(Some(app_props)) = self.extended_properties.loaded() {
+            let uri = ensure_package_rel(
+                &mut self.package,
+                rel_types::EXTENDED_PROPERTIES,
+                "docProps/app.xml",
+            )?;
+            store_part(
+                &mut self.package,
+                &uri,
+                crate::opc::EXTENDED_PROPERTIES,
+                || app_props.to_xml(),
+            )?;
         }
 
-        // 2. Ensure document-level relationships
-        {
-            let doc_part = self.package.part_mut(&doc_uri).unwrap();
-            let rels = doc_part.ensure_relationships();
-
-            if self.numbering.is_some() && rels.by_type(rel_types::NUMBERING).is_none() {
-                rels.add(rel_types::NUMBERING, "numbering.xml");
-            }
-            if self.styles.is_some() && rels.by_type(rel_types::STYLES).is_none() {
-                rels.add(rel_types::STYLES, "styles.xml");
-            }
-            if self.footnotes.is_some() && rels.by_type(rel_types::FOOTNOTES).is_none() {
-                rels.add(rel_types::FOOTNOTES, "footnotes.xml");
-            }
-            if self.endnotes.is_some() && rels.by_type(rel_types::ENDNOTES).is_none() {
-                rels.add(rel_types::ENDNOTES, "endnotes.xml");
-            }
-            if self.comments.is_some() && rels.by_type(rel_types::COMMENTS).is_none() {
-                rels.add(rel_types::COMMENTS, "comments.xml");
-            }
+        if let Some(Some(custom_props)) = self.custom_properties.loaded() {
+            let uri = ensure_package_rel(
+                &mut self.package,
+                rel_types::CUSTOM_PROPERTIES,
+                "docProps/custom.xml",
+            )?;
+            store_part(
+                &mut self.package,
+                &uri,
+                crate::opc::CUSTOM_PROPERTIES,
+                || custom_props.to_xml(),
+            )?;
         }
 
-        // 3. Write all part data
-        if let Some(ref numbering) = self.numbering {
-            self.package.add_part(Part::new(
-                PartUri::new("/word/numbering.xml")?,
-                crate::opc::NUMBERING,
-                numbering.to_xml()?.into_bytes(),
-            ));
+        // 3. Parts referenced from document.xml
+        if let Some(Some(numbering)) = self.numbering.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::NUMBERING, "numbering.xml")?;
+            store_part(&mut self.package, &uri, crate::opc::NUMBERING, || {
+                numbering.to_xml()
+            })?;
         }
 
-        if let Some(ref styles) = self.styles {
-            self.package.add_part(Part::new(
-                PartUri::new("/word/styles.xml")?,
-                crate::opc::STYLES,
-                styles.to_xml()?.into_bytes(),
-            ));
+        if let Some(Some(styles)) = self.styles.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::STYLES, "styles.xml")?;
+            store_part(&mut self.package, &uri, crate::opc::STYLES, || {
+                styles.to_xml()
+            })?;
         }
 
-        if let Some(ref core_props) = self.core_properties {
-            self.package.add_part(Part::new(
-                PartUri::new("/docProps/core.xml")?,
-                crate::opc::CORE_PROPERTIES,
-                core_props.to_xml()?.into_bytes(),
-            ));
+        if let Some(Some(settings)) = self.settings.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::SETTINGS, "settings.xml")?;
+            store_part(&mut self.package, &uri, crate::opc::SETTINGS, || {
+                settings.to_xml()
+            })?;
         }
 
-        if let Some(ref fn_notes) = self.footnotes {
-            self.package.add_part(Part::new(
-                PartUri::new("/word/footnotes.xml")?,
-                crate::opc::FOOTNOTES,
-                fn_notes.to_xml()?.into_bytes(),
-            ));
+        if let Some(Some(fn_notes)) = self.footnotes.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::FOOTNOTES, "footnotes.xml")?;
+            store_part(&mut self.package, &uri, crate::opc::FOOTNOTES, || {
+                fn_notes.to_xml()
+            })?;
         }
 
-        if let Some(ref en_notes) = self.endnotes {
-            self.package.add_part(Part::new(
-                PartUri::new("/word/endnotes.xml")?,
-                crate::opc::ENDNOTES,
-                en_notes.to_xml()?.into_bytes(),
-            ));
+        if let Some(Some(en_notes)) = self.endnotes.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::ENDNOTES, "endnotes.xml")?;
+            store_part(&mut self.package, &uri, crate::opc::ENDNOTES, || {
+                en_notes.to_xml()
+            })?;
         }
 
-        if let Some(ref comments) = self.comments {
-            self.package.add_part(Part::new(
-                PartUri::new("/word/comments.xml")?,
-                crate::opc::COMMENTS,
-                comments.to_xml()?.into_bytes(),
-            ));
+        if let Some(Some(_)) = self.comments.loaded() {
+            let uri = ensure_doc_rel(&mut self.package, rel_types::COMMENTS, "comments.xml")?;
+            if self.package.part(&uri).map_or(true, |p| p.is_modified()) {
+                self.store_comment_parts(&uri)?;
+            }
         }
 
-        for (r_id, hf) in &self.headers {
-            self.package.add_part(Part::new(
-                PartUri::new(&format!("/word/header_{}.xml", r_id))?,
-                crate::opc::HEADER,
-                hf.to_xml()?.into_bytes(),
-            ));
+        let (headers, footers) = match self.headers_footers.loaded() {
+            Some((headers, footers)) => (headers.as_slice(), footers.as_slice()),
+            None => (&[][..], &[][..]),
+        };
+        for (r_id, hf) in headers.iter().chain(footers) {
+            let content_type = if hf.is_header {
+                crate::opc::HEADER
+            } else {
+                crate::opc::FOOTER
+            };
+            if let Some(uri) = loaders::doc_part_uri_by_id(&self.package, r_id) {
+                store_part(&mut self.package, &uri, content_type, || hf.to_xml())?;
+            }
         }
 
-        for (r_id, hf) in &self.footers {
-            self.package.add_part(Part::new(
-                PartUri::new(&format!("/word/footer_{}.xml", r_id))?,
-                crate::opc::FOOTER,
-                hf.to_xml()?.into_bytes(),
-            ));
+        Ok(())
+    }
+
+    /// Mark a part as modified so that it is re-serialized on save
+    fn mark_part_modified(&mut self, uri: Option<PartUri>) {
+        if let Some(part) = uri.and_then(|uri| self.package.part_mut(&uri)) {
+            part.mark_modified();
         }
+    }
 
-        Ok(())
+    /// Mark document.xml as modified
+    fn mark_body_modified(&mut self) {
+        self.mark_part_modified(loaders::main_document_uri(&self.package));
+    }
+
+    /// Mark a part referenced from the package relationships as modified
+    fn mark_package_part_modified(&mut self, rel_type: &str) {
+        self.mark_part_modified(loaders::package_part_uri(&self.package, rel_type));
+    }
+
+    /// Mark a part referenced from document.xml as modified
+    fn mark_doc_part_modified(&mut self, rel_type: &str) {
+        self.mark_part_modified(loaders::doc_part_uri(&self.package, rel_type));
+    }
+
+    /// Mark a header or footer part as modified
+    fn mark_header_footer_modified(&mut self, r_id: &str) {
+        self.mark_part_modified(loaders::doc_part_uri_by_id(&self.package, r_id));
     }
 
     /// Get all paragraphs
@@ -314,13 +452,9 @@ impl Document {
         self.body.tables().nth(index)
     }
 
-    /// Get all text in the document
+    /// Get all text in the document, including block-level content controls
     pub fn text(&self) -> String {
-        self.body
-            .paragraphs()
-            .map(|p| p.text())
-            .collect::<Vec<_>>()
-            .join("\n")
+        sdt::blocks_text(&self.body.content).join("\n")
     }
 
     /// Get the underlying package
@@ -330,12 +464,17 @@ impl Document {
 
     /// Get mutable body
     pub fn body_mut(&mut self) -> &mut Body {
+        self.mark_body_modified();
         &mut self.body
     }
 
-    /// Add a paragraph with text
+    /// Add a paragraph with text (a tracked insertion while tracking changes)
     pub fn add_paragraph(&mut self, text: impl Into<String>) -> &mut Paragraph {
-        let para = Paragraph::new(text);
+        let mut para = Paragraph::new(text);
+        if let Some(ref mut tracker) = self.tracker {
+            para.track_insertion(tracker);
+        }
+        self.mark_body_modified();
         self.body.add_paragraph(para);
         // Return mutable reference to the last paragraph
         self.body
@@ -354,6 +493,7 @@ impl Document {
 
     /// Add an empty paragraph
     pub fn add_empty_paragraph(&mut self) -> &mut Paragraph {
+        self.mark_body_modified();
         self.body.add_paragraph(Paragraph::default());
         self.body
             .content
@@ -371,12 +511,13 @@ impl Document {
 
     /// Get numbering definitions
     pub fn numbering(&self) -> Option<&Numbering> {
-        self.numbering.as_ref()
+        self.numbering.get(&self.package).as_ref()
     }
 
     /// Get mutable numbering definitions
     pub fn numbering_mut(&mut self) -> Option<&mut Numbering> {
-        self.numbering.as_mut()
+        self.mark_doc_part_modified(crate::opc::rel_types::NUMBERING);
+        self.numbering.get_mut(&self.package).as_mut()
     }
 
     /// Check if a paragraph is a list item
@@ -387,7 +528,7 @@ impl Document {
     /// Check if a paragraph is a bullet list item
     pub fn is_bullet_list_item(&self, para: &Paragraph) -> bool {
         if let Some(num_id) = para.properties.as_ref().and_then(|p| p.num_id) {
-            if let Some(ref numbering) = self.numbering {
+            if let Some(numbering) = self.numbering() {
                 return numbering.is_bullet_list(num_id);
             }
         }
@@ -410,11 +551,12 @@ impl Document {
         let props = para.properties.as_ref()?;
         let num_id = props.num_id?;
         let level = props.num_level.unwrap_or(0) as u8;
-        self.numbering.as_ref()?.get_format(num_id, level)
+        self.numbering()?.get_format(num_id, level)
     }
 
     /// Add a table to the document
     pub fn add_table(&mut self, table: Table) -> &mut Table {
+        self.mark_body_modified();
         self.body.add_table(table);
         // Return mutable reference to the last table
         self.body
@@ -438,30 +580,207 @@ impl Document {
 
     /// Get styles
     pub fn styles(&self) -> Option<&Styles> {
-        self.styles.as_ref()
+        self.styles.get(&self.package).as_ref()
     }
 
     /// Get mutable styles
     pub fn styles_mut(&mut self) -> &mut Styles {
-        self.styles.get_or_insert_with(Styles::default)
+        self.mark_doc_part_modified(crate::opc::rel_types::STYLES);
+        self.styles
+            .get_mut(&self.package)
+            .get_or_insert_with(Styles::default)
     }
 
     /// Get a style by ID
     pub fn style(&self, style_id: &str) -> Option<&Style> {
-        self.styles.as_ref()?.get(style_id)
+        self.styles()?.get(style_id)
+    }
+
+    /// Get document settings
+    pub fn settings(&self) -> Option<&Settings> {
+        self.settings.get(&self.package).as_ref()
+    }
+
+    /// Get mutable document settings (creates empty settings if None)
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        self.mark_doc_part_modified(crate::opc::rel_types::SETTINGS);
+        self.settings
+            .get_mut(&self.package)
+            .get_or_insert_with(Settings::default)
+    }
+
+    /// Get the document protection, if any
+    pub fn protection(&self) -> Option<&DocumentProtection> {
+        self.settings()?.document_protection.as_ref()
+    }
+
+    /// Enforce an editing restriction, optionally with a password
+    ///
+    /// The password is stored as a salted SHA-512 hash, as Word does; it
+    /// deters editing in Word but does not encrypt the document.
+    pub fn protect(&mut self, edit: EditRestriction, password: Option<&str>) -> Result<()> {
+        let protection = match password {
+            Some(password) => DocumentProtection::with_password(edit, password)?,
+            None => DocumentProtection::new(edit),
+        };
+        self.settings_mut().document_protection = Some(protection);
+        Ok(())
+    }
+
+    /// Remove document protection
+    pub fn unprotect(&mut self) {
+        if self.protection().is_some() {
+            self.settings_mut().document_protection = None;
+        }
+    }
+
+    /// Editable ranges in the body, including table cells
+    pub fn editable_ranges(&self) -> Vec<&PermStart> {
+        let mut ranges = Vec::new();
+        for block in &self.body.content {
+            match block {
+                BlockContent::Paragraph(p) => ranges.extend(p.editable_ranges()),
+                BlockContent::Table(t) => {
+                    for cell in t.rows.iter().flat_map(|r| r.cells.iter()) {
+                        ranges.extend(cell.paragraphs().flat_map(|p| p.editable_ranges()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        ranges
+    }
+
+    /// Tracked changes in the body, headers, footers and notes
+    pub fn revisions(&self) -> Vec<TrackedChange> {
+        let mut collector = RevisionCollector::default();
+        collector.blocks(&self.body.content);
+        let (headers, footers) = self.headers_footers.get(&self.package);
+        for (_, hf) in headers.iter().chain(footers) {
+            collector.paragraphs(&hf.paragraphs);
+        }
+        for notes in [self.footnotes(), self.endnotes()].into_iter().flatten() {
+            for note in &notes.notes {
+                collector.paragraphs(&note.paragraphs);
+            }
+        }
+        collector.changes
+    }
+
+    /// Accept all tracked changes; returns the number of changes accepted
+    pub fn accept_all_revisions(&mut self) -> usize {
+        self.resolve_revisions(true, &|_| true)
+    }
+
+    /// Reject all tracked changes; returns the number of changes rejected
+    pub fn reject_all_revisions(&mut self) -> usize {
+        self.resolve_revisions(false, &|_| true)
+    }
+
+    /// Accept the tracked changes made by an author
+    pub fn accept_revisions_by(&mut self, author: &str) -> usize {
+        self.resolve_revisions(true, &|info| info.author.as_deref() == Some(author))
+    }
+
+    /// Reject the tracked changes made by an author
+    pub fn reject_revisions_by(&mut self, author: &str) -> usize {
+        self.resolve_revisions(false, &|info| info.author.as_deref() == Some(author))
+    }
+
+    /// Accept the tracked change with the given ID; returns false if not found
+    pub fn accept_revision(&mut self, id: &str) -> bool {
+        self.resolve_revisions(true, &|info| info.id == id) > 0
+    }
+
+    /// Reject the tracked change with the given ID; returns false if not found
+    pub fn reject_revision(&mut self, id: &str) -> bool {
+        self.resolve_revisions(false, &|info| info.id == id) > 0
+    }
+
+    /// Accept or reject the tracked changes selected by `filter` in every story
+    fn resolve_revisions(&mut self, accept: bool, filter: &dyn Fn(&RevisionInfo) -> bool) -> usize {
+        let mut resolver = RevisionResolver::new(accept, filter);
+
+        resolver.blocks(&mut self.body.content);
+        if resolver.count > 0 {
+            self.mark_body_modified();
+        }
+
+        let mut modified = Vec::new();
+        let (headers, footers) = self.headers_footers.get_mut(&self.package);
+        for (r_id, hf) in headers.iter_mut().chain(footers.iter_mut()) {
+            let before = resolver.count;
+            resolver.paragraphs(&mut hf.paragraphs, &mut hf.content_controls);
+            if resolver.count > before {
+                modified.push(r_id.clone());
+            }
+        }
+        for r_id in modified {
+            self.mark_header_footer_modified(&r_id);
+        }
+
+        for (is_footnotes, rel_type) in [
+            (true, crate::opc::rel_types::FOOTNOTES),
+            (false, crate::opc::rel_types::ENDNOTES),
+        ] {
+            let notes = if is_footnotes {
+                &mut self.footnotes
+            } else {
+                &mut self.endnotes
+            };
+            let before = resolver.count;
+            if let Some(notes) = notes.get_mut(&self.package) {
+                for note in &mut notes.notes {
+                    resolver.paragraphs(&mut note.paragraphs, &mut []);
+                }
+            }
+            if resolver.count > before {
+                self.mark_doc_part_modified(rel_type);
+            }
+        }
+
+        resolver.count
     }
 
     /// Get core properties
     pub fn core_properties(&self) -> Option<&CoreProperties> {
-        self.core_properties.as_ref()
+        self.core_properties.get(&self.package).as_ref()
     }
 
     /// Get mutable core properties (creates default if None)
     pub fn core_properties_mut(&mut self) -> &mut CoreProperties {
+        self.mark_package_part_modified(crate::opc::rel_types::CORE_PROPERTIES);
         self.core_properties
+            .get_mut(&self.package)
             .get_or_insert_with(CoreProperties::default)
     }
 
+    /// Get extended properties (app.xml)
+    pub fn extended_properties(&self) -> Option<&ExtendedProperties> {
+        self.extended_properties.get(&self.package).as_ref()
+    }
+
+    /// Get mutable extended properties (creates default if None)
+    pub fn extended_properties_mut(&mut self) -> &mut ExtendedProperties {
+        self.mark_package_part_modified(crate::opc::rel_types::EXTENDED_PROPERTIES);
+        self.extended_properties
+            .get_mut(&self.package)
+            .get_or_insert_with(ExtendedProperties::default)
+    }
+
+    /// Get custom properties (custom.xml)
+    pub fn custom_properties(&self) -> Option<&CustomProperties> {
+        self.custom_properties.get(&self.package).as_ref()
+    }
+
+    /// Get mutable custom properties (creates empty if None)
+    pub fn custom_properties_mut(&mut self) -> &mut CustomProperties {
+        self.mark_package_part_modified(crate::opc::rel_types::CUSTOM_PROPERTIES);
+        self.custom_properties
+            .get_mut(&self.package)
+            .get_or_insert_with(CustomProperties::default)
+    }
+
     /// Get section properties from body
     pub fn section_properties(&self) -> Option<&SectionProperties> {
         self.body.section_properties.as_ref()
@@ -469,6 +788,7 @@ impl Document {
 
     /// Get mutable section properties (creates default if None)
     pub fn section_properties_mut(&mut self) -> &mut SectionProperties {
+        self.mark_body_modified();
         self.body
             .section_properties
             .get_or_insert_with(SectionProperties::default)
@@ -476,6 +796,7 @@ impl Document {
 
     /// Get mutable table by index
     pub fn table_mut(&mut self, index: usize) -> Option<&mut Table> {
+        self.mark_body_modified();
         self.body
             .content
             .iter_mut()
@@ -491,6 +812,7 @@ impl Document {
 
     /// Get mutable paragraph by index
     pub fn paragraph_mut(&mut self, index: usize) -> Option<&mut Paragraph> {
+        self.mark_body_modified();
         self.body
             .content
             .iter_mut()
@@ -506,11 +828,17 @@ impl Document {
 
     /// Get mutable paragraphs iterator
     pub fn paragraphs_mut(&mut self) -> impl Iterator<Item = &mut Paragraph> {
+        self.mark_body_modified();
         self.body.paragraphs_mut()
     }
 
     /// Insert a paragraph at a specific index in the body content
-    pub fn insert_paragraph(&mut self, index: usize, para: Paragraph) {
+    /// (a tracked insertion while tracking changes)
+    pub fn insert_paragraph(&mut self, index: usize, mut para: Paragraph) {
+        if let Some(ref mut tracker) = self.tracker {
+            para.track_insertion(tracker);
+        }
+        self.mark_body_modified();
         // Find the position in body.content corresponding to the nth paragraph
         let mut para_count = 0;
         for i in 0..self.body.content.len() {
@@ -530,13 +858,21 @@ impl Document {
             .push(BlockContent::Paragraph(Box::new(para)));
     }
 
-    /// Remove a paragraph by index
+    /// Remove a paragraph by index (marked deleted while tracking changes)
     pub fn remove_paragraph(&mut self, index: usize) -> bool {
+        self.mark_body_modified();
         let mut para_count = 0;
         for i in 0..self.body.content.len() {
             if matches!(self.body.content[i], BlockContent::Paragraph(_)) {
                 if para_count == index {
-                    self.body.content.remove(i);
+                    match (&mut self.body.content[i], &mut self.tracker) {
+                        (BlockContent::Paragraph(para), Some(tracker)) => {
+                            para.track_deletion(tracker)
+                        }
+                        _ => {
+                            self.body.content.remove(i);
+                        }
+                    }
                     return true;
                 }
                 para_count += 1;
@@ -547,6 +883,7 @@ impl Document {
 
     /// Remove a table by index
     pub fn remove_table(&mut self, index: usize) -> bool {
+        self.mark_body_modified();
         let mut table_count = 0;
         for i in 0..self.body.content.len() {
             if matches!(self.body.content[i], BlockContent::Table(_)) {
@@ -562,74 +899,87 @@ impl Document {
 
     /// Get all headers
     pub fn headers(&self) -> &[(String, HeaderFooter)] {
-        &self.headers
+        &self.headers_footers.get(&self.package).0
     }
 
     /// Get all footers
     pub fn footers(&self) -> &[(String, HeaderFooter)] {
-        &self.footers
+        &self.headers_footers.get(&self.package).1
     }
 
     /// Get default header (first one)
     pub fn default_header(&self) -> Option<&HeaderFooter> {
-        self.headers.first().map(|(_, hf)| hf)
+        self.headers().first().map(|(_, hf)| hf)
     }
 
     /// Get default footer (first one)
     pub fn default_footer(&self) -> Option<&HeaderFooter> {
-        self.footers.first().map(|(_, hf)| hf)
+        self.footers().first().map(|(_, hf)| hf)
     }
 
     /// Get mutable default header
     pub fn default_header_mut(&mut self) -> Option<&mut HeaderFooter> {
-        self.headers.first_mut().map(|(_, hf)| hf)
+        if let Some(r_id) = self.headers().first().map(|(r_id, _)| r_id.clone()) {
+            self.mark_header_footer_modified(&r_id);
+        }
+        let (headers, _) = self.headers_footers.get_mut(&self.package);
+        headers.first_mut().map(|(_, hf)| hf)
     }
 
     /// Get mutable default footer
     pub fn default_footer_mut(&mut self) -> Option<&mut HeaderFooter> {
-        self.footers.first_mut().map(|(_, hf)| hf)
+        if let Some(r_id) = self.footers().first().map(|(r_id, _)| r_id.clone()) {
+            self.mark_header_footer_modified(&r_id);
+        }
+        let (_, footers) = self.headers_footers.get_mut(&self.package);
+        footers.first_mut().map(|(_, hf)| hf)
     }
 
     /// Get footnotes
     pub fn footnotes(&self) -> Option<&Notes> {
-        self.footnotes.as_ref()
+        self.footnotes.get(&self.package).as_ref()
     }
 
     /// Get mutable footnotes (creates if None)
     pub fn footnotes_mut(&mut self) -> &mut Notes {
-        self.footnotes.get_or_insert_with(|| Notes {
-            is_footnotes: true,
-            ..Default::default()
-        })
+        self.mark_doc_part_modified(crate::opc::rel_types::FOOTNOTES);
+        self.footnotes
+            .get_mut(&self.package)
+            .get_or_insert_with(|| Notes::new(true))
     }
 
     /// Get endnotes
     pub fn endnotes(&self) -> Option<&Notes> {
-        self.endnotes.as_ref()
+        self.endnotes.get(&self.package).as_ref()
     }
 
     /// Get mutable endnotes (creates if None)
     pub fn endnotes_mut(&mut self) -> &mut Notes {
-        self.endnotes.get_or_insert_with(|| Notes {
-            is_footnotes: false,
-            ..Default::default()
-        })
+        self.mark_doc_part_modified(crate::opc::rel_types::ENDNOTES);
+        self.endnotes
+            .get_mut(&self.package)
+            .get_or_insert_with(|| Notes::new(false))
     }
 
     /// Get comments
     pub fn comments(&self) -> Option<&Comments> {
-        self.comments.as_ref()
+        self.comments.get(&self.package).as_ref()
     }
 
     /// Get mutable comments (creates if None)
     pub fn comments_mut(&mut self) -> &mut Comments {
-        self.comments.get_or_insert_with(Comments::default)
+        self.mark_doc_part_modified(crate::opc::rel_types::COMMENTS);
+        self.comments
+            .get_mut(&self.package)
+            .get_or_insert_with(Comments::default)
     }
 
     /// Add an image to the document package and return its relationship ID.
     ///
     /// The returned `r_id` can be used with `InlineImage::new(r_id, w, h)` to
-    /// create a drawing element, which can then be added to a Run.
+    /// create a drawing element, which can then be added to a Run. Only the
+    /// image's own bytes are added; see [`Document::add_svg_image`] for SVG
+    /// images with a fallback.
     ///
     /// # Example
     /// ```rust,ignore
@@ -648,27 +998,86 @@ impl Document {
     pub fn add_image(&mut self, image: ImageData) -> String {
         use crate::opc::rel_types;
 
-        // Count existing images to generate unique filename
-        let img_count = self
-            .package
-            .part_uris()
-            .filter(|u| u.as_str().contains("/word/media/"))
-            .count();
-        let filename = format!("image{}.{}", img_count + 1, image.extension);
-        let part_path = format!("/word/media/{}", filename);
+        let uri = image_inventory::next_media_uri(&self.package, &image.extension);
+        let doc_uri = self.ensure_main_document_part();
+        let rel_target = image_inventory::media_target(&doc_uri, &uri);
 
         // Add image part
-        let uri = PartUri::new(&part_path).expect("valid image URI");
         let part = Part::new(uri, image.content_type, image.data);
         self.package.add_part(part);
 
         // Add relationship from document part
-        let doc_uri = PartUri::new("/word/document.xml").expect("valid doc URI");
         let doc_part = self.package.part_mut(&doc_uri).expect("doc part exists");
         let rels = doc_part.ensure_relationships();
-        let rel_target = format!("media/{}", filename);
         rels.add(rel_types::IMAGE, &rel_target)
     }
+
+    /// Add an SVG image and its raster fallback (see [`ImageData::svg`])
+    ///
+    /// Returns the relationship IDs of the fallback and of the SVG, for
+    /// `InlineImage::new(fallback_r_id, w, h).with_svg(svg_r_id)`. Fails with
+    /// [`Error::Unsupported`] when the image has no fallback.
+    pub fn add_svg_image(&mut self, image: ImageData) -> Result<(String, String)> {
+        match image.into_blips() {
+            (fallback, Some(svg)) => Ok((self.add_image(fallback), self.add_image(svg))),
+            _ => Err(Error::Unsupported(
+                "SVG image without a raster fallback".into(),
+            )),
+        }
+    }
+
+    /// Resolve document.xml, adding the part and its relationship to a new package
+    fn ensure_main_document_part(&mut self) -> PartUri {
+        let uri = match loaders::main_document_uri(&self.package) {
+            Some(uri) if self.package.part(&uri).is_some() => return uri,
+            Some(uri) => uri,
+            None => {
+                let uri = crate::opc::well_known::document();
+                self.package
+                    .add_relationship(crate::opc::rel_types::OFFICE_DOCUMENT, uri.as_str());
+                uri
+            }
+        };
+        let mut part = Part::new(uri.clone(), crate::opc::MAIN_DOCUMENT, Vec::new());
+        part.mark_modified();
+        self.package.add_part(part);
+        uri
+    }
+}
+
+/// Write serialized XML into a part that is new or marked modified
+fn store_part<F>(package: &mut Package, uri: &PartUri, content_type: &str, xml: F) -> Result<()>
+where
+    F: FnOnce() -> Result<String>,
+{
+    match package.part_mut(uri) {
+        Some(part) if !part.is_modified() => {}
+        Some(part) => part.set_data(xml()?.into_bytes()),
+        None => package.add_part(Part::new(uri.clone(), content_type, xml()?.into_bytes())),
+    }
+    Ok(())
+}
+
+/// Resolve a package-level part, adding the relationship if missing
+fn ensure_package_rel(package: &mut Package, rel_type: &str, target: &str) -> Result<PartUri> {
+    if let Some(uri) = loaders::package_part_uri(package, rel_type) {
+        return Ok(uri);
+    }
+    package.add_relationship(rel_type, target);
+    PartUri::new(&format!("/{}", target))
+}
+
+/// Resolve a part referenced from document.xml, adding the relationship if missing
+fn ensure_doc_rel(package: &mut Package, rel_type: &str, target: &str) -> Result<PartUri> {
+    if let Some(uri) = loaders::doc_part_uri(package, rel_type) {
+        return Ok(uri);
+    }
+    package
+        .main_document_part_mut()
+        .ok_or_else(|| Error::MissingPart("Main document part not found".into()))?
+        .ensure_relationships()
+        .add(rel_type, target);
+    PartUri::new(&format!("/word/{}", target))
 }
 
 impl Default for Document {
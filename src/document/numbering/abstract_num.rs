@@ -131,10 +131,8 @@ impl AbstractNum {
                         }
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"abstractNum" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"abstractNum" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -160,10 +160,8 @@ impl Level {
                         }
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"lvl" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"lvl" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -262,10 +260,8 @@ impl LevelOverride {
                         start_override = get_w_val(&e).and_then(|v| v.parse().ok());
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"lvlOverride" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"lvlOverride" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -349,10 +345,8 @@ impl LevelParagraphProperties {
                     };
                     props.unknown_children.push(RawXmlNode::Element(raw));
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"pPr" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"pPr" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -405,10 +399,8 @@ impl LevelRunProperties {
                     };
                     props.unknown_children.push(RawXmlNode::Element(raw));
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"rPr" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"rPr" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -64,10 +64,8 @@ impl Num {
                         abstract_num_id = get_w_val(&e).and_then(|v| v.parse().ok()).unwrap_or(0);
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"num" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"num" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -4,13 +4,18 @@ mod properties;
 
 pub use properties::{Alignment, Indentation, LineSpacing, ParagraphProperties};
 
+use crate::document::field::FieldText;
 use crate::document::numbering::NumberingInfo;
-use crate::document::Run;
-use crate::error::Result;
+use crate::document::{
+    Document, FieldDisplay, ImageData, InlineImage, PermStart, Revision, RevisionKind, Run,
+    RunContent, Sdt, SdtContent, SimpleField, TextBox,
+};
+use crate::error::{Error, Result};
 use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
+use std::path::Path;
 
 /// Paragraph element (w:p)
 #[derive(Clone, Debug, Default)]
@@ -36,6 +41,20 @@ pub enum ParagraphContent {
     BookmarkStart { id: String, name: String },
     /// Bookmark end
     BookmarkEnd { id: String },
+    /// Start of an editable range in a protected document
+    PermStart(PermStart),
+    /// End of an editable range
+    PermEnd { id: String },
+    /// Tracked insertion, deletion or move
+    Revision(Revision),
+    /// Start of the text a comment annotates
+    CommentRangeStart { id: u32 },
+    /// End of the text a comment annotates
+    CommentRangeEnd { id: u32 },
+    /// Simple field (w:fldSimple)
+    SimpleField(SimpleField),
+    /// Run-level content control (w:sdt)
+    Sdt(Box<Sdt>),
     /// Unknown element (preserved)
     Unknown(RawXmlNode),
 }
@@ -47,8 +66,11 @@ pub struct Hyperlink {
     pub r_id: Option<String>,
     /// Anchor (for internal links)
     pub anchor: Option<String>,
-    /// Content runs
-    pub runs: Vec<Run>,
+    /// Content (runs, tracked changes, fields, ...)
+    ///
+    /// This replaces the `runs` field of earlier versions; use
+    /// [`Hyperlink::runs`] and [`Hyperlink::runs_mut`] for the runs alone.
+    pub content: Vec<ParagraphContent>,
 }
 
 impl Paragraph {
@@ -67,90 +89,13 @@ impl Paragraph {
         loop {
             match reader.read_event_into(&mut buf)? {
                 Event::Start(e) => {
-                    let local = e.name().local_name();
-                    match local.as_ref() {
-                        b"pPr" => {
-                            para.properties = Some(ParagraphProperties::from_reader(reader)?);
-                        }
-                        b"r" => {
-                            para.content
-                                .push(ParagraphContent::Run(Run::from_reader(reader, &e)?));
-                        }
-                        b"hyperlink" => {
-                            para.content
-                                .push(ParagraphContent::Hyperlink(Hyperlink::from_reader(
-                                    reader, &e,
-                                )?));
-                        }
-                        b"bookmarkStart" => {
-                            let id = crate::xml::get_attr(&e, "w:id")
-                                .or_else(|| crate::xml::get_attr(&e, "id"))
-                                .unwrap_or_default();
-                            let name = crate::xml::get_attr(&e, "w:name")
-                                .or_else(|| crate::xml::get_attr(&e, "name"))
-                                .unwrap_or_default();
-                            para.content
-                                .push(ParagraphContent::BookmarkStart { id, name });
-                            skip_to_end(reader, &e)?;
-                        }
-                        b"bookmarkEnd" => {
-                            let id = crate::xml::get_attr(&e, "w:id")
-                                .or_else(|| crate::xml::get_attr(&e, "id"))
-                                .unwrap_or_default();
-                            para.content.push(ParagraphContent::BookmarkEnd { id });
-                            skip_to_end(reader, &e)?;
-                        }
-                        _ => {
-                            let raw = RawXmlElement::from_reader(reader, &e)?;
-                            para.content
-                                .push(ParagraphContent::Unknown(RawXmlNode::Element(raw)));
-                        }
-                    }
-                }
-                Event::Empty(e) => {
-                    let local = e.name().local_name();
-                    match local.as_ref() {
-                        b"r" => {
-                            para.content
-                                .push(ParagraphContent::Run(Run::from_empty(&e)?));
-                        }
-                        b"bookmarkStart" => {
-                            let id = crate::xml::get_attr(&e, "w:id")
-                                .or_else(|| crate::xml::get_attr(&e, "id"))
-                                .unwrap_or_default();
-                            let name = crate::xml::get_attr(&e, "w:name")
-                                .or_else(|| crate::xml::get_attr(&e, "name"))
-                                .unwrap_or_default();
-                            para.content
-                                .push(ParagraphContent::BookmarkStart { id, name });
-                        }
-                        b"bookmarkEnd" => {
-                            let id = crate::xml::get_attr(&e, "w:id")
-                                .or_else(|| crate::xml::get_attr(&e, "id"))
-                                .unwrap_or_default();
-                            para.content.push(ParagraphContent::BookmarkEnd { id });
-                        }
-                        _ => {
-                            let raw = RawXmlElement {
-                                name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
-                                attributes: e
-                                    .attributes()
-                                    .filter_map(|a| a.ok())
-                                    .map(|a| {
-                                        (
-                                            String::from_utf8_lossy(a.key.as_ref()).to_string(),
-                                            String::from_utf8_lossy(&a.value).to_string(),
-                                        )
-                                    })
-                                    .collect(),
-                                children: Vec::new(),
-                                self_closing: true,
-                            };
-                            para.content
-                                .push(ParagraphContent::Unknown(RawXmlNode::Element(raw)));
-                        }
+                    if e.name().local_name().as_ref() == b"pPr" {
+                        para.properties = Some(ParagraphProperties::from_reader(reader)?);
+                    } else {
+                        para.content.push(ParagraphContent::from_start(reader, &e)?);
                     }
                 }
+                Event::Empty(e) => para.content.push(ParagraphContent::from_empty(&e)?),
                 Event::End(e) if e.name().local_name().as_ref() == b"p" => break,
                 Event::Eof => break,
                 _ => {}
@@ -172,21 +117,14 @@ impl Paragraph {
         Ok(para)
     }
 
-    /// Get all text in this paragraph
+    /// Get all text in this paragraph, as it reads with tracked changes accepted
     pub fn text(&self) -> String {
-        let mut result = String::new();
-        for content in &self.content {
-            match content {
-                ParagraphContent::Run(run) => result.push_str(&run.text()),
-                ParagraphContent::Hyperlink(link) => {
-                    for run in &link.runs {
-                        result.push_str(&run.text());
-                    }
-                }
-                _ => {}
-            }
-        }
-        result
+        content_text(&self.content)
+    }
+
+    /// Get all text, showing either field results or field codes
+    pub fn text_with(&self, display: FieldDisplay) -> String {
+        content_text_with(&self.content, display)
     }
 
     /// Get style ID
@@ -205,6 +143,23 @@ impl Paragraph {
         })
     }
 
+    /// Get the text boxes anchored in this paragraph
+    ///
+    /// Includes those in hyperlinks, simple fields, run-level content controls
+    /// and tracked insertions.
+    pub fn text_boxes(&self) -> impl Iterator<Item = &TextBox> {
+        let mut found = Vec::new();
+        content_text_boxes(&self.content, &mut found);
+        found.into_iter()
+    }
+
+    /// Get the text boxes anchored in this paragraph mutably
+    pub fn text_boxes_mut(&mut self) -> impl Iterator<Item = &mut TextBox> {
+        let mut found = Vec::new();
+        content_text_boxes_mut(&mut self.content, &mut found);
+        found.into_iter()
+    }
+
     /// Check if this is a heading
     pub fn is_heading(&self) -> bool {
         if let Some(ref props) = self.properties {
@@ -298,6 +253,38 @@ impl Paragraph {
         self.content.push(ParagraphContent::Run(run));
     }
 
+    /// Add a picture from a file in a new run
+    ///
+    /// The image is stored in `doc`, so the paragraph belongs in its body.
+    /// Without a width the picture gets its natural size; with one, the
+    /// height follows the image's aspect ratio.
+    pub fn add_picture(
+        &mut self,
+        doc: &mut Document,
+        path: impl AsRef<Path>,
+        width_emu: Option<i64>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let image = ImageData::from_file(path)?;
+        let (natural_w, natural_h) = image
+            .natural_size()
+            .ok_or_else(|| Error::Unsupported("cannot read the size of the image".into()))?;
+        let (width, height) = match width_emu {
+            Some(w) => (
+                w,
+                (w as f64 * natural_h as f64 / natural_w as f64).round() as i64,
+            ),
+            None => (natural_w, natural_h),
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+
+        let r_id = doc.add_image(image);
+        let mut run = Run::new("");
+        run.add_image(InlineImage::new(r_id, width, height).with_name(name.unwrap_or_default()));
+        self.add_run(run);
+        Ok(())
+    }
+
     /// Set style
     pub fn set_style(&mut self, style: impl Into<String>) {
         self.properties.get_or_insert_with(Default::default).style = Some(style.into());
@@ -400,7 +387,7 @@ impl Paragraph {
         let link = Hyperlink {
             r_id: Some(r_id.into()),
             anchor: None,
-            runs: vec![Run::new(text)],
+            content: vec![ParagraphContent::Run(Run::new(text))],
         };
         self.content.push(ParagraphContent::Hyperlink(link));
     }
@@ -410,7 +397,7 @@ impl Paragraph {
         let link = Hyperlink {
             r_id: None,
             anchor: Some(anchor.into()),
-            runs: vec![Run::new(text)],
+            content: vec![ParagraphContent::Run(Run::new(text))],
         };
         self.content.push(ParagraphContent::Hyperlink(link));
     }
@@ -425,6 +412,21 @@ impl Paragraph {
         self.content.push(ParagraphContent::BookmarkEnd { id });
     }
 
+    /// Make the whole paragraph an editable range of a protected document
+    pub fn add_editable_range(&mut self, perm: PermStart) {
+        let id = perm.id.clone();
+        self.content.insert(0, ParagraphContent::PermStart(perm));
+        self.content.push(ParagraphContent::PermEnd { id });
+    }
+
+    /// Editable ranges starting in this paragraph
+    pub fn editable_ranges(&self) -> impl Iterator<Item = &PermStart> {
+        self.content.iter().filter_map(|c| match c {
+            ParagraphContent::PermStart(perm) => Some(perm),
+            _ => None,
+        })
+    }
+
     /// Remove a run by index
     pub fn remove_run(&mut self, index: usize) -> bool {
         let mut run_idx = 0;
@@ -442,6 +444,69 @@ impl Paragraph {
 }
 
 impl ParagraphContent {
+    /// Parse content from a start tag (other than w:pPr)
+    pub fn from_start<R: BufRead>(reader: &mut Reader<R>, e: &BytesStart) -> Result<Self> {
+        let local = e.name().local_name();
+        let content = match local.as_ref() {
+            b"r" => ParagraphContent::Run(Run::from_reader(reader, e)?),
+            b"hyperlink" => ParagraphContent::Hyperlink(Hyperlink::from_reader(reader, e)?),
+            b"fldSimple" => ParagraphContent::SimpleField(SimpleField::from_reader(reader, e)?),
+            b"sdt" => ParagraphContent::Sdt(Box::new(Sdt::from_reader_inline(reader, e)?)),
+            b"ins" | b"del" | b"moveFrom" | b"moveTo" => {
+                let kind = RevisionKind::from_local_name(local.as_ref()).expect("revision element");
+                ParagraphContent::Revision(Revision::from_reader(reader, e, kind)?)
+            }
+            b"bookmarkStart" | b"bookmarkEnd" | b"permStart" | b"permEnd"
+            | b"commentRangeStart" | b"commentRangeEnd" => {
+                let content = Self::from_empty(e)?;
+                skip_to_end(reader, e)?;
+                content
+            }
+            _ => {
+                let raw = RawXmlElement::from_reader(reader, e)?;
+                ParagraphContent::Unknown(RawXmlNode::Element(raw))
+            }
+        };
+        Ok(content)
+    }
+
+    /// Parse content from an empty element
+    pub fn from_empty(e: &BytesStart) -> Result<Self> {
+        let local = e.name().local_name();
+        let content = match local.as_ref() {
+            b"r" => ParagraphContent::Run(Run::from_empty(e)?),
+            b"bookmarkStart" => {
+                let id = crate::xml::get_attr(e, "w:id")
+                    .or_else(|| crate::xml::get_attr(e, "id"))
+                    .unwrap_or_default();
+                let name = crate::xml::get_attr(e, "w:name")
+                    .or_else(|| crate::xml::get_attr(e, "name"))
+                    .unwrap_or_default();
+                ParagraphContent::BookmarkStart { id, name }
+            }
+            b"bookmarkEnd" => {
+                let id = crate::xml::get_attr(e, "w:id")
+                    .or_else(|| crate::xml::get_attr(e, "id"))
+                    .unwrap_or_default();
+                ParagraphContent::BookmarkEnd { id }
+            }
+            b"permStart" => ParagraphContent::PermStart(PermStart::from_element(e)),
+            b"fldSimple" => ParagraphContent::SimpleField(SimpleField::from_element(e)?),
+            b"permEnd" => {
+                let id = crate::xml::get_attr(e, "w:id").unwrap_or_default();
+                ParagraphContent::PermEnd { id }
+            }
+            b"ins" | b"del" | b"moveFrom" | b"moveTo" => {
+                let kind = RevisionKind::from_local_name(local.as_ref()).expect("revision element");
+                ParagraphContent::Revision(Revision::from_empty(e, kind)?)
+            }
+            b"commentRangeStart" => ParagraphContent::CommentRangeStart { id: comment_id(e) },
+            b"commentRangeEnd" => ParagraphContent::CommentRangeEnd { id: comment_id(e) },
+            _ => ParagraphContent::Unknown(RawXmlNode::Element(RawXmlElement::from_empty(e))),
+        };
+        Ok(content)
+    }
+
     /// Write to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         match self {
@@ -460,12 +525,117 @@ impl ParagraphContent {
                 writer.write_event(Event::Empty(elem))?;
                 Ok(())
             }
+            ParagraphContent::PermStart(perm) => perm.write_to(writer),
+            ParagraphContent::PermEnd { id } => {
+                let mut elem = BytesStart::new("w:permEnd");
+                elem.push_attribute(("w:id", id.as_str()));
+                writer.write_event(Event::Empty(elem))?;
+                Ok(())
+            }
+            ParagraphContent::Revision(rev) => rev.write_to(writer),
+            ParagraphContent::CommentRangeStart { id } => {
+                let mut elem = BytesStart::new("w:commentRangeStart");
+                elem.push_attribute(("w:id", id.to_string().as_str()));
+                writer.write_event(Event::Empty(elem))?;
+                Ok(())
+            }
+            ParagraphContent::CommentRangeEnd { id } => {
+                let mut elem = BytesStart::new("w:commentRangeEnd");
+                elem.push_attribute(("w:id", id.to_string().as_str()));
+                writer.write_event(Event::Empty(elem))?;
+                Ok(())
+            }
+            ParagraphContent::SimpleField(field) => field.write_to(writer),
+            ParagraphContent::Sdt(sdt) => sdt.write_to(writer),
             ParagraphContent::Unknown(node) => node.write_to(writer),
         }
     }
 }
 
+/// Text of paragraph content with tracked changes accepted
+pub(crate) fn content_text(content: &[ParagraphContent]) -> String {
+    content_text_with(content, FieldDisplay::Results)
+}
+
+fn content_text_boxes<'a>(content: &'a [ParagraphContent], out: &mut Vec<&'a TextBox>) {
+    for item in content {
+        match item {
+            ParagraphContent::Run(run) => {
+                for item in &run.content {
+                    if let RunContent::TextBox(text_box) = item {
+                        out.push(text_box);
+                    }
+                }
+            }
+            ParagraphContent::Hyperlink(link) => content_text_boxes(&link.content, out),
+            ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                content_text_boxes(&rev.content, out)
+            }
+            ParagraphContent::SimpleField(field) => content_text_boxes(&field.content, out),
+            ParagraphContent::Sdt(sdt) => {
+                if let SdtContent::Inline(inner) = &sdt.content {
+                    content_text_boxes(inner, out)
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn content_text_boxes_mut<'a>(content: &'a mut [ParagraphContent], out: &mut Vec<&'a mut TextBox>) {
+    for item in content {
+        match item {
+            ParagraphContent::Run(run) => {
+                for item in &mut run.content {
+                    if let RunContent::TextBox(text_box) = item {
+                        out.push(text_box);
+                    }
+                }
+            }
+            ParagraphContent::Hyperlink(link) => content_text_boxes_mut(&mut link.content, out),
+            ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                content_text_boxes_mut(&mut rev.content, out)
+            }
+            ParagraphContent::SimpleField(field) => content_text_boxes_mut(&mut field.content, out),
+            ParagraphContent::Sdt(sdt) => {
+                if let SdtContent::Inline(inner) = &mut sdt.content {
+                    content_text_boxes_mut(inner, out)
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Text of paragraph content showing field results or field codes
+pub(crate) fn content_text_with(content: &[ParagraphContent], display: FieldDisplay) -> String {
+    let mut text = FieldText::new(display);
+    text.content(content);
+    text.text
+}
+
 impl Hyperlink {
+    /// Get the runs directly in the hyperlink
+    pub fn runs(&self) -> impl Iterator<Item = &Run> {
+        self.content.iter().filter_map(|c| match c {
+            ParagraphContent::Run(r) => Some(r),
+            _ => None,
+        })
+    }
+
+    /// Get the runs directly in the hyperlink mutably
+    pub fn runs_mut(&mut self) -> impl Iterator<Item = &mut Run> {
+        self.content.iter_mut().filter_map(|c| match c {
+            ParagraphContent::Run(r) => Some(r),
+            _ => None,
+        })
+    }
+
+    /// Get all text, as it reads with tracked changes accepted
+    pub fn text(&self) -> String {
+        content_text(&self.content)
+    }
+
     /// Parse from reader
     pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
         let mut link = Hyperlink {
@@ -478,18 +648,8 @@ impl Hyperlink {
         let mut buf = Vec::new();
         loop {
             match reader.read_event_into(&mut buf)? {
-                Event::Start(e) => {
-                    if e.name().local_name().as_ref() == b"r" {
-                        link.runs.push(Run::from_reader(reader, &e)?);
-                    } else {
-                        skip_to_end(reader, &e)?;
-                    }
-                }
-                Event::Empty(e) => {
-                    if e.name().local_name().as_ref() == b"r" {
-                        link.runs.push(Run::from_empty(&e)?);
-                    }
-                }
+                Event::Start(e) => link.content.push(ParagraphContent::from_start(reader, &e)?),
+                Event::Empty(e) => link.content.push(ParagraphContent::from_empty(&e)?),
                 Event::End(e) if e.name().local_name().as_ref() == b"hyperlink" => break,
                 Event::Eof => break,
                 _ => {}
@@ -510,12 +670,12 @@ impl Hyperlink {
             start.push_attribute(("w:anchor", anchor.as_str()));
         }
 
-        if self.runs.is_empty() {
+        if self.content.is_empty() {
             writer.write_event(Event::Empty(start))?;
         } else {
             writer.write_event(Event::Start(start))?;
-            for run in &self.runs {
-                run.write_to(writer)?;
+            for content in &self.content {
+                content.write_to(writer)?;
             }
             writer.write_event(Event::End(BytesEnd::new("w:hyperlink")))?;
         }
@@ -524,6 +684,13 @@ impl Hyperlink {
     }
 }
 
+/// Numeric `w:id` of a comment marker
+pub(crate) fn comment_id(e: &BytesStart) -> u32 {
+    crate::xml::get_attr(e, "w:id")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
 /// Skip to end of current element
 fn skip_to_end<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<()> {
     let name = start.name();
@@ -1,7 +1,8 @@
 //! Paragraph properties and related types
 
+use crate::document::ParagraphPropertiesChange;
 use crate::error::Result;
-use crate::xml::{get_w_val, RawXmlElement, RawXmlNode};
+use crate::xml::{get_w_val, schema, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -85,6 +86,8 @@ pub struct ParagraphProperties {
     pub page_break_before: Option<bool>,
     /// Run properties for paragraph mark
     pub run_properties: Option<crate::document::RunProperties>,
+    /// Tracked formatting change (w:pPrChange)
+    pub change: Option<Box<ParagraphPropertiesChange>>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
 }
@@ -107,6 +110,11 @@ impl ParagraphProperties {
                             props.run_properties =
                                 Some(crate::document::RunProperties::from_reader(reader)?);
                         }
+                        b"pPrChange" => {
+                            props.change = Some(Box::new(ParagraphPropertiesChange::from_reader(
+                                reader, &e,
+                            )?));
+                        }
                         _ => {
                             let raw = RawXmlElement::from_reader(reader, &e)?;
                             props.unknown_children.push(RawXmlNode::Element(raw));
@@ -179,116 +187,149 @@ impl ParagraphProperties {
         Ok(props)
     }
 
+    /// Check if no properties are set
+    pub fn is_empty(&self) -> bool {
+        self.style.is_none()
+            && self.justification.is_none()
+            && self.num_id.is_none()
+            && self.outline_level.is_none()
+            && self.indentation.is_none()
+            && self.spacing.is_none()
+            && self.keep_next.is_none()
+            && self.keep_lines.is_none()
+            && self.page_break_before.is_none()
+            && self.run_properties.is_none()
+            && self.change.is_none()
+            && self.unknown_children.is_empty()
+    }
+
     /// Write to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
-        let has_content = self.style.is_some()
-            || self.justification.is_some()
-            || self.num_id.is_some()
-            || self.outline_level.is_some()
-            || self.indentation.is_some()
-            || self.spacing.is_some()
-            || self.keep_next.is_some()
-            || self.keep_lines.is_some()
-            || self.page_break_before.is_some()
-            || self.run_properties.is_some()
-            || !self.unknown_children.is_empty();
-
-        if !has_content {
+        if self.is_empty() {
             return Ok(());
         }
 
         writer.write_event(Event::Start(BytesStart::new("w:pPr")))?;
 
-        if let Some(style) = &self.style {
-            let mut elem = BytesStart::new("w:pStyle");
-            elem.push_attribute(("w:val", style.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        if let Some(true) = self.keep_next {
-            writer.write_event(Event::Empty(BytesStart::new("w:keepNext")))?;
-        }
-        if let Some(true) = self.keep_lines {
-            writer.write_event(Event::Empty(BytesStart::new("w:keepLines")))?;
-        }
-        if let Some(true) = self.page_break_before {
-            writer.write_event(Event::Empty(BytesStart::new("w:pageBreakBefore")))?;
-        }
-
-        if self.num_id.is_some() || self.num_level.is_some() {
-            writer.write_event(Event::Start(BytesStart::new("w:numPr")))?;
-            if let Some(level) = self.num_level {
-                let mut elem = BytesStart::new("w:ilvl");
-                elem.push_attribute(("w:val", level.to_string().as_str()));
-                writer.write_event(Event::Empty(elem))?;
-            }
-            if let Some(num_id) = self.num_id {
-                let mut elem = BytesStart::new("w:numId");
-                elem.push_attribute(("w:val", num_id.to_string().as_str()));
-                writer.write_event(Event::Empty(elem))?;
-            }
-            writer.write_event(Event::End(BytesEnd::new("w:numPr")))?;
-        }
-
-        if let Some(ref sp) = self.spacing {
-            let mut elem = BytesStart::new("w:spacing");
-            if let Some(v) = sp.before {
-                elem.push_attribute(("w:before", v.to_string().as_str()));
-            }
-            if let Some(v) = sp.after {
-                elem.push_attribute(("w:after", v.to_string().as_str()));
-            }
-            if let Some(v) = sp.line {
-                elem.push_attribute(("w:line", v.to_string().as_str()));
-            }
-            if let Some(ref rule) = sp.line_rule {
-                elem.push_attribute(("w:lineRule", rule.as_str()));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        if let Some(ref ind) = self.indentation {
-            let mut elem = BytesStart::new("w:ind");
-            if let Some(v) = ind.left {
-                elem.push_attribute(("w:left", v.to_string().as_str()));
-            }
-            if let Some(v) = ind.right {
-                elem.push_attribute(("w:right", v.to_string().as_str()));
-            }
-            if let Some(v) = ind.first_line {
-                elem.push_attribute(("w:firstLine", v.to_string().as_str()));
-            }
-            if let Some(v) = ind.hanging {
-                elem.push_attribute(("w:hanging", v.to_string().as_str()));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        if let Some(jc) = &self.justification {
-            let mut elem = BytesStart::new("w:jc");
-            elem.push_attribute(("w:val", jc.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        if let Some(level) = self.outline_level {
-            let mut elem = BytesStart::new("w:outlineLvl");
-            elem.push_attribute(("w:val", level.to_string().as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        if let Some(ref rpr) = self.run_properties {
-            rpr.write_to(writer)?;
-        }
-
-        for child in &self.unknown_children {
-            child.write_to(writer)?;
-        }
+        schema::write_in_order(
+            writer,
+            schema::PPR,
+            &self.unknown_children,
+            |writer, name| {
+                match name {
+                    "pStyle" => {
+                        if let Some(style) = &self.style {
+                            let mut elem = BytesStart::new("w:pStyle");
+                            elem.push_attribute(("w:val", style.as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "keepNext" => write_toggle(writer, "w:keepNext", self.keep_next)?,
+                    "keepLines" => write_toggle(writer, "w:keepLines", self.keep_lines)?,
+                    "pageBreakBefore" => {
+                        write_toggle(writer, "w:pageBreakBefore", self.page_break_before)?
+                    }
+                    "numPr" if self.num_id.is_some() || self.num_level.is_some() => {
+                        writer.write_event(Event::Start(BytesStart::new("w:numPr")))?;
+                        if let Some(level) = self.num_level {
+                            let mut elem = BytesStart::new("w:ilvl");
+                            elem.push_attribute(("w:val", level.to_string().as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                        if let Some(num_id) = self.num_id {
+                            let mut elem = BytesStart::new("w:numId");
+                            elem.push_attribute(("w:val", num_id.to_string().as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                        writer.write_event(Event::End(BytesEnd::new("w:numPr")))?;
+                    }
+                    "spacing" => {
+                        if let Some(ref sp) = self.spacing {
+                            let mut elem = BytesStart::new("w:spacing");
+                            if let Some(v) = sp.before {
+                                elem.push_attribute(("w:before", v.to_string().as_str()));
+                            }
+                            if let Some(v) = sp.after {
+                                elem.push_attribute(("w:after", v.to_string().as_str()));
+                            }
+                            if let Some(v) = sp.line {
+                                elem.push_attribute(("w:line", v.to_string().as_str()));
+                            }
+                            if let Some(ref rule) = sp.line_rule {
+                                elem.push_attribute(("w:lineRule", rule.as_str()));
+                            }
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "ind" => {
+                        if let Some(ref ind) = self.indentation {
+                            let mut elem = BytesStart::new("w:ind");
+                            if let Some(v) = ind.left {
+                                elem.push_attribute(("w:left", v.to_string().as_str()));
+                            }
+                            if let Some(v) = ind.right {
+                                elem.push_attribute(("w:right", v.to_string().as_str()));
+                            }
+                            if let Some(v) = ind.first_line {
+                                elem.push_attribute(("w:firstLine", v.to_string().as_str()));
+                            }
+                            if let Some(v) = ind.hanging {
+                                elem.push_attribute(("w:hanging", v.to_string().as_str()));
+                            }
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "jc" => {
+                        if let Some(jc) = &self.justification {
+                            let mut elem = BytesStart::new("w:jc");
+                            elem.push_attribute(("w:val", jc.as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "outlineLvl" => {
+                        if let Some(level) = self.outline_level {
+                            let mut elem = BytesStart::new("w:outlineLvl");
+                            elem.push_attribute(("w:val", level.to_string().as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "rPr" => {
+                        if let Some(ref rpr) = self.run_properties {
+                            rpr.write_to(writer)?;
+                        }
+                    }
+                    "pPrChange" => {
+                        if let Some(ref change) = self.change {
+                            change.write_to(writer)?;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            },
+        )?;
 
         writer.write_event(Event::End(BytesEnd::new("w:pPr")))?;
         Ok(())
     }
 }
 
+/// Write an on/off element; `false` is written explicitly so it can override a style
+fn write_toggle<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+    value: Option<bool>,
+) -> Result<()> {
+    if let Some(on) = value {
+        let mut elem = BytesStart::new(tag);
+        if !on {
+            elem.push_attribute(("w:val", "0"));
+        }
+        writer.write_event(Event::Empty(elem))?;
+    }
+    Ok(())
+}
+
 /// Parse numbering properties
 fn parse_num_pr<R: BufRead>(reader: &mut Reader<R>, props: &mut ParagraphProperties) -> Result<()> {
     let mut buf = Vec::new();
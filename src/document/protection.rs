@@ -0,0 +1,529 @@
+//! Document protection (`w:documentProtection`) and editable ranges (`w:permStart`/`w:permEnd`)
+
+use crate::error::{Error, Result};
+use crate::xml::read_attributes;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Writer;
+use sha2::Digest;
+
+/// Spin count Word uses when setting a protection password
+pub const DEFAULT_SPIN_COUNT: u32 = 100_000;
+
+/// Editing restriction enforced by document protection (`w:edit`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditRestriction {
+    /// No editing restriction (formatting restrictions only)
+    None,
+    /// Read-only, except for editable ranges
+    ReadOnly,
+    /// Only comments may be added
+    Comments,
+    /// All edits are tracked
+    TrackedChanges,
+    /// Only form fields may be filled in
+    Forms,
+}
+
+impl EditRestriction {
+    /// Parse from the `w:edit` attribute value
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Self::None),
+            "readOnly" => Some(Self::ReadOnly),
+            "comments" => Some(Self::Comments),
+            "trackedChanges" => Some(Self::TrackedChanges),
+            "forms" => Some(Self::Forms),
+            _ => None,
+        }
+    }
+
+    /// Value of the `w:edit` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::ReadOnly => "readOnly",
+            Self::Comments => "comments",
+            Self::TrackedChanges => "trackedChanges",
+            Self::Forms => "forms",
+        }
+    }
+}
+
+/// Hash algorithm of a protection password
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Parse from the `w:algorithmName` attribute value
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "SHA-1" => Some(Self::Sha1),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-384" => Some(Self::Sha384),
+            "SHA-512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Value of the `w:algorithmName` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+        }
+    }
+
+    /// Parse from the legacy `w:cryptAlgorithmSid` attribute value
+    fn from_sid(sid: &str) -> Option<Self> {
+        match sid {
+            "4" => Some(Self::Sha1),
+            "12" => Some(Self::Sha256),
+            "13" => Some(Self::Sha384),
+            "14" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Value of the legacy `w:cryptAlgorithmSid` attribute
+    fn sid(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "4",
+            Self::Sha256 => "12",
+            Self::Sha384 => "13",
+            Self::Sha512 => "14",
+        }
+    }
+}
+
+/// Salted, iterated password hash of a protected document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordHash {
+    pub algorithm: HashAlgorithm,
+    /// Base64 hash value
+    pub hash: String,
+    /// Base64 salt
+    pub salt: String,
+    pub spin_count: u32,
+    /// Written with the Word 2010 `w:cryptAlgorithmSid`/`w:hash`/`w:salt` attributes
+    pub legacy: bool,
+}
+
+impl PasswordHash {
+    /// Hash a password with a random 16-byte salt
+    pub fn new(password: &str, algorithm: HashAlgorithm, spin_count: u32) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt).map_err(|e| Error::Crypto(e.to_string()))?;
+        let hash = word_password_hash(password, algorithm, &salt, spin_count);
+        Ok(Self {
+            algorithm,
+            hash: BASE64.encode(hash),
+            salt: BASE64.encode(salt),
+            spin_count,
+            legacy: false,
+        })
+    }
+
+    /// Check a password against this hash
+    pub fn verify(&self, password: &str) -> bool {
+        let (Ok(salt), Ok(expected)) = (BASE64.decode(&self.salt), BASE64.decode(&self.hash))
+        else {
+            return false;
+        };
+        word_password_hash(password, self.algorithm, &salt, self.spin_count) == expected
+    }
+}
+
+/// Document protection settings (`w:documentProtection` in settings.xml)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentProtection {
+    /// Editing restriction
+    pub edit: EditRestriction,
+    /// Whether the protection is enforced
+    pub enforcement: bool,
+    /// Restrict formatting to the allowed styles
+    pub formatting: Option<bool>,
+    /// Password required to remove the protection
+    pub password: Option<PasswordHash>,
+    /// Unknown attributes (preserved for round-trip)
+    pub unknown_attrs: Vec<(String, String)>,
+}
+
+impl DocumentProtection {
+    /// Enforced protection without a password
+    pub fn new(edit: EditRestriction) -> Self {
+        Self {
+            edit,
+            enforcement: true,
+            formatting: None,
+            password: None,
+            unknown_attrs: Vec::new(),
+        }
+    }
+
+    /// Enforced protection with a SHA-512 password hash, as Word writes it
+    pub fn with_password(edit: EditRestriction, password: &str) -> Result<Self> {
+        Ok(Self {
+            password: Some(PasswordHash::new(
+                password,
+                HashAlgorithm::Sha512,
+                DEFAULT_SPIN_COUNT,
+            )?),
+            ..Self::new(edit)
+        })
+    }
+
+    /// Check a password; protection without a password accepts any
+    pub fn verify_password(&self, password: &str) -> bool {
+        self.password.as_ref().map_or(true, |p| p.verify(password))
+    }
+
+    /// Parse from a `w:documentProtection` element
+    pub fn from_element(e: &BytesStart) -> Self {
+        let mut protection = Self::new(EditRestriction::None);
+        protection.enforcement = false;
+
+        let mut algorithm = None;
+        let mut hash = None;
+        let mut salt = None;
+        let mut spin_count = None;
+        let mut legacy = false;
+
+        for (key, value) in read_attributes(e) {
+            match key.as_str() {
+                "w:edit" => {
+                    protection.edit =
+                        EditRestriction::parse(&value).unwrap_or(EditRestriction::None)
+                }
+                "w:enforcement" => protection.enforcement = is_on(&value),
+                "w:formatting" => protection.formatting = Some(is_on(&value)),
+                "w:algorithmName" => algorithm = HashAlgorithm::parse(&value),
+                "w:hashValue" => hash = Some(value),
+                "w:saltValue" => salt = Some(value),
+                "w:spinCount" => spin_count = value.parse().ok(),
+                "w:cryptAlgorithmSid" => {
+                    algorithm = HashAlgorithm::from_sid(&value);
+                    legacy = true;
+                }
+                "w:hash" => hash = Some(value),
+                "w:salt" => salt = Some(value),
+                "w:cryptSpinCount" => spin_count = value.parse().ok(),
+                _ => protection.unknown_attrs.push((key, value)),
+            }
+        }
+
+        if let (Some(algorithm), Some(hash), Some(salt)) = (algorithm, hash, salt) {
+            protection.password = Some(PasswordHash {
+                algorithm,
+                hash,
+                salt,
+                spin_count: spin_count.unwrap_or(0),
+                legacy,
+            });
+        }
+        protection
+    }
+
+    /// Write as a `w:documentProtection` element
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:documentProtection");
+        elem.push_attribute(("w:edit", self.edit.as_str()));
+        if let Some(formatting) = self.formatting {
+            elem.push_attribute(("w:formatting", if formatting { "1" } else { "0" }));
+        }
+        elem.push_attribute(("w:enforcement", if self.enforcement { "1" } else { "0" }));
+
+        if let Some(ref password) = self.password {
+            let spin_count = password.spin_count.to_string();
+            if password.legacy {
+                elem.push_attribute(("w:cryptAlgorithmSid", password.algorithm.sid()));
+                elem.push_attribute(("w:cryptSpinCount", spin_count.as_str()));
+                elem.push_attribute(("w:hash", password.hash.as_str()));
+                elem.push_attribute(("w:salt", password.salt.as_str()));
+            } else {
+                elem.push_attribute(("w:algorithmName", password.algorithm.as_str()));
+                elem.push_attribute(("w:hashValue", password.hash.as_str()));
+                elem.push_attribute(("w:saltValue", password.salt.as_str()));
+                elem.push_attribute(("w:spinCount", spin_count.as_str()));
+            }
+        }
+
+        for (key, value) in &self.unknown_attrs {
+            elem.push_attribute((key.as_str(), value.as_str()));
+        }
+        writer.write_event(Event::Empty(elem))?;
+        Ok(())
+    }
+}
+
+/// Start of a range that stays editable in a protected document (`w:permStart`)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PermStart {
+    /// Range ID, matched by the `w:permEnd` closing the range
+    pub id: String,
+    /// User allowed to edit (e.g. `DOMAIN\user` or an email address)
+    pub ed: Option<String>,
+    /// Group allowed to edit: `everyone`, `administrators`, `contributors`,
+    /// `editors`, `owners` or `current`
+    pub ed_group: Option<String>,
+    /// First table column covered by the range
+    pub col_first: Option<u32>,
+    /// Last table column covered by the range
+    pub col_last: Option<u32>,
+    /// Unknown attributes (preserved for round-trip)
+    pub unknown_attrs: Vec<(String, String)>,
+}
+
+impl PermStart {
+    /// Range editable by a single user
+    pub fn user(id: impl Into<String>, user: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ed: Some(user.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Range editable by a group such as `everyone`
+    pub fn group(id: impl Into<String>, group: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ed_group: Some(group.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Parse from a `w:permStart` element
+    pub fn from_element(e: &BytesStart) -> Self {
+        let mut perm = Self::default();
+        for (key, value) in read_attributes(e) {
+            match key.as_str() {
+                "w:id" => perm.id = value,
+                "w:ed" => perm.ed = Some(value),
+                "w:edGrp" => perm.ed_group = Some(value),
+                "w:colFirst" => perm.col_first = value.parse().ok(),
+                "w:colLast" => perm.col_last = value.parse().ok(),
+                _ => perm.unknown_attrs.push((key, value)),
+            }
+        }
+        perm
+    }
+
+    /// Write as a `w:permStart` element
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:permStart");
+        elem.push_attribute(("w:id", self.id.as_str()));
+        if let Some(ref ed) = self.ed {
+            elem.push_attribute(("w:ed", ed.as_str()));
+        }
+        if let Some(ref group) = self.ed_group {
+            elem.push_attribute(("w:edGrp", group.as_str()));
+        }
+        if let Some(col) = self.col_first {
+            elem.push_attribute(("w:colFirst", col.to_string().as_str()));
+        }
+        if let Some(col) = self.col_last {
+            elem.push_attribute(("w:colLast", col.to_string().as_str()));
+        }
+        for (key, value) in &self.unknown_attrs {
+            elem.push_attribute((key.as_str(), value.as_str()));
+        }
+        writer.write_event(Event::Empty(elem))?;
+        Ok(())
+    }
+}
+
+fn is_on(value: &str) -> bool {
+    matches!(value, "1" | "true" | "on")
+}
+
+/// Word's password hash for document protection.
+///
+/// The password is first reduced to the legacy 32-bit XOR key, whose bytes
+/// are written in reverse order as an uppercase hex string. That string is
+/// hashed in UTF-16LE after the salt, then re-hashed `spin_count` times with
+/// the little-endian iteration number appended.
+fn word_password_hash(
+    password: &str,
+    algorithm: HashAlgorithm,
+    salt: &[u8],
+    spin_count: u32,
+) -> Vec<u8> {
+    let key = legacy_password_key(password).to_le_bytes();
+    let hex: String = key.iter().map(|b| format!("{:02X}", b)).collect();
+    let input: Vec<u8> = hex.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+
+    match algorithm {
+        HashAlgorithm::Sha1 => iterated_hash::<sha1::Sha1>(salt, &input, spin_count),
+        HashAlgorithm::Sha256 => iterated_hash::<sha2::Sha256>(salt, &input, spin_count),
+        HashAlgorithm::Sha384 => iterated_hash::<sha2::Sha384>(salt, &input, spin_count),
+        HashAlgorithm::Sha512 => iterated_hash::<sha2::Sha512>(salt, &input, spin_count),
+    }
+}
+
+fn iterated_hash<D: Digest>(salt: &[u8], input: &[u8], spin_count: u32) -> Vec<u8> {
+    let mut hash = D::new().chain_update(salt).chain_update(input).finalize();
+    for i in 0..spin_count {
+        hash = D::new()
+            .chain_update(&hash)
+            .chain_update(i.to_le_bytes())
+            .finalize();
+    }
+    hash.to_vec()
+}
+
+/// Initial high-order word of the legacy key, by password length
+const INITIAL_CODE_ARRAY: [u16; 15] = [
+    0xE1F0, 0x1D0F, 0xCC9C, 0x84C0, 0x110C, 0x0E10, 0xF1CE, 0x313E, 0x1872, 0xE139, 0xD40F, 0x84F9,
+    0x280C, 0xA96A, 0x4EC3,
+];
+
+/// XOR values of the legacy key, by character position and bit
+const ENCRYPTION_MATRIX: [[u16; 7]; 15] = [
+    [0xAEFC, 0x4DD9, 0x9BB2, 0x2745, 0x4E8A, 0x9D14, 0x2A09],
+    [0x7B61, 0xF6C2, 0xFDA5, 0xEB6B, 0xC6F7, 0x9DCF, 0x2BBF],
+    [0x4563, 0x8AC6, 0x05AD, 0x0B5A, 0x16B4, 0x2D68, 0x5AD0],
+    [0x0375, 0x06EA, 0x0DD4, 0x1BA8, 0x3750, 0x6EA0, 0xDD40],
+    [0xD849, 0xA0B3, 0x5147, 0xA28E, 0x553D, 0xAA7A, 0x44D5],
+    [0x6F45, 0xDE8A, 0xAD35, 0x4A4B, 0x9496, 0x390D, 0x721A],
+    [0xEB23, 0xC667, 0x9CEF, 0x29FF, 0x53FE, 0xA7FC, 0x5FD9],
+    [0x47D3, 0x8FA6, 0x0F6D, 0x1EDA, 0x3DB4, 0x7B68, 0xF6D0],
+    [0xB861, 0x60E3, 0xC1C6, 0x93AD, 0x377B, 0x6EF6, 0xDDEC],
+    [0x45A0, 0x8B40, 0x06A1, 0x0D42, 0x1A84, 0x3508, 0x6A10],
+    [0xAA51, 0x4483, 0x8906, 0x022D, 0x045A, 0x08B4, 0x1168],
+    [0x76B4, 0xED68, 0xCAF1, 0x85C3, 0x1BA7, 0x374E, 0x6E9C],
+    [0x3730, 0x6E60, 0xDCC0, 0xA9A1, 0x4363, 0x86C6, 0x1DAD],
+    [0x3331, 0x6662, 0xCCC4, 0x89A9, 0x0373, 0x06E6, 0x0DCC],
+    [0x1021, 0x2042, 0x4084, 0x8108, 0x1231, 0x2462, 0x48C4],
+];
+
+/// Legacy 32-bit password key (ECMA-376 Part 4, 2.15.1.28)
+fn legacy_password_key(password: &str) -> u32 {
+    // Single-byte form of the first 15 characters
+    let bytes: Vec<u8> = password
+        .chars()
+        .take(15)
+        .map(|c| {
+            let c = c as u32;
+            match (c & 0xFF) as u8 {
+                0 => ((c >> 8) & 0xFF) as u8,
+                low => low,
+            }
+        })
+        .collect();
+    if bytes.is_empty() {
+        return 0;
+    }
+
+    let mut high = INITIAL_CODE_ARRAY[bytes.len() - 1];
+    for (i, &byte) in bytes.iter().enumerate() {
+        let row = &ENCRYPTION_MATRIX[15 - bytes.len() + i];
+        for (bit, value) in row.iter().enumerate() {
+            if byte & (1 << bit) != 0 {
+                high ^= value;
+            }
+        }
+    }
+
+    let rotate = |v: u16| ((v >> 14) & 1) | ((v << 1) & 0x7FFF);
+    let mut low: u16 = 0;
+    for &byte in bytes.iter().rev() {
+        low = rotate(low) ^ byte as u16;
+    }
+    low = rotate(low) ^ bytes.len() as u16 ^ 0xCE4B;
+
+    ((high as u32) << 16) | low as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    fn parse_empty(xml: &str) -> BytesStart<'static> {
+        let mut reader = Reader::from_str(xml);
+        match reader.read_event().unwrap() {
+            Event::Empty(e) => e.into_owned(),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_legacy_key() {
+        // Example from ECMA-376 Part 4
+        assert_eq!(legacy_password_key("Example"), 0x64CE_ED7E);
+        assert_eq!(legacy_password_key(""), 0);
+    }
+
+    #[test]
+    fn test_password_hash_verify() {
+        let hash = PasswordHash::new("secret", HashAlgorithm::Sha512, 1000).unwrap();
+        assert!(hash.verify("secret"));
+        assert!(!hash.verify("Secret"));
+        assert_eq!(BASE64.decode(&hash.hash).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_parse_legacy_protection() {
+        let e = parse_empty(
+            r#"<w:documentProtection w:edit="readOnly" w:enforcement="1" w:cryptProviderType="rsaAES" w:cryptAlgorithmClass="hash" w:cryptAlgorithmType="typeAny" w:cryptAlgorithmSid="14" w:cryptSpinCount="100000" w:hash="aGFzaA==" w:salt="c2FsdA=="/>"#,
+        );
+        let protection = DocumentProtection::from_element(&e);
+        assert_eq!(protection.edit, EditRestriction::ReadOnly);
+        assert!(protection.enforcement);
+        let password = protection.password.as_ref().unwrap();
+        assert_eq!(password.algorithm, HashAlgorithm::Sha512);
+        assert_eq!(password.spin_count, 100_000);
+        assert!(password.legacy);
+        assert_eq!(protection.unknown_attrs.len(), 3);
+
+        let mut buf = Vec::new();
+        protection.write_to(&mut Writer::new(&mut buf)).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains(r#"w:cryptAlgorithmSid="14""#));
+        assert!(xml.contains(r#"w:cryptProviderType="rsaAES""#));
+        assert_eq!(
+            DocumentProtection::from_element(&parse_empty(&xml)),
+            protection
+        );
+    }
+
+    #[test]
+    fn test_perm_start_roundtrip() {
+        let e = parse_empty(r#"<w:permStart w:id="7" w:edGrp="everyone" w:colFirst="1"/>"#);
+        let perm = PermStart::from_element(&e);
+        assert_eq!(perm.id, "7");
+        assert_eq!(perm.ed_group.as_deref(), Some("everyone"));
+        assert_eq!(perm.col_first, Some(1));
+
+        let mut buf = Vec::new();
+        perm.write_to(&mut Writer::new(&mut buf)).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert_eq!(PermStart::from_element(&parse_empty(&xml)), perm);
+    }
+
+    #[test]
+    fn test_perm_start_escaped_editor() {
+        let mut perm = PermStart::user("1", "R&D Team");
+        perm.unknown_attrs
+            .push(("w:note".into(), r#"<"quoted">"#.into()));
+        for _ in 0..3 {
+            let mut buf = Vec::new();
+            perm.write_to(&mut Writer::new(&mut buf)).unwrap();
+            let xml = String::from_utf8(buf).unwrap();
+            assert!(xml.contains(r#"w:ed="R&amp;D Team""#));
+            perm = PermStart::from_element(&parse_empty(&xml));
+        }
+        assert_eq!(perm.ed.as_deref(), Some("R&D Team"));
+        assert_eq!(perm.unknown_attrs[0].1, r#"<"quoted">"#);
+    }
+}
@@ -0,0 +1,990 @@
+//! Tracked changes (revisions)
+//!
+//! Insertions, deletions and moves wrap paragraph content in `w:ins`,
+//! `w:del`, `w:moveFrom` and `w:moveTo`. Formatting changes keep the previous
+//! properties in `w:rPrChange`/`w:pPrChange`, and the paragraph mark and table
+//! rows carry their own insertion and deletion markers.
+
+use crate::document::paragraph::content_text;
+use crate::document::sdt::remap_ranges;
+use crate::document::{
+    BlockContent, Paragraph, ParagraphContent, ParagraphProperties, Run, RunContent, RunProperties,
+    Sdt, SdtContent, Table, TableCellProperties,
+};
+use crate::error::Result;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashSet;
+use std::io::BufRead;
+
+/// Kind of tracked change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevisionKind {
+    /// Inserted content (`w:ins`)
+    Insertion,
+    /// Deleted content (`w:del`)
+    Deletion,
+    /// Source of moved content (`w:moveFrom`)
+    MoveFrom,
+    /// Destination of moved content (`w:moveTo`)
+    MoveTo,
+    /// Formatting change (`w:rPrChange`, `w:pPrChange`, `w:trPrChange`, `w:tblPrChange`)
+    Formatting,
+}
+
+impl RevisionKind {
+    /// Kind of a content revision element by local name
+    pub fn from_local_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"ins" => Some(Self::Insertion),
+            b"del" => Some(Self::Deletion),
+            b"moveFrom" => Some(Self::MoveFrom),
+            b"moveTo" => Some(Self::MoveTo),
+            _ => None,
+        }
+    }
+
+    /// Element name of a content revision
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Insertion => "w:ins",
+            Self::Deletion => "w:del",
+            Self::MoveFrom => "w:moveFrom",
+            Self::MoveTo => "w:moveTo",
+            Self::Formatting => "w:rPrChange",
+        }
+    }
+
+    /// Whether the change adds content (kept when accepted)
+    pub fn is_addition(&self) -> bool {
+        matches!(self, Self::Insertion | Self::MoveTo)
+    }
+}
+
+/// ID, author and date of a tracked change
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RevisionInfo {
+    /// Revision ID (`w:id`)
+    pub id: String,
+    /// Author (`w:author`)
+    pub author: Option<String>,
+    /// Date in ISO 8601 format (`w:date`)
+    pub date: Option<String>,
+    /// Unknown attributes (preserved for round-trip)
+    pub unknown_attrs: Vec<(String, String)>,
+}
+
+impl RevisionInfo {
+    /// Create revision metadata
+    pub fn new(id: impl Into<String>, author: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            author: Some(author.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Read from the attributes of a revision element
+    pub fn from_element(e: &BytesStart) -> Result<Self> {
+        let mut info = Self::default();
+        for attr in e.attributes() {
+            let attr = attr?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = attr.unescape_value()?.to_string();
+            info.set_attr(key, value);
+        }
+        Ok(info)
+    }
+
+    /// Read from the attributes of a raw revision element (already unescaped)
+    pub(crate) fn from_raw(e: &RawXmlElement) -> Self {
+        let mut info = Self::default();
+        for (key, value) in &e.attributes {
+            info.set_attr(key.clone(), value.clone());
+        }
+        info
+    }
+
+    fn set_attr(&mut self, key: String, value: String) {
+        match key.as_str() {
+            "w:id" => self.id = value,
+            "w:author" => self.author = Some(value),
+            "w:date" => self.date = Some(value),
+            _ => self.unknown_attrs.push((key, value)),
+        }
+    }
+
+    /// Add the attributes to a revision element
+    pub fn push_attributes(&self, elem: &mut BytesStart) {
+        elem.push_attribute(("w:id", self.id.as_str()));
+        if let Some(ref author) = self.author {
+            elem.push_attribute(("w:author", author.as_str()));
+        }
+        if let Some(ref date) = self.date {
+            elem.push_attribute(("w:date", date.as_str()));
+        }
+        for (key, value) in &self.unknown_attrs {
+            elem.push_attribute((key.as_str(), value.as_str()));
+        }
+    }
+}
+
+/// Inserted, deleted or moved paragraph content (`w:ins`, `w:del`, `w:moveFrom`, `w:moveTo`)
+///
+/// Without content it marks a paragraph mark (in [`RunProperties::mark_revision`]).
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub kind: RevisionKind,
+    pub info: RevisionInfo,
+    /// Wrapped content (runs, possibly nested revisions)
+    pub content: Vec<ParagraphContent>,
+}
+
+impl Revision {
+    /// Create a revision wrapping `content`
+    pub fn new(kind: RevisionKind, info: RevisionInfo, content: Vec<ParagraphContent>) -> Self {
+        Self {
+            kind,
+            info,
+            content,
+        }
+    }
+
+    /// Parse from reader (after the start tag)
+    pub fn from_reader<R: BufRead>(
+        reader: &mut Reader<R>,
+        start: &BytesStart,
+        kind: RevisionKind,
+    ) -> Result<Self> {
+        let mut revision = Self::new(kind, RevisionInfo::from_element(start)?, Vec::new());
+        let end = start.name().as_ref().to_vec();
+        let mut buf = Vec::new();
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    let content = ParagraphContent::from_start(reader, &e)?;
+                    revision.content.push(content);
+                }
+                Event::Empty(e) => revision.content.push(ParagraphContent::from_empty(&e)?),
+                Event::End(e) if e.name().as_ref() == end.as_slice() => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(revision)
+    }
+
+    /// Create from an empty element
+    pub fn from_empty(start: &BytesStart, kind: RevisionKind) -> Result<Self> {
+        Ok(Self::new(
+            kind,
+            RevisionInfo::from_element(start)?,
+            Vec::new(),
+        ))
+    }
+
+    /// Text of the content as it reads once the change is accepted
+    pub fn text(&self) -> String {
+        if self.kind.is_addition() {
+            content_text(&self.content)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Text inserted or deleted by this change, ignoring nested changes
+    pub fn changed_text(&self) -> String {
+        let mut text = String::new();
+        for content in &self.content {
+            match content {
+                ParagraphContent::Run(run) => text.push_str(&run.text_with_deletions()),
+                ParagraphContent::Hyperlink(link) => {
+                    for run in link.runs() {
+                        text.push_str(&run.text_with_deletions());
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new(self.kind.tag());
+        self.info.push_attributes(&mut elem);
+        if self.content.is_empty() {
+            writer.write_event(Event::Empty(elem))?;
+        } else {
+            writer.write_event(Event::Start(elem))?;
+            for content in &self.content {
+                content.write_to(writer)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new(self.kind.tag())))?;
+        }
+        Ok(())
+    }
+}
+
+/// Run formatting change (`w:rPrChange`) holding the previous properties
+#[derive(Clone, Debug, Default)]
+pub struct RunPropertiesChange {
+    pub info: RevisionInfo,
+    /// Run properties before the change
+    pub previous: RunProperties,
+}
+
+impl RunPropertiesChange {
+    /// Parse from reader (after w:rPrChange start tag)
+    pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
+        let mut change = Self {
+            info: RevisionInfo::from_element(start)?,
+            previous: RunProperties::default(),
+        };
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) if e.local_name().as_ref() == b"rPr" => {
+                    change.previous = RunProperties::from_reader(reader)?;
+                }
+                Event::End(e) if e.local_name().as_ref() == b"rPrChange" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(change)
+    }
+
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:rPrChange");
+        self.info.push_attributes(&mut elem);
+        writer.write_event(Event::Start(elem))?;
+        if self.previous.is_empty() {
+            writer.write_event(Event::Empty(BytesStart::new("w:rPr")))?;
+        } else {
+            self.previous.write_to(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("w:rPrChange")))?;
+        Ok(())
+    }
+}
+
+/// Paragraph formatting change (`w:pPrChange`) holding the previous properties
+#[derive(Clone, Debug, Default)]
+pub struct ParagraphPropertiesChange {
+    pub info: RevisionInfo,
+    /// Paragraph properties before the change (without mark run properties)
+    pub previous: ParagraphProperties,
+}
+
+impl ParagraphPropertiesChange {
+    /// Parse from reader (after w:pPrChange start tag)
+    pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
+        let mut change = Self {
+            info: RevisionInfo::from_element(start)?,
+            previous: ParagraphProperties::default(),
+        };
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) if e.local_name().as_ref() == b"pPr" => {
+                    change.previous = ParagraphProperties::from_reader(reader)?;
+                }
+                Event::End(e) if e.local_name().as_ref() == b"pPrChange" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(change)
+    }
+
+    /// Write to XML writer
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        let mut elem = BytesStart::new("w:pPrChange");
+        self.info.push_attributes(&mut elem);
+        writer.write_event(Event::Start(elem))?;
+        if self.previous.is_empty() {
+            writer.write_event(Event::Empty(BytesStart::new("w:pPr")))?;
+        } else {
+            self.previous.write_to(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("w:pPrChange")))?;
+        Ok(())
+    }
+}
+
+/// A tracked change found in the document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackedChange {
+    pub kind: RevisionKind,
+    pub info: RevisionInfo,
+    /// Inserted or deleted text; empty for formatting, paragraph mark and row changes
+    pub text: String,
+}
+
+/// Collects the tracked changes of paragraphs and tables
+#[derive(Default)]
+pub(crate) struct RevisionCollector {
+    pub changes: Vec<TrackedChange>,
+}
+
+impl RevisionCollector {
+    fn push(&mut self, kind: RevisionKind, info: &RevisionInfo, text: String) {
+        self.changes.push(TrackedChange {
+            kind,
+            info: info.clone(),
+            text,
+        });
+    }
+
+    pub fn blocks(&mut self, blocks: &[BlockContent]) {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.paragraph(p),
+                BlockContent::Table(t) => self.table(t),
+                BlockContent::Sdt(sdt) => {
+                    if let SdtContent::Blocks(inner) = &sdt.content {
+                        self.blocks(inner)
+                    }
+                }
+                BlockContent::Unknown(_) => {}
+            }
+        }
+    }
+
+    pub fn paragraphs(&mut self, paras: &[Paragraph]) {
+        for para in paras {
+            self.paragraph(para);
+        }
+    }
+
+    fn paragraph(&mut self, para: &Paragraph) {
+        if let Some(ref props) = para.properties {
+            if let Some(ref change) = props.change {
+                self.push(RevisionKind::Formatting, &change.info, String::new());
+            }
+            if let Some(ref rpr) = props.run_properties {
+                self.run_properties(rpr);
+            }
+        }
+        self.content(&para.content);
+    }
+
+    fn content(&mut self, content: &[ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::Run(run) => self.run(run),
+                ParagraphContent::Hyperlink(link) => self.content(&link.content),
+                ParagraphContent::Revision(rev) => {
+                    self.push(rev.kind, &rev.info, rev.changed_text());
+                    self.content(&rev.content);
+                }
+                ParagraphContent::Sdt(sdt) => {
+                    if let SdtContent::Inline(inner) = &sdt.content {
+                        self.content(inner);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn run(&mut self, run: &Run) {
+        if let Some(ref props) = run.properties {
+            self.run_properties(props);
+        }
+    }
+
+    fn run_properties(&mut self, props: &RunProperties) {
+        if let Some(ref rev) = props.mark_revision {
+            self.push(rev.kind, &rev.info, String::new());
+        }
+        if let Some(ref change) = props.change {
+            self.push(RevisionKind::Formatting, &change.info, String::new());
+        }
+    }
+
+    fn table(&mut self, table: &Table) {
+        if let Some(RawXmlNode::Element(ref props)) = table.properties {
+            self.raw_changes(&props.children, "tblPrChange");
+        }
+        for row in &table.rows {
+            if let Some(RawXmlNode::Element(ref props)) = row.properties {
+                if let Some((kind, info)) = row_revision(props) {
+                    self.push(kind, &info, String::new());
+                }
+                self.raw_changes(&props.children, "trPrChange");
+            }
+            for cell in &row.cells {
+                if let Some(ref props) = cell.properties {
+                    self.raw_changes(&props.unknown_children, "tcPrChange");
+                }
+                self.paragraphs(&cell.paragraphs);
+            }
+        }
+    }
+
+    fn raw_changes(&mut self, children: &[RawXmlNode], change: &str) {
+        for child in children {
+            if let RawXmlNode::Element(e) = child {
+                if local_name(&e.name) == change {
+                    self.push(
+                        RevisionKind::Formatting,
+                        &RevisionInfo::from_raw(e),
+                        String::new(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Accepts or rejects the tracked changes selected by a filter
+pub(crate) struct RevisionResolver<'a> {
+    accept: bool,
+    filter: &'a dyn Fn(&RevisionInfo) -> bool,
+    /// IDs of move ranges whose start marker was removed
+    move_ranges: HashSet<String>,
+    /// Number of changes resolved
+    pub count: usize,
+}
+
+impl<'a> RevisionResolver<'a> {
+    pub fn new(accept: bool, filter: &'a dyn Fn(&RevisionInfo) -> bool) -> Self {
+        Self {
+            accept,
+            filter,
+            move_ranges: HashSet::new(),
+            count: 0,
+        }
+    }
+
+    /// Whether the change is selected, counting it if so
+    fn selects(&mut self, info: &RevisionInfo) -> bool {
+        let selected = (self.filter)(info);
+        if selected {
+            self.count += 1;
+        }
+        selected
+    }
+
+    /// Whether content of a selected change of this kind stays in the document
+    fn keeps(&self, kind: RevisionKind) -> bool {
+        self.accept == kind.is_addition()
+    }
+
+    /// Resolve changes in body-level content
+    pub fn blocks(&mut self, blocks: &mut Vec<BlockContent>) {
+        let mut resolved = Vec::with_capacity(blocks.len());
+        // Paragraph whose mark was removed, waiting to be joined with the next one
+        let mut pending: Option<Box<Paragraph>> = None;
+
+        for block in blocks.drain(..) {
+            match block {
+                BlockContent::Paragraph(mut para) => {
+                    let joins_next = self.paragraph(&mut para);
+                    if let Some(prev) = pending.take() {
+                        join_paragraphs(*prev, &mut para);
+                    }
+                    if joins_next {
+                        pending = Some(para);
+                    } else {
+                        resolved.push(BlockContent::Paragraph(para));
+                    }
+                }
+                BlockContent::Table(mut table) => {
+                    if let Some(prev) = pending.take() {
+                        resolved.push(BlockContent::Paragraph(prev));
+                    }
+                    self.table(&mut table);
+                    if !table.rows.is_empty() {
+                        resolved.push(BlockContent::Table(table));
+                    }
+                }
+                BlockContent::Sdt(mut sdt) => {
+                    if let Some(prev) = pending.take() {
+                        resolved.push(BlockContent::Paragraph(prev));
+                    }
+                    if let SdtContent::Blocks(inner) = &mut sdt.content {
+                        self.blocks(inner);
+                    }
+                    resolved.push(BlockContent::Sdt(sdt));
+                }
+                other => {
+                    if let Some(prev) = pending.take() {
+                        resolved.push(BlockContent::Paragraph(prev));
+                    }
+                    resolved.push(other);
+                }
+            }
+        }
+        // A trailing paragraph left empty by removing its mark goes entirely
+        if let Some(prev) = pending.filter(|p| !p.content.is_empty()) {
+            resolved.push(BlockContent::Paragraph(prev));
+        }
+        *blocks = resolved;
+    }
+
+    /// Resolve changes in a list of paragraphs (table cells, headers, notes),
+    /// keeping the ranges of the content controls around them in step
+    pub fn paragraphs(&mut self, paras: &mut Vec<Paragraph>, controls: &mut [Sdt]) {
+        let mut resolved = Vec::with_capacity(paras.len());
+        let mut pending: Option<Paragraph> = None;
+        let mut positions = Vec::with_capacity(paras.len() + 1);
+
+        for mut para in paras.drain(..) {
+            positions.push(resolved.len());
+            let joins_next = self.paragraph(&mut para);
+            if let Some(prev) = pending.take() {
+                join_paragraphs(prev, &mut para);
+            }
+            if joins_next {
+                pending = Some(para);
+            } else {
+                resolved.push(para);
+            }
+        }
+        // Table cells and notes need at least one paragraph
+        if let Some(prev) = pending.filter(|p| !p.content.is_empty() || resolved.is_empty()) {
+            resolved.push(prev);
+        }
+        positions.push(resolved.len());
+        remap_ranges(controls, &positions);
+        *paras = resolved;
+    }
+
+    /// Resolve changes in a paragraph; returns whether its mark was removed
+    fn paragraph(&mut self, para: &mut Paragraph) -> bool {
+        let mut mark_removed = false;
+        if let Some(ref mut props) = para.properties {
+            if let Some(change) = props.change.take() {
+                if !self.selects(&change.info) {
+                    props.change = Some(change);
+                } else if !self.accept {
+                    restore_paragraph_properties(props, change.previous);
+                }
+            }
+            if let Some(ref mut rpr) = props.run_properties {
+                if let Some(mark) = rpr.mark_revision.take() {
+                    if !self.selects(&mark.info) {
+                        rpr.mark_revision = Some(mark);
+                    } else {
+                        mark_removed = !self.keeps(mark.kind);
+                    }
+                }
+                self.run_properties(rpr);
+            }
+        }
+        para.content = self.content(std::mem::take(&mut para.content));
+        mark_removed
+    }
+
+    fn content(&mut self, content: Vec<ParagraphContent>) -> Vec<ParagraphContent> {
+        let mut resolved = Vec::with_capacity(content.len());
+        for item in content {
+            match item {
+                ParagraphContent::Revision(mut rev) => {
+                    rev.content = self.content(std::mem::take(&mut rev.content));
+                    if !self.selects(&rev.info) {
+                        resolved.push(ParagraphContent::Revision(rev));
+                    } else if self.keeps(rev.kind) {
+                        if !rev.kind.is_addition() {
+                            restore_deleted_text(&mut rev.content);
+                        }
+                        resolved.extend(rev.content);
+                    }
+                }
+                ParagraphContent::Run(mut run) => {
+                    self.run(&mut run);
+                    resolved.push(ParagraphContent::Run(run));
+                }
+                ParagraphContent::Hyperlink(mut link) => {
+                    link.content = self.content(std::mem::take(&mut link.content));
+                    resolved.push(ParagraphContent::Hyperlink(link));
+                }
+                ParagraphContent::Sdt(mut sdt) => {
+                    if let SdtContent::Inline(inner) = &mut sdt.content {
+                        *inner = self.content(std::mem::take(inner));
+                    }
+                    resolved.push(ParagraphContent::Sdt(sdt));
+                }
+                ParagraphContent::Unknown(RawXmlNode::Element(ref e))
+                    if self.is_resolved_move_range(e) => {}
+                other => resolved.push(other),
+            }
+        }
+        resolved
+    }
+
+    /// Whether a move range marker belongs to a selected move
+    fn is_resolved_move_range(&mut self, e: &RawXmlElement) -> bool {
+        match local_name(&e.name) {
+            "moveFromRangeStart" | "moveToRangeStart" => {
+                let info = RevisionInfo::from_raw(e);
+                if (self.filter)(&info) {
+                    self.move_ranges.insert(info.id);
+                    true
+                } else {
+                    false
+                }
+            }
+            "moveFromRangeEnd" | "moveToRangeEnd" => {
+                let info = RevisionInfo::from_raw(e);
+                self.move_ranges.contains(&info.id)
+            }
+            _ => false,
+        }
+    }
+
+    fn run(&mut self, run: &mut Run) {
+        if let Some(ref mut props) = run.properties {
+            self.run_properties(props);
+        }
+    }
+
+    fn run_properties(&mut self, props: &mut RunProperties) {
+        if let Some(change) = props.change.take() {
+            if !self.selects(&change.info) {
+                props.change = Some(change);
+            } else if !self.accept {
+                let mark_revision = props.mark_revision.take();
+                *props = change.previous;
+                props.mark_revision = mark_revision;
+            }
+        }
+    }
+
+    fn table(&mut self, table: &mut Table) {
+        if let Some(RawXmlNode::Element(ref mut props)) = table.properties {
+            self.raw_change(props, "tblPrChange");
+        }
+
+        let mut rows = Vec::with_capacity(table.rows.len());
+        let mut positions = Vec::with_capacity(table.rows.len() + 1);
+        for mut row in table.rows.drain(..) {
+            positions.push(rows.len());
+            let mut keep = true;
+            if let Some(RawXmlNode::Element(ref mut props)) = row.properties {
+                if let Some((kind, info)) = row_revision(props) {
+                    if self.selects(&info) {
+                        keep = self.keeps(kind);
+                        props
+                            .children
+                            .retain(|c| !matches!(c, RawXmlNode::Element(e) if is_row_revision(e)));
+                    }
+                }
+                self.raw_change(props, "trPrChange");
+            }
+            if !keep {
+                continue;
+            }
+            for cell in &mut row.cells {
+                if let Some(ref mut props) = cell.properties {
+                    self.cell_properties(props);
+                }
+                self.paragraphs(&mut cell.paragraphs, &mut cell.content_controls);
+            }
+            rows.push(row);
+        }
+        positions.push(rows.len());
+        remap_ranges(&mut table.content_controls, &positions);
+        table.rows = rows;
+    }
+
+    fn cell_properties(&mut self, props: &mut TableCellProperties) {
+        let Some(pos) = props.unknown_children.iter().position(
+            |c| matches!(c, RawXmlNode::Element(e) if local_name(&e.name) == "tcPrChange"),
+        ) else {
+            return;
+        };
+        let RawXmlNode::Element(change) = props.unknown_children.remove(pos) else {
+            return;
+        };
+        if !self.selects(&RevisionInfo::from_raw(&change)) {
+            props
+                .unknown_children
+                .insert(pos, RawXmlNode::Element(change));
+        } else if !self.accept {
+            if let Some(previous) = first_child_element(&change).and_then(parse_cell_properties) {
+                *props = previous;
+            }
+        }
+    }
+
+    /// Resolve a raw `w:trPrChange`/`w:tblPrChange` inside raw properties
+    fn raw_change(&mut self, props: &mut RawXmlElement, change_name: &str) {
+        let Some(pos) = props.children.iter().position(
+            |c| matches!(c, RawXmlNode::Element(e) if local_name(&e.name) == change_name),
+        ) else {
+            return;
+        };
+        let RawXmlNode::Element(change) = props.children.remove(pos) else {
+            return;
+        };
+        if !self.selects(&RevisionInfo::from_raw(&change)) {
+            props.children.insert(pos, RawXmlNode::Element(change));
+        } else if !self.accept {
+            if let Some(previous) = first_child_element(&change) {
+                // Row insertion/deletion markers are not part of the formatting
+                let markers: Vec<_> = props
+                    .children
+                    .drain(..)
+                    .filter(|c| matches!(c, RawXmlNode::Element(e) if is_row_revision(e)))
+                    .collect();
+                props.children = previous.children.clone();
+                props.children.extend(markers);
+            }
+        }
+    }
+}
+
+/// Join `prev`, whose paragraph mark was removed, to the start of `next`
+fn join_paragraphs(mut prev: Paragraph, next: &mut Paragraph) {
+    prev.content.append(&mut next.content);
+    next.content = prev.content;
+}
+
+/// Keep the paragraph mark formatting and section break when restoring old properties
+fn restore_paragraph_properties(props: &mut ParagraphProperties, previous: ParagraphProperties) {
+    let run_properties = props.run_properties.take();
+    let section: Vec<_> = props
+        .unknown_children
+        .drain(..)
+        .filter(|c| matches!(c, RawXmlNode::Element(e) if local_name(&e.name) == "sectPr"))
+        .collect();
+    *props = previous;
+    props.run_properties = run_properties;
+    props.unknown_children.extend(section);
+}
+
+/// Turn `w:delText` back into `w:t` when a deletion is rejected
+fn restore_deleted_text(content: &mut [ParagraphContent]) {
+    let restore = |run: &mut Run| {
+        for item in &mut run.content {
+            if let RunContent::DeletedText(text) = item {
+                *item = RunContent::Text(std::mem::take(text));
+            }
+        }
+    };
+    for item in content {
+        match item {
+            ParagraphContent::Run(run) => restore(run),
+            ParagraphContent::Hyperlink(link) => restore_deleted_text(&mut link.content),
+            _ => {}
+        }
+    }
+}
+
+/// Insertion or deletion marker of a table row (`w:trPr/w:ins`, `w:trPr/w:del`)
+pub(crate) fn row_revision(props: &RawXmlElement) -> Option<(RevisionKind, RevisionInfo)> {
+    props.children.iter().find_map(|c| match c {
+        RawXmlNode::Element(e) if is_row_revision(e) => {
+            let kind = RevisionKind::from_local_name(local_name(&e.name).as_bytes())?;
+            Some((kind, RevisionInfo::from_raw(e)))
+        }
+        _ => None,
+    })
+}
+
+fn is_row_revision(e: &RawXmlElement) -> bool {
+    matches!(local_name(&e.name), "ins" | "del")
+}
+
+fn first_child_element(e: &RawXmlElement) -> Option<&RawXmlElement> {
+    e.children.iter().find_map(|c| match c {
+        RawXmlNode::Element(child) => Some(child),
+        _ => None,
+    })
+}
+
+/// Parse raw `w:tcPr` into cell properties
+fn parse_cell_properties(raw: &RawXmlElement) -> Option<TableCellProperties> {
+    let mut writer = Writer::new(Vec::new());
+    raw.write_to(&mut writer).ok()?;
+    let xml = writer.into_inner();
+    let mut reader = Reader::from_reader(xml.as_slice());
+    let mut buf = Vec::new();
+    match reader.read_event_into(&mut buf).ok()? {
+        Event::Start(_) => TableCellProperties::from_reader(&mut reader).ok(),
+        _ => Some(TableCellProperties::default()),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_paragraph(xml: &str) -> Paragraph {
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        loop {
+            if let Event::Start(e) = reader.read_event_into(&mut buf).unwrap() {
+                return Paragraph::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    fn write_paragraph(para: &Paragraph) -> String {
+        let mut writer = Writer::new(Vec::new());
+        para.write_to(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    const REDLINE: &str = r#"<w:p><w:r><w:t xml:space="preserve">The </w:t></w:r><w:del w:id="1" w:author="Ann" w:date="2024-01-01T00:00:00Z"><w:r><w:delText>quick</w:delText></w:r></w:del><w:ins w:id="2" w:author="Bob"><w:r><w:t>slow</w:t></w:r></w:ins><w:r><w:t xml:space="preserve"> fox</w:t></w:r></w:p>"#;
+
+    #[test]
+    fn test_parse_revisions() {
+        let para = parse_paragraph(REDLINE);
+        assert_eq!(para.text(), "The slow fox");
+
+        let revisions: Vec<_> = para
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                ParagraphContent::Revision(rev) => Some(rev),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].kind, RevisionKind::Deletion);
+        assert_eq!(revisions[0].info.author.as_deref(), Some("Ann"));
+        assert_eq!(
+            revisions[0].info.date.as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(revisions[0].changed_text(), "quick");
+        assert_eq!(revisions[1].text(), "slow");
+    }
+
+    #[test]
+    fn test_revisions_roundtrip() {
+        let para = parse_paragraph(REDLINE);
+        let xml = write_paragraph(&para);
+        assert!(xml.contains(r#"<w:del w:id="1" w:author="Ann""#));
+        assert!(xml.contains("<w:delText>quick</w:delText>"));
+        assert!(xml.contains(r#"<w:ins w:id="2" w:author="Bob">"#));
+    }
+
+    #[test]
+    fn test_accept_and_reject() {
+        let all = |_: &RevisionInfo| true;
+
+        let mut paras = vec![parse_paragraph(REDLINE)];
+        let mut resolver = RevisionResolver::new(true, &all);
+        resolver.paragraphs(&mut paras, &mut []);
+        assert_eq!(resolver.count, 2);
+        assert_eq!(paras[0].text(), "The slow fox");
+        assert!(!write_paragraph(&paras[0]).contains("w:ins"));
+
+        let mut paras = vec![parse_paragraph(REDLINE)];
+        let mut resolver = RevisionResolver::new(false, &all);
+        resolver.paragraphs(&mut paras, &mut []);
+        assert_eq!(paras[0].text(), "The quick fox");
+        assert!(!write_paragraph(&paras[0]).contains("delText"));
+    }
+
+    #[test]
+    fn test_formatting_change() {
+        let xml = r#"<w:p><w:r><w:rPr><w:b/><w:rPrChange w:id="3" w:author="Ann"><w:rPr><w:i/></w:rPr></w:rPrChange></w:rPr><w:t>x</w:t></w:r></w:p>"#;
+        let para = parse_paragraph(xml);
+        let out = write_paragraph(&para);
+        assert!(out.contains(
+            r#"<w:b/><w:rPrChange w:id="3" w:author="Ann"><w:rPr><w:i/></w:rPr></w:rPrChange>"#
+        ));
+
+        let all = |_: &RevisionInfo| true;
+        let mut paras = vec![para];
+        RevisionResolver::new(false, &all).paragraphs(&mut paras, &mut []);
+        let run = paras[0].runs().next().unwrap();
+        assert!(run.italic());
+        assert!(!run.bold());
+    }
+
+    #[test]
+    fn test_deleted_paragraph_mark() {
+        let first = parse_paragraph(
+            r#"<w:p><w:pPr><w:rPr><w:del w:id="4" w:author="Ann"/></w:rPr></w:pPr><w:r><w:t>One</w:t></w:r></w:p>"#,
+        );
+        let second = parse_paragraph(r#"<w:p><w:r><w:t>Two</w:t></w:r></w:p>"#);
+
+        let all = |_: &RevisionInfo| true;
+        let mut paras = vec![first.clone(), second.clone()];
+        RevisionResolver::new(true, &all).paragraphs(&mut paras, &mut []);
+        assert_eq!(paras.len(), 1);
+        assert_eq!(paras[0].text(), "OneTwo");
+
+        let mut paras = vec![first, second];
+        RevisionResolver::new(false, &all).paragraphs(&mut paras, &mut []);
+        assert_eq!(paras.len(), 2);
+        assert!(!write_paragraph(&paras[0]).contains("w:del"));
+    }
+
+    #[test]
+    fn test_revisions_in_hyperlink() {
+        const LINK: &str = r#"<w:p><w:hyperlink r:id="rId7"><w:ins w:id="1" w:author="Ann"><w:r><w:t>link</w:t></w:r></w:ins><w:del w:id="2" w:author="Ann"><w:r><w:delText>old</w:delText></w:r></w:del></w:hyperlink></w:p>"#;
+        let para = parse_paragraph(LINK);
+        assert_eq!(para.text(), "link");
+        let mut collector = RevisionCollector::default();
+        collector.paragraphs(std::slice::from_ref(&para));
+        let texts: Vec<_> = collector.changes.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["link", "old"]);
+        assert!(write_paragraph(&para).contains(r#"<w:hyperlink r:id="rId7"><w:ins "#));
+
+        let all = |_: &RevisionInfo| true;
+        let mut paras = vec![para.clone()];
+        RevisionResolver::new(true, &all).paragraphs(&mut paras, &mut []);
+        assert_eq!(paras[0].text(), "link");
+        let xml = write_paragraph(&paras[0]);
+        assert!(xml.contains("<w:hyperlink r:id=\"rId7\"><w:r><w:t>link</w:t></w:r></w:hyperlink>"));
+
+        let mut paras = vec![para];
+        RevisionResolver::new(false, &all).paragraphs(&mut paras, &mut []);
+        assert_eq!(paras[0].text(), "old");
+        assert!(!write_paragraph(&paras[0]).contains("delText"));
+    }
+
+    #[test]
+    fn test_author_with_entity() {
+        let para = parse_paragraph(
+            r#"<w:p><w:ins w:id="1" w:author="R&amp;amp;D"><w:r><w:t>x</w:t></w:r></w:ins></w:p>"#,
+        );
+        let ParagraphContent::Revision(inline) = &para.content[0] else {
+            panic!("expected a revision");
+        };
+        assert_eq!(inline.info.author.as_deref(), Some("R&amp;D"));
+
+        let xml = r#"<w:trPr><w:ins w:id="2" w:author="R&amp;amp;D"/></w:trPr>"#;
+        let mut reader = Reader::from_str(xml);
+        let props = loop {
+            if let Event::Start(e) = reader.read_event().unwrap() {
+                break RawXmlElement::from_reader(&mut reader, &e).unwrap();
+            }
+        };
+        let (kind, info) = row_revision(&props).unwrap();
+        assert_eq!(kind, RevisionKind::Insertion);
+        assert_eq!(info.author, inline.info.author);
+    }
+
+    #[test]
+    fn test_filter_by_author() {
+        let only_bob = |info: &RevisionInfo| info.author.as_deref() == Some("Bob");
+        let mut paras = vec![parse_paragraph(REDLINE)];
+        let mut resolver = RevisionResolver::new(false, &only_bob);
+        resolver.paragraphs(&mut paras, &mut []);
+        assert_eq!(resolver.count, 1);
+        // Ann's deletion is still pending
+        assert_eq!(paras[0].text(), "The  fox");
+        assert!(write_paragraph(&paras[0]).contains("<w:del "));
+    }
+}
@@ -1,8 +1,10 @@
 //! Run element (w:r) - a contiguous run of text with uniform formatting
 
 use crate::document::image::InlineImage;
+use crate::document::{AnchoredImage, TextBox};
+use crate::document::{FieldChar, Revision, RevisionKind, RunPropertiesChange};
 use crate::error::Result;
-use crate::xml::{get_w_val, parse_bool, RawXmlElement, RawXmlNode};
+use crate::xml::{get_w_val, parse_bool, schema, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -25,6 +27,8 @@ pub struct Run {
 pub enum RunContent {
     /// Text (w:t)
     Text(String),
+    /// Deleted text (w:delText), found inside tracked deletions
+    DeletedText(String),
     /// Tab (w:tab)
     Tab,
     /// Break (w:br)
@@ -37,6 +41,20 @@ pub enum RunContent {
     NoBreakHyphen,
     /// Drawing (inline image)
     Drawing(InlineImage),
+    /// Drawing (floating image)
+    AnchoredDrawing(AnchoredImage),
+    /// Text box (DrawingML or VML), with its content parsed
+    TextBox(TextBox),
+    /// Reference mark of a comment (w:commentReference)
+    CommentReference(u32),
+    /// Reference mark of a footnote (w:footnoteReference)
+    FootnoteReference(i32),
+    /// Reference mark of an endnote (w:endnoteReference)
+    EndnoteReference(i32),
+    /// Begin, separator or end of a complex field (w:fldChar)
+    FieldChar(FieldChar),
+    /// Field code of a complex field (w:instrText)
+    InstrText(String),
     /// Unknown (preserved)
     Unknown(RawXmlNode),
 }
@@ -77,6 +95,10 @@ pub struct RunProperties {
     pub font_east_asia: Option<String>,
     /// Vertical alignment (superscript/subscript)
     pub vertical_align: Option<String>,
+    /// Tracked insertion or deletion of the paragraph mark (only in paragraph mark properties)
+    pub mark_revision: Option<Box<Revision>>,
+    /// Tracked formatting change (w:rPrChange)
+    pub change: Option<Box<RunPropertiesChange>>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
 }
@@ -110,6 +132,40 @@ impl Run {
                             let text = read_text_content(reader)?;
                             run.content.push(RunContent::Text(text));
                         }
+                        b"delText" => {
+                            let text = read_text_content(reader)?;
+                            run.content.push(RunContent::DeletedText(text));
+                        }
+                        b"instrText" => {
+                            let code = read_text_content(reader)?;
+                            run.content.push(RunContent::InstrText(code));
+                        }
+                        b"fldChar" => {
+                            let fld_char = FieldChar::from_reader(reader, &e)?;
+                            run.content.push(RunContent::FieldChar(fld_char));
+                        }
+                        b"drawing" => {
+                            let raw = RawXmlElement::from_reader(reader, &e)?;
+                            let content = InlineImage::from_raw(raw)
+                                .map(RunContent::Drawing)
+                                .or_else(|raw| {
+                                    AnchoredImage::from_raw(raw).map(RunContent::AnchoredDrawing)
+                                })
+                                .or_else(|raw| TextBox::from_raw(raw).map(RunContent::TextBox))
+                                .unwrap_or_else(|raw| {
+                                    RunContent::Unknown(RawXmlNode::Element(raw))
+                                });
+                            run.content.push(content);
+                        }
+                        b"pict" | b"AlternateContent" => {
+                            let raw = RawXmlElement::from_reader(reader, &e)?;
+                            let content = TextBox::from_raw(raw)
+                                .map(RunContent::TextBox)
+                                .unwrap_or_else(|raw| {
+                                    RunContent::Unknown(RawXmlNode::Element(raw))
+                                });
+                            run.content.push(content);
+                        }
                         _ => {
                             // Unknown - preserve
                             let raw = RawXmlElement::from_reader(reader, &e)?;
@@ -127,6 +183,16 @@ impl Run {
                             // Empty text element
                             run.content.push(RunContent::Text(String::new()));
                         }
+                        b"delText" => {
+                            run.content.push(RunContent::DeletedText(String::new()));
+                        }
+                        b"instrText" => {
+                            run.content.push(RunContent::InstrText(String::new()));
+                        }
+                        b"fldChar" => {
+                            run.content
+                                .push(RunContent::FieldChar(FieldChar::from_element(&e)?));
+                        }
                         b"tab" => {
                             run.content.push(RunContent::Tab);
                         }
@@ -150,6 +216,15 @@ impl Run {
                         b"noBreakHyphen" => {
                             run.content.push(RunContent::NoBreakHyphen);
                         }
+                        b"commentReference" => {
+                            let id = crate::document::paragraph::comment_id(&e);
+                            run.content.push(RunContent::CommentReference(id));
+                        }
+                        b"footnoteReference" | b"endnoteReference"
+                            if note_reference(&e).is_some() =>
+                        {
+                            run.content.extend(note_reference(&e));
+                        }
                         _ => {
                             // Unknown - preserve
                             let raw = RawXmlElement {
@@ -172,10 +247,8 @@ impl Run {
                         }
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"r" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"r" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -199,16 +272,22 @@ impl Run {
         Ok(run)
     }
 
-    /// Get all text in this run
+    /// Get all text in this run (deleted text excluded)
     pub fn text(&self) -> String {
+        self.collect_text(false)
+    }
+
+    /// Get all text including tracked deleted text
+    pub(crate) fn text_with_deletions(&self) -> String {
+        self.collect_text(true)
+    }
+
+    fn collect_text(&self, deleted: bool) -> String {
         let mut result = String::new();
         for content in &self.content {
             match content {
-                RunContent::Text(t) => result.push_str(t),
-                RunContent::Tab => result.push('\t'),
-                RunContent::Break(BreakType::TextWrapping) => result.push('\n'),
-                RunContent::CarriageReturn => result.push('\n'),
-                _ => {}
+                RunContent::DeletedText(t) if deleted => result.push_str(t),
+                other => result.extend(other.plain_text()),
             }
         }
         result
@@ -299,6 +378,42 @@ impl Run {
         }
     }
 
+    /// Create a footnote reference mark in the FootnoteReference style
+    pub fn footnote_reference(id: i32) -> Self {
+        let mut run = Run {
+            content: vec![RunContent::FootnoteReference(id)],
+            ..Default::default()
+        };
+        run.set_style("FootnoteReference");
+        run
+    }
+
+    /// Create an endnote reference mark in the EndnoteReference style
+    pub fn endnote_reference(id: i32) -> Self {
+        let mut run = Run {
+            content: vec![RunContent::EndnoteReference(id)],
+            ..Default::default()
+        };
+        run.set_style("EndnoteReference");
+        run
+    }
+
+    /// ID of the footnote this run references
+    pub fn footnote_id(&self) -> Option<i32> {
+        self.content.iter().find_map(|c| match c {
+            RunContent::FootnoteReference(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// ID of the endnote this run references
+    pub fn endnote_id(&self) -> Option<i32> {
+        self.content.iter().find_map(|c| match c {
+            RunContent::EndnoteReference(id) => Some(*id),
+            _ => None,
+        })
+    }
+
     /// Set bold
     pub fn set_bold(&mut self, bold: bool) {
         self.properties.get_or_insert_with(Default::default).bold = Some(bold);
@@ -424,22 +539,34 @@ impl Run {
     pub fn add_image(&mut self, image: InlineImage) {
         self.content.push(RunContent::Drawing(image));
     }
+
+    /// Add a floating image to this run
+    pub fn add_anchored_image(&mut self, image: AnchoredImage) {
+        self.content.push(RunContent::AnchoredDrawing(image));
+    }
+
+    /// Add a text box to this run
+    pub fn add_text_box(&mut self, text_box: TextBox) {
+        self.content.push(RunContent::TextBox(text_box));
+    }
 }
 
 impl RunContent {
+    /// Text this content contributes to the run text, if any
+    pub(crate) fn plain_text(&self) -> Option<&str> {
+        match self {
+            RunContent::Text(t) => Some(t),
+            RunContent::Tab => Some("\t"),
+            RunContent::Break(BreakType::TextWrapping) | RunContent::CarriageReturn => Some("\n"),
+            _ => None,
+        }
+    }
+
     /// Write to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         match self {
-            RunContent::Text(text) => {
-                let mut start = BytesStart::new("w:t");
-                // Preserve space if text has leading/trailing whitespace
-                if text.starts_with(' ') || text.ends_with(' ') || text.contains("  ") {
-                    start.push_attribute(("xml:space", "preserve"));
-                }
-                writer.write_event(Event::Start(start))?;
-                writer.write_event(Event::Text(BytesText::new(text)))?;
-                writer.write_event(Event::End(BytesEnd::new("w:t")))?;
-            }
+            RunContent::Text(text) => write_text(writer, "w:t", text)?,
+            RunContent::DeletedText(text) => write_text(writer, "w:delText", text)?,
             RunContent::Tab => {
                 writer.write_event(Event::Empty(BytesStart::new("w:tab")))?;
             }
@@ -464,6 +591,27 @@ impl RunContent {
             RunContent::Drawing(img) => {
                 img.to_drawing_xml(writer)?;
             }
+            RunContent::AnchoredDrawing(img) => {
+                img.to_drawing_xml(writer)?;
+            }
+            RunContent::TextBox(text_box) => text_box.write_to(writer)?,
+            RunContent::CommentReference(id) => {
+                let mut elem = BytesStart::new("w:commentReference");
+                elem.push_attribute(("w:id", id.to_string().as_str()));
+                writer.write_event(Event::Empty(elem))?;
+            }
+            RunContent::FootnoteReference(id) | RunContent::EndnoteReference(id) => {
+                let tag = if matches!(self, RunContent::FootnoteReference(_)) {
+                    "w:footnoteReference"
+                } else {
+                    "w:endnoteReference"
+                };
+                let mut elem = BytesStart::new(tag);
+                elem.push_attribute(("w:id", id.to_string().as_str()));
+                writer.write_event(Event::Empty(elem))?;
+            }
+            RunContent::FieldChar(fld_char) => fld_char.write_to(writer)?,
+            RunContent::InstrText(code) => write_text(writer, "w:instrText", code)?,
             RunContent::Unknown(node) => {
                 node.write_to(writer)?;
             }
@@ -494,6 +642,16 @@ impl RunProperties {
                             // Skip to end
                             skip_element(reader, &e)?;
                         }
+                        b"ins" | b"del" | b"moveFrom" | b"moveTo" => {
+                            let kind = RevisionKind::from_local_name(local.as_ref())
+                                .expect("revision element");
+                            props.mark_revision =
+                                Some(Box::new(Revision::from_reader(reader, &e, kind)?));
+                        }
+                        b"rPrChange" => {
+                            props.change =
+                                Some(Box::new(RunPropertiesChange::from_reader(reader, &e)?));
+                        }
                         _ => {
                             // Unknown - preserve
                             let raw = RawXmlElement::from_reader(reader, &e)?;
@@ -512,15 +670,9 @@ impl RunProperties {
                         b"b" => {
                             props.bold = Some(parse_bool(&e));
                         }
-                        b"bCs" => {
-                            // Complex script bold - ignore for now
-                        }
                         b"i" => {
                             props.italic = Some(parse_bool(&e));
                         }
-                        b"iCs" => {
-                            // Complex script italic - ignore for now
-                        }
                         b"u" => {
                             props.underline = get_w_val(&e).or(Some("single".into()));
                         }
@@ -533,9 +685,6 @@ impl RunProperties {
                         b"sz" => {
                             props.size = get_w_val(&e).and_then(|v| v.parse().ok());
                         }
-                        b"szCs" => {
-                            // Complex script size - ignore for now
-                        }
                         b"color" => {
                             props.color = get_w_val(&e);
                         }
@@ -551,6 +700,11 @@ impl RunProperties {
                             props.font_east_asia = crate::xml::get_attr(&e, "w:eastAsia")
                                 .or_else(|| crate::xml::get_attr(&e, "eastAsia"));
                         }
+                        b"ins" | b"del" | b"moveFrom" | b"moveTo" => {
+                            let kind = RevisionKind::from_local_name(local.as_ref())
+                                .expect("revision element");
+                            props.mark_revision = Some(Box::new(Revision::from_empty(&e, kind)?));
+                        }
                         _ => {
                             // Unknown - preserve
                             let raw = RawXmlElement {
@@ -572,10 +726,8 @@ impl RunProperties {
                         }
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"rPr" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"rPr" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -586,129 +738,147 @@ impl RunProperties {
         Ok(props)
     }
 
+    /// Check if no properties are set
+    pub fn is_empty(&self) -> bool {
+        self.style.is_none()
+            && self.bold.is_none()
+            && self.italic.is_none()
+            && self.underline.is_none()
+            && self.strike.is_none()
+            && self.double_strike.is_none()
+            && self.size.is_none()
+            && self.color.is_none()
+            && self.highlight.is_none()
+            && self.font_ascii.is_none()
+            && self.font_east_asia.is_none()
+            && self.vertical_align.is_none()
+            && self.mark_revision.is_none()
+            && self.change.is_none()
+            && self.unknown_children.is_empty()
+    }
+
     /// Write to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
         // Check if there are any properties to write
-        let has_content = self.style.is_some()
-            || self.bold.is_some()
-            || self.italic.is_some()
-            || self.underline.is_some()
-            || self.strike.is_some()
-            || self.double_strike.is_some()
-            || self.size.is_some()
-            || self.color.is_some()
-            || self.highlight.is_some()
-            || self.font_ascii.is_some()
-            || self.vertical_align.is_some()
-            || !self.unknown_children.is_empty();
-
-        if !has_content {
+        if self.is_empty() {
             return Ok(());
         }
 
         writer.write_event(Event::Start(BytesStart::new("w:rPr")))?;
 
-        // Style
-        if let Some(style) = &self.style {
-            let mut elem = BytesStart::new("w:rStyle");
-            elem.push_attribute(("w:val", style.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Fonts
-        if self.font_ascii.is_some() || self.font_east_asia.is_some() {
-            let mut elem = BytesStart::new("w:rFonts");
-            if let Some(font) = &self.font_ascii {
-                elem.push_attribute(("w:ascii", font.as_str()));
-            }
-            if let Some(font) = &self.font_east_asia {
-                elem.push_attribute(("w:eastAsia", font.as_str()));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Bold
-        if let Some(bold) = self.bold {
-            let mut elem = BytesStart::new("w:b");
-            if !bold {
-                elem.push_attribute(("w:val", "0"));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Italic
-        if let Some(italic) = self.italic {
-            let mut elem = BytesStart::new("w:i");
-            if !italic {
-                elem.push_attribute(("w:val", "0"));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Strike
-        if let Some(strike) = self.strike {
-            let mut elem = BytesStart::new("w:strike");
-            if !strike {
-                elem.push_attribute(("w:val", "0"));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Double strike
-        if let Some(dstrike) = self.double_strike {
-            let mut elem = BytesStart::new("w:dstrike");
-            if !dstrike {
-                elem.push_attribute(("w:val", "0"));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Underline
-        if let Some(underline) = &self.underline {
-            let mut elem = BytesStart::new("w:u");
-            elem.push_attribute(("w:val", underline.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
+        schema::write_in_order(
+            writer,
+            schema::RPR,
+            &self.unknown_children,
+            |writer, name| {
+                match name {
+                    "rStyle" => write_val(writer, "w:rStyle", self.style.as_deref())?,
+                    "rFonts" if self.font_ascii.is_some() || self.font_east_asia.is_some() => {
+                        let mut elem = BytesStart::new("w:rFonts");
+                        if let Some(font) = &self.font_ascii {
+                            elem.push_attribute(("w:ascii", font.as_str()));
+                        }
+                        if let Some(font) = &self.font_east_asia {
+                            elem.push_attribute(("w:eastAsia", font.as_str()));
+                        }
+                        writer.write_event(Event::Empty(elem))?;
+                    }
+                    "b" => write_toggle(writer, "w:b", self.bold)?,
+                    "i" => write_toggle(writer, "w:i", self.italic)?,
+                    "strike" => write_toggle(writer, "w:strike", self.strike)?,
+                    "dstrike" => write_toggle(writer, "w:dstrike", self.double_strike)?,
+                    "color" => write_val(writer, "w:color", self.color.as_deref())?,
+                    "sz" => {
+                        let size = self.size.map(|s| s.to_string());
+                        write_val(writer, "w:sz", size.as_deref())?;
+                    }
+                    "highlight" => write_val(writer, "w:highlight", self.highlight.as_deref())?,
+                    "u" => write_val(writer, "w:u", self.underline.as_deref())?,
+                    "vertAlign" => {
+                        write_val(writer, "w:vertAlign", self.vertical_align.as_deref())?
+                    }
+                    "ins" | "del" | "moveFrom" | "moveTo" => {
+                        if let Some(ref rev) = self.mark_revision {
+                            if rev.kind.tag().strip_prefix("w:") == Some(name) {
+                                rev.write_to(writer)?;
+                            }
+                        }
+                    }
+                    "rPrChange" => {
+                        if let Some(ref change) = self.change {
+                            change.write_to(writer)?;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            },
+        )?;
 
-        // Color
-        if let Some(color) = &self.color {
-            let mut elem = BytesStart::new("w:color");
-            elem.push_attribute(("w:val", color.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
+        writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
+        Ok(())
+    }
+}
 
-        // Size
-        if let Some(size) = self.size {
-            let mut elem = BytesStart::new("w:sz");
-            elem.push_attribute(("w:val", size.to_string().as_str()));
-            writer.write_event(Event::Empty(elem))?;
+/// Write an on/off element; `false` is written explicitly so it can override a style
+fn write_toggle<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+    value: Option<bool>,
+) -> Result<()> {
+    if let Some(on) = value {
+        let mut elem = BytesStart::new(tag);
+        if !on {
+            elem.push_attribute(("w:val", "0"));
         }
+        writer.write_event(Event::Empty(elem))?;
+    }
+    Ok(())
+}
 
-        // Highlight
-        if let Some(highlight) = &self.highlight {
-            let mut elem = BytesStart::new("w:highlight");
-            elem.push_attribute(("w:val", highlight.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
+/// Write an element carrying a single `w:val` attribute
+fn write_val<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    tag: &str,
+    value: Option<&str>,
+) -> Result<()> {
+    if let Some(v) = value {
+        let mut elem = BytesStart::new(tag);
+        elem.push_attribute(("w:val", v));
+        writer.write_event(Event::Empty(elem))?;
+    }
+    Ok(())
+}
 
-        // Vertical align
-        if let Some(valign) = &self.vertical_align {
-            let mut elem = BytesStart::new("w:vertAlign");
-            elem.push_attribute(("w:val", valign.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
+/// Write a text element, preserving significant whitespace
+fn write_text<W: std::io::Write>(writer: &mut Writer<W>, tag: &str, text: &str) -> Result<()> {
+    let mut start = BytesStart::new(tag);
+    // Preserve space if text has leading/trailing whitespace
+    if text.starts_with(' ') || text.ends_with(' ') || text.contains("  ") {
+        start.push_attribute(("xml:space", "preserve"));
+    }
+    writer.write_event(Event::Start(start))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(tag)))?;
+    Ok(())
+}
 
-        // Unknown children
-        for child in &self.unknown_children {
-            child.write_to(writer)?;
+/// Footnote or endnote reference with a plain ID, or None for custom marks
+fn note_reference(e: &BytesStart) -> Option<RunContent> {
+    let mut id = None;
+    for attr in e.attributes().filter_map(|a| a.ok()) {
+        if attr.key.local_name().as_ref() != b"id" {
+            return None;
         }
-
-        writer.write_event(Event::End(BytesEnd::new("w:rPr")))?;
-        Ok(())
+        id = std::str::from_utf8(&attr.value).ok()?.parse().ok();
+    }
+    match e.local_name().as_ref() {
+        b"footnoteReference" => Some(RunContent::FootnoteReference(id?)),
+        _ => Some(RunContent::EndnoteReference(id?)),
     }
 }
 
-/// Read text content from w:t element
+/// Read text content from w:t, w:delText or w:instrText element
 fn read_text_content<R: BufRead>(reader: &mut Reader<R>) -> Result<String> {
     let mut text = String::new();
     let mut buf = Vec::new();
@@ -718,11 +888,7 @@ fn read_text_content<R: BufRead>(reader: &mut Reader<R>) -> Result<String> {
             Event::Text(t) => {
                 text.push_str(&t.unescape()?);
             }
-            Event::End(e) => {
-                if e.name().local_name().as_ref() == b"t" {
-                    break;
-                }
-            }
+            Event::End(_) => break,
             Event::Eof => break,
             _ => {}
         }
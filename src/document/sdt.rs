@@ -0,0 +1,1787 @@
+//! Content controls (w:sdt)
+//!
+//! Structured document tags wrap block content, runs, table rows or table
+//! cells. Block- and run-level controls own their content. Controls around
+//! table rows, cells, or the paragraphs of a cell or header keep their content
+//! in the enclosing list and record the range they span, so table and header
+//! APIs keep seeing every row, cell and paragraph.
+
+use crate::document::field_update::{format_date, DateTime};
+use crate::document::paragraph::content_text;
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, Run, RunProperties, Table, TableCell,
+    TableRow,
+};
+use crate::error::{Error, Result};
+use crate::xml::{schema, RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::io::BufRead;
+use std::ops::Range;
+
+/// Character style Word gives placeholder text
+const PLACEHOLDER_STYLE: &str = "PlaceholderText";
+
+/// Content control (w:sdt)
+#[derive(Clone, Debug)]
+pub struct Sdt {
+    /// Control properties (w:sdtPr)
+    pub properties: SdtProperties,
+    /// Formatting of the control's end mark (w:sdtEndPr), kept as is
+    pub end_properties: Option<RawXmlNode>,
+    /// Content (w:sdtContent)
+    pub content: SdtContent,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+/// Content of a content control
+#[derive(Clone, Debug)]
+pub enum SdtContent {
+    /// Paragraphs, tables and nested controls (block-level)
+    Blocks(Vec<BlockContent>),
+    /// Runs and other paragraph content (run-level)
+    Inline(Vec<ParagraphContent>),
+    /// Paragraphs of a table cell or header, as indices into its paragraphs
+    Paragraphs(Range<usize>),
+    /// Table rows, as indices into [`Table::rows`]
+    Rows(Range<usize>),
+    /// Table cells, as indices into [`TableRow::cells`]
+    Cells(Range<usize>),
+}
+
+/// Where a content control sits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SdtLevel {
+    /// Around paragraphs and tables
+    Block,
+    /// Inside a paragraph
+    Inline,
+    /// Around table rows
+    Row,
+    /// Around table cells
+    Cell,
+}
+
+/// Content control properties (w:sdtPr)
+#[derive(Clone, Debug, Default)]
+pub struct SdtProperties {
+    /// Unique ID (w:id)
+    pub id: Option<i64>,
+    /// Tag for programmatic lookup (w:tag)
+    pub tag: Option<String>,
+    /// Friendly name shown in Word (w:alias)
+    pub alias: Option<String>,
+    /// Locking (w:lock)
+    pub lock: Option<SdtLock>,
+    /// Glossary document part holding the placeholder text (w:placeholder)
+    pub placeholder: Option<String>,
+    /// Whether the content is the placeholder text (w:showingPlcHdr)
+    pub showing_placeholder: bool,
+    /// XML mapping into a custom XML part (w:dataBinding)
+    pub data_binding: Option<DataBinding>,
+    /// Type of control
+    pub kind: SdtKind,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+/// Content control locking (w:lock)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SdtLock {
+    /// The control can't be deleted
+    SdtLocked,
+    /// The content can't be edited
+    ContentLocked,
+    /// Neither the control nor its content can be changed
+    SdtContentLocked,
+    /// No locking
+    Unlocked,
+}
+
+impl SdtLock {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sdtLocked" => Some(Self::SdtLocked),
+            "contentLocked" => Some(Self::ContentLocked),
+            "sdtContentLocked" => Some(Self::SdtContentLocked),
+            "unlocked" => Some(Self::Unlocked),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SdtLocked => "sdtLocked",
+            Self::ContentLocked => "contentLocked",
+            Self::SdtContentLocked => "sdtContentLocked",
+            Self::Unlocked => "unlocked",
+        }
+    }
+}
+
+/// Mapping of a content control to a node in a custom XML part
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataBinding {
+    /// XPath of the bound node
+    pub xpath: String,
+    /// Datastore item ID of the custom XML part
+    pub store_item_id: Option<String>,
+    /// Namespace prefixes used by the XPath (`xmlns:ns0='...'`)
+    pub prefix_mappings: Option<String>,
+}
+
+/// Type of a content control
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SdtKind {
+    /// Rich text (no type element)
+    #[default]
+    RichText,
+    /// Plain text (w:text)
+    PlainText {
+        /// Whether line breaks are allowed
+        multi_line: bool,
+    },
+    /// Checkbox (w14:checkbox)
+    Checkbox(SdtCheckbox),
+    /// Drop-down list (w:dropDownList)
+    DropDownList(SdtList),
+    /// Combo box (w:comboBox)
+    ComboBox(SdtList),
+    /// Date picker (w:date)
+    Date(SdtDate),
+    /// Picture (w:picture)
+    Picture,
+    /// Building block gallery such as a table of contents (w:docPartObj)
+    DocPart(SdtDocPart),
+    /// Group (w:group)
+    Group,
+    /// Another type, kept in [`SdtProperties::unknown_children`]
+    Other,
+}
+
+/// Checkbox state and symbols
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SdtCheckbox {
+    /// Whether the box is checked
+    pub checked: bool,
+    /// Symbol shown when checked
+    pub checked_symbol: CheckboxSymbol,
+    /// Symbol shown when unchecked
+    pub unchecked_symbol: CheckboxSymbol,
+}
+
+impl Default for SdtCheckbox {
+    fn default() -> Self {
+        Self {
+            checked: false,
+            checked_symbol: CheckboxSymbol::new("2612", "MS Gothic"),
+            unchecked_symbol: CheckboxSymbol::new("2610", "MS Gothic"),
+        }
+    }
+}
+
+/// A checkbox symbol: a character code and the font it's drawn in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckboxSymbol {
+    /// Hexadecimal character code, e.g. `2612` for ☒
+    pub code: String,
+    /// Font name
+    pub font: String,
+}
+
+impl CheckboxSymbol {
+    pub fn new(code: impl Into<String>, font: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            font: font.into(),
+        }
+    }
+
+    /// The symbol as a character
+    pub fn char(&self) -> Option<char> {
+        u32::from_str_radix(&self.code, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+}
+
+/// Items of a drop-down list or combo box
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SdtList {
+    /// List items
+    pub items: Vec<ListItem>,
+    /// Value of the selected item (w:lastValue)
+    pub last_value: Option<String>,
+}
+
+/// A drop-down list or combo box item
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListItem {
+    /// Text shown in the document
+    pub display_text: String,
+    /// Value stored for the item
+    pub value: String,
+}
+
+impl ListItem {
+    pub fn new(display_text: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            display_text: display_text.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Date picker settings
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SdtDate {
+    /// Selected date (ISO 8601)
+    pub full_date: Option<String>,
+    /// Display format picture, e.g. `M/d/yyyy`
+    pub format: Option<String>,
+    /// Language ID, e.g. `en-US`
+    pub locale: Option<String>,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+/// Building block gallery of a document part control
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SdtDocPart {
+    /// Gallery, e.g. `Table of Contents` or `Page Numbers (Bottom of Page)`
+    pub gallery: Option<String>,
+    /// Category within the gallery
+    pub category: Option<String>,
+    /// Whether the document may hold only one such control
+    pub unique: bool,
+}
+
+/// A content control found in a document
+#[derive(Clone, Debug)]
+pub struct ContentControl {
+    /// Control properties
+    pub properties: SdtProperties,
+    /// Where the control sits
+    pub level: SdtLevel,
+    /// Text of the content; paragraphs and rows are separated by `\n`, cells by `\t`
+    pub text: String,
+}
+
+impl ContentControl {
+    /// Tag of the control
+    pub fn tag(&self) -> Option<&str> {
+        self.properties.tag.as_deref()
+    }
+
+    /// Friendly name of the control
+    pub fn alias(&self) -> Option<&str> {
+        self.properties.alias.as_deref()
+    }
+
+    /// Current value: `true`/`false` for checkboxes, the ISO date for date
+    /// pickers, the item value for lists and the text otherwise
+    pub fn value(&self) -> String {
+        self.properties.value(&self.text)
+    }
+}
+
+impl Sdt {
+    /// Create a rich text control holding `text`
+    pub fn rich_text(text: impl Into<String>) -> Self {
+        Self::with_kind(SdtKind::RichText, text)
+    }
+
+    /// Create a plain text control holding `text`
+    pub fn plain_text(text: impl Into<String>) -> Self {
+        Self::with_kind(SdtKind::PlainText { multi_line: false }, text)
+    }
+
+    /// Create a checkbox
+    pub fn checkbox(checked: bool) -> Self {
+        let checkbox = SdtCheckbox {
+            checked,
+            ..Default::default()
+        };
+        let symbol = if checked {
+            checkbox.checked_symbol.clone()
+        } else {
+            checkbox.unchecked_symbol.clone()
+        };
+        let mut run = Run::new(symbol.char().map(String::from).unwrap_or_default());
+        run.set_font(symbol.font.clone());
+        run.set_font_east_asia(symbol.font);
+        let mut sdt = Self::with_kind(SdtKind::Checkbox(checkbox), "");
+        sdt.content = SdtContent::Inline(vec![ParagraphContent::Run(run)]);
+        sdt
+    }
+
+    /// Create a drop-down list showing the item with value `selected`
+    pub fn dropdown(items: Vec<ListItem>, selected: Option<&str>) -> Self {
+        Self::list(items, selected, false)
+    }
+
+    /// Create a combo box showing the item with value `selected`
+    pub fn combo_box(items: Vec<ListItem>, selected: Option<&str>) -> Self {
+        Self::list(items, selected, true)
+    }
+
+    fn list(items: Vec<ListItem>, selected: Option<&str>, combo: bool) -> Self {
+        let item = selected.and_then(|value| items.iter().find(|item| item.value == value));
+        let text = item
+            .map(|item| item.display_text.clone())
+            .unwrap_or_default();
+        let list = SdtList {
+            last_value: item.map(|item| item.value.clone()),
+            items,
+        };
+        let kind = if combo {
+            SdtKind::ComboBox(list)
+        } else {
+            SdtKind::DropDownList(list)
+        };
+        Self::with_kind(kind, text)
+    }
+
+    /// Create a date picker showing an ISO 8601 `date` with a format picture
+    /// such as `d MMMM yyyy`
+    pub fn date(date: &str, format: impl Into<String>) -> Self {
+        let mut sdt = Self::with_kind(
+            SdtKind::Date(SdtDate {
+                format: Some(format.into()),
+                locale: Some("en-US".into()),
+                ..Default::default()
+            }),
+            "",
+        );
+        if sdt.set_value(date).is_err() {
+            sdt.properties.kind = SdtKind::Date(SdtDate::default());
+        }
+        sdt
+    }
+
+    fn with_kind(kind: SdtKind, text: impl Into<String>) -> Self {
+        Self {
+            properties: SdtProperties {
+                kind,
+                ..Default::default()
+            },
+            end_properties: None,
+            content: SdtContent::Inline(vec![ParagraphContent::Run(Run::new(text))]),
+            unknown_children: Vec::new(),
+        }
+    }
+
+    /// Set the tag
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.properties.tag = Some(tag.into());
+        self
+    }
+
+    /// Set the friendly name
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.properties.alias = Some(alias.into());
+        self
+    }
+
+    /// Set the locking
+    pub fn with_lock(mut self, lock: SdtLock) -> Self {
+        self.properties.lock = Some(lock);
+        self
+    }
+
+    /// Bind the control to a node of a custom XML part
+    pub fn with_data_binding(mut self, binding: DataBinding) -> Self {
+        self.properties.data_binding = Some(binding);
+        self
+    }
+
+    /// Show `text` as placeholder text until a value is set
+    pub fn with_placeholder_text(mut self, text: impl Into<String>) -> Self {
+        let mut run = Run::new(text);
+        run.set_style(PLACEHOLDER_STYLE);
+        self.content = SdtContent::Inline(vec![ParagraphContent::Run(run)]);
+        self.properties.showing_placeholder = true;
+        self
+    }
+
+    /// Where the control sits
+    pub fn level(&self) -> SdtLevel {
+        self.content.level()
+    }
+
+    /// Text of block or run-level content; paragraphs are separated by `\n`
+    pub fn text(&self) -> String {
+        match &self.content {
+            SdtContent::Blocks(blocks) => blocks_paragraphs(blocks)
+                .iter()
+                .map(|p| p.text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            SdtContent::Inline(content) => content_text(content),
+            _ => String::new(),
+        }
+    }
+
+    /// Set the value, keeping the formatting of the first run
+    ///
+    /// Text controls take any text. Checkboxes take `true`/`false` (or
+    /// `1`/`0`), date pickers an ISO 8601 date, and lists the value or
+    /// display text of one of their items; combo boxes also take free text.
+    /// Controls around table rows, cells or cell paragraphs are set through
+    /// [`Document::set_content_control_value`].
+    pub fn set_value(&mut self, value: &str) -> Result<()> {
+        if !matches!(self.content, SdtContent::Blocks(_) | SdtContent::Inline(_)) {
+            return Err(Error::Unsupported(
+                "content control content is held by the enclosing table or header".into(),
+            ));
+        }
+        let display = self.properties.apply_value(value)?;
+        match &mut self.content {
+            SdtContent::Inline(content) => set_inline_text(content, &display),
+            SdtContent::Blocks(blocks) => {
+                let (props, run_props) = first_formatting(blocks_paragraphs(blocks));
+                blocks.clear();
+                blocks.push(BlockContent::Paragraph(Box::new(formatted_paragraph(
+                    props, run_props, &display,
+                ))));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parse a control, handing each child element of w:sdtContent to `child`
+    ///
+    /// The returned control has empty inline content for the caller to fill in.
+    pub(crate) fn parse<R, F>(reader: &mut Reader<R>, mut child: F) -> Result<Self>
+    where
+        R: BufRead,
+        F: FnMut(&mut Reader<R>, &BytesStart, bool) -> Result<()>,
+    {
+        let mut sdt = Sdt {
+            properties: SdtProperties::default(),
+            end_properties: None,
+            content: SdtContent::Inline(Vec::new()),
+            unknown_children: Vec::new(),
+        };
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => match e.local_name().as_ref() {
+                    b"sdtPr" => {
+                        let raw = RawXmlElement::from_reader(reader, &e)?;
+                        sdt.properties = SdtProperties::from_raw(raw);
+                    }
+                    b"sdtEndPr" => {
+                        let raw = RawXmlElement::from_reader(reader, &e)?;
+                        sdt.end_properties = Some(RawXmlNode::Element(raw));
+                    }
+                    b"sdtContent" => read_children(reader, &mut child)?,
+                    _ => {
+                        let raw = RawXmlElement::from_reader(reader, &e)?;
+                        sdt.unknown_children.push(RawXmlNode::Element(raw));
+                    }
+                },
+                Event::Empty(e) => match e.local_name().as_ref() {
+                    b"sdtPr" => {}
+                    b"sdtEndPr" => {
+                        let raw = RawXmlElement::from_empty(&e);
+                        sdt.end_properties = Some(RawXmlNode::Element(raw));
+                    }
+                    b"sdtContent" => {}
+                    _ => {
+                        let raw = RawXmlElement::from_empty(&e);
+                        sdt.unknown_children.push(RawXmlNode::Element(raw));
+                    }
+                },
+                Event::End(e) if e.local_name().as_ref() == b"sdt" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(sdt)
+    }
+
+    /// Parse a block-level control (after the w:sdt start tag)
+    pub fn from_reader_block<R: BufRead>(
+        reader: &mut Reader<R>,
+        _start: &BytesStart,
+    ) -> Result<Self> {
+        let mut blocks = Vec::new();
+        let mut sdt = Self::parse(reader, |reader, e, empty| {
+            blocks.push(BlockContent::from_element(reader, e, empty)?);
+            Ok(())
+        })?;
+        sdt.content = SdtContent::Blocks(blocks);
+        Ok(sdt)
+    }
+
+    /// Parse a run-level control (after the w:sdt start tag)
+    pub fn from_reader_inline<R: BufRead>(
+        reader: &mut Reader<R>,
+        _start: &BytesStart,
+    ) -> Result<Self> {
+        let mut content = Vec::new();
+        let mut sdt = Self::parse(reader, |reader, e, empty| {
+            content.push(if empty {
+                ParagraphContent::from_empty(e)?
+            } else {
+                ParagraphContent::from_start(reader, e)?
+            });
+            Ok(())
+        })?;
+        sdt.content = SdtContent::Inline(content);
+        Ok(sdt)
+    }
+
+    /// Write w:sdt, w:sdtPr, w:sdtEndPr and the w:sdtContent start tag
+    pub(crate) fn write_start<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        writer.write_event(Event::Start(BytesStart::new("w:sdt")))?;
+        self.properties.write_to(writer)?;
+        if let Some(end) = &self.end_properties {
+            end.write_to(writer)?;
+        }
+        writer.write_event(Event::Start(BytesStart::new("w:sdtContent")))?;
+        Ok(())
+    }
+
+    /// Write the w:sdtContent and w:sdt end tags
+    pub(crate) fn write_end<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        writer.write_event(Event::End(BytesEnd::new("w:sdtContent")))?;
+        for child in &self.unknown_children {
+            child.write_to(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("w:sdt")))?;
+        Ok(())
+    }
+
+    /// Write a block or run-level control; ranged content is written by its owner
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        self.write_start(writer)?;
+        match &self.content {
+            SdtContent::Blocks(blocks) => {
+                for block in blocks {
+                    block.write_to(writer)?;
+                }
+            }
+            SdtContent::Inline(content) => {
+                for item in content {
+                    item.write_to(writer)?;
+                }
+            }
+            _ => {}
+        }
+        self.write_end(writer)
+    }
+
+    fn range(&self) -> Option<Range<usize>> {
+        match &self.content {
+            SdtContent::Paragraphs(r) | SdtContent::Rows(r) | SdtContent::Cells(r) => {
+                Some(r.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn range_mut(&mut self) -> Option<&mut Range<usize>> {
+        match &mut self.content {
+            SdtContent::Paragraphs(r) | SdtContent::Rows(r) | SdtContent::Cells(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl SdtContent {
+    /// Where a control with this content sits
+    pub fn level(&self) -> SdtLevel {
+        match self {
+            SdtContent::Blocks(_) | SdtContent::Paragraphs(_) => SdtLevel::Block,
+            SdtContent::Inline(_) => SdtLevel::Inline,
+            SdtContent::Rows(_) => SdtLevel::Row,
+            SdtContent::Cells(_) => SdtLevel::Cell,
+        }
+    }
+}
+
+impl SdtProperties {
+    /// Interpret a parsed w:sdtPr element
+    fn from_raw(raw: RawXmlElement) -> Self {
+        let mut props = SdtProperties::default();
+        for child in raw.children {
+            let RawXmlNode::Element(e) = child else {
+                continue;
+            };
+            let local = e.name.rsplit(':').next().unwrap_or(&e.name).to_string();
+            match local.as_str() {
+                "id" => props.id = attr(&e, "w:val").and_then(|v| v.parse().ok()),
+                "tag" => props.tag = attr(&e, "w:val").map(String::from),
+                "alias" => props.alias = attr(&e, "w:val").map(String::from),
+                "lock" => props.lock = attr(&e, "w:val").and_then(SdtLock::parse),
+                "placeholder" => {
+                    props.placeholder = elements(&e)
+                        .find(|c| local_name(c) == "docPart")
+                        .and_then(|c| attr(c, "w:val"))
+                        .map(String::from)
+                }
+                "showingPlcHdr" => props.showing_placeholder = bool_val(&e),
+                "dataBinding" => {
+                    props.data_binding = Some(DataBinding {
+                        xpath: attr(&e, "w:xpath").unwrap_or_default().to_string(),
+                        store_item_id: attr(&e, "w:storeItemID").map(String::from),
+                        prefix_mappings: attr(&e, "w:prefixMappings").map(String::from),
+                    })
+                }
+                "text" if e.name.starts_with("w:") => {
+                    props.kind = SdtKind::PlainText {
+                        multi_line: attr(&e, "w:multiLine").is_some_and(is_true),
+                    }
+                }
+                "richText" => props.kind = SdtKind::RichText,
+                "picture" => props.kind = SdtKind::Picture,
+                "group" => props.kind = SdtKind::Group,
+                "dropDownList" => props.kind = SdtKind::DropDownList(SdtList::from_raw(&e)),
+                "comboBox" => props.kind = SdtKind::ComboBox(SdtList::from_raw(&e)),
+                "date" => props.kind = SdtKind::Date(SdtDate::from_raw(e)),
+                "docPartObj" => props.kind = SdtKind::DocPart(SdtDocPart::from_raw(&e)),
+                "checkbox" if e.name.starts_with("w14:") => {
+                    props.kind = SdtKind::Checkbox(SdtCheckbox::from_raw(&e))
+                }
+                "equation" | "docPartList" | "citation" | "bibliography" => {
+                    props.kind = SdtKind::Other;
+                    props.unknown_children.push(RawXmlNode::Element(e));
+                }
+                _ => props.unknown_children.push(RawXmlNode::Element(e)),
+            }
+        }
+        props
+    }
+
+    /// Value of a control with these properties showing `text`
+    /// (see [`ContentControl::value`])
+    pub(crate) fn value(&self, text: &str) -> String {
+        match &self.kind {
+            SdtKind::Checkbox(checkbox) => checkbox.checked.to_string(),
+            SdtKind::Date(date) => date.full_date.clone().unwrap_or_default(),
+            SdtKind::DropDownList(list) | SdtKind::ComboBox(list) => list
+                .items
+                .iter()
+                .find(|item| item.display_text == text)
+                .map(|item| item.value.clone())
+                .unwrap_or_else(|| text.to_string()),
+            _ if self.showing_placeholder => String::new(),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Check whether setting `value` changes a control currently at `current`
+    ///
+    /// Values the control does not take count as no change.
+    pub(crate) fn would_change(&self, current: &str, value: &str) -> bool {
+        let mut probe = self.clone();
+        probe
+            .apply_value(value)
+            .is_ok_and(|display| probe.value(&display) != current)
+    }
+
+    /// Apply a value to the control state; returns the text to show
+    fn apply_value(&mut self, value: &str) -> Result<String> {
+        let is_combo = matches!(self.kind, SdtKind::ComboBox(_));
+        let display = match &mut self.kind {
+            SdtKind::RichText | SdtKind::PlainText { .. } => value.to_string(),
+            SdtKind::Checkbox(checkbox) => {
+                checkbox.checked = match value.trim().to_ascii_lowercase().as_str() {
+                    "true" | "1" | "yes" | "on" | "x" => true,
+                    "false" | "0" | "no" | "off" | "" => false,
+                    _ => {
+                        return Err(Error::InvalidDocument(format!(
+                            "not a checkbox value: {}",
+                            value
+                        )))
+                    }
+                };
+                let symbol = if checkbox.checked {
+                    &checkbox.checked_symbol
+                } else {
+                    &checkbox.unchecked_symbol
+                };
+                symbol.char().into_iter().collect()
+            }
+            SdtKind::DropDownList(list) | SdtKind::ComboBox(list) => {
+                match list
+                    .items
+                    .iter()
+                    .find(|item| item.value == value || item.display_text == value)
+                {
+                    Some(item) => {
+                        list.last_value = Some(item.value.clone());
+                        item.display_text.clone()
+                    }
+                    None if is_combo => {
+                        list.last_value = Some(value.to_string());
+                        value.to_string()
+                    }
+                    None => return Err(Error::NotFound(format!("list item {}", value))),
+                }
+            }
+            SdtKind::Date(date) => {
+                let parsed = DateTime::parse(value)
+                    .ok_or_else(|| Error::InvalidDocument(format!("not an ISO date: {}", value)))?;
+                date.full_date = Some(format!(
+                    "{}T00:00:00Z",
+                    value.split('T').next().unwrap_or(value)
+                ));
+                format_date(&parsed, date.format.as_deref().unwrap_or("M/d/yyyy"))
+            }
+            SdtKind::Picture | SdtKind::DocPart(_) | SdtKind::Group | SdtKind::Other => {
+                return Err(Error::Unsupported(
+                    "only text, checkbox, list and date controls take a value".into(),
+                ))
+            }
+        };
+        self.showing_placeholder = false;
+        Ok(display)
+    }
+
+    /// Write w:sdtPr
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        writer.write_event(Event::Start(BytesStart::new("w:sdtPr")))?;
+        schema::write_in_order(writer, schema::SDTPR, &self.unknown_children, |w, name| {
+            let element = match name {
+                "alias" => self.alias.as_deref().map(|v| val("w:alias", v)),
+                "tag" => self.tag.as_deref().map(|v| val("w:tag", v)),
+                "id" => self.id.map(|id| val("w:id", &id.to_string())),
+                "lock" => self.lock.map(|lock| val("w:lock", lock.as_str())),
+                "placeholder" => self
+                    .placeholder
+                    .as_deref()
+                    .map(|v| RawXmlElement::new("w:placeholder").with_child(val("w:docPart", v))),
+                "showingPlcHdr" if self.showing_placeholder => Some(empty("w:showingPlcHdr")),
+                "dataBinding" => self.data_binding.as_ref().map(|binding| {
+                    let mut e = empty("w:dataBinding");
+                    if let Some(prefixes) = &binding.prefix_mappings {
+                        e = e.with_attr("w:prefixMappings", prefixes.clone());
+                    }
+                    e = e.with_attr("w:xpath", binding.xpath.clone());
+                    if let Some(id) = &binding.store_item_id {
+                        e = e.with_attr("w:storeItemID", id.clone());
+                    }
+                    e
+                }),
+                "text" => match self.kind {
+                    SdtKind::PlainText { multi_line: true } => {
+                        Some(empty("w:text").with_attr("w:multiLine", "1"))
+                    }
+                    SdtKind::PlainText { multi_line: false } => Some(empty("w:text")),
+                    _ => None,
+                },
+                "picture" if self.kind == SdtKind::Picture => Some(empty("w:picture")),
+                "group" if self.kind == SdtKind::Group => Some(empty("w:group")),
+                "dropDownList" => match &self.kind {
+                    SdtKind::DropDownList(list) => Some(list.to_raw("w:dropDownList")),
+                    _ => None,
+                },
+                "comboBox" => match &self.kind {
+                    SdtKind::ComboBox(list) => Some(list.to_raw("w:comboBox")),
+                    _ => None,
+                },
+                "date" => match &self.kind {
+                    SdtKind::Date(date) => Some(date.to_raw()),
+                    _ => None,
+                },
+                "docPartObj" => match &self.kind {
+                    SdtKind::DocPart(part) => Some(part.to_raw()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(element) = element {
+                element.write_to(w)?;
+            }
+            Ok(())
+        })?;
+        if let SdtKind::Checkbox(checkbox) = &self.kind {
+            checkbox.to_raw().write_to(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("w:sdtPr")))?;
+        Ok(())
+    }
+}
+
+impl SdtList {
+    fn from_raw(e: &RawXmlElement) -> Self {
+        Self {
+            items: elements(e)
+                .filter(|c| local_name(c) == "listItem")
+                .map(|c| {
+                    let value = attr(c, "w:value").unwrap_or_default();
+                    ListItem::new(attr(c, "w:displayText").unwrap_or(value), value)
+                })
+                .collect(),
+            last_value: attr(e, "w:lastValue").map(String::from),
+        }
+    }
+
+    fn to_raw(&self, name: &str) -> RawXmlElement {
+        let mut e = RawXmlElement::new(name);
+        if let Some(last) = &self.last_value {
+            e = e.with_attr("w:lastValue", last.clone());
+        }
+        for item in &self.items {
+            e = e.with_child(
+                empty("w:listItem")
+                    .with_attr("w:displayText", item.display_text.clone())
+                    .with_attr("w:value", item.value.clone()),
+            );
+        }
+        e.self_closing = self.items.is_empty();
+        e
+    }
+}
+
+impl SdtDate {
+    fn from_raw(e: RawXmlElement) -> Self {
+        let mut date = SdtDate {
+            full_date: attr(&e, "w:fullDate").map(String::from),
+            ..Default::default()
+        };
+        for child in e.children {
+            match &child {
+                RawXmlNode::Element(c) if local_name(c) == "dateFormat" => {
+                    date.format = attr(c, "w:val").map(String::from)
+                }
+                RawXmlNode::Element(c) if local_name(c) == "lid" => {
+                    date.locale = attr(c, "w:val").map(String::from)
+                }
+                RawXmlNode::Element(_) => date.unknown_children.push(child),
+                _ => {}
+            }
+        }
+        date
+    }
+
+    fn to_raw(&self) -> RawXmlElement {
+        let mut e = RawXmlElement::new("w:date");
+        if let Some(full) = &self.full_date {
+            e = e.with_attr("w:fullDate", full.clone());
+        }
+        if let Some(format) = &self.format {
+            e = e.with_child(val("w:dateFormat", format));
+        }
+        if let Some(locale) = &self.locale {
+            e = e.with_child(val("w:lid", locale));
+        }
+        e.children.extend(self.unknown_children.iter().cloned());
+        e.self_closing = e.children.is_empty();
+        e
+    }
+}
+
+impl SdtDocPart {
+    fn from_raw(e: &RawXmlElement) -> Self {
+        let child = |name: &str| elements(e).find(|c| local_name(c) == name);
+        Self {
+            gallery: child("docPartGallery").and_then(|c| attr(c, "w:val").map(String::from)),
+            category: child("docPartCategory").and_then(|c| attr(c, "w:val").map(String::from)),
+            unique: child("docPartUnique").is_some_and(bool_val),
+        }
+    }
+
+    fn to_raw(&self) -> RawXmlElement {
+        let mut e = RawXmlElement::new("w:docPartObj");
+        if let Some(gallery) = &self.gallery {
+            e = e.with_child(val("w:docPartGallery", gallery));
+        }
+        if let Some(category) = &self.category {
+            e = e.with_child(val("w:docPartCategory", category));
+        }
+        if self.unique {
+            e = e.with_child(empty("w:docPartUnique"));
+        }
+        e
+    }
+}
+
+impl SdtCheckbox {
+    fn from_raw(e: &RawXmlElement) -> Self {
+        let mut checkbox = SdtCheckbox::default();
+        for c in elements(e) {
+            let symbol = || {
+                CheckboxSymbol::new(
+                    attr(c, "w14:val").unwrap_or_default(),
+                    attr(c, "w14:font").unwrap_or_default(),
+                )
+            };
+            match local_name(c) {
+                "checked" => checkbox.checked = attr(c, "w14:val").is_some_and(is_true),
+                "checkedState" => checkbox.checked_symbol = symbol(),
+                "uncheckedState" => checkbox.unchecked_symbol = symbol(),
+                _ => {}
+            }
+        }
+        checkbox
+    }
+
+    fn to_raw(&self) -> RawXmlElement {
+        let state = |name: &str, symbol: &CheckboxSymbol| {
+            empty(name)
+                .with_attr("w14:val", symbol.code.clone())
+                .with_attr("w14:font", symbol.font.clone())
+        };
+        RawXmlElement::new("w14:checkbox")
+            .with_child(
+                empty("w14:checked").with_attr("w14:val", if self.checked { "1" } else { "0" }),
+            )
+            .with_child(state("w14:checkedState", &self.checked_symbol))
+            .with_child(state("w14:uncheckedState", &self.unchecked_symbol))
+    }
+}
+
+/// Read the children of an element, handing start and empty tags to `child`
+fn read_children<R, F>(reader: &mut Reader<R>, child: &mut F) -> Result<()>
+where
+    R: BufRead,
+    F: FnMut(&mut Reader<R>, &BytesStart, bool) -> Result<()>,
+{
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => child(reader, &e.into_owned(), false)?,
+            Event::Empty(e) => child(reader, &e.into_owned(), true)?,
+            Event::End(_) | Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Parse a control whose content stays in a flat list, such as table rows
+///
+/// `parse` turns an element into an item, or returns `None` for elements that
+/// aren't items, which go to `unknown`. Items are appended to `items` and
+/// nested controls are recorded in `controls` after their parent.
+pub(crate) fn read_ranged<R, T, F>(
+    reader: &mut Reader<R>,
+    items: &mut Vec<T>,
+    controls: &mut Vec<Sdt>,
+    unknown: &mut Vec<RawXmlNode>,
+    wrap: fn(Range<usize>) -> SdtContent,
+    parse: &mut F,
+) -> Result<()>
+where
+    R: BufRead,
+    F: FnMut(&mut Reader<R>, &BytesStart, bool) -> Result<Option<T>>,
+{
+    let index = controls.len();
+    let first = items.len();
+    let mut nested = Vec::new();
+    let mut sdt = Sdt::parse(reader, |reader, e, empty| {
+        if e.local_name().as_ref() == b"sdt" && !empty {
+            read_ranged(reader, items, &mut nested, unknown, wrap, parse)?;
+        } else if let Some(item) = parse(reader, e, empty)? {
+            items.push(item);
+        } else if empty {
+            unknown.push(RawXmlNode::Element(RawXmlElement::from_empty(e)));
+        } else {
+            let raw = RawXmlElement::from_reader(reader, e)?;
+            unknown.push(RawXmlNode::Element(raw));
+        }
+        Ok(())
+    })?;
+    sdt.content = wrap(first..items.len());
+    controls.insert(index, sdt);
+    controls.extend(nested);
+    Ok(())
+}
+
+/// Item parser for [`read_ranged`] over a list of paragraphs
+pub(crate) fn read_paragraph<R: BufRead>(
+    reader: &mut Reader<R>,
+    e: &BytesStart,
+    empty: bool,
+) -> Result<Option<Paragraph>> {
+    match e.local_name().as_ref() {
+        b"p" if empty => Ok(Some(Paragraph::from_empty(e)?)),
+        b"p" => Ok(Some(Paragraph::from_reader(reader, e)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Write a flat list of items, wrapping ranges of them in their controls
+///
+/// `controls` is ordered by start, outer controls first.
+pub(crate) fn write_ranged<W, T, F>(
+    writer: &mut Writer<W>,
+    items: &[T],
+    controls: &[Sdt],
+    mut write_item: F,
+) -> Result<()>
+where
+    W: std::io::Write,
+    F: FnMut(&mut Writer<W>, &T) -> Result<()>,
+{
+    let mut open: Vec<(&Sdt, usize)> = Vec::new();
+    let mut next = 0;
+    for i in 0..=items.len() {
+        while let Some((sdt, end)) = open.last() {
+            if *end > i {
+                break;
+            }
+            sdt.write_end(writer)?;
+            open.pop();
+        }
+        while let Some(sdt) = controls.get(next) {
+            let Some(range) = sdt.range() else {
+                next += 1;
+                continue;
+            };
+            if range.start > i && i < items.len() {
+                break;
+            }
+            sdt.write_start(writer)?;
+            if range.end <= i {
+                sdt.write_end(writer)?;
+            } else {
+                open.push((sdt, range.end));
+            }
+            next += 1;
+        }
+        if let Some(item) = items.get(i) {
+            write_item(writer, item)?;
+        }
+    }
+    for (sdt, _) in open.into_iter().rev() {
+        sdt.write_end(writer)?;
+    }
+    Ok(())
+}
+
+/// Keep control ranges in step with an item inserted at `index`
+pub(crate) fn shift_for_insert(controls: &mut [Sdt], index: usize) {
+    for range in controls.iter_mut().filter_map(Sdt::range_mut) {
+        if index <= range.start {
+            range.start += 1;
+            range.end += 1;
+        } else if index < range.end {
+            range.end += 1;
+        }
+    }
+}
+
+/// Keep control ranges in step with the item at `index` being removed
+pub(crate) fn shift_for_remove(controls: &mut [Sdt], index: usize) {
+    for range in controls.iter_mut().filter_map(Sdt::range_mut) {
+        if index < range.start {
+            range.start -= 1;
+            range.end -= 1;
+        } else if index < range.end {
+            range.end -= 1;
+        }
+    }
+}
+
+/// Move control ranges after items were dropped or merged
+///
+/// `positions[i]` is the new index of old item `i`, with one extra entry for
+/// the end of the list.
+pub(crate) fn remap_ranges(controls: &mut [Sdt], positions: &[usize]) {
+    for range in controls.iter_mut().filter_map(Sdt::range_mut) {
+        let last = positions.len().saturating_sub(1);
+        range.start = positions[range.start.min(last)];
+        range.end = positions[range.end.min(last)].max(range.start);
+    }
+}
+
+/// Lines of text of block content, descending into block-level controls but
+/// not tables
+pub(crate) fn blocks_text(blocks: &[BlockContent]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for block in blocks {
+        match block {
+            BlockContent::Paragraph(p) => lines.push(p.text()),
+            BlockContent::Sdt(sdt) => {
+                if let SdtContent::Blocks(inner) = &sdt.content {
+                    lines.extend(blocks_text(inner));
+                }
+            }
+            BlockContent::Table(_) | BlockContent::Unknown(_) => {}
+        }
+    }
+    lines
+}
+
+/// Paragraphs of block content, descending into tables and block-level controls
+fn blocks_paragraphs(blocks: &[BlockContent]) -> Vec<&Paragraph> {
+    crate::document::comments::body_paragraphs(blocks).collect()
+}
+
+/// Paragraph and first run formatting of some paragraphs
+fn first_formatting(
+    paragraphs: Vec<&Paragraph>,
+) -> (
+    Option<crate::document::ParagraphProperties>,
+    Option<RunProperties>,
+) {
+    let props = paragraphs.first().and_then(|p| p.properties.clone());
+    let run_props = paragraphs
+        .iter()
+        .flat_map(|p| p.runs())
+        .find_map(|r| r.properties.clone());
+    (props, run_props)
+}
+
+/// A paragraph holding `text` with the given formatting
+fn formatted_paragraph(
+    props: Option<crate::document::ParagraphProperties>,
+    run_props: Option<RunProperties>,
+    text: &str,
+) -> Paragraph {
+    let mut para = Paragraph {
+        properties: props,
+        ..Default::default()
+    };
+    para.add_run(formatted_run(run_props, text));
+    para
+}
+
+/// A run holding `text`, dropping the placeholder style
+fn formatted_run(run_props: Option<RunProperties>, text: &str) -> Run {
+    let mut run = Run::new(text);
+    run.properties = run_props.and_then(|mut props| {
+        if props.style.as_deref() == Some(PLACEHOLDER_STYLE) {
+            props.style = None;
+        }
+        (!props.is_empty()).then_some(props)
+    });
+    run
+}
+
+/// Replace inline content with a single run showing `text`
+fn set_inline_text(content: &mut Vec<ParagraphContent>, text: &str) {
+    let run_props = content.iter().find_map(|c| match c {
+        ParagraphContent::Run(r) => r.properties.clone(),
+        _ => None,
+    });
+    content.clear();
+    content.push(ParagraphContent::Run(formatted_run(run_props, text)));
+}
+
+/// Replace the paragraphs a ranged control spans with one paragraph showing `text`
+fn set_ranged_paragraphs(
+    paragraphs: &mut Vec<Paragraph>,
+    controls: &mut [Sdt],
+    index: usize,
+    value: &str,
+) -> Result<()> {
+    let display = controls[index].properties.apply_value(value)?;
+    let Some(range) = controls[index].range() else {
+        return Ok(());
+    };
+    let (props, run_props) = first_formatting(paragraphs[range.clone()].iter().collect());
+    let para = formatted_paragraph(props, run_props, &display);
+    if range.is_empty() {
+        // Controls listed before this one that enclose it take the new paragraph too
+        paragraphs.insert(range.start, para);
+        for (i, other) in controls.iter_mut().enumerate() {
+            let Some(r) = other.range_mut() else {
+                continue;
+            };
+            let encloses = i < index && r.start <= range.start && r.end >= range.start;
+            if i == index || encloses {
+                r.end += 1;
+            } else if r.start >= range.start {
+                r.start += 1;
+                r.end += 1;
+            } else if r.end > range.start {
+                r.end += 1;
+            }
+        }
+        return Ok(());
+    }
+    paragraphs[range.start] = para;
+    for i in (range.start + 1..range.end).rev() {
+        paragraphs.remove(i);
+        shift_for_remove(controls, i);
+    }
+    Ok(())
+}
+
+/// Gathers content controls in document order
+#[derive(Default)]
+pub(crate) struct ContentControlCollector {
+    pub controls: Vec<ContentControl>,
+}
+
+impl ContentControlCollector {
+    fn push(&mut self, sdt: &Sdt, text: String) {
+        self.controls.push(ContentControl {
+            properties: sdt.properties.clone(),
+            level: sdt.level(),
+            text,
+        });
+    }
+
+    pub fn blocks(&mut self, blocks: &[BlockContent]) {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.content(&p.content),
+                BlockContent::Table(t) => self.table(t),
+                BlockContent::Sdt(sdt) => {
+                    self.push(sdt, sdt.text());
+                    match &sdt.content {
+                        SdtContent::Blocks(inner) => self.blocks(inner),
+                        SdtContent::Inline(content) => self.content(content),
+                        _ => {}
+                    }
+                }
+                BlockContent::Unknown(_) => {}
+            }
+        }
+    }
+
+    /// Controls of a paragraph list, with ranged controls over its paragraphs
+    pub fn paragraphs(&mut self, paragraphs: &[Paragraph], controls: &[Sdt]) {
+        let mut pending = controls.iter().peekable();
+        for (i, para) in paragraphs.iter().enumerate() {
+            while let Some(sdt) = pending.next_if(|s| s.range().is_some_and(|r| r.start <= i)) {
+                let range = sdt.range().unwrap_or_default();
+                let text = paragraphs[range]
+                    .iter()
+                    .map(|p| p.text())
+                    .collect::<Vec<_>>();
+                self.push(sdt, text.join("\n"));
+            }
+            self.content(&para.content);
+        }
+        for sdt in pending {
+            self.push(sdt, String::new());
+        }
+    }
+
+    fn table(&mut self, table: &Table) {
+        for sdt in &table.content_controls {
+            let range = sdt.range().unwrap_or_default();
+            let rows = table.rows.get(range).unwrap_or_default();
+            let text = rows.iter().map(row_text).collect::<Vec<_>>().join("\n");
+            self.push(sdt, text);
+        }
+        for row in &table.rows {
+            for sdt in &row.content_controls {
+                let range = sdt.range().unwrap_or_default();
+                let cells = row.cells.get(range).unwrap_or_default();
+                let text = cells.iter().map(|c| c.text()).collect::<Vec<_>>();
+                self.push(sdt, text.join("\t"));
+            }
+            for cell in &row.cells {
+                self.paragraphs(&cell.paragraphs, &cell.content_controls);
+            }
+        }
+    }
+
+    pub fn content(&mut self, content: &[ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::Sdt(sdt) => {
+                    self.push(sdt, sdt.text());
+                    if let SdtContent::Inline(inner) = &sdt.content {
+                        self.content(inner);
+                    }
+                }
+                ParagraphContent::Revision(rev) if rev.kind.is_addition() => {
+                    self.content(&rev.content)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn row_text(row: &TableRow) -> String {
+    row.cells
+        .iter()
+        .map(|c| c.text())
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Sets the value of content controls
+///
+/// `value_for` gets each control's properties and current value and returns
+/// the value to set, if any. Controls that do not take the value fail the
+/// walk when `strict` and are skipped otherwise.
+pub(crate) struct ValueSetter<F> {
+    pub value_for: F,
+    pub strict: bool,
+    pub count: usize,
+}
+
+impl<F: FnMut(&SdtProperties, &str) -> Option<String>> ValueSetter<F> {
+    fn apply(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => self.count += 1,
+            Err(e) if self.strict => return Err(e),
+            Err(_) => {}
+        }
+        Ok(())
+    }
+
+    fn sdt(&mut self, sdt: &mut Sdt) -> Result<()> {
+        let current = sdt.properties.value(&sdt.text());
+        if let Some(value) = (self.value_for)(&sdt.properties, &current) {
+            let result = sdt.set_value(&value);
+            return self.apply(result);
+        }
+        match &mut sdt.content {
+            SdtContent::Blocks(blocks) => self.blocks(blocks),
+            SdtContent::Inline(content) => self.content(content),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn blocks(&mut self, blocks: &mut [BlockContent]) -> Result<()> {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.content(&mut p.content)?,
+                BlockContent::Table(t) => {
+                    for row in &mut t.rows {
+                        for cell in &mut row.cells {
+                            self.paragraphs(&mut cell.paragraphs, &mut cell.content_controls)?;
+                        }
+                    }
+                }
+                BlockContent::Sdt(sdt) => self.sdt(sdt)?,
+                BlockContent::Unknown(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn paragraphs(
+        &mut self,
+        paragraphs: &mut Vec<Paragraph>,
+        controls: &mut [Sdt],
+    ) -> Result<()> {
+        for index in 0..controls.len() {
+            let range = controls[index].range().unwrap_or_default();
+            let text = paragraphs.get(range).unwrap_or_default();
+            let text = text.iter().map(|p| p.text()).collect::<Vec<_>>().join("\n");
+            let current = controls[index].properties.value(&text);
+            if let Some(value) = (self.value_for)(&controls[index].properties, &current) {
+                let result = set_ranged_paragraphs(paragraphs, controls, index, &value);
+                self.apply(result)?;
+            }
+        }
+        for para in paragraphs {
+            self.content(&mut para.content)?;
+        }
+        Ok(())
+    }
+
+    pub fn content(&mut self, content: &mut [ParagraphContent]) -> Result<()> {
+        for item in content {
+            if let ParagraphContent::Sdt(sdt) = item {
+                self.sdt(sdt)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Document {
+    /// Get all content controls: body (including tables), headers, footers
+    /// and notes, in document order
+    pub fn content_controls(&self) -> Vec<ContentControl> {
+        let mut collector = ContentControlCollector::default();
+        collector.blocks(&self.body.content);
+        let (headers, footers) = self.headers_footers.get(&self.package);
+        for (_, hf) in headers.iter().chain(footers) {
+            collector.paragraphs(&hf.paragraphs, &hf.content_controls);
+        }
+        for notes in [self.footnotes(), self.endnotes()].into_iter().flatten() {
+            for para in notes.notes.iter().flat_map(|n| n.paragraphs.iter()) {
+                collector.content(&para.content);
+            }
+        }
+        collector.controls
+    }
+
+    /// Find the first content control with a tag
+    pub fn find_content_control_by_tag(&self, tag: &str) -> Option<ContentControl> {
+        self.content_controls()
+            .into_iter()
+            .find(|c| c.tag() == Some(tag))
+    }
+
+    /// Set the value of every content control with a tag (see [`Sdt::set_value`])
+    ///
+    /// Returns the number of controls set. Controls around table rows or
+    /// cells are skipped.
+    pub fn set_content_control_value(&mut self, tag: &str, value: &str) -> Result<usize> {
+        self.set_content_control_values(ValueSetter {
+            value_for: |props: &SdtProperties, _: &str| {
+                (props.tag.as_deref() == Some(tag)).then(|| value.to_string())
+            },
+            strict: true,
+            count: 0,
+        })
+    }
+
+    /// Run a value setter over every story, marking the parts it changes
+    pub(crate) fn set_content_control_values<F>(
+        &mut self,
+        mut setter: ValueSetter<F>,
+    ) -> Result<usize>
+    where
+        F: FnMut(&SdtProperties, &str) -> Option<String>,
+    {
+        setter.blocks(&mut self.body.content)?;
+        if setter.count > 0 {
+            self.mark_body_modified();
+        }
+
+        let mut touched = Vec::new();
+        let (headers, footers) = self.headers_footers.get_mut(&self.package);
+        for (r_id, hf) in headers.iter_mut().chain(footers) {
+            let before = setter.count;
+            setter.paragraphs(&mut hf.paragraphs, &mut hf.content_controls)?;
+            if setter.count > before {
+                touched.push(r_id.clone());
+            }
+        }
+        for r_id in touched {
+            self.mark_header_footer_modified(&r_id);
+        }
+
+        let before = setter.count;
+        if let Some(notes) = self.footnotes.get_mut(&self.package) {
+            for para in notes.notes.iter_mut().flat_map(|n| n.paragraphs.iter_mut()) {
+                setter.content(&mut para.content)?;
+            }
+        }
+        if setter.count > before {
+            self.mark_doc_part_modified(crate::opc::rel_types::FOOTNOTES);
+        }
+
+        let before = setter.count;
+        if let Some(notes) = self.endnotes.get_mut(&self.package) {
+            for para in notes.notes.iter_mut().flat_map(|n| n.paragraphs.iter_mut()) {
+                setter.content(&mut para.content)?;
+            }
+        }
+        if setter.count > before {
+            self.mark_doc_part_modified(crate::opc::rel_types::ENDNOTES);
+        }
+
+        Ok(setter.count)
+    }
+
+    /// Add a block-level content control at the end of the body
+    ///
+    /// Run-level content is wrapped in a paragraph. A control without an ID
+    /// gets one that is unused in the body.
+    pub fn add_content_control(&mut self, mut sdt: Sdt) -> &mut Sdt {
+        if let SdtContent::Inline(content) = &mut sdt.content {
+            let para = Paragraph {
+                content: std::mem::take(content),
+                ..Default::default()
+            };
+            sdt.content = SdtContent::Blocks(vec![BlockContent::Paragraph(Box::new(para))]);
+        }
+        if sdt.properties.id.is_none() {
+            sdt.properties.id = Some(self.next_content_control_id());
+        }
+        self.mark_body_modified();
+        self.body.content.push(BlockContent::Sdt(Box::new(sdt)));
+        match self.body.content.last_mut() {
+            Some(BlockContent::Sdt(sdt)) => sdt,
+            _ => unreachable!("just added content control"),
+        }
+    }
+
+    /// A content control ID not used in the body
+    pub(crate) fn next_content_control_id(&self) -> i64 {
+        let mut collector = ContentControlCollector::default();
+        collector.blocks(&self.body.content);
+        collector
+            .controls
+            .iter()
+            .filter_map(|c| c.properties.id)
+            .max()
+            .map_or(1, |id| id.saturating_add(1))
+    }
+}
+
+impl Paragraph {
+    /// Add a run-level content control
+    ///
+    /// Block content is flattened into the paragraph's runs.
+    pub fn add_content_control(&mut self, mut sdt: Sdt) {
+        if let SdtContent::Blocks(blocks) = &sdt.content {
+            let content = blocks_paragraphs(blocks)
+                .into_iter()
+                .flat_map(|p| p.content.iter().cloned())
+                .collect();
+            sdt.content = SdtContent::Inline(content);
+        }
+        self.content.push(ParagraphContent::Sdt(Box::new(sdt)));
+    }
+}
+
+impl Table {
+    /// Wrap a range of rows in a content control, such as a repeating section
+    pub fn add_content_control(&mut self, rows: Range<usize>, mut sdt: Sdt) {
+        let rows = rows.start.min(self.rows.len())..rows.end.min(self.rows.len());
+        sdt.content = SdtContent::Rows(rows.clone());
+        let index = ranged_position(&self.content_controls, &rows);
+        self.content_controls.insert(index, sdt);
+    }
+}
+
+impl TableRow {
+    /// Wrap a range of cells in a content control
+    pub fn add_content_control(&mut self, cells: Range<usize>, mut sdt: Sdt) {
+        let cells = cells.start.min(self.cells.len())..cells.end.min(self.cells.len());
+        sdt.content = SdtContent::Cells(cells.clone());
+        let index = ranged_position(&self.content_controls, &cells);
+        self.content_controls.insert(index, sdt);
+    }
+}
+
+impl TableCell {
+    /// Wrap a range of the cell's paragraphs in a content control
+    pub fn add_content_control(&mut self, paragraphs: Range<usize>, mut sdt: Sdt) {
+        let len = self.paragraphs.len();
+        let paragraphs = paragraphs.start.min(len)..paragraphs.end.min(len);
+        sdt.content = SdtContent::Paragraphs(paragraphs.clone());
+        let index = ranged_position(&self.content_controls, &paragraphs);
+        self.content_controls.insert(index, sdt);
+    }
+}
+
+/// Where a new ranged control goes to keep controls ordered by start, outer first
+fn ranged_position(controls: &[Sdt], range: &Range<usize>) -> usize {
+    controls
+        .iter()
+        .position(|sdt| {
+            sdt.range().is_some_and(|r| {
+                r.start > range.start || (r.start == range.start && r.end <= range.end)
+            })
+        })
+        .unwrap_or(controls.len())
+}
+
+fn attr<'a>(e: &'a RawXmlElement, name: &str) -> Option<&'a str> {
+    e.attributes
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+fn local_name(e: &RawXmlElement) -> &str {
+    e.name.rsplit(':').next().unwrap_or(&e.name)
+}
+
+fn elements(e: &RawXmlElement) -> impl Iterator<Item = &RawXmlElement> {
+    e.children.iter().filter_map(|c| match c {
+        RawXmlNode::Element(child) => Some(child),
+        _ => None,
+    })
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(value, "1" | "true" | "on")
+}
+
+/// An on/off element's w:val, which defaults to on
+fn bool_val(e: &RawXmlElement) -> bool {
+    attr(e, "w:val").map_or(true, is_true)
+}
+
+/// Self-closing element with a w:val attribute
+fn val(name: &str, value: &str) -> RawXmlElement {
+    empty(name).with_attr("w:val", value)
+}
+
+/// Self-closing element with no attributes
+fn empty(name: &str) -> RawXmlElement {
+    RawXmlElement {
+        self_closing: true,
+        ..RawXmlElement::new(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_paragraph(xml: &str) -> Paragraph {
+        let xml = format!(
+            r#"<w:p xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml">{}</w:p>"#,
+            xml
+        );
+        let mut reader = Reader::from_str(&xml);
+        let mut buf = Vec::new();
+        loop {
+            if let Event::Start(e) = reader.read_event_into(&mut buf).unwrap() {
+                return Paragraph::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    fn write_paragraph(para: &Paragraph) -> String {
+        let mut buffer = Vec::new();
+        para.write_to(&mut Writer::new(&mut buffer)).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn inline_sdt(para: &Paragraph) -> &Sdt {
+        para.content
+            .iter()
+            .find_map(|c| match c {
+                ParagraphContent::Sdt(sdt) => Some(sdt.as_ref()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    const DROPDOWN: &str = concat!(
+        r#"<w:r><w:t xml:space="preserve">Status: </w:t></w:r>"#,
+        r#"<w:sdt><w:sdtPr><w:alias w:val="Status"/><w:tag w:val="status"/><w:id w:val="-1510901420"/>"#,
+        r#"<w:lock w:val="sdtLocked"/><w:placeholder><w:docPart w:val="DefaultPlaceholder_-1854013438"/></w:placeholder>"#,
+        r#"<w:showingPlcHdr/><w15:appearance w15:val="hidden"/>"#,
+        r#"<w:dropDownList><w:listItem w:displayText="Open" w:value="open"/><w:listItem w:displayText="Closed" w:value="closed"/></w:dropDownList>"#,
+        r#"</w:sdtPr><w:sdtContent><w:r><w:rPr><w:rStyle w:val="PlaceholderText"/><w:b/></w:rPr><w:t>Choose an item.</w:t></w:r></w:sdtContent></w:sdt>"#,
+    );
+
+    #[test]
+    fn test_parse_dropdown() {
+        let para = parse_paragraph(DROPDOWN);
+        assert_eq!(para.text(), "Status: Choose an item.");
+
+        let props = &inline_sdt(&para).properties;
+        assert_eq!(props.tag.as_deref(), Some("status"));
+        assert_eq!(props.alias.as_deref(), Some("Status"));
+        assert_eq!(props.id, Some(-1510901420));
+        assert_eq!(props.lock, Some(SdtLock::SdtLocked));
+        assert_eq!(
+            props.placeholder.as_deref(),
+            Some("DefaultPlaceholder_-1854013438")
+        );
+        assert!(props.showing_placeholder);
+        let SdtKind::DropDownList(list) = &props.kind else {
+            panic!("expected a drop-down list");
+        };
+        assert_eq!(list.items[1], ListItem::new("Closed", "closed"));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let para = parse_paragraph(DROPDOWN);
+        let xml = write_paragraph(&para);
+        assert!(xml.contains(
+            r#"<w:alias w:val="Status"/><w:tag w:val="status"/><w:id w:val="-1510901420"/><w:lock w:val="sdtLocked"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<w:showingPlcHdr/><w:dropDownList><w:listItem w:displayText="Open" w:value="open"/>"#
+        ));
+        assert!(xml.contains(r#"<w15:appearance w15:val="hidden"/></w:sdtPr>"#));
+        let para2 = parse_paragraph(&xml[xml.find("<w:r>").unwrap()..xml.rfind("</w:p>").unwrap()]);
+        assert_eq!(para2.text(), para.text());
+    }
+
+    #[test]
+    fn test_set_dropdown_value() {
+        let mut para = parse_paragraph(DROPDOWN);
+        let ParagraphContent::Sdt(sdt) = &mut para.content[1] else {
+            panic!("expected a content control");
+        };
+        assert!(sdt.set_value("maybe").is_err());
+        sdt.set_value("closed").unwrap();
+        assert!(!sdt.properties.showing_placeholder);
+        assert_eq!(sdt.text(), "Closed");
+
+        let xml = write_paragraph(&para);
+        assert!(xml.contains(r#"<w:dropDownList w:lastValue="closed">"#));
+        // The placeholder style goes, other formatting stays
+        assert!(xml.contains(r#"<w:rPr><w:b/></w:rPr><w:t>Closed</w:t>"#));
+    }
+
+    #[test]
+    fn test_checkbox() {
+        let xml = concat!(
+            r#"<w:sdt><w:sdtPr><w:tag w:val="agree"/><w14:checkbox><w14:checked w14:val="0"/>"#,
+            r#"<w14:checkedState w14:val="2612" w14:font="MS Gothic"/><w14:uncheckedState w14:val="2610" w14:font="MS Gothic"/>"#,
+            r#"</w14:checkbox></w:sdtPr><w:sdtContent><w:r><w:t>☐</w:t></w:r></w:sdtContent></w:sdt>"#,
+        );
+        let mut para = parse_paragraph(xml);
+        let ParagraphContent::Sdt(sdt) = &mut para.content[0] else {
+            panic!("expected a content control");
+        };
+        assert!(matches!(&sdt.properties.kind, SdtKind::Checkbox(c) if !c.checked));
+        sdt.set_value("true").unwrap();
+        assert_eq!(sdt.text(), "☒");
+        assert!(write_paragraph(&para).contains(r#"<w14:checked w14:val="1"/>"#));
+    }
+
+    #[test]
+    fn test_date() {
+        let sdt = Sdt::date("2024-05-01", "d MMMM yyyy").with_tag("due");
+        assert_eq!(sdt.text(), "1 May 2024");
+        let SdtKind::Date(date) = &sdt.properties.kind else {
+            panic!("expected a date picker");
+        };
+        assert_eq!(date.full_date.as_deref(), Some("2024-05-01T00:00:00Z"));
+
+        let mut para = Paragraph::default();
+        para.add_content_control(sdt);
+        let xml = write_paragraph(&para);
+        assert!(xml.contains(r#"<w:date w:fullDate="2024-05-01T00:00:00Z"><w:dateFormat w:val="d MMMM yyyy"/><w:lid w:val="en-US"/></w:date>"#));
+    }
+
+    #[test]
+    fn test_write_ranged() {
+        let items = ["a", "b", "c"];
+        let controls = vec![
+            Sdt::rich_text("").with_tag("outer"),
+            Sdt::rich_text("").with_tag("inner"),
+        ];
+        let mut controls = controls;
+        controls[0].content = SdtContent::Rows(0..2);
+        controls[1].content = SdtContent::Rows(1..2);
+
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(&mut buffer);
+        write_ranged(&mut writer, &items, &controls, |w, item| {
+            w.write_event(Event::Empty(BytesStart::new(*item)))?;
+            Ok(())
+        })
+        .unwrap();
+        let xml = String::from_utf8(buffer).unwrap();
+        let shape: String = xml
+            .replace("<w:sdtContent>", "")
+            .replace("</w:sdtContent>", "")
+            .split("<w:sdtPr>")
+            .map(|part| match part.find("</w:sdtPr>") {
+                Some(end) => &part[end + "</w:sdtPr>".len()..],
+                None => part,
+            })
+            .collect();
+        assert_eq!(shape, "<w:sdt><a/><w:sdt><b/></w:sdt></w:sdt><c/>");
+    }
+
+    #[test]
+    fn test_shift_ranges() {
+        let mut controls = vec![Sdt::rich_text("")];
+        controls[0].content = SdtContent::Rows(1..3);
+        shift_for_insert(&mut controls, 2);
+        assert_eq!(controls[0].range(), Some(1..4));
+        shift_for_insert(&mut controls, 0);
+        assert_eq!(controls[0].range(), Some(2..5));
+        shift_for_remove(&mut controls, 4);
+        assert_eq!(controls[0].range(), Some(2..4));
+        shift_for_remove(&mut controls, 0);
+        assert_eq!(controls[0].range(), Some(1..3));
+    }
+}
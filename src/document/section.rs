@@ -1,7 +1,7 @@
 //! Section properties (w:sectPr)
 
 use crate::error::Result;
-use crate::xml::{get_attr, RawXmlElement, RawXmlNode};
+use crate::xml::{get_attr, schema, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -164,91 +164,42 @@ impl SectionProperties {
 
         writer.write_event(Event::Start(start))?;
 
-        // Header references
-        for href in &self.header_references {
-            write_header_footer_ref(writer, "w:headerReference", href)?;
-        }
-
-        // Footer references
-        for fref in &self.footer_references {
-            write_header_footer_ref(writer, "w:footerReference", fref)?;
-        }
-
-        // Page size
-        if let Some(ref pg) = self.page_size {
-            let mut elem = BytesStart::new("w:pgSz");
-            if let Some(w) = pg.width {
-                elem.push_attribute(("w:w", w.to_string().as_str()));
-            }
-            if let Some(h) = pg.height {
-                elem.push_attribute(("w:h", h.to_string().as_str()));
-            }
-            if let Some(ref orient) = pg.orient {
-                elem.push_attribute((
-                    "w:orient",
-                    match orient {
-                        PageOrientation::Portrait => "portrait",
-                        PageOrientation::Landscape => "landscape",
-                    },
-                ));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Page margin
-        if let Some(ref m) = self.page_margin {
-            let mut elem = BytesStart::new("w:pgMar");
-            if let Some(v) = m.top {
-                elem.push_attribute(("w:top", v.to_string().as_str()));
-            }
-            if let Some(v) = m.right {
-                elem.push_attribute(("w:right", v.to_string().as_str()));
-            }
-            if let Some(v) = m.bottom {
-                elem.push_attribute(("w:bottom", v.to_string().as_str()));
-            }
-            if let Some(v) = m.left {
-                elem.push_attribute(("w:left", v.to_string().as_str()));
-            }
-            if let Some(v) = m.header {
-                elem.push_attribute(("w:header", v.to_string().as_str()));
-            }
-            if let Some(v) = m.footer {
-                elem.push_attribute(("w:footer", v.to_string().as_str()));
-            }
-            if let Some(v) = m.gutter {
-                elem.push_attribute(("w:gutter", v.to_string().as_str()));
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Columns
-        if let Some(ref cols) = self.columns {
-            let mut elem = BytesStart::new("w:cols");
-            if let Some(n) = cols.count {
-                elem.push_attribute(("w:num", n.to_string().as_str()));
-            }
-            if let Some(s) = cols.space {
-                elem.push_attribute(("w:space", s.to_string().as_str()));
-            }
-            if let Some(eq) = cols.equal_width {
-                elem.push_attribute(("w:equalWidth", if eq { "1" } else { "0" }));
-            }
-            if cols.unknown_children.is_empty() {
-                writer.write_event(Event::Empty(elem))?;
-            } else {
-                writer.write_event(Event::Start(elem))?;
-                for child in &cols.unknown_children {
-                    child.write_to(writer)?;
+        schema::write_in_order(
+            writer,
+            schema::SECTPR,
+            &self.unknown_children,
+            |writer, name| {
+                match name {
+                    "headerReference" => {
+                        for href in &self.header_references {
+                            write_header_footer_ref(writer, "w:headerReference", href)?;
+                        }
+                    }
+                    "footerReference" => {
+                        for fref in &self.footer_references {
+                            write_header_footer_ref(writer, "w:footerReference", fref)?;
+                        }
+                    }
+                    "pgSz" => {
+                        if let Some(ref pg) = self.page_size {
+                            write_page_size(writer, pg)?;
+                        }
+                    }
+                    "pgMar" => {
+                        if let Some(ref m) = self.page_margin {
+                            write_page_margin(writer, m)?;
+                        }
+                    }
+                    "cols" => {
+                        if let Some(ref cols) = self.columns {
+                            write_columns(writer, cols)?;
+                        }
+                    }
+                    _ => {}
                 }
-                writer.write_event(Event::End(BytesEnd::new("w:cols")))?;
-            }
-        }
-
-        // Unknown children
-        for child in &self.unknown_children {
-            child.write_to(writer)?;
-        }
+                Ok(())
+            },
+        )?;
 
         writer.write_event(Event::End(BytesEnd::new("w:sectPr")))?;
         Ok(())
@@ -379,6 +330,77 @@ fn parse_columns_empty(e: &BytesStart) -> Columns {
     }
 }
 
+fn write_page_size<W: std::io::Write>(writer: &mut Writer<W>, pg: &PageSize) -> Result<()> {
+    let mut elem = BytesStart::new("w:pgSz");
+    if let Some(w) = pg.width {
+        elem.push_attribute(("w:w", w.to_string().as_str()));
+    }
+    if let Some(h) = pg.height {
+        elem.push_attribute(("w:h", h.to_string().as_str()));
+    }
+    if let Some(ref orient) = pg.orient {
+        elem.push_attribute((
+            "w:orient",
+            match orient {
+                PageOrientation::Portrait => "portrait",
+                PageOrientation::Landscape => "landscape",
+            },
+        ));
+    }
+    writer.write_event(Event::Empty(elem))?;
+    Ok(())
+}
+
+fn write_page_margin<W: std::io::Write>(writer: &mut Writer<W>, m: &PageMargin) -> Result<()> {
+    let mut elem = BytesStart::new("w:pgMar");
+    if let Some(v) = m.top {
+        elem.push_attribute(("w:top", v.to_string().as_str()));
+    }
+    if let Some(v) = m.right {
+        elem.push_attribute(("w:right", v.to_string().as_str()));
+    }
+    if let Some(v) = m.bottom {
+        elem.push_attribute(("w:bottom", v.to_string().as_str()));
+    }
+    if let Some(v) = m.left {
+        elem.push_attribute(("w:left", v.to_string().as_str()));
+    }
+    if let Some(v) = m.header {
+        elem.push_attribute(("w:header", v.to_string().as_str()));
+    }
+    if let Some(v) = m.footer {
+        elem.push_attribute(("w:footer", v.to_string().as_str()));
+    }
+    if let Some(v) = m.gutter {
+        elem.push_attribute(("w:gutter", v.to_string().as_str()));
+    }
+    writer.write_event(Event::Empty(elem))?;
+    Ok(())
+}
+
+fn write_columns<W: std::io::Write>(writer: &mut Writer<W>, cols: &Columns) -> Result<()> {
+    let mut elem = BytesStart::new("w:cols");
+    if let Some(n) = cols.count {
+        elem.push_attribute(("w:num", n.to_string().as_str()));
+    }
+    if let Some(s) = cols.space {
+        elem.push_attribute(("w:space", s.to_string().as_str()));
+    }
+    if let Some(eq) = cols.equal_width {
+        elem.push_attribute(("w:equalWidth", if eq { "1" } else { "0" }));
+    }
+    if cols.unknown_children.is_empty() {
+        writer.write_event(Event::Empty(elem))?;
+    } else {
+        writer.write_event(Event::Start(elem))?;
+        for child in &cols.unknown_children {
+            child.write_to(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("w:cols")))?;
+    }
+    Ok(())
+}
+
 fn write_header_footer_ref<W: std::io::Write>(
     writer: &mut Writer<W>,
     tag: &str,
@@ -0,0 +1,296 @@
+//! Document settings (settings.xml)
+
+use crate::document::DocumentProtection;
+use crate::error::Result;
+use crate::xml::{get_w_val, parse_bool, schema, RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::io::Cursor;
+
+/// Compatibility mode of documents created by Word 2013 and later
+pub const CURRENT_COMPATIBILITY_MODE: u32 = 15;
+
+/// URI identifying Word's own `w:compatSetting` entries
+const WORD_COMPAT_URI: &str = "http://schemas.microsoft.com/office/word";
+
+/// Document settings from settings.xml
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    /// Use different headers and footers for even and odd pages
+    pub even_and_odd_headers: bool,
+    /// Ask Word to update fields (e.g. a TOC) when the document is opened
+    pub update_fields: bool,
+    /// Document protection (`w:documentProtection`)
+    pub document_protection: Option<DocumentProtection>,
+    /// Default tab stop interval in twips
+    pub default_tab_stop: Option<u32>,
+    /// Compatibility settings (`w:compat`), kept as raw XML
+    pub compat: Option<RawXmlElement>,
+    /// Attributes of the `w:settings` root (namespace declarations, `mc:Ignorable`, ...)
+    pub root_attributes: Vec<(String, String)>,
+    /// Unknown children (preserved for round-trip)
+    pub unknown_children: Vec<RawXmlNode>,
+}
+
+impl Settings {
+    /// Settings for a new document: current compatibility mode and 0.5" tab stops
+    pub fn new() -> Self {
+        let mut settings = Settings {
+            default_tab_stop: Some(720),
+            ..Default::default()
+        };
+        settings.set_compatibility_mode(CURRENT_COMPATIBILITY_MODE);
+        settings
+    }
+
+    /// Parse from XML string
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut settings = Settings::default();
+        let mut buf = Vec::new();
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    let local = e.name().local_name();
+                    match local.as_ref() {
+                        b"settings" => {
+                            settings.root_attributes = root_attributes(&e)?;
+                        }
+                        b"compat" => {
+                            settings.compat = Some(RawXmlElement::from_reader(&mut reader, &e)?);
+                        }
+                        _ => {
+                            let raw = RawXmlElement::from_reader(&mut reader, &e)?;
+                            settings.unknown_children.push(RawXmlNode::Element(raw));
+                        }
+                    }
+                }
+                Event::Empty(e) => {
+                    let local = e.name().local_name();
+                    match local.as_ref() {
+                        b"evenAndOddHeaders" => settings.even_and_odd_headers = parse_bool(&e),
+                        b"updateFields" => settings.update_fields = parse_bool(&e),
+                        b"documentProtection" => {
+                            settings.document_protection =
+                                Some(DocumentProtection::from_element(&e))
+                        }
+                        b"defaultTabStop" => {
+                            settings.default_tab_stop = get_w_val(&e).and_then(|v| v.parse().ok())
+                        }
+                        b"compat" => settings.compat = Some(RawXmlElement::from_empty(&e)),
+                        _ => {
+                            let raw = RawXmlElement::from_empty(&e);
+                            settings.unknown_children.push(RawXmlNode::Element(raw));
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(settings)
+    }
+
+    /// Serialize to XML string
+    pub fn to_xml(&self) -> Result<String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut buffer);
+
+        writer.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+
+        let mut start = BytesStart::new("w:settings");
+        for (attr, value) in &self.root_attributes {
+            start.push_attribute((attr.as_str(), value.as_str()));
+        }
+        if !self.root_attributes.iter().any(|(k, _)| k == "xmlns:w") {
+            start.push_attribute(("xmlns:w", crate::xml::W));
+        }
+        writer.write_event(Event::Start(start))?;
+
+        schema::write_in_order(
+            &mut writer,
+            schema::SETTINGS,
+            &self.unknown_children,
+            |writer, name| {
+                match name {
+                    "evenAndOddHeaders" if self.even_and_odd_headers => {
+                        writer.write_event(Event::Empty(BytesStart::new("w:evenAndOddHeaders")))?;
+                    }
+                    "updateFields" if self.update_fields => {
+                        let mut elem = BytesStart::new("w:updateFields");
+                        elem.push_attribute(("w:val", "true"));
+                        writer.write_event(Event::Empty(elem))?;
+                    }
+                    "documentProtection" => {
+                        if let Some(ref protection) = self.document_protection {
+                            protection.write_to(writer)?;
+                        }
+                    }
+                    "defaultTabStop" => {
+                        if let Some(tab) = self.default_tab_stop {
+                            let mut elem = BytesStart::new("w:defaultTabStop");
+                            elem.push_attribute(("w:val", tab.to_string().as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "compat" => {
+                        if let Some(ref compat) = self.compat {
+                            compat.write_to(writer)?;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            },
+        )?;
+
+        writer.write_event(Event::End(BytesEnd::new("w:settings")))?;
+
+        let xml_bytes = buffer.into_inner();
+        String::from_utf8(xml_bytes)
+            .map_err(|e| crate::error::Error::InvalidDocument(e.to_string()))
+    }
+
+    /// Get the compatibility mode (`w:compatSetting` named `compatibilityMode`)
+    ///
+    /// Documents without one are opened by Word in compatibility mode.
+    pub fn compatibility_mode(&self) -> Option<u32> {
+        self.compat
+            .as_ref()?
+            .children
+            .iter()
+            .find_map(|child| match child {
+                RawXmlNode::Element(e) if is_compatibility_mode(e) => attr(e, "w:val"),
+                _ => None,
+            })?
+            .parse()
+            .ok()
+    }
+
+    /// Set the compatibility mode, e.g. [`CURRENT_COMPATIBILITY_MODE`]
+    pub fn set_compatibility_mode(&mut self, mode: u32) {
+        let compat = self
+            .compat
+            .get_or_insert_with(|| RawXmlElement::new("w:compat"));
+        compat.self_closing = false;
+
+        let existing = compat.children.iter_mut().find_map(|child| match child {
+            RawXmlNode::Element(e) if is_compatibility_mode(e) => Some(e),
+            _ => None,
+        });
+        match existing {
+            Some(setting) => match setting.attributes.iter_mut().find(|(k, _)| k == "w:val") {
+                Some((_, val)) => *val = mode.to_string(),
+                None => setting.attributes.push(("w:val".into(), mode.to_string())),
+            },
+            None => {
+                let mut setting = RawXmlElement::new("w:compatSetting")
+                    .with_attr("w:name", "compatibilityMode")
+                    .with_attr("w:uri", WORD_COMPAT_URI)
+                    .with_attr("w:val", mode.to_string());
+                setting.self_closing = true;
+                compat.children.push(RawXmlNode::Element(setting));
+            }
+        }
+    }
+}
+
+/// Whether a `w:compat` child is the `compatibilityMode` setting
+fn is_compatibility_mode(e: &RawXmlElement) -> bool {
+    e.name.ends_with("compatSetting") && attr(e, "w:name") == Some("compatibilityMode")
+}
+
+/// Attribute value of a raw element
+fn attr<'a>(e: &'a RawXmlElement, name: &str) -> Option<&'a str> {
+    e.attributes
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Collect the root element's attributes
+fn root_attributes(e: &BytesStart) -> Result<Vec<(String, String)>> {
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        attrs.push((key, attr.unescape_value()?.to_string()));
+    }
+    Ok(attrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" mc:Ignorable="w14">
+  <w:zoom w:percent="100"/>
+  <w:proofState w:spelling="clean"/>
+  <w:defaultTabStop w:val="708"/>
+  <w:characterSpacingControl w:val="doNotCompress"/>
+  <w:compat>
+    <w:useFELayout/>
+    <w:compatSetting w:name="compatibilityMode" w:uri="http://schemas.microsoft.com/office/word" w:val="14"/>
+  </w:compat>
+  <w:rsids><w:rsidRoot w:val="00A1B2C3"/></w:rsids>
+  <w14:docId w14:val="1A2B3C4D"/>
+</w:settings>"#;
+
+    #[test]
+    fn test_parse_settings() {
+        let settings = Settings::from_xml(SETTINGS_XML).unwrap();
+        assert_eq!(settings.default_tab_stop, Some(708));
+        assert_eq!(settings.compatibility_mode(), Some(14));
+        assert!(!settings.even_and_odd_headers);
+        assert!(!settings.update_fields);
+        assert_eq!(settings.unknown_children.len(), 5);
+        assert!(settings
+            .root_attributes
+            .iter()
+            .any(|(k, v)| k == "mc:Ignorable" && v == "w14"));
+    }
+
+    #[test]
+    fn test_settings_roundtrip_in_schema_order() {
+        let mut settings = Settings::from_xml(SETTINGS_XML).unwrap();
+        settings.even_and_odd_headers = true;
+        settings.update_fields = true;
+        settings.set_compatibility_mode(CURRENT_COMPATIBILITY_MODE);
+
+        let xml = settings.to_xml().unwrap();
+        let pos = |needle: &str| xml.find(needle).unwrap();
+        assert!(pos("<w:proofState") < pos("<w:defaultTabStop"));
+        assert!(pos("<w:defaultTabStop") < pos("<w:evenAndOddHeaders/>"));
+        assert!(pos("<w:evenAndOddHeaders/>") < pos("<w:characterSpacingControl"));
+        assert!(pos("<w:characterSpacingControl") < pos("<w:updateFields"));
+        assert!(pos("<w:updateFields") < pos("<w:compat>"));
+        assert!(pos("<w:compat>") < pos("<w:rsids>"));
+        assert!(xml.contains("<w:useFELayout/>"));
+        assert!(xml.contains("xmlns:w14="));
+
+        let settings2 = Settings::from_xml(&xml).unwrap();
+        assert!(settings2.even_and_odd_headers);
+        assert!(settings2.update_fields);
+        assert_eq!(settings2.compatibility_mode(), Some(15));
+    }
+
+    #[test]
+    fn test_new_settings() {
+        let settings = Settings::new();
+        assert_eq!(
+            settings.compatibility_mode(),
+            Some(CURRENT_COMPATIBILITY_MODE)
+        );
+        assert_eq!(settings.default_tab_stop, Some(720));
+    }
+}
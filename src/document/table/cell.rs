@@ -1,9 +1,9 @@
 //! Table cell elements (w:tc, w:tcPr)
 
-use crate::document::Paragraph;
+use crate::document::sdt::{read_paragraph, read_ranged, write_ranged};
+use crate::document::{Paragraph, Sdt, SdtContent};
 use crate::error::Result;
-use crate::xml::RawXmlElement;
-use crate::xml::RawXmlNode;
+use crate::xml::{schema, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -17,6 +17,8 @@ pub struct TableCell {
     pub properties: Option<TableCellProperties>,
     /// Cell content (paragraphs)
     pub paragraphs: Vec<Paragraph>,
+    /// Content controls around ranges of paragraphs, outer controls first
+    pub content_controls: Vec<Sdt>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
 }
@@ -54,6 +56,7 @@ impl TableCell {
     /// Set the cell text (replaces all paragraphs with a single one)
     pub fn set_text(&mut self, text: impl Into<String>) {
         self.paragraphs.clear();
+        self.content_controls.clear();
         self.paragraphs.push(Paragraph::new(text));
     }
 
@@ -102,6 +105,14 @@ impl TableCell {
                             let para = Paragraph::from_reader(reader, &e)?;
                             cell.paragraphs.push(para);
                         }
+                        b"sdt" => read_ranged(
+                            reader,
+                            &mut cell.paragraphs,
+                            &mut cell.content_controls,
+                            &mut cell.unknown_children,
+                            SdtContent::Paragraphs,
+                            &mut read_paragraph,
+                        )?,
                         _ => {
                             let raw = RawXmlElement::from_reader(reader, &e)?;
                             cell.unknown_children.push(RawXmlNode::Element(raw));
@@ -132,10 +143,8 @@ impl TableCell {
                         cell.unknown_children.push(RawXmlNode::Element(raw));
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"tc" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"tc" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -225,14 +234,16 @@ impl TableCell {
             props.write_to(writer)?;
         }
 
-        // Paragraphs (at least one required in cell)
+        // Paragraphs, with any content controls around them
+        write_ranged(
+            writer,
+            &self.paragraphs,
+            &self.content_controls,
+            |w, para| para.write_to(w),
+        )?;
         if self.paragraphs.is_empty() {
-            // Write empty paragraph if none exist
+            // A cell needs at least one paragraph
             writer.write_event(Event::Empty(BytesStart::new("w:p")))?;
-        } else {
-            for para in &self.paragraphs {
-                para.write_to(writer)?;
-            }
         }
 
         // Unknown children
@@ -300,10 +311,8 @@ impl TableCellProperties {
                         }
                     }
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"tcPr" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"tcPr" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -328,42 +337,49 @@ impl TableCellProperties {
 
         writer.write_event(Event::Start(BytesStart::new("w:tcPr")))?;
 
-        // Width
-        if let Some(width) = self.width {
-            let mut elem = BytesStart::new("w:tcW");
-            elem.push_attribute(("w:w", width.to_string().as_str()));
-            elem.push_attribute(("w:type", "dxa"));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Grid span
-        if let Some(span) = self.grid_span {
-            let mut elem = BytesStart::new("w:gridSpan");
-            elem.push_attribute(("w:val", span.to_string().as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Vertical merge
-        if let Some(v_merge) = &self.v_merge {
-            let mut elem = BytesStart::new("w:vMerge");
-            match v_merge {
-                VMerge::Restart => elem.push_attribute(("w:val", "restart")),
-                VMerge::Continue => {}
-            }
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Vertical alignment
-        if let Some(v_align) = &self.v_align {
-            let mut elem = BytesStart::new("w:vAlign");
-            elem.push_attribute(("w:val", v_align.as_str()));
-            writer.write_event(Event::Empty(elem))?;
-        }
-
-        // Unknown children
-        for child in &self.unknown_children {
-            child.write_to(writer)?;
-        }
+        schema::write_in_order(
+            writer,
+            schema::TCPR,
+            &self.unknown_children,
+            |writer, name| {
+                match name {
+                    "tcW" => {
+                        if let Some(width) = self.width {
+                            let mut elem = BytesStart::new("w:tcW");
+                            elem.push_attribute(("w:w", width.to_string().as_str()));
+                            elem.push_attribute(("w:type", "dxa"));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "gridSpan" => {
+                        if let Some(span) = self.grid_span {
+                            let mut elem = BytesStart::new("w:gridSpan");
+                            elem.push_attribute(("w:val", span.to_string().as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "vMerge" => {
+                        if let Some(v_merge) = &self.v_merge {
+                            let mut elem = BytesStart::new("w:vMerge");
+                            match v_merge {
+                                VMerge::Restart => elem.push_attribute(("w:val", "restart")),
+                                VMerge::Continue => {}
+                            }
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    "vAlign" => {
+                        if let Some(v_align) = &self.v_align {
+                            let mut elem = BytesStart::new("w:vAlign");
+                            elem.push_attribute(("w:val", v_align.as_str()));
+                            writer.write_event(Event::Empty(elem))?;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            },
+        )?;
 
         writer.write_event(Event::End(BytesEnd::new("w:tcPr")))?;
         Ok(())
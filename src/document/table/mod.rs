@@ -10,6 +10,8 @@ pub use cell::{TableCell, TableCellProperties};
 pub use row::TableRow;
 pub use types::{GridColumn, TableAlignment, TableWidth, VMerge, VerticalAlignment};
 
+use crate::document::sdt::{read_ranged, shift_for_insert, shift_for_remove, write_ranged};
+use crate::document::{Sdt, SdtContent};
 use crate::error::Result;
 use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
@@ -25,6 +27,8 @@ pub struct Table {
     pub grid: Vec<GridColumn>,
     /// Table rows
     pub rows: Vec<TableRow>,
+    /// Content controls around ranges of rows, outer controls first
+    pub content_controls: Vec<Sdt>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
 }
@@ -101,6 +105,17 @@ impl Table {
                             let row = TableRow::from_reader(reader, &e)?;
                             table.rows.push(row);
                         }
+                        b"sdt" => read_ranged(
+                            reader,
+                            &mut table.rows,
+                            &mut table.content_controls,
+                            &mut table.unknown_children,
+                            SdtContent::Rows,
+                            &mut |reader, e, empty| match e.local_name().as_ref() {
+                                b"tr" if !empty => Ok(Some(TableRow::from_reader(reader, e)?)),
+                                _ => Ok(None),
+                            },
+                        )?,
                         _ => {
                             let raw = RawXmlElement::from_reader(reader, &e)?;
                             table.unknown_children.push(RawXmlNode::Element(raw));
@@ -126,10 +141,8 @@ impl Table {
                     };
                     table.unknown_children.push(RawXmlNode::Element(raw));
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"tbl" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"tbl" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -191,12 +204,14 @@ impl Table {
     pub fn insert_row(&mut self, index: usize, row: TableRow) {
         if index <= self.rows.len() {
             self.rows.insert(index, row);
+            shift_for_insert(&mut self.content_controls, index);
         }
     }
 
     /// Remove a row at the specified index
     pub fn remove_row(&mut self, index: usize) -> Option<TableRow> {
         if index < self.rows.len() {
+            shift_for_remove(&mut self.content_controls, index);
             Some(self.rows.remove(index))
         } else {
             None
@@ -270,10 +285,10 @@ impl Table {
             writer.write_event(Event::End(BytesEnd::new("w:tblGrid")))?;
         }
 
-        // Rows
-        for row in &self.rows {
-            row.write_to(writer)?;
-        }
+        // Rows, with any content controls around them
+        write_ranged(writer, &self.rows, &self.content_controls, |w, row| {
+            row.write_to(w)
+        })?;
 
         // Unknown children
         for child in &self.unknown_children {
@@ -292,18 +307,14 @@ fn parse_table_grid<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<GridColumn
 
     loop {
         match reader.read_event_into(&mut buf)? {
-            Event::Empty(e) => {
-                if e.name().local_name().as_ref() == b"gridCol" {
-                    let width = crate::xml::get_attr(&e, "w:w")
-                        .or_else(|| crate::xml::get_attr(&e, "w"))
-                        .and_then(|v| v.parse().ok());
-                    columns.push(GridColumn { width });
-                }
+            Event::Empty(e) if e.name().local_name().as_ref() == b"gridCol" => {
+                let width = crate::xml::get_attr(&e, "w:w")
+                    .or_else(|| crate::xml::get_attr(&e, "w"))
+                    .and_then(|v| v.parse().ok());
+                columns.push(GridColumn { width });
             }
-            Event::End(e) => {
-                if e.name().local_name().as_ref() == b"tblGrid" {
-                    break;
-                }
+            Event::End(e) if e.name().local_name().as_ref() == b"tblGrid" => {
+                break;
             }
             Event::Eof => break,
             _ => {}
@@ -1,5 +1,7 @@
 //! Table row elements (w:tr)
 
+use crate::document::sdt::{read_ranged, shift_for_insert, shift_for_remove, write_ranged};
+use crate::document::{Sdt, SdtContent};
 use crate::error::Result;
 use crate::xml::{RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
@@ -15,6 +17,8 @@ pub struct TableRow {
     pub properties: Option<RawXmlNode>,
     /// Cells
     pub cells: Vec<TableCell>,
+    /// Content controls around ranges of cells, outer controls first
+    pub content_controls: Vec<Sdt>,
     /// Unknown children (preserved)
     pub unknown_children: Vec<RawXmlNode>,
 }
@@ -57,6 +61,17 @@ impl TableRow {
                             let cell = TableCell::from_reader(reader, &e)?;
                             row.cells.push(cell);
                         }
+                        b"sdt" => read_ranged(
+                            reader,
+                            &mut row.cells,
+                            &mut row.content_controls,
+                            &mut row.unknown_children,
+                            SdtContent::Cells,
+                            &mut |reader, e, empty| match e.local_name().as_ref() {
+                                b"tc" if !empty => Ok(Some(TableCell::from_reader(reader, e)?)),
+                                _ => Ok(None),
+                            },
+                        )?,
                         _ => {
                             let raw = RawXmlElement::from_reader(reader, &e)?;
                             row.unknown_children.push(RawXmlNode::Element(raw));
@@ -81,10 +96,8 @@ impl TableRow {
                     };
                     row.unknown_children.push(RawXmlNode::Element(raw));
                 }
-                Event::End(e) => {
-                    if e.name().local_name().as_ref() == b"tr" {
-                        break;
-                    }
+                Event::End(e) if e.name().local_name().as_ref() == b"tr" => {
+                    break;
                 }
                 Event::Eof => break,
                 _ => {}
@@ -124,12 +137,14 @@ impl TableRow {
     pub fn insert_cell(&mut self, index: usize, cell: TableCell) {
         if index <= self.cells.len() {
             self.cells.insert(index, cell);
+            shift_for_insert(&mut self.content_controls, index);
         }
     }
 
     /// Remove a cell at the specified index
     pub fn remove_cell(&mut self, index: usize) -> Option<TableCell> {
         if index < self.cells.len() {
+            shift_for_remove(&mut self.content_controls, index);
             Some(self.cells.remove(index))
         } else {
             None
@@ -145,10 +160,10 @@ impl TableRow {
             props.write_to(writer)?;
         }
 
-        // Cells
-        for cell in &self.cells {
-            cell.write_to(writer)?;
-        }
+        // Cells, with any content controls around them
+        write_ranged(writer, &self.cells, &self.content_controls, |w, cell| {
+            cell.write_to(w)
+        })?;
 
         // Unknown children
         for child in &self.unknown_children {
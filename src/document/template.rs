@@ -3,7 +3,8 @@
 //! Supports `{{placeholder}}` syntax for text replacement in paragraphs,
 //! headers, footers, and table cells.
 
-use crate::document::{BlockContent, Document, ParagraphContent, RunContent};
+use crate::document::comments::body_paragraphs_mut;
+use crate::document::{Document, ParagraphContent, RevisionTracker, RunContent};
 use std::collections::HashMap;
 
 /// Template context: a map of placeholder names to replacement values
@@ -16,7 +17,8 @@ impl Document {
     /// from the context map. Works across paragraphs, tables, headers,
     /// footers, footnotes, and endnotes.
     ///
-    /// Returns the total number of replacements made.
+    /// Returns the total number of replacements made. While tracking changes,
+    /// each fill is recorded as a tracked deletion and insertion.
     ///
     /// # Example
     /// ```rust,ignore
@@ -34,55 +36,54 @@ impl Document {
         let mut count = 0;
 
         // Body content
-        for block in &mut self.body.content {
-            match block {
-                BlockContent::Paragraph(para) => {
-                    count += fill_paragraph_runs(para, context);
-                }
-                BlockContent::Table(table) => {
-                    for row in &mut table.rows {
-                        for cell in &mut row.cells {
-                            for para in &mut cell.paragraphs {
-                                count += fill_paragraph_runs(para, context);
-                            }
-                        }
-                    }
-                }
-                BlockContent::Unknown(_) => {}
-            }
+        for para in body_paragraphs_mut(&mut self.body.content) {
+            count += fill_paragraph_runs(para, context, self.tracker.as_mut());
         }
-
-        // Headers
-        for (_, hf) in &mut self.headers {
-            for para in &mut hf.paragraphs {
-                count += fill_paragraph_runs(para, context);
-            }
+        if count > 0 {
+            self.mark_body_modified();
         }
 
-        // Footers
-        for (_, hf) in &mut self.footers {
+        // Headers and footers
+        let mut touched = Vec::new();
+        let (headers, footers) = self.headers_footers.get_mut(&self.package);
+        for (r_id, hf) in headers.iter_mut().chain(footers) {
+            let before = count;
             for para in &mut hf.paragraphs {
-                count += fill_paragraph_runs(para, context);
+                count += fill_paragraph_runs(para, context, self.tracker.as_mut());
+            }
+            if count > before {
+                touched.push(r_id.clone());
             }
         }
+        for r_id in touched {
+            self.mark_header_footer_modified(&r_id);
+        }
 
         // Footnotes
-        if let Some(ref mut notes) = self.footnotes {
+        let before = count;
+        if let Some(notes) = self.footnotes.get_mut(&self.package) {
             for note in &mut notes.notes {
                 for para in &mut note.paragraphs {
-                    count += fill_paragraph_runs(para, context);
+                    count += fill_paragraph_runs(para, context, self.tracker.as_mut());
                 }
             }
         }
+        if count > before {
+            self.mark_doc_part_modified(crate::opc::rel_types::FOOTNOTES);
+        }
 
         // Endnotes
-        if let Some(ref mut notes) = self.endnotes {
+        let before = count;
+        if let Some(notes) = self.endnotes.get_mut(&self.package) {
             for note in &mut notes.notes {
                 for para in &mut note.paragraphs {
-                    count += fill_paragraph_runs(para, context);
+                    count += fill_paragraph_runs(para, context, self.tracker.as_mut());
                 }
             }
         }
+        if count > before {
+            self.mark_doc_part_modified(crate::opc::rel_types::ENDNOTES);
+        }
 
         count
     }
@@ -116,9 +117,44 @@ impl Document {
 }
 
 /// Replace `{{key}}` placeholders in a paragraph's runs
-fn fill_paragraph_runs(para: &mut crate::document::Paragraph, context: &TemplateContext) -> usize {
+fn fill_paragraph_runs(
+    para: &mut crate::document::Paragraph,
+    context: &TemplateContext,
+    mut tracker: Option<&mut RevisionTracker>,
+) -> usize {
+    let mut boxed = 0;
+    let mut has_text_boxes = false;
+    for text_box in para.text_boxes_mut() {
+        has_text_boxes = true;
+        for inner in body_paragraphs_mut(&mut text_box.content) {
+            boxed += fill_paragraph_runs(inner, context, tracker.as_deref_mut());
+        }
+    }
+    boxed + fill_own_runs(para, context, tracker, has_text_boxes)
+}
+
+/// Replace `{{key}}` placeholders in the runs of a paragraph itself
+///
+/// Merging runs to catch split placeholders would drop anchored text boxes,
+/// so it is skipped when the paragraph has any.
+fn fill_own_runs(
+    para: &mut crate::document::Paragraph,
+    context: &TemplateContext,
+    mut tracker: Option<&mut RevisionTracker>,
+    has_text_boxes: bool,
+) -> usize {
     let mut count = 0;
 
+    if let Some(ref mut tracker) = tracker {
+        for (key, value) in context {
+            let placeholder = format!("{{{{{}}}}}", key);
+            count += para.replace_text_tracked(&placeholder, value, tracker);
+        }
+        if count > 0 {
+            return count;
+        }
+    }
+
     // First, try simple per-run replacement
     for content in &mut para.content {
         if let ParagraphContent::Run(run) = content {
@@ -139,7 +175,7 @@ fn fill_paragraph_runs(para: &mut crate::document::Paragraph, context: &Template
 
     // Handle cross-run placeholders: when {{ and }} span multiple runs.
     // Merge all text, do replacements, then check if anything changed.
-    if count == 0 {
+    if count == 0 && !has_text_boxes {
         let full_text = para.text();
         let mut has_placeholder = false;
         for key in context.keys() {
@@ -162,6 +198,11 @@ fn fill_paragraph_runs(para: &mut crate::document::Paragraph, context: &Template
                 }
             }
             if count > 0 {
+                if let Some(tracker) = tracker {
+                    para.set_text_tracked(merged, tracker);
+                    return count;
+                }
+
                 // Replace content with single run preserving first run's properties
                 let first_props = para.content.iter().find_map(|c| {
                     if let ParagraphContent::Run(r) = c {
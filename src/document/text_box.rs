@@ -0,0 +1,456 @@
+//! Text boxes (wps:txbx and VML v:textbox)
+//!
+//! A text box is a shape holding its own story of paragraphs and tables
+//! (`w:txbxContent`). The shape stays raw XML; its content is parsed into
+//! [`BlockContent`] and written back into every copy of the content, since
+//! Word repeats it in the VML fallback of `mc:AlternateContent`.
+
+use crate::document::comments::body_paragraphs;
+use crate::document::drawing::{self, children, empty, local_name};
+use crate::document::{
+    AnchorPosition, AnchoredImage, BlockContent, Paragraph, Table, WrapMode, WrapSide,
+};
+use crate::error::Result;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// Width Word gives a new text box's border, in EMU (0.5pt)
+const DEFAULT_BORDER_WIDTH: i64 = 6350;
+
+/// A text box in the document
+#[derive(Clone, Debug)]
+pub struct TextBox {
+    /// Paragraphs, tables and content controls in the text box
+    pub content: Vec<BlockContent>,
+    /// The run content holding the shape (`w:drawing`, `w:pict` or
+    /// `mc:AlternateContent`), with its `w:txbxContent` left empty
+    pub raw_xml: RawXmlElement,
+}
+
+impl TextBox {
+    /// Create a text box builder with a size in EMU
+    pub fn builder(width_emu: i64, height_emu: i64) -> TextBoxBuilder {
+        let mut frame = AnchoredImage::new("", width_emu, height_emu);
+        frame.name = "Text Box".into();
+        TextBoxBuilder {
+            frame,
+            fill: Some("FFFFFF".into()),
+            border: Some(("000000".into(), DEFAULT_BORDER_WIDTH)),
+            content: Vec::new(),
+        }
+    }
+
+    /// Read run content holding a single text box
+    ///
+    /// Anything else, including groups of several text boxes, is handed back unchanged.
+    pub(crate) fn from_raw(raw: RawXmlElement) -> std::result::Result<Self, RawXmlElement> {
+        let (mut main, mut fallback) = (Vec::new(), Vec::new());
+        find_slots(&raw, &mut Vec::new(), false, &mut main, &mut fallback);
+        if main.len() != 1 || fallback.len() > 1 {
+            return Err(raw);
+        }
+        let Ok(content) = parse_blocks(drawing::element_at(&raw, &main[0])) else {
+            return Err(raw);
+        };
+        let mut raw_xml = raw;
+        for path in main.iter().chain(&fallback) {
+            drawing::element_at_mut(&mut raw_xml, path).children.clear();
+        }
+        Ok(TextBox { content, raw_xml })
+    }
+
+    /// Get the paragraphs
+    pub fn paragraphs(&self) -> impl Iterator<Item = &Paragraph> {
+        self.content.iter().filter_map(|c| match c {
+            BlockContent::Paragraph(p) => Some(p.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Get the paragraphs mutably
+    pub fn paragraphs_mut(&mut self) -> impl Iterator<Item = &mut Paragraph> {
+        self.content.iter_mut().filter_map(|c| match c {
+            BlockContent::Paragraph(p) => Some(p.as_mut()),
+            _ => None,
+        })
+    }
+
+    /// Get the tables
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.content.iter().filter_map(|c| match c {
+            BlockContent::Table(t) => Some(t.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Add a paragraph
+    pub fn add_paragraph(&mut self, para: Paragraph) {
+        self.content.push(BlockContent::Paragraph(Box::new(para)));
+    }
+
+    /// Add a table
+    pub fn add_table(&mut self, table: Table) {
+        self.content.push(BlockContent::Table(Box::new(table)));
+    }
+
+    /// Get all text, one line per paragraph (including those in tables)
+    pub fn text(&self) -> String {
+        body_paragraphs(&self.content)
+            .map(|p| p.text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Drawing object ID (`wp:docPr id`) of the shape
+    ///
+    /// `None` for legacy VML text boxes. A built text box has ID 0 until the
+    /// document gives it an unused one when it is saved.
+    pub fn id(&self) -> Option<u32> {
+        let mut ids = Vec::new();
+        drawing::doc_pr_ids(&self.raw_xml, &mut ids);
+        ids.first().copied()
+    }
+
+    /// Set the drawing object ID of the shape
+    pub fn set_id(&mut self, id: u32) {
+        drawing::set_doc_pr_id(&mut self.raw_xml, id);
+    }
+
+    /// Write the shape with the content filled in
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        write_shape(&self.raw_xml, &self.content, writer)
+    }
+}
+
+/// Paths to `w:txbxContent` elements, split by whether they sit in an `mc:Fallback`
+fn find_slots(
+    e: &RawXmlElement,
+    path: &mut Vec<usize>,
+    in_fallback: bool,
+    main: &mut Vec<Vec<usize>>,
+    fallback: &mut Vec<Vec<usize>>,
+) {
+    for (i, child) in children(e) {
+        path.push(i);
+        match local_name(child) {
+            "txbxContent" if in_fallback => fallback.push(path.clone()),
+            "txbxContent" => main.push(path.clone()),
+            "Fallback" => find_slots(child, path, true, main, fallback),
+            _ => find_slots(child, path, in_fallback, main, fallback),
+        }
+        path.pop();
+    }
+}
+
+/// Parse the block content of a `w:txbxContent`
+fn parse_blocks(slot: &RawXmlElement) -> Result<Vec<BlockContent>> {
+    let mut xml = Vec::new();
+    let mut writer = Writer::new(&mut xml);
+    for child in &slot.children {
+        child.write_to(&mut writer)?;
+    }
+
+    let mut reader = Reader::from_reader(xml.as_slice());
+    let mut blocks = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => blocks.push(BlockContent::from_element(&mut reader, &e, false)?),
+            Event::Empty(e) => blocks.push(BlockContent::from_element(&mut reader, &e, true)?),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(blocks)
+}
+
+/// Write raw shape XML, filling each `w:txbxContent` with the content
+fn write_shape<W: std::io::Write>(
+    e: &RawXmlElement,
+    content: &[BlockContent],
+    writer: &mut Writer<W>,
+) -> Result<()> {
+    let mut start = BytesStart::new(e.name.as_str());
+    for (key, value) in &e.attributes {
+        start.push_attribute((key.as_str(), value.as_str()));
+    }
+    if local_name(e) == "txbxContent" {
+        writer.write_event(Event::Start(start))?;
+        for block in content {
+            block.write_to(writer)?;
+        }
+        // A text box holds at least one paragraph
+        if content.is_empty() {
+            writer.write_event(Event::Empty(BytesStart::new("w:p")))?;
+        }
+    } else if e.children.is_empty() && e.self_closing {
+        writer.write_event(Event::Empty(start))?;
+        return Ok(());
+    } else {
+        writer.write_event(Event::Start(start))?;
+        for child in &e.children {
+            match child {
+                RawXmlNode::Element(child) => write_shape(child, content, writer)?,
+                other => other.write_to(writer)?,
+            }
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(e.name.as_str())))?;
+    Ok(())
+}
+
+/// Builder for text boxes
+///
+/// The text box floats like an [`AnchoredImage`] and is written as a Word
+/// 2010 shape (`wps:wsp`).
+pub struct TextBoxBuilder {
+    frame: AnchoredImage,
+    fill: Option<String>,
+    border: Option<(String, i64)>,
+    content: Vec<BlockContent>,
+}
+
+impl TextBoxBuilder {
+    /// Set the horizontal position
+    pub fn position_h(mut self, position: AnchorPosition) -> Self {
+        self.frame.position_h = position;
+        self
+    }
+
+    /// Set the vertical position
+    pub fn position_v(mut self, position: AnchorPosition) -> Self {
+        self.frame.position_v = position;
+        self
+    }
+
+    /// Set text wrapping around the box
+    pub fn wrap(mut self, wrap: WrapMode) -> Self {
+        self.frame.wrap = wrap;
+        self
+    }
+
+    /// Set the sides text wraps along
+    pub fn wrap_side(mut self, side: WrapSide) -> Self {
+        self.frame.wrap_side = side;
+        self
+    }
+
+    /// Place the box behind the text
+    pub fn behind_text(mut self, behind: bool) -> Self {
+        self.frame.behind_text = behind;
+        self
+    }
+
+    /// Set the stacking order among floating objects
+    pub fn z_order(mut self, z_order: u32) -> Self {
+        self.frame.z_order = z_order;
+        self
+    }
+
+    /// Set the distance from surrounding text in EMU (top, bottom, left, right)
+    pub fn distance(mut self, top: i64, bottom: i64, left: i64, right: i64) -> Self {
+        self.frame.dist_top = top;
+        self.frame.dist_bottom = bottom;
+        self.frame.dist_left = left;
+        self.frame.dist_right = right;
+        self
+    }
+
+    /// Set the shape name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.frame.name = name.into();
+        self
+    }
+
+    /// Set alt text
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.frame.description = description.into();
+        self
+    }
+
+    /// Set the fill color (hex RGB, e.g. "FFF2CC")
+    pub fn fill(mut self, color: impl Into<String>) -> Self {
+        self.fill = Some(color.into());
+        self
+    }
+
+    /// Leave the box transparent
+    pub fn no_fill(mut self) -> Self {
+        self.fill = None;
+        self
+    }
+
+    /// Set the border color (hex RGB) and width in EMU (12700 per point)
+    pub fn border(mut self, color: impl Into<String>, width_emu: i64) -> Self {
+        self.border = Some((color.into(), width_emu));
+        self
+    }
+
+    /// Draw no border
+    pub fn no_border(mut self) -> Self {
+        self.border = None;
+        self
+    }
+
+    /// Add a paragraph
+    pub fn paragraph(mut self, para: Paragraph) -> Self {
+        self.content.push(BlockContent::Paragraph(Box::new(para)));
+        self
+    }
+
+    /// Add a paragraph of text
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.paragraph(Paragraph::new(text))
+    }
+
+    /// Add a table
+    pub fn table(mut self, table: Table) -> Self {
+        self.content.push(BlockContent::Table(Box::new(table)));
+        self
+    }
+
+    /// Build the text box
+    pub fn build(self) -> TextBox {
+        let solid = |color: &str| {
+            RawXmlElement::new("a:solidFill")
+                .with_child(empty("a:srgbClr").with_attr("val", color.trim_start_matches('#')))
+        };
+        let mut sp_pr = RawXmlElement::new("wps:spPr")
+            .with_child(
+                RawXmlElement::new("a:xfrm")
+                    .with_child(empty("a:off").with_attr("x", "0").with_attr("y", "0"))
+                    .with_child(
+                        empty("a:ext")
+                            .with_attr("cx", self.frame.width_emu.to_string())
+                            .with_attr("cy", self.frame.height_emu.to_string()),
+                    ),
+            )
+            .with_child(
+                RawXmlElement::new("a:prstGeom")
+                    .with_attr("prst", "rect")
+                    .with_child(empty("a:avLst")),
+            );
+        sp_pr = match &self.fill {
+            Some(color) => sp_pr.with_child(solid(color)),
+            None => sp_pr.with_child(empty("a:noFill")),
+        };
+        sp_pr = match &self.border {
+            Some((color, width)) => sp_pr.with_child(
+                RawXmlElement::new("a:ln")
+                    .with_attr("w", width.to_string())
+                    .with_child(solid(color)),
+            ),
+            None => sp_pr.with_child(RawXmlElement::new("a:ln").with_child(empty("a:noFill"))),
+        };
+
+        let shape = RawXmlElement::new("wps:wsp")
+            .with_child(empty("wps:cNvSpPr").with_attr("txBox", "1"))
+            .with_child(sp_pr)
+            .with_child(RawXmlElement::new("wps:txbx").with_child(empty("w:txbxContent")))
+            .with_child(
+                RawXmlElement::new("wps:bodyPr")
+                    .with_attr("rot", "0")
+                    .with_attr("vert", "horz")
+                    .with_attr("wrap", "square")
+                    .with_attr("lIns", "91440")
+                    .with_attr("tIns", "45720")
+                    .with_attr("rIns", "91440")
+                    .with_attr("bIns", "45720")
+                    .with_attr("anchor", "t")
+                    .with_attr("anchorCtr", "0")
+                    .with_child(empty("a:noAutofit")),
+            );
+        let raw_xml = RawXmlElement::new("mc:AlternateContent")
+            .with_attr("xmlns:mc", crate::xml::MC)
+            .with_attr("xmlns:wps", crate::xml::WPS)
+            .with_child(
+                RawXmlElement::new("mc:Choice")
+                    .with_attr("Requires", "wps")
+                    .with_child(self.frame.anchor_graphic(crate::xml::WPS, shape)),
+            );
+        TextBox {
+            content: self.content,
+            raw_xml,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> RawXmlElement {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            if let Event::Start(e) = reader.read_event().unwrap() {
+                return RawXmlElement::from_reader(&mut reader, &e).unwrap();
+            }
+        }
+    }
+
+    fn write(text_box: &TextBox) -> String {
+        let mut buf = Vec::new();
+        text_box.write_to(&mut Writer::new(&mut buf)).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    const SHAPE: &str = r#"<mc:AlternateContent><mc:Choice Requires="wps"><w:drawing><wp:anchor><wp:extent cx="1828800" cy="457200"/><a:graphic><a:graphicData><wps:wsp><wps:txbx><w:txbxContent><w:p><w:r><w:t>Dear {{name}}</w:t></w:r></w:p></w:txbxContent></wps:txbx></wps:wsp></a:graphicData></a:graphic></wp:anchor></w:drawing></mc:Choice><mc:Fallback><w:pict><v:shape><v:textbox><w:txbxContent><w:p><w:r><w:t>Dear {{name}}</w:t></w:r></w:p></w:txbxContent></v:textbox></v:shape></w:pict></mc:Fallback></mc:AlternateContent>"#;
+
+    #[test]
+    fn test_parse_and_write_both_copies() {
+        let mut text_box = TextBox::from_raw(parse(SHAPE)).unwrap();
+        assert_eq!(text_box.text(), "Dear {{name}}");
+
+        let para = text_box.paragraphs_mut().next().unwrap();
+        para.set_text("Dear Ada");
+        text_box.add_paragraph(Paragraph::new("Regards"));
+
+        let xml = write(&text_box);
+        assert_eq!(xml.matches("<w:t>Dear Ada</w:t>").count(), 2);
+        assert_eq!(xml.matches("<w:t>Regards</w:t>").count(), 2);
+        assert!(!xml.contains("{{name}}"));
+        assert!(xml.contains("<v:textbox><w:txbxContent><w:p>"));
+    }
+
+    #[test]
+    fn test_several_text_boxes_stay_raw() {
+        let group = r#"<w:pict><v:group><v:shape><v:textbox><w:txbxContent><w:p/></w:txbxContent></v:textbox></v:shape><v:shape><v:textbox><w:txbxContent><w:p/></w:txbxContent></v:textbox></v:shape></v:group></w:pict>"#;
+        assert!(TextBox::from_raw(parse(group)).is_err());
+
+        let picture = r#"<w:drawing><wp:inline><a:graphic/></wp:inline></w:drawing>"#;
+        assert!(TextBox::from_raw(parse(picture)).is_err());
+    }
+
+    #[test]
+    fn test_build_text_box() {
+        let text_box = TextBox::builder(2743200, 914400)
+            .fill("FFF2CC")
+            .border("#C00000", 12700)
+            .text("Invoice {{number}}")
+            .build();
+        assert_eq!(text_box.text(), "Invoice {{number}}");
+
+        let xml = write(&text_box);
+        assert!(xml.starts_with("<mc:AlternateContent"));
+        assert!(xml.contains(r#"<mc:Choice Requires="wps"><w:drawing><wp:anchor"#));
+        assert!(xml.contains(r#"<wp:extent cx="2743200" cy="914400"/>"#));
+        assert!(xml.contains(r#"<a:graphicData uri="http://schemas.microsoft.com/office/word/2010/wordprocessingShape"><wps:wsp><wps:cNvSpPr txBox="1"/>"#));
+        assert!(xml.contains(r#"<a:solidFill><a:srgbClr val="FFF2CC"/></a:solidFill><a:ln w="12700"><a:solidFill><a:srgbClr val="C00000"/>"#));
+        assert!(xml.contains(
+            "<w:txbxContent><w:p><w:r><w:t>Invoice {{number}}</w:t></w:r></w:p></w:txbxContent>"
+        ));
+
+        let reparsed = TextBox::from_raw(parse(&xml)).unwrap();
+        assert_eq!(reparsed.text(), "Invoice {{number}}");
+
+        let empty = TextBox::builder(914400, 914400)
+            .no_fill()
+            .no_border()
+            .build();
+        let xml = write(&empty);
+        assert!(xml.contains("<w:txbxContent><w:p/></w:txbxContent>"));
+        assert!(xml.contains("<a:noFill/><a:ln><a:noFill/></a:ln>"));
+    }
+}
@@ -1,6 +1,9 @@
 //! Text search and replace operations for Document
 
-use crate::document::{BlockContent, Document, Paragraph, ParagraphContent, RunContent};
+use crate::document::comments::body_paragraphs_mut;
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, RevisionTracker, RunContent,
+};
 
 /// Text location in the document
 #[derive(Clone, Debug)]
@@ -11,13 +14,18 @@ pub struct TextLocation {
 
 impl Document {
     /// Replace text across all paragraphs. Returns number of replacements.
+    ///
+    /// While tracking changes, each replacement is a tracked deletion and insertion.
     pub fn replace_text(&mut self, find: &str, replace: &str) -> usize {
         let mut count = 0;
         for content in &mut self.body.content {
             if let BlockContent::Paragraph(para) = content {
-                count += replace_text_in_paragraph(para, find, replace);
+                count += replace_text_in_paragraph(para, find, replace, self.tracker.as_mut());
             }
         }
+        if count > 0 {
+            self.mark_body_modified();
+        }
         count
     }
 
@@ -39,9 +47,22 @@ impl Document {
     }
 }
 
-/// Replace text in a paragraph's runs
-fn replace_text_in_paragraph(para: &mut Paragraph, find: &str, replace: &str) -> usize {
+/// Replace text in a paragraph's runs, as tracked changes if a tracker is given
+fn replace_text_in_paragraph(
+    para: &mut Paragraph,
+    find: &str,
+    replace: &str,
+    mut tracker: Option<&mut RevisionTracker>,
+) -> usize {
     let mut count = 0;
+    for text_box in para.text_boxes_mut() {
+        for inner in body_paragraphs_mut(&mut text_box.content) {
+            count += replace_text_in_paragraph(inner, find, replace, tracker.as_deref_mut());
+        }
+    }
+    if let Some(tracker) = tracker {
+        return count + para.replace_text_tracked(find, replace, tracker);
+    }
     for content in &mut para.content {
         if let ParagraphContent::Run(run) = content {
             for rc in &mut run.content {
@@ -0,0 +1,476 @@
+//! Table of contents generation
+//!
+//! Inserts a TOC field inside a "Table of Contents" document part content
+//! control, with one pre-populated entry per heading. Entries link to `_Toc`
+//! bookmarks on the headings through PAGEREF fields whose page numbers are
+//! placeholders until Word updates the field.
+
+use crate::document::comments::body_paragraphs_mut;
+use crate::document::field::{FieldChar, FieldCharType};
+use crate::document::paragraph::Hyperlink;
+use crate::document::{
+    BlockContent, Document, Field, Indentation, Paragraph, ParagraphContent, Run, RunContent, Sdt,
+    SdtContent, SdtDocPart, SdtKind, SdtProperties, Style, StyleType,
+};
+use crate::error::Result;
+use crate::xml::{RawXmlElement, RawXmlNode};
+use std::ops::RangeInclusive;
+
+/// Right tab stop for page numbers, in twips (a 6.5" text column)
+const PAGE_NUMBER_TAB: u32 = 9350;
+
+/// Page number shown in entries until Word updates the TOC
+const PLACEHOLDER_PAGE: &str = "1";
+
+/// Options for [`Document::insert_toc`]
+#[derive(Clone, Debug)]
+pub struct TocOptions {
+    /// Heading levels to include (`\o "1-3"`)
+    pub levels: RangeInclusive<u8>,
+    /// Make entries hyperlinks to their headings (`\h`)
+    pub hyperlinks: bool,
+    /// Hide tab leaders and page numbers in Web Layout view (`\z`)
+    pub hide_in_web_layout: bool,
+    /// Include paragraphs with an outline level (`\u`)
+    pub outline_levels: bool,
+    /// Show page numbers
+    pub page_numbers: bool,
+    /// Heading above the entries, in the "TOC Heading" style
+    pub title: Option<String>,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            levels: 1..=3,
+            hyperlinks: true,
+            hide_in_web_layout: true,
+            outline_levels: true,
+            page_numbers: true,
+            title: Some("Contents".into()),
+        }
+    }
+}
+
+impl TocOptions {
+    /// Field code for these options, e.g. `TOC \o "1-3" \h \z \u`
+    fn instruction(&self) -> String {
+        let mut code = format!("TOC \\o \"{}-{}\"", self.levels.start(), self.levels.end());
+        for (enabled, switch) in [
+            (self.hyperlinks, " \\h"),
+            (!self.page_numbers, " \\n"),
+            (self.hide_in_web_layout, " \\z"),
+            (self.outline_levels, " \\u"),
+        ] {
+            if enabled {
+                code.push_str(switch);
+            }
+        }
+        code
+    }
+}
+
+/// A heading listed in the TOC
+struct TocEntry {
+    level: u8,
+    text: String,
+    bookmark: String,
+}
+
+impl Document {
+    /// Insert a table of contents before the paragraph at `position`
+    ///
+    /// Every body heading within `options.levels`, including those in table
+    /// cells and block-level content controls, gets a `_Toc` bookmark
+    /// (existing ones are reused) and a pre-populated entry in the TOC1–TOC9
+    /// style of its level. Headings in text boxes are not listed, as in Word. Page numbers are placeholders, so `updateFields`
+    /// is turned on in the settings for Word to refresh the TOC on open.
+    ///
+    /// Returns the number of entries.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use linch_docx_rs::{Document, TocOptions};
+    ///
+    /// let mut doc = Document::open("report.docx")?;
+    /// doc.insert_toc(0, &TocOptions::default())?;
+    /// doc.save("report-with-toc.docx")?;
+    /// ```
+    pub fn insert_toc(&mut self, position: usize, options: &TocOptions) -> Result<usize> {
+        let entries = self.bookmark_headings(options);
+
+        let mut paragraphs = Vec::new();
+        if let Some(title) = &options.title {
+            let mut para = Paragraph::new(title.clone());
+            para.set_style("TOCHeading");
+            paragraphs.push(para);
+        }
+        paragraphs.extend(toc_paragraphs(&entries, options));
+
+        let sdt = Sdt {
+            properties: SdtProperties {
+                id: Some(self.next_content_control_id()),
+                kind: SdtKind::DocPart(SdtDocPart {
+                    gallery: Some("Table of Contents".into()),
+                    category: None,
+                    unique: true,
+                }),
+                ..Default::default()
+            },
+            end_properties: Some(RawXmlNode::Element(RawXmlElement {
+                self_closing: true,
+                ..RawXmlElement::new("w:sdtEndPr")
+            })),
+            content: SdtContent::Blocks(
+                paragraphs
+                    .into_iter()
+                    .map(|p| BlockContent::Paragraph(Box::new(p)))
+                    .collect(),
+            ),
+            unknown_children: Vec::new(),
+        };
+
+        let index = self
+            .body
+            .content
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| matches!(block, BlockContent::Paragraph(_)))
+            .nth(position)
+            .map_or(self.body.content.len(), |(i, _)| i);
+        self.body
+            .content
+            .insert(index, BlockContent::Sdt(Box::new(sdt)));
+        self.mark_body_modified();
+
+        let levels: Vec<u8> = entries.iter().map(|e| e.level).collect();
+        for level in options.levels.clone().filter(|l| (1..=9).contains(l)) {
+            if levels.contains(&level) {
+                self.ensure_toc_style(level);
+            }
+        }
+        if options.title.is_some() {
+            self.ensure_toc_heading_style();
+        }
+        self.settings_mut().update_fields = true;
+
+        Ok(entries.len())
+    }
+
+    /// Bookmark the body headings within the TOC levels
+    fn bookmark_headings(&mut self, options: &TocOptions) -> Vec<TocEntry> {
+        let mut next_id = self.next_bookmark_id();
+
+        let mut entries = Vec::new();
+        let mut changed = false;
+        for para in body_paragraphs_mut(&mut self.body.content) {
+            let Some(level) = para.heading_level() else {
+                continue;
+            };
+            let from_outline = para
+                .properties
+                .as_ref()
+                .is_some_and(|p| p.outline_level.is_some() && p.style.is_none());
+            if !options.levels.contains(&level) || (from_outline && !options.outline_levels) {
+                continue;
+            }
+
+            let existing = para.content.iter().find_map(|c| match c {
+                ParagraphContent::BookmarkStart { name, .. } if name.starts_with("_Toc") => {
+                    Some(name.clone())
+                }
+                _ => None,
+            });
+            let bookmark = match existing {
+                Some(name) => name,
+                None => {
+                    let id = next_id.to_string();
+                    let name = format!("_Toc{:09}", next_id);
+                    next_id += 1;
+                    para.content.insert(
+                        0,
+                        ParagraphContent::BookmarkStart {
+                            id: id.clone(),
+                            name: name.clone(),
+                        },
+                    );
+                    para.content.push(ParagraphContent::BookmarkEnd { id });
+                    changed = true;
+                    name
+                }
+            };
+            entries.push(TocEntry {
+                level,
+                text: para.text(),
+                bookmark,
+            });
+        }
+        if changed {
+            self.mark_body_modified();
+        }
+        entries
+    }
+
+    /// Smallest bookmark id above those used in any story of the document
+    fn next_bookmark_id(&self) -> u32 {
+        let mut ids = BookmarkIds::default();
+        ids.blocks(&self.body.content);
+        for (_, part) in self.headers().iter().chain(self.footers()) {
+            ids.paragraphs(&part.paragraphs, &part.unknown_children);
+        }
+        for notes in [self.footnotes(), self.endnotes()].into_iter().flatten() {
+            for note in &notes.notes {
+                ids.paragraphs(&note.paragraphs, &note.unknown_children);
+            }
+        }
+        if let Some(comments) = self.comments() {
+            for comment in &comments.comments {
+                ids.paragraphs(&comment.paragraphs, &comment.unknown_children);
+            }
+        }
+        ids.max.map_or(0, |id| id + 1)
+    }
+
+    /// Add the built-in "toc N" style unless the document defines it
+    fn ensure_toc_style(&mut self, level: u8) {
+        let style_id = format!("TOC{}", level);
+        if self.style(&style_id).is_some() {
+            return;
+        }
+        let mut style = Style {
+            style_type: Some(StyleType::Paragraph),
+            style_id,
+            name: Some(format!("toc {}", level)),
+            based_on: Some("Normal".into()),
+            next_style: Some("Normal".into()),
+            ui_priority: Some(39),
+            unhide_when_used: true,
+            ..Default::default()
+        };
+        let props = style
+            .paragraph_properties
+            .get_or_insert_with(Default::default);
+        props.spacing = Some(crate::document::LineSpacing {
+            after: Some(100),
+            ..Default::default()
+        });
+        if level > 1 {
+            props.indentation = Some(Indentation {
+                left: Some(220 * (level as i32 - 1)),
+                ..Default::default()
+            });
+        }
+        self.styles_mut().add(style);
+    }
+
+    /// Add the built-in "TOC Heading" style unless the document defines it
+    fn ensure_toc_heading_style(&mut self) {
+        if self.style("TOCHeading").is_some() {
+            return;
+        }
+        let mut style = Style {
+            style_type: Some(StyleType::Paragraph),
+            style_id: "TOCHeading".into(),
+            name: Some("TOC Heading".into()),
+            based_on: Some("Heading1".into()),
+            next_style: Some("Normal".into()),
+            ui_priority: Some(39),
+            unhide_when_used: true,
+            qformat: true,
+            ..Default::default()
+        };
+        // Based on Heading 1 for its look, but not listed in the TOC itself
+        style
+            .paragraph_properties
+            .get_or_insert_with(Default::default)
+            .outline_level = Some(9);
+        self.styles_mut().add(style);
+    }
+}
+
+/// Finds the highest bookmark id, including bookmarks in tables, content
+/// controls, hyperlinks, text boxes and preserved raw XML
+#[derive(Default)]
+struct BookmarkIds {
+    max: Option<u32>,
+}
+
+impl BookmarkIds {
+    fn add(&mut self, id: &str) {
+        if let Ok(id) = id.parse::<u32>() {
+            self.max = self.max.max(Some(id));
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[BlockContent]) {
+        for block in blocks {
+            match block {
+                BlockContent::Paragraph(p) => self.paragraphs(std::slice::from_ref(p), &[]),
+                BlockContent::Table(t) => {
+                    for row in &t.rows {
+                        for cell in &row.cells {
+                            self.paragraphs(&cell.paragraphs, &cell.unknown_children);
+                        }
+                        self.raw(&row.unknown_children);
+                    }
+                    self.raw(&t.unknown_children);
+                }
+                BlockContent::Sdt(sdt) => self.sdt(sdt),
+                BlockContent::Unknown(node) => self.raw(std::slice::from_ref(node)),
+            }
+        }
+    }
+
+    fn paragraphs(&mut self, paragraphs: &[Paragraph], unknown: &[RawXmlNode]) {
+        for para in paragraphs {
+            self.content(&para.content);
+            self.raw(&para.unknown_children);
+        }
+        self.raw(unknown);
+    }
+
+    fn sdt(&mut self, sdt: &Sdt) {
+        match &sdt.content {
+            SdtContent::Blocks(inner) => self.blocks(inner),
+            SdtContent::Inline(inner) => self.content(inner),
+            _ => {}
+        }
+    }
+
+    fn content(&mut self, content: &[ParagraphContent]) {
+        for item in content {
+            match item {
+                ParagraphContent::BookmarkStart { id, .. } => self.add(id),
+                ParagraphContent::Run(run) => {
+                    for item in &run.content {
+                        match item {
+                            RunContent::TextBox(text_box) => self.blocks(&text_box.content),
+                            RunContent::Unknown(node) => self.raw(std::slice::from_ref(node)),
+                            _ => {}
+                        }
+                    }
+                }
+                ParagraphContent::Hyperlink(link) => self.content(&link.content),
+                ParagraphContent::Revision(rev) => self.content(&rev.content),
+                ParagraphContent::SimpleField(field) => self.content(&field.content),
+                ParagraphContent::Sdt(sdt) => self.sdt(sdt),
+                ParagraphContent::Unknown(node) => self.raw(std::slice::from_ref(node)),
+                _ => {}
+            }
+        }
+    }
+
+    fn raw(&mut self, nodes: &[RawXmlNode]) {
+        for node in nodes {
+            if let RawXmlNode::Element(e) = node {
+                if e.name == "w:bookmarkStart" {
+                    if let Some((_, id)) = e.attributes.iter().find(|(name, _)| name == "w:id") {
+                        self.add(id);
+                    }
+                }
+                self.raw(&e.children);
+            }
+        }
+    }
+}
+
+/// Entry paragraphs, with the TOC field starting in the first one and ending
+/// in a paragraph of its own
+fn toc_paragraphs(entries: &[TocEntry], options: &TocOptions) -> Vec<Paragraph> {
+    let field_char = |kind| Run {
+        content: vec![RunContent::FieldChar(FieldChar::new(kind))],
+        ..Default::default()
+    };
+    let mut begin = FieldChar::new(FieldCharType::Begin);
+    begin.dirty = Some(true);
+    let field_start = [
+        Run {
+            content: vec![RunContent::FieldChar(begin)],
+            ..Default::default()
+        },
+        Run {
+            content: vec![RunContent::InstrText(format!(
+                " {} ",
+                options.instruction()
+            ))],
+            ..Default::default()
+        },
+        field_char(FieldCharType::Separate),
+    ];
+
+    let mut paragraphs = Vec::new();
+    if entries.is_empty() {
+        let mut para = Paragraph::default();
+        para.content
+            .extend(field_start.iter().cloned().map(ParagraphContent::Run));
+        para.add_run(Run::new("No table of contents entries found."));
+        paragraphs.push(para);
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let mut para = Paragraph::default();
+        para.set_style(format!("TOC{}", entry.level.min(9)));
+        let props = para.properties.get_or_insert_with(Default::default);
+        props.unknown_children.push(RawXmlNode::Element(
+            RawXmlElement::new("w:tabs").with_child(
+                RawXmlElement {
+                    self_closing: true,
+                    ..RawXmlElement::new("w:tab")
+                }
+                .with_attr("w:val", "right")
+                .with_attr("w:leader", "dot")
+                .with_attr("w:pos", PAGE_NUMBER_TAB.to_string()),
+            ),
+        ));
+        if i == 0 {
+            para.content
+                .extend(field_start.iter().cloned().map(ParagraphContent::Run));
+        }
+
+        let mut runs = vec![Run::new(entry.text.clone())];
+        if options.page_numbers {
+            runs.push(Run {
+                content: vec![RunContent::Tab],
+                ..Default::default()
+            });
+            let pageref = format!("PAGEREF {} \\h", entry.bookmark);
+            runs.extend(Field::complex_runs(pageref, PLACEHOLDER_PAGE));
+        }
+        if options.hyperlinks {
+            para.content.push(ParagraphContent::Hyperlink(Hyperlink {
+                r_id: None,
+                anchor: Some(entry.bookmark.clone()),
+                content: runs.into_iter().map(ParagraphContent::Run).collect(),
+            }));
+        } else {
+            para.content
+                .extend(runs.into_iter().map(ParagraphContent::Run));
+        }
+        paragraphs.push(para);
+    }
+
+    let mut end = Paragraph::default();
+    end.add_run(field_char(FieldCharType::End));
+    paragraphs.push(end);
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toc_instruction() {
+        let options = TocOptions::default();
+        assert_eq!(options.instruction(), r#"TOC \o "1-3" \h \z \u"#);
+
+        let options = TocOptions {
+            levels: 1..=2,
+            hyperlinks: false,
+            hide_in_web_layout: false,
+            outline_levels: false,
+            page_numbers: false,
+            title: None,
+        };
+        assert_eq!(options.instruction(), r#"TOC \o "1-2" \n"#);
+    }
+}
@@ -0,0 +1,392 @@
+//! Editing with tracked changes
+//!
+//! While change tracking is on, edits are recorded as `w:ins`/`w:del`
+//! revisions instead of overwriting content, so they can be reviewed in Word.
+
+use crate::document::{
+    BlockContent, Document, Paragraph, ParagraphContent, Revision, RevisionInfo, RevisionKind, Run,
+    RunContent,
+};
+
+/// Author, date and ID counter for new tracked changes
+#[derive(Clone, Debug)]
+pub struct RevisionTracker {
+    /// Author recorded on each change
+    pub author: String,
+    /// Date recorded on each change (ISO 8601)
+    pub date: Option<String>,
+    next_id: u32,
+}
+
+impl RevisionTracker {
+    /// Create a tracker for an author
+    pub fn new(author: impl Into<String>) -> Self {
+        Self {
+            author: author.into(),
+            date: None,
+            next_id: 1,
+        }
+    }
+
+    /// Set the date recorded on each change
+    pub fn with_date(mut self, date: impl Into<String>) -> Self {
+        self.date = Some(date.into());
+        self
+    }
+
+    /// Start numbering changes after existing IDs
+    pub fn with_first_id(mut self, id: u32) -> Self {
+        self.next_id = id;
+        self
+    }
+
+    /// Metadata for a new change, with a unique ID
+    pub fn next_info(&mut self) -> RevisionInfo {
+        let id = self.next_id;
+        self.next_id += 1;
+        RevisionInfo {
+            id: id.to_string(),
+            author: Some(self.author.clone()),
+            date: self.date.clone(),
+            unknown_attrs: Vec::new(),
+        }
+    }
+
+    fn revision(&mut self, kind: RevisionKind, content: Vec<ParagraphContent>) -> ParagraphContent {
+        ParagraphContent::Revision(Revision::new(kind, self.next_info(), content))
+    }
+}
+
+impl Paragraph {
+    /// Replace all text as a tracked change: the current runs are marked
+    /// deleted and the new text inserted (hyperlinks are kept)
+    pub fn set_text_tracked(&mut self, text: impl Into<String>, tracker: &mut RevisionTracker) {
+        let properties = self.runs().next().and_then(|r| r.properties.clone());
+        let mut kept = Vec::new();
+        let mut removed = Vec::new();
+        let mut position = None;
+        for item in std::mem::take(&mut self.content) {
+            if matches!(
+                item,
+                ParagraphContent::Run(_) | ParagraphContent::Revision(_)
+            ) {
+                position.get_or_insert(kept.len());
+                removed.push(item);
+            } else {
+                kept.push(item);
+            }
+        }
+
+        let mut replacement = delete_content(removed, tracker);
+        let text = text.into();
+        if !text.is_empty() {
+            let mut run = Run::new(text);
+            run.properties = properties;
+            replacement
+                .push(tracker.revision(RevisionKind::Insertion, vec![ParagraphContent::Run(run)]));
+        }
+        let position = position.unwrap_or(kept.len());
+        kept.splice(position..position, replacement);
+        self.content = kept;
+    }
+
+    /// Add a run as a tracked insertion
+    pub fn add_run_tracked(&mut self, run: Run, tracker: &mut RevisionTracker) {
+        let revision = tracker.revision(RevisionKind::Insertion, vec![ParagraphContent::Run(run)]);
+        self.content.push(revision);
+    }
+
+    /// Mark a run (by index among plain runs) as a tracked deletion
+    pub fn remove_run_tracked(&mut self, index: usize, tracker: &mut RevisionTracker) -> bool {
+        let position = self
+            .content
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| matches!(c, ParagraphContent::Run(_)))
+            .nth(index)
+            .map(|(i, _)| i);
+        let Some(position) = position else {
+            return false;
+        };
+        let run = self.content.remove(position);
+        let deleted = delete_content(vec![run], tracker);
+        self.content.splice(position..position, deleted);
+        true
+    }
+
+    /// Replace text in plain runs as tracked deletions and insertions
+    pub fn replace_text_tracked(
+        &mut self,
+        find: &str,
+        replace: &str,
+        tracker: &mut RevisionTracker,
+    ) -> usize {
+        if find.is_empty() {
+            return 0;
+        }
+        let mut count = 0;
+        let mut content = Vec::with_capacity(self.content.len());
+        for item in std::mem::take(&mut self.content) {
+            match item {
+                ParagraphContent::Run(run) if run.text().contains(find) => {
+                    count += replace_in_run(run, find, replace, tracker, &mut content);
+                }
+                other => content.push(other),
+            }
+        }
+        self.content = content;
+        count
+    }
+
+    /// Mark the whole paragraph, including its mark, as a tracked insertion
+    pub fn track_insertion(&mut self, tracker: &mut RevisionTracker) {
+        let content = std::mem::take(&mut self.content);
+        if !content.is_empty() {
+            self.content
+                .push(tracker.revision(RevisionKind::Insertion, content));
+        }
+        self.set_mark_revision(RevisionKind::Insertion, tracker);
+    }
+
+    /// Mark the whole paragraph, including its mark, as a tracked deletion
+    pub fn track_deletion(&mut self, tracker: &mut RevisionTracker) {
+        let content = std::mem::take(&mut self.content);
+        self.content = delete_content(content, tracker);
+        self.set_mark_revision(RevisionKind::Deletion, tracker);
+    }
+
+    fn set_mark_revision(&mut self, kind: RevisionKind, tracker: &mut RevisionTracker) {
+        let mark = Revision::new(kind, tracker.next_info(), Vec::new());
+        self.properties
+            .get_or_insert_with(Default::default)
+            .run_properties
+            .get_or_insert_with(Default::default)
+            .mark_revision = Some(Box::new(mark));
+    }
+}
+
+/// Wrap content in tracked deletions
+///
+/// Consecutive runs share one `w:del`; existing deletions are left alone and
+/// content of existing insertions is deleted inside the insertion.
+fn delete_content(
+    content: Vec<ParagraphContent>,
+    tracker: &mut RevisionTracker,
+) -> Vec<ParagraphContent> {
+    let mut result = Vec::new();
+    let mut runs = Vec::new();
+    for item in content {
+        match item {
+            ParagraphContent::Run(mut run) => {
+                mark_text_deleted(&mut run);
+                runs.push(ParagraphContent::Run(run));
+            }
+            ParagraphContent::Revision(mut rev) if rev.kind.is_addition() => {
+                flush_deleted(&mut runs, &mut result, tracker);
+                rev.content = delete_content(std::mem::take(&mut rev.content), tracker);
+                result.push(ParagraphContent::Revision(rev));
+            }
+            other => {
+                flush_deleted(&mut runs, &mut result, tracker);
+                result.push(other);
+            }
+        }
+    }
+    flush_deleted(&mut runs, &mut result, tracker);
+    result
+}
+
+fn flush_deleted(
+    runs: &mut Vec<ParagraphContent>,
+    result: &mut Vec<ParagraphContent>,
+    tracker: &mut RevisionTracker,
+) {
+    if !runs.is_empty() {
+        result.push(tracker.revision(RevisionKind::Deletion, std::mem::take(runs)));
+    }
+}
+
+fn mark_text_deleted(run: &mut Run) {
+    for item in &mut run.content {
+        if let RunContent::Text(text) = item {
+            *item = RunContent::DeletedText(std::mem::take(text));
+        }
+    }
+}
+
+/// Split a run around each match of `find`, recording a deletion of the
+/// match and an insertion of `replace`
+fn replace_in_run(
+    run: Run,
+    find: &str,
+    replace: &str,
+    tracker: &mut RevisionTracker,
+    out: &mut Vec<ParagraphContent>,
+) -> usize {
+    let piece = |content: Vec<RunContent>| Run {
+        properties: run.properties.clone(),
+        content,
+        unknown_attrs: run.unknown_attrs.clone(),
+        unknown_children: Vec::new(),
+    };
+    let mut count = 0;
+    let mut current = Vec::new();
+
+    for item in run.content.iter().cloned() {
+        let RunContent::Text(text) = item else {
+            current.push(item);
+            continue;
+        };
+        let mut rest = text.as_str();
+        while let Some(pos) = rest.find(find) {
+            if pos > 0 {
+                current.push(RunContent::Text(rest[..pos].to_string()));
+            }
+            if !current.is_empty() {
+                out.push(ParagraphContent::Run(piece(std::mem::take(&mut current))));
+            }
+            let deleted = piece(vec![RunContent::DeletedText(find.to_string())]);
+            out.push(
+                tracker.revision(RevisionKind::Deletion, vec![ParagraphContent::Run(deleted)]),
+            );
+            if !replace.is_empty() {
+                let inserted = piece(vec![RunContent::Text(replace.to_string())]);
+                out.push(tracker.revision(
+                    RevisionKind::Insertion,
+                    vec![ParagraphContent::Run(inserted)],
+                ));
+            }
+            rest = &rest[pos + find.len()..];
+            count += 1;
+        }
+        if !rest.is_empty() {
+            current.push(RunContent::Text(rest.to_string()));
+        }
+    }
+    if !current.is_empty() || !run.unknown_children.is_empty() {
+        let mut last = piece(current);
+        last.unknown_children = run.unknown_children;
+        out.push(ParagraphContent::Run(last));
+    }
+    count
+}
+
+impl Document {
+    /// Record subsequent edits as tracked changes by `author`
+    ///
+    /// Affects [`add_paragraph`](Self::add_paragraph),
+    /// [`insert_paragraph`](Self::insert_paragraph),
+    /// [`remove_paragraph`](Self::remove_paragraph),
+    /// [`set_paragraph_text`](Self::set_paragraph_text),
+    /// [`replace_text`](Self::replace_text) and
+    /// [`fill_template`](Self::fill_template).
+    pub fn track_changes(&mut self, author: impl Into<String>, date: Option<&str>) {
+        let first_id = self
+            .revisions()
+            .iter()
+            .filter_map(|r| r.info.id.parse::<u32>().ok())
+            .max()
+            .map_or(1, |id| id + 1);
+        let mut tracker = RevisionTracker::new(author).with_first_id(first_id);
+        tracker.date = date.map(str::to_string);
+        self.tracker = Some(tracker);
+    }
+
+    /// Stop recording tracked changes, returning the tracker
+    pub fn stop_tracking_changes(&mut self) -> Option<RevisionTracker> {
+        self.tracker.take()
+    }
+
+    /// Get the tracker while change tracking is on
+    pub fn revision_tracker(&self) -> Option<&RevisionTracker> {
+        self.tracker.as_ref()
+    }
+
+    /// Get a paragraph together with the tracker, for tracked edits such as
+    /// [`Paragraph::remove_run_tracked`]
+    pub fn paragraph_mut_tracked(
+        &mut self,
+        index: usize,
+    ) -> Option<(&mut Paragraph, &mut RevisionTracker)> {
+        self.tracker.as_ref()?;
+        self.mark_body_modified();
+        let para = self
+            .body
+            .content
+            .iter_mut()
+            .filter_map(|c| match c {
+                BlockContent::Paragraph(p) => Some(p.as_mut()),
+                _ => None,
+            })
+            .nth(index)?;
+        Some((para, self.tracker.as_mut()?))
+    }
+
+    /// Replace the text of a paragraph, tracked when change tracking is on
+    pub fn set_paragraph_text(&mut self, index: usize, text: impl Into<String>) -> bool {
+        let text = text.into();
+        if let Some((para, tracker)) = self.paragraph_mut_tracked(index) {
+            para.set_text_tracked(text, tracker);
+            return true;
+        }
+        match self.paragraph_mut(index) {
+            Some(para) => {
+                para.set_text(text);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_text_tracked() {
+        let mut tracker = RevisionTracker::new("Bot").with_date("2024-05-01T00:00:00Z");
+        let mut para = Paragraph::new("Dear NAME, hello NAME");
+        assert_eq!(para.replace_text_tracked("NAME", "Ann", &mut tracker), 2);
+        assert_eq!(para.text(), "Dear Ann, hello Ann");
+
+        let ids: Vec<_> = para
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                ParagraphContent::Revision(rev) => Some(rev.info.id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, ["1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn test_set_text_tracked() {
+        let mut tracker = RevisionTracker::new("Bot");
+        let mut para = Paragraph::new("Old");
+        para.add_bookmark("0", "mark");
+        para.set_text_tracked("New", &mut tracker);
+        assert_eq!(para.text(), "New");
+        assert!(matches!(
+            &para.content[0],
+            ParagraphContent::Revision(rev) if rev.kind == RevisionKind::Deletion
+                && rev.changed_text() == "Old"
+        ));
+        assert!(matches!(
+            para.content.last(),
+            Some(ParagraphContent::BookmarkEnd { .. })
+        ));
+    }
+
+    #[test]
+    fn test_remove_run_tracked() {
+        let mut tracker = RevisionTracker::new("Bot");
+        let mut para = Paragraph::new("Keep");
+        para.add_run(Run::new("Drop"));
+        assert!(para.remove_run_tracked(1, &mut tracker));
+        assert!(!para.remove_run_tracked(1, &mut tracker));
+        assert_eq!(para.text(), "Keep");
+        assert_eq!(para.content.len(), 2);
+    }
+}
@@ -0,0 +1,236 @@
+//! Streaming writer for very large documents
+//!
+//! Body content is spooled to a temporary file as it is written, so memory
+//! use does not grow with the body. Styles, numbering, images and
+//! properties are kept in memory. [`DocumentWriter::finish`] writes
+//! `[Content_Types].xml` first, then copies the body into
+//! `word/document.xml` and writes the remaining parts, giving the same
+//! entry order as [`Package::write_to`](crate::Package::write_to).
+
+use crate::document::anchored_image::DrawingIds;
+use crate::document::xml_ops::{write_document_end, write_document_start};
+use crate::document::{
+    loaders, CoreProperties, Document, ImageData, Numbering, Paragraph, SectionProperties, Styles,
+    Table,
+};
+use crate::error::{Error, Result};
+use crate::opc::PartUri;
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::Writer;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zip::write::{SimpleFileOptions, ZipWriter};
+use zip::{CompressionMethod, DateTime};
+
+/// Incremental document writer
+///
+/// [`DocumentWriter::finish`] must be called to complete the file.
+///
+/// # Example
+/// ```rust,ignore
+/// use linch_docx_rs::{DocumentWriter, Paragraph};
+///
+/// let mut writer = DocumentWriter::create("report.docx")?;
+/// for i in 0..200_000 {
+///     writer.write_paragraph(&Paragraph::new(format!("Row {}", i)))?;
+/// }
+/// writer.finish()?;
+/// ```
+pub struct DocumentWriter<W: Write + Seek> {
+    /// Destination of the finished archive
+    writer: W,
+    /// XML writer over the spooled body
+    xml: Writer<BufWriter<File>>,
+    /// Removes the spool file when the writer is dropped
+    _spool: Spool,
+    /// Holds every part except the body
+    doc: Document,
+    /// URI of the main document part
+    doc_uri: PartUri,
+    /// Final section properties, written as the last child of `w:body`
+    section: Option<SectionProperties>,
+    /// Hands out drawing object IDs to new floating images and text boxes
+    drawing_ids: DrawingIds,
+}
+
+impl DocumentWriter<BufWriter<File>> {
+    /// Create a file and start writing a document to it
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> DocumentWriter<W> {
+    /// Start writing a document to `writer`
+    pub fn new(writer: W) -> Result<Self> {
+        let mut doc = Document::new();
+        // Creates the main document part and its relationship
+        doc.update_package()?;
+        let doc_uri = loaders::main_document_uri(&doc.package)
+            .ok_or_else(|| Error::MissingPart("Main document part not found".into()))?;
+
+        let (spool, file) = Spool::create()?;
+        let mut xml = Writer::new(BufWriter::new(file));
+        write_document_start(&mut xml, &doc.root)?;
+        xml.write_event(Event::Start(BytesStart::new("w:body")))?;
+
+        Ok(Self {
+            writer,
+            xml,
+            _spool: spool,
+            doc,
+            doc_uri,
+            section: None,
+            // New inline images are written with ID 1
+            drawing_ids: DrawingIds::starting_at(2),
+        })
+    }
+
+    /// Write a paragraph to the body
+    ///
+    /// Floating images and text boxes without a drawing object ID get one
+    /// above those written so far.
+    pub fn write_paragraph(&mut self, para: &Paragraph) -> Result<()> {
+        let mut para = para.clone();
+        self.drawing_ids.content(&mut para.content);
+        para.write_to(&mut self.xml)
+    }
+
+    /// Write a table to the body
+    ///
+    /// Drawing object IDs are handed out as for [`DocumentWriter::write_paragraph`].
+    pub fn write_table(&mut self, table: &Table) -> Result<()> {
+        let mut table = table.clone();
+        self.drawing_ids.table(&mut table);
+        table.write_to(&mut self.xml)
+    }
+
+    /// Write a paragraph with plain text
+    pub fn write_text(&mut self, text: &str) -> Result<()> {
+        self.write_paragraph(&Paragraph::new(text))
+    }
+
+    /// Set the section properties written at the end of the body
+    pub fn set_section_properties(&mut self, props: SectionProperties) {
+        self.section = Some(props);
+    }
+
+    /// Get mutable styles (creates empty styles if none)
+    pub fn styles_mut(&mut self) -> &mut Styles {
+        self.doc.styles_mut()
+    }
+
+    /// Get mutable numbering definitions (creates empty numbering if none)
+    pub fn numbering_mut(&mut self) -> &mut Numbering {
+        self.doc
+            .numbering
+            .get_mut(&self.doc.package)
+            .get_or_insert_with(Numbering::default)
+    }
+
+    /// Get mutable core properties (creates default if none)
+    pub fn core_properties_mut(&mut self) -> &mut CoreProperties {
+        self.doc.core_properties_mut()
+    }
+
+    /// Add an image to the package, returning its relationship ID
+    pub fn add_image(&mut self, image: ImageData) -> String {
+        self.doc.add_image(image)
+    }
+
+    /// Add an SVG image and its raster fallback, returning both relationship IDs
+    pub fn add_svg_image(&mut self, image: ImageData) -> Result<(String, String)> {
+        self.doc.add_svg_image(image)
+    }
+
+    /// Close the body and write the remaining parts, returning the writer
+    pub fn finish(mut self) -> Result<W> {
+        if let Some(sect_pr) = &self.section {
+            sect_pr.write_to(&mut self.xml)?;
+        }
+        self.xml.write_event(Event::End(BytesEnd::new("w:body")))?;
+        write_document_end(&mut self.xml, &self.doc.root)?;
+        let mut body = self
+            .xml
+            .into_inner()
+            .into_inner()
+            .map_err(|e| e.into_error())?;
+        body.seek(SeekFrom::Start(0))?;
+
+        self.doc.update_package()?;
+        let package = &self.doc.package;
+        let mut zip = ZipWriter::new(self.writer);
+        let mut written = HashSet::new();
+        package.write_content_types(&mut zip, &mut written)?;
+
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default());
+        zip.start_file(&self.doc_uri.as_str()[1..], options)?;
+        io::copy(&mut body, &mut zip)?;
+        written.insert(self.doc_uri.as_str()[1..].to_string());
+        package.write_part_rels(&mut zip, &self.doc_uri, &mut written)?;
+        package.write_entries(&mut zip, written)?;
+        Ok(zip.finish()?)
+    }
+}
+
+/// Temporary file holding the body until the archive is written
+struct Spool {
+    path: PathBuf,
+}
+
+impl Spool {
+    /// Create a new, empty spool file in the system temporary directory
+    fn create() -> Result<(Self, File)> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir();
+        loop {
+            let name = format!(
+                "linch-docx-{}-{}.xml",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = dir.join(name);
+            match OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(file) => return Ok((Self { path }, file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_paragraphs_and_table() {
+        let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.write_text("First").unwrap();
+        writer.write_table(&Table::new(2, 2)).unwrap();
+        writer.write_text("Last").unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let doc = Document::from_bytes(&bytes).unwrap();
+        assert_eq!(doc.paragraph_count(), 2);
+        assert_eq!(doc.table_count(), 1);
+        assert_eq!(doc.paragraph(1).unwrap().text(), "Last");
+    }
+}
@@ -2,18 +2,31 @@
 
 use crate::document::Body;
 use crate::error::{Error, Result};
-use crate::xml;
+use crate::xml::{self, RawXmlElement, RawXmlNode};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
-use std::io::{BufRead, Cursor};
+use std::io::Cursor;
+
+/// Root `w:document` element state, preserved for round-trip
+#[derive(Clone, Debug, Default)]
+pub struct DocumentRoot {
+    /// Root attributes in original order (namespace declarations, `mc:Ignorable`, ...)
+    pub attributes: Vec<(String, String)>,
+    /// Sibling elements before `w:body` (e.g. `w:background`)
+    pub before_body: Vec<RawXmlNode>,
+    /// Sibling elements after `w:body`
+    pub after_body: Vec<RawXmlNode>,
+}
 
 /// Parse document.xml content
-pub fn parse_document_xml(xml_str: &str) -> Result<Body> {
+pub fn parse_document_xml(xml_str: &str) -> Result<(Body, DocumentRoot)> {
     let mut reader = Reader::from_str(xml_str);
-    reader.config_mut().trim_text(true);
+    // Whitespace in w:t is significant, so text is not trimmed
+    reader.config_mut().trim_text(false);
 
     let mut buf = Vec::new();
     let mut body = None;
+    let mut root = DocumentRoot::default();
 
     loop {
         match reader.read_event_into(&mut buf)? {
@@ -23,9 +36,23 @@ pub fn parse_document_xml(xml_str: &str) -> Result<Body> {
                     b"body" => {
                         body = Some(Body::from_reader(&mut reader)?);
                     }
-                    b"document" => {}
+                    b"document" => {
+                        root.attributes = xml::read_attributes(&e);
+                    }
+                    _ => {
+                        let raw = RawXmlElement::from_reader(&mut reader, &e)?;
+                        root.push_sibling(RawXmlNode::Element(raw), body.is_some());
+                    }
+                }
+            }
+            Event::Empty(e) => {
+                let local = e.name().local_name();
+                match local.as_ref() {
+                    b"body" => body = Some(Body::default()),
+                    b"document" => root.attributes = xml::read_attributes(&e),
                     _ => {
-                        skip_element(&mut reader, &e)?;
+                        let raw = RawXmlElement::from_empty(&e);
+                        root.push_sibling(RawXmlNode::Element(raw), body.is_some());
                     }
                 }
             }
@@ -35,14 +62,31 @@ pub fn parse_document_xml(xml_str: &str) -> Result<Body> {
         buf.clear();
     }
 
-    body.ok_or_else(|| Error::InvalidDocument("Missing w:body element".into()))
+    let body = body.ok_or_else(|| Error::InvalidDocument("Missing w:body element".into()))?;
+    Ok((body, root))
 }
 
 /// Serialize body to document.xml content
-pub fn serialize_document_xml(body: &Body) -> Result<String> {
+///
+/// The original root attributes and `w:body` siblings are written back verbatim.
+/// Namespaces the serializer relies on are declared if the source lacked them.
+pub fn serialize_document_xml(body: &Body, root: &DocumentRoot) -> Result<String> {
     let mut buffer = Cursor::new(Vec::new());
     let mut writer = Writer::new(&mut buffer);
 
+    write_document_start(&mut writer, root)?;
+    body.write_to(&mut writer)?;
+    write_document_end(&mut writer, root)?;
+
+    let xml_bytes = buffer.into_inner();
+    String::from_utf8(xml_bytes).map_err(|e| Error::InvalidDocument(e.to_string()))
+}
+
+/// Write the XML declaration, `w:document` start tag and siblings before `w:body`
+pub fn write_document_start<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    root: &DocumentRoot,
+) -> Result<()> {
     writer.write_event(Event::Decl(BytesDecl::new(
         "1.0",
         Some("UTF-8"),
@@ -50,41 +94,43 @@ pub fn serialize_document_xml(body: &Body) -> Result<String> {
     )))?;
 
     let mut doc_start = BytesStart::new("w:document");
+    for (attr, value) in &root.attributes {
+        doc_start.push_attribute((attr.as_str(), value.as_str()));
+    }
     for (attr, value) in xml::document_namespaces() {
-        doc_start.push_attribute((attr, value));
+        if !root.attributes.iter().any(|(k, _)| k == attr) {
+            doc_start.push_attribute((attr, value));
+        }
     }
     writer.write_event(Event::Start(doc_start))?;
 
-    body.write_to(&mut writer)?;
+    for node in &root.before_body {
+        node.write_to(writer)?;
+    }
+    Ok(())
+}
 
+/// Write the siblings after `w:body` and the `w:document` end tag
+pub fn write_document_end<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    root: &DocumentRoot,
+) -> Result<()> {
+    for node in &root.after_body {
+        node.write_to(writer)?;
+    }
     writer.write_event(Event::End(BytesEnd::new("w:document")))?;
-
-    let xml_bytes = buffer.into_inner();
-    String::from_utf8(xml_bytes).map_err(|e| Error::InvalidDocument(e.to_string()))
+    Ok(())
 }
 
-/// Skip an element and all its children
-fn skip_element<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<()> {
-    let target = start.name().as_ref().to_vec();
-    let mut depth = 1;
-    let mut buf = Vec::new();
-
-    loop {
-        match reader.read_event_into(&mut buf)? {
-            Event::Start(e) if e.name().as_ref() == target => depth += 1,
-            Event::End(e) if e.name().as_ref() == target => {
-                depth -= 1;
-                if depth == 0 {
-                    break;
-                }
-            }
-            Event::Eof => break,
-            _ => {}
+impl DocumentRoot {
+    /// Record a sibling of `w:body` on the correct side
+    fn push_sibling(&mut self, node: RawXmlNode, after_body: bool) {
+        if after_body {
+            self.after_body.push(node);
+        } else {
+            self.before_body.push(node);
         }
-        buf.clear();
     }
-
-    Ok(())
 }
 
 #[cfg(test)]
@@ -115,7 +161,7 @@ mod tests {
 
     #[test]
     fn test_parse_simple_document() {
-        let body = parse_document_xml(SIMPLE_DOC).unwrap();
+        let (body, _) = parse_document_xml(SIMPLE_DOC).unwrap();
 
         let paras: Vec<_> = body.paragraphs().collect();
         assert_eq!(paras.len(), 2);
@@ -147,7 +193,7 @@ mod tests {
   </w:body>
 </w:document>"#;
 
-        let body = parse_document_xml(xml).unwrap();
+        let (body, _) = parse_document_xml(xml).unwrap();
         let para = body.paragraphs().next().unwrap();
         let run = para.runs().next().unwrap();
 
@@ -156,4 +202,44 @@ mod tests {
         assert_eq!(run.font_size_pt(), Some(14.0));
         assert_eq!(run.color(), Some("FF0000"));
     }
+
+    #[test]
+    fn test_preserve_root_namespaces_and_siblings() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml" mc:Ignorable="w14">
+  <w:background w:color="FFFF00"/>
+  <w:body>
+    <w:p w14:paraId="1A2B3C4D"><w:r><w:t>Hi</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+
+        let (body, root) = parse_document_xml(xml).unwrap();
+        assert_eq!(root.attributes[0].0, "xmlns:mc");
+        assert_eq!(root.before_body.len(), 1);
+
+        let output = serialize_document_xml(&body, &root).unwrap();
+        assert!(
+            output.contains(r#"xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml""#)
+        );
+        assert!(output.contains(r#"mc:Ignorable="w14""#));
+        assert!(output.contains(r#"w14:paraId="1A2B3C4D""#));
+        // Required namespaces are added when the source lacks them
+        assert!(output.contains("xmlns:r="));
+        assert_eq!(output.matches("xmlns:w=").count(), 1);
+        // w:background stays ahead of w:body
+        let bg = output.find("<w:background").unwrap();
+        assert!(bg < output.find("<w:body").unwrap());
+    }
+
+    #[test]
+    fn test_root_attribute_with_entity() {
+        let xml = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:x="urn:x" x:foo="a&amp;b"><w:body/></w:document>"#;
+        let (body, root) = parse_document_xml(xml).unwrap();
+        assert_eq!(root.attributes[2], ("x:foo".to_string(), "a&b".to_string()));
+
+        let output = serialize_document_xml(&body, &root).unwrap();
+        assert!(output.contains(r#"x:foo="a&amp;b""#));
+        let (_, again) = parse_document_xml(&output).unwrap();
+        assert_eq!(again.attributes[2], root.attributes[2]);
+    }
 }
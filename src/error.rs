@@ -47,11 +47,35 @@ pub enum Error {
     #[error("Index out of bounds: {index} (max: {max})")]
     IndexOutOfBounds { index: usize, max: usize },
 
+    #[error("Document is encrypted; open it with a password")]
+    Encrypted,
+
+    #[error("Wrong password")]
+    WrongPassword,
+
+    #[error("Cryptography error: {0}")]
+    Crypto(String),
+
     #[error("Unsupported feature: {0}")]
     Unsupported(String),
 
     #[error("Element not found: {0}")]
     NotFound(String),
+
+    #[error("Package exceeds the total size limit of {limit} bytes")]
+    PackageTooLarge { limit: u64 },
+
+    #[error("Part '{name}' exceeds the size limit of {limit} bytes")]
+    PartTooLarge { name: String, limit: u64 },
+
+    #[error("Package has {count} entries (limit: {limit})")]
+    TooManyEntries { count: usize, limit: usize },
+
+    #[error("Part '{name}' exceeds the compression ratio limit of {limit}")]
+    CompressionRatioExceeded { name: String, limit: u64 },
+
+    #[error("Part '{name}' exceeds the XML nesting depth limit of {limit}")]
+    XmlTooDeep { name: String, limit: usize },
 }
 
 /// Result type alias
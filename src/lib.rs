@@ -34,11 +34,16 @@ pub mod units;
 pub mod xml;
 
 pub use document::{
-    Alignment, Comment, Comments, CoreProperties, DocDefaults, Document, HeaderFooter, ImageData,
-    Indentation, InlineImage, LineSpacing, Note, Notes, PageMargin, PageOrientation, PageSize,
-    Paragraph, Run, RunContent, SectionProperties, Style, StyleType, Styles, Table, TableAlignment,
-    TableCell, TableRow, TableWidth, TemplateContext, TextLocation, VerticalAlignment,
+    Alignment, AnchorPosition, AnchoredImage, Comment, CommentRange, Comments, ContentControl,
+    CoreProperties, CustomProperties, DocDefaults, Document, DocumentProtection, DocumentWriter,
+    EditRestriction, ExtendedProperties, Field, FieldContext, FieldDisplay, FieldInstruction,
+    HeaderFooter, ImageData, ImageFormat, ImageInfo, ImageLocation, Indentation, InlineImage,
+    LineSpacing, Note, Notes, PageMargin, PageOrientation, PageSize, Paragraph, PermStart,
+    Placement, PositionAlign, PropertyValue, RelativeFrom, Revision, RevisionInfo, RevisionKind,
+    Run, RunContent, Sdt, SdtKind, SectionProperties, Settings, Style, StyleType, Styles, SvgBlip,
+    Table, TableAlignment, TableCell, TableRow, TableWidth, TemplateContext, TextBox, TextLocation,
+    TocOptions, TrackedChange, VerticalAlignment, WrapMode, WrapSide,
 };
 pub use error::{Error, Result};
-pub use opc::{Package, Part, PartUri};
+pub use opc::{CustomXmlPart, LoadOptions, Package, Part, PartUri};
 pub use units::{Cm, Emu, HalfPt, Inch, Mm, Pt, Twip};
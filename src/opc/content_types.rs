@@ -16,6 +16,8 @@ pub struct ContentTypes {
     defaults: HashMap<String, String>,
     /// Override mappings (part URI -> content type)
     overrides: HashMap<PartUri, String>,
+    /// Whether mappings changed since parsing
+    modified: bool,
 }
 
 impl ContentTypes {
@@ -105,8 +107,10 @@ impl ContentTypes {
         types.push_attribute(("xmlns", NS_CONTENT_TYPES));
         xml.write_event(Event::Start(types))?;
 
-        // Default elements
-        for (ext, content_type) in &self.defaults {
+        // Default elements (sorted for deterministic output)
+        let mut defaults: Vec<_> = self.defaults.iter().collect();
+        defaults.sort();
+        for (ext, content_type) in defaults {
             let mut default = BytesStart::new("Default");
             default.push_attribute(("Extension", ext.as_str()));
             default.push_attribute(("ContentType", content_type.as_str()));
@@ -114,7 +118,9 @@ impl ContentTypes {
         }
 
         // Override elements
-        for (uri, content_type) in &self.overrides {
+        let mut overrides: Vec<_> = self.overrides.iter().collect();
+        overrides.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        for (uri, content_type) in overrides {
             let mut override_elem = BytesStart::new("Override");
             override_elem.push_attribute(("PartName", uri.as_str()));
             override_elem.push_attribute(("ContentType", content_type.as_str()));
@@ -128,13 +134,20 @@ impl ContentTypes {
 
     /// Add a default extension mapping
     pub fn add_default(&mut self, extension: &str, content_type: &str) {
-        self.defaults
+        let previous = self
+            .defaults
             .insert(extension.to_lowercase(), content_type.to_string());
+        if previous.as_deref() != Some(content_type) {
+            self.modified = true;
+        }
     }
 
     /// Add an override for a specific part
     pub fn add_override(&mut self, uri: &PartUri, content_type: &str) {
-        self.overrides.insert(uri.clone(), content_type.to_string());
+        let previous = self.overrides.insert(uri.clone(), content_type.to_string());
+        if previous.as_deref() != Some(content_type) {
+            self.modified = true;
+        }
     }
 
     /// Get the content type for a part
@@ -152,7 +165,16 @@ impl ContentTypes {
 
     /// Remove an override
     pub fn remove_override(&mut self, uri: &PartUri) -> Option<String> {
-        self.overrides.remove(uri)
+        let removed = self.overrides.remove(uri);
+        if removed.is_some() {
+            self.modified = true;
+        }
+        removed
+    }
+
+    /// Check if mappings changed since parsing
+    pub fn is_modified(&self) -> bool {
+        self.modified
     }
 }
 
@@ -182,7 +204,13 @@ pub const NUMBERING: &str =
     "application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml";
 pub const STYLES: &str =
     "application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml";
+pub const SETTINGS: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml";
 pub const CORE_PROPERTIES: &str = "application/vnd.openxmlformats-package.core-properties+xml";
+pub const EXTENDED_PROPERTIES: &str =
+    "application/vnd.openxmlformats-officedocument.extended-properties+xml";
+pub const CUSTOM_PROPERTIES: &str =
+    "application/vnd.openxmlformats-officedocument.custom-properties+xml";
 pub const HEADER: &str =
     "application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml";
 pub const FOOTER: &str =
@@ -193,6 +221,14 @@ pub const ENDNOTES: &str =
     "application/vnd.openxmlformats-officedocument.wordprocessingml.endnotes+xml";
 pub const COMMENTS: &str =
     "application/vnd.openxmlformats-officedocument.wordprocessingml.comments+xml";
+pub const COMMENTS_EXTENDED: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.commentsExtended+xml";
+pub const COMMENTS_IDS: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.commentsIds+xml";
+pub const PEOPLE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.people+xml";
+pub const CUSTOM_XML_PROPERTIES: &str =
+    "application/vnd.openxmlformats-officedocument.customXmlProperties+xml";
 
 #[cfg(test)]
 mod tests {
@@ -228,6 +264,22 @@ mod tests {
         assert_eq!(ct2.get(&doc_uri), Some(MAIN_DOCUMENT));
     }
 
+    #[test]
+    fn test_modified_only_on_change() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+</Types>"#;
+
+        let mut ct = ContentTypes::from_xml(xml).unwrap();
+        let doc_uri = PartUri::new("/word/document.xml").unwrap();
+        ct.add_override(&doc_uri, MAIN_DOCUMENT);
+        assert!(!ct.is_modified());
+
+        ct.add_override(&PartUri::new("/word/styles.xml").unwrap(), STYLES);
+        assert!(ct.is_modified());
+    }
+
     #[test]
     fn test_get_by_extension() {
         let ct = ContentTypes::new();
@@ -0,0 +1,336 @@
+//! Custom XML data parts (customXml/itemN.xml)
+//!
+//! Each data part is related to a properties part (itemPropsN.xml) whose
+//! `ds:itemID` is the datastore ID content control data bindings refer to
+//! through `w:storeItemID`.
+
+use crate::error::{Error, Result};
+use crate::opc::content_types::{CUSTOM_XML_PROPERTIES, XML};
+use crate::opc::relationships::rel_types;
+use crate::opc::{Package, Part, PartUri};
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Custom XML properties namespace
+const NS_DATASTORE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/customXml";
+
+/// A custom XML data part with its datastore properties
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomXmlPart {
+    /// URI of the data part, e.g. `/customXml/item1.xml`
+    pub uri: PartUri,
+    /// URI of the properties part, e.g. `/customXml/itemProps1.xml`
+    pub props_uri: Option<PartUri>,
+    /// Datastore item ID, e.g. `{6E5B4B3A-...}`
+    pub item_id: Option<String>,
+    /// Schema namespaces listed in the properties part
+    pub schema_refs: Vec<String>,
+    /// XML content of the data part
+    pub xml: String,
+}
+
+impl CustomXmlPart {
+    /// Check whether this part has a datastore item ID
+    ///
+    /// IDs are compared case-insensitively, with or without braces.
+    pub fn has_item_id(&self, item_id: &str) -> bool {
+        self.item_id
+            .as_deref()
+            .is_some_and(|id| normalize_item_id(id) == normalize_item_id(item_id))
+    }
+}
+
+impl Package {
+    /// Get the custom XML data parts, in part name order
+    pub fn custom_xml_parts(&self) -> Vec<CustomXmlPart> {
+        let mut uris: Vec<&PartUri> = self
+            .part_uris()
+            .filter(|uri| is_custom_xml_item(uri))
+            .collect();
+        // item2.xml sorts before item10.xml
+        uris.sort_by_key(|uri| (uri.as_str().len(), uri.as_str()));
+        uris.into_iter()
+            .filter_map(|uri| self.read_custom_xml_part(uri))
+            .collect()
+    }
+
+    /// Find a custom XML data part by datastore item ID
+    pub fn custom_xml_part(&self, item_id: &str) -> Option<CustomXmlPart> {
+        self.custom_xml_parts()
+            .into_iter()
+            .find(|part| part.has_item_id(item_id))
+    }
+
+    /// Add a custom XML data part with a new datastore item ID
+    ///
+    /// Creates `customXml/itemN.xml`, its properties part listing
+    /// `schema_refs`, and the relationship from the main document part,
+    /// which must exist.
+    pub fn add_custom_xml_part(
+        &mut self,
+        xml: &str,
+        schema_refs: &[&str],
+    ) -> Result<CustomXmlPart> {
+        check_xml(xml)?;
+        // Targets are relative to the folder of the main document part
+        let depth = self
+            .main_document_part()
+            .ok_or_else(|| Error::MissingPart("Main document part not found".into()))?
+            .uri()
+            .as_str()
+            .matches('/')
+            .count()
+            - 1;
+        let n = (1..)
+            .find(|n| {
+                [item_uri(*n), props_uri(*n)]
+                    .iter()
+                    .all(|uri| self.part_uris().all(|u| u.as_str() != uri))
+            })
+            .unwrap_or(1);
+        let uri = PartUri::new(&item_uri(n))?;
+        let props = PartUri::new(&props_uri(n))?;
+        let item_id = new_item_id()?;
+
+        let mut part = Part::new(uri.clone(), XML, xml.as_bytes().to_vec());
+        part.ensure_relationships()
+            .add(rel_types::CUSTOM_XML_PROPS, &format!("itemProps{}.xml", n));
+        self.add_part(part);
+        let props_xml = props_xml(&item_id, schema_refs);
+        self.add_part(Part::new(
+            props.clone(),
+            CUSTOM_XML_PROPERTIES,
+            props_xml.into_bytes(),
+        ));
+
+        let target = format!("{}customXml/item{}.xml", "../".repeat(depth), n);
+        if let Some(main) = self.main_document_part_mut() {
+            main.ensure_relationships()
+                .add(rel_types::CUSTOM_XML, &target);
+        }
+
+        Ok(CustomXmlPart {
+            uri,
+            props_uri: Some(props),
+            item_id: Some(item_id),
+            schema_refs: schema_refs.iter().map(|s| s.to_string()).collect(),
+            xml: xml.to_string(),
+        })
+    }
+
+    /// Replace the XML of the custom XML data part with a datastore item ID
+    pub fn replace_custom_xml_part(&mut self, item_id: &str, xml: &str) -> Result<()> {
+        check_xml(xml)?;
+        let part = self
+            .custom_xml_part(item_id)
+            .ok_or_else(|| Error::NotFound(format!("custom XML part {}", item_id)))?;
+        if let Some(data) = self.part_mut(&part.uri) {
+            data.set_data(xml.as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// Read a data part and its properties
+    fn read_custom_xml_part(&self, uri: &PartUri) -> Option<CustomXmlPart> {
+        let part = self.part(uri)?;
+        let props_uri = part
+            .relationships()
+            .and_then(|rels| rels.by_type(rel_types::CUSTOM_XML_PROPS))
+            .and_then(|rel| uri.resolve(&rel.target).ok());
+        let (item_id, schema_refs) = props_uri
+            .as_ref()
+            .and_then(|props| self.part(props))
+            .map(|props| parse_props(&decode_xml(props.data())))
+            .unwrap_or_default();
+        Some(CustomXmlPart {
+            uri: uri.clone(),
+            props_uri,
+            item_id,
+            schema_refs,
+            xml: decode_xml(part.data()),
+        })
+    }
+}
+
+/// Check whether a part is a custom XML data part
+fn is_custom_xml_item(uri: &PartUri) -> bool {
+    let path = uri.as_str();
+    path.strip_prefix("/customXml/")
+        .is_some_and(|name| name.starts_with("item") && !name.starts_with("itemProps"))
+        && path.ends_with(".xml")
+        && !uri.is_relationships()
+}
+
+fn item_uri(n: usize) -> String {
+    format!("/customXml/item{}.xml", n)
+}
+
+fn props_uri(n: usize) -> String {
+    format!("/customXml/itemProps{}.xml", n)
+}
+
+/// Upper-case an item ID and drop its braces
+fn normalize_item_id(id: &str) -> String {
+    id.trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .to_ascii_uppercase()
+}
+
+/// A random GUID in the braced form Word uses for item IDs
+fn new_item_id() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| Error::Crypto(e.to_string()))?;
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    Ok(format!(
+        "{{{}-{}-{}-{}-{}}}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// Decode XML bytes, honouring a UTF-8 or UTF-16 byte order mark
+fn decode_xml(data: &[u8]) -> String {
+    let utf16 = |be: bool| {
+        let units: Vec<u16> = data[2..]
+            .chunks_exact(2)
+            .map(|c| match be {
+                true => u16::from_be_bytes([c[0], c[1]]),
+                false => u16::from_le_bytes([c[0], c[1]]),
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match data {
+        [0xFF, 0xFE, ..] => utf16(false),
+        [0xFE, 0xFF, ..] => utf16(true),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Check that a string is a well-formed XML document with a root element
+fn check_xml(xml: &str) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    let mut depth = 0usize;
+    let mut roots = 0usize;
+    loop {
+        match reader.read_event()? {
+            Event::Start(_) => {
+                roots += usize::from(depth == 0);
+                depth += 1;
+            }
+            Event::Empty(_) => roots += usize::from(depth == 0),
+            Event::End(_) => depth = depth.saturating_sub(1),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    match (roots, depth) {
+        (1, 0) => Ok(()),
+        _ => Err(Error::InvalidDocument(
+            "custom XML must have exactly one root element".into(),
+        )),
+    }
+}
+
+/// Read the item ID and schema references of a properties part
+fn parse_props(xml: &str) -> (Option<String>, Vec<String>) {
+    let mut reader = Reader::from_str(xml);
+    let mut item_id = None;
+    let mut schema_refs = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = e.local_name();
+                for attr in e.attributes().filter_map(|a| a.ok()) {
+                    let value = || attr.unescape_value().ok().map(|v| v.into_owned());
+                    match (name.as_ref(), attr.key.local_name().as_ref()) {
+                        (b"datastoreItem", b"itemID") => item_id = value(),
+                        (b"schemaRef", b"uri") => schema_refs.extend(value()),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    (item_id, schema_refs)
+}
+
+/// Properties part for a new data part
+fn props_xml(item_id: &str, schema_refs: &[&str]) -> String {
+    let refs: String = schema_refs
+        .iter()
+        .map(|uri| format!(r#"<ds:schemaRef ds:uri="{}"/>"#, escape(*uri)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<ds:datastoreItem ds:itemID="{}" xmlns:ds="{}"><ds:schemaRefs>{}</ds:schemaRefs></ds:datastoreItem>"#,
+        item_id, NS_DATASTORE, refs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_find_custom_xml_part() {
+        let mut pkg = Package::new();
+        assert!(matches!(
+            pkg.add_custom_xml_part("<root/>", &[]),
+            Err(Error::MissingPart(_))
+        ));
+        assert_eq!(pkg.part_uris().count(), 0);
+
+        let bytes = crate::Document::new().to_bytes().unwrap();
+        let mut pkg = Package::from_bytes(&bytes).unwrap();
+        let added = pkg
+            .add_custom_xml_part("<root><name>Ada</name></root>", &["urn:crm"])
+            .unwrap();
+        assert_eq!(added.uri.as_str(), "/customXml/item1.xml");
+        let id = added.item_id.clone().unwrap();
+        assert_eq!(id.len(), 38);
+        assert!(id.starts_with('{') && id.ends_with('}'));
+
+        let second = pkg.add_custom_xml_part("<other/>", &[]).unwrap();
+        assert_eq!(second.uri.as_str(), "/customXml/item2.xml");
+
+        let parts = pkg.custom_xml_parts();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], added);
+        let lower = id.trim_matches(|c| c == '{' || c == '}').to_lowercase();
+        assert_eq!(pkg.custom_xml_part(&lower).unwrap().uri, added.uri);
+
+        pkg.replace_custom_xml_part(&id, "<root><name>Grace</name></root>")
+            .unwrap();
+        assert!(pkg.custom_xml_part(&id).unwrap().xml.contains("Grace"));
+        assert!(pkg.replace_custom_xml_part("{0}", "<x/>").is_err());
+        assert!(pkg.replace_custom_xml_part(&id, "<a/><b/>").is_err());
+    }
+
+    #[test]
+    fn test_parse_props_and_decode() {
+        let (id, refs) = parse_props(
+            r#"<ds:datastoreItem ds:itemID="{ABC}" xmlns:ds="http://schemas.openxmlformats.org/officeDocument/2006/customXml"><ds:schemaRefs><ds:schemaRef ds:uri="urn:a"/></ds:schemaRefs></ds:datastoreItem>"#,
+        );
+        assert_eq!(id.as_deref(), Some("{ABC}"));
+        assert_eq!(refs, ["urn:a"]);
+
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("<a/>".encode_utf16().flat_map(|u| u.to_le_bytes()))
+            .collect();
+        assert_eq!(decode_xml(&utf16), "<a/>");
+        assert_eq!(decode_xml(b"\xEF\xBB\xBF<a/>"), "<a/>");
+    }
+}
@@ -0,0 +1,460 @@
+//! Agile encryption (MS-OFFCRYPTO 2.3.4.10), used by Office 2010 and later
+//!
+//! The package is encrypted with a random secret key in 4096-byte segments.
+//! The secret key is stored encrypted with a key derived from the password,
+//! and an HMAC over the encrypted package guards against tampering.
+
+use super::cipher::{
+    aes_cbc_decrypt, aes_cbc_encrypt, fit, random_bytes, utf16le, HashAlgorithm, AES_BLOCK_SIZE,
+};
+use crate::error::{Error, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// `EncryptionInfo` version of agile encryption
+pub(crate) const VERSION: (u16, u16) = (4, 4);
+
+/// `EncryptionInfo` flags of agile encryption (fAgile)
+const FLAGS: u32 = 0x40;
+
+/// Size of the independently encrypted package segments
+const SEGMENT_SIZE: usize = 4096;
+
+/// Spin count written for new files, as Office does
+const SPIN_COUNT: u32 = 100_000;
+
+/// Largest spin count Office accepts (MS-OFFCRYPTO 2.3.4.10); each round is a hash
+const MAX_SPIN_COUNT: u32 = 10_000_000;
+
+const NS_ENCRYPTION: &str = "http://schemas.microsoft.com/office/2006/encryption";
+const NS_PASSWORD: &str = "http://schemas.microsoft.com/office/2006/keyEncryptor/password";
+const NS_CERTIFICATE: &str = "http://schemas.microsoft.com/office/2006/keyEncryptor/certificate";
+
+// Block keys of the password key encryptor and data integrity (2.3.4.11 - 2.3.4.14)
+const BLOCK_VERIFIER_INPUT: [u8; 8] = [0xfe, 0xa7, 0xd2, 0x76, 0x3b, 0x4b, 0x9e, 0x79];
+const BLOCK_VERIFIER_VALUE: [u8; 8] = [0xd7, 0xaa, 0x0f, 0x6d, 0x30, 0x61, 0x34, 0x4e];
+const BLOCK_KEY_VALUE: [u8; 8] = [0x14, 0x6e, 0x0b, 0xe7, 0xab, 0xac, 0xd0, 0xd6];
+const BLOCK_HMAC_KEY: [u8; 8] = [0x5f, 0xb2, 0xad, 0x01, 0x0c, 0xb9, 0xe1, 0xf6];
+const BLOCK_HMAC_VALUE: [u8; 8] = [0xa0, 0x67, 0x7f, 0x02, 0xb2, 0x2c, 0x84, 0x33];
+
+/// Cipher parameters shared by `keyData` and `p:encryptedKey`
+#[derive(Clone, Debug)]
+struct CipherParams {
+    salt: Vec<u8>,
+    block_size: usize,
+    key_bytes: usize,
+    hash: HashAlgorithm,
+}
+
+impl CipherParams {
+    /// Read the cipher attributes, rejecting anything but AES-CBC
+    fn from_element(e: &BytesStart) -> Result<Self> {
+        let cipher = attr(e, "cipherAlgorithm").unwrap_or_default();
+        let chaining = attr(e, "cipherChaining").unwrap_or_default();
+        if cipher != "AES" || chaining != "ChainingModeCBC" {
+            return Err(Error::Unsupported(format!(
+                "agile encryption with {} {}",
+                cipher, chaining
+            )));
+        }
+        let hash_name = attr(e, "hashAlgorithm").unwrap_or_default();
+        let hash = HashAlgorithm::parse(&hash_name).ok_or_else(|| {
+            Error::Unsupported(format!("agile encryption hash algorithm {}", hash_name))
+        })?;
+        let key_bits: usize = number_attr(e, "keyBits")?;
+        let block_size: usize = number_attr(e, "blockSize")?;
+        if block_size != AES_BLOCK_SIZE {
+            return Err(invalid(format!("AES block size {}", block_size)));
+        }
+        Ok(Self {
+            salt: base64_attr(e, "saltValue")?,
+            block_size,
+            key_bytes: key_bits / 8,
+            hash,
+        })
+    }
+
+    /// IV derived from the salt and a block key or segment number
+    fn iv(&self, block_key: &[u8]) -> Vec<u8> {
+        fit(
+            self.hash.digest(&[&self.salt, block_key]),
+            self.block_size,
+            0x36,
+        )
+    }
+
+    /// Write the cipher attributes
+    fn push_attributes(&self, elem: &mut BytesStart) {
+        elem.push_attribute(("saltSize", self.salt.len().to_string().as_str()));
+        elem.push_attribute(("blockSize", self.block_size.to_string().as_str()));
+        elem.push_attribute(("keyBits", (self.key_bytes * 8).to_string().as_str()));
+        elem.push_attribute(("hashSize", self.hash.size().to_string().as_str()));
+        elem.push_attribute(("cipherAlgorithm", "AES"));
+        elem.push_attribute(("cipherChaining", "ChainingModeCBC"));
+        elem.push_attribute(("hashAlgorithm", self.hash.name()));
+        elem.push_attribute(("saltValue", BASE64.encode(&self.salt).as_str()));
+    }
+}
+
+/// Password key encryptor (`p:encryptedKey`)
+#[derive(Clone, Debug)]
+struct PasswordKey {
+    params: CipherParams,
+    spin_count: u32,
+    encrypted_verifier_input: Vec<u8>,
+    encrypted_verifier_value: Vec<u8>,
+    encrypted_key_value: Vec<u8>,
+}
+
+impl PasswordKey {
+    /// Hash of the password after `spin_count` iterations
+    fn password_hash(&self, password: &str) -> Vec<u8> {
+        let hash = self.params.hash;
+        let mut h = hash.digest(&[&self.params.salt, &utf16le(password)]);
+        for i in 0..self.spin_count {
+            h = hash.digest(&[&i.to_le_bytes(), &h]);
+        }
+        h
+    }
+
+    /// Key for one of the password block keys
+    fn derive_key(&self, password_hash: &[u8], block_key: &[u8]) -> Vec<u8> {
+        let key = self.params.hash.digest(&[password_hash, block_key]);
+        fit(key, self.params.key_bytes, 0x36)
+    }
+
+    /// Decrypt a value encrypted with a password-derived key
+    fn decrypt(&self, password_hash: &[u8], block_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        let iv = fit(self.params.salt.clone(), self.params.block_size, 0x36);
+        aes_cbc_decrypt(&self.derive_key(password_hash, block_key), &iv, data)
+    }
+
+    /// Encrypt a value with a password-derived key, padding it to whole blocks
+    fn encrypt(&self, password_hash: &[u8], block_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        let iv = fit(self.params.salt.clone(), self.params.block_size, 0x36);
+        let data = pad_to_block(data.to_vec(), self.params.block_size);
+        aes_cbc_encrypt(&self.derive_key(password_hash, block_key), &iv, &data)
+    }
+}
+
+/// Parsed agile `EncryptionInfo`
+#[derive(Clone, Debug)]
+struct AgileInfo {
+    key_data: CipherParams,
+    password_key: PasswordKey,
+    /// Encrypted HMAC key and value (`dataIntegrity`)
+    integrity: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl AgileInfo {
+    /// Parse the XML descriptor that follows the version and flags
+    fn from_xml(xml: &[u8]) -> Result<Self> {
+        let xml = std::str::from_utf8(xml)?;
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut key_data = None;
+        let mut password_key = None;
+        let mut integrity = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                    b"keyData" => key_data = Some(CipherParams::from_element(&e)?),
+                    b"dataIntegrity" => {
+                        integrity = Some((
+                            base64_attr(&e, "encryptedHmacKey")?,
+                            base64_attr(&e, "encryptedHmacValue")?,
+                        ));
+                    }
+                    // Only the password key encryptor has a spin count
+                    b"encryptedKey" if attr(&e, "spinCount").is_some() => {
+                        let spin_count = number_attr(&e, "spinCount")?;
+                        if spin_count > MAX_SPIN_COUNT {
+                            return Err(invalid(format!(
+                                "spinCount {} exceeds {}",
+                                spin_count, MAX_SPIN_COUNT
+                            )));
+                        }
+                        password_key = Some(PasswordKey {
+                            params: CipherParams::from_element(&e)?,
+                            spin_count,
+                            encrypted_verifier_input: base64_attr(
+                                &e,
+                                "encryptedVerifierHashInput",
+                            )?,
+                            encrypted_verifier_value: base64_attr(
+                                &e,
+                                "encryptedVerifierHashValue",
+                            )?,
+                            encrypted_key_value: base64_attr(&e, "encryptedKeyValue")?,
+                        });
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            key_data: key_data.ok_or_else(|| invalid("missing keyData"))?,
+            password_key: password_key.ok_or_else(|| {
+                Error::Unsupported("agile encryption without a password key encryptor".into())
+            })?,
+            integrity,
+        })
+    }
+
+    /// Serialize as the XML descriptor
+    fn to_xml(&self) -> Result<Vec<u8>> {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))?;
+
+        let mut root = BytesStart::new("encryption");
+        root.push_attribute(("xmlns", NS_ENCRYPTION));
+        root.push_attribute(("xmlns:p", NS_PASSWORD));
+        root.push_attribute(("xmlns:c", NS_CERTIFICATE));
+        writer.write_event(Event::Start(root))?;
+
+        let mut key_data = BytesStart::new("keyData");
+        self.key_data.push_attributes(&mut key_data);
+        writer.write_event(Event::Empty(key_data))?;
+
+        if let Some((ref key, ref value)) = self.integrity {
+            let mut elem = BytesStart::new("dataIntegrity");
+            elem.push_attribute(("encryptedHmacKey", BASE64.encode(key).as_str()));
+            elem.push_attribute(("encryptedHmacValue", BASE64.encode(value).as_str()));
+            writer.write_event(Event::Empty(elem))?;
+        }
+
+        writer.write_event(Event::Start(BytesStart::new("keyEncryptors")))?;
+        let mut encryptor = BytesStart::new("keyEncryptor");
+        encryptor.push_attribute(("uri", NS_PASSWORD));
+        writer.write_event(Event::Start(encryptor))?;
+
+        let pk = &self.password_key;
+        let mut elem = BytesStart::new("p:encryptedKey");
+        elem.push_attribute(("spinCount", pk.spin_count.to_string().as_str()));
+        pk.params.push_attributes(&mut elem);
+        let values = [
+            ("encryptedVerifierHashInput", &pk.encrypted_verifier_input),
+            ("encryptedVerifierHashValue", &pk.encrypted_verifier_value),
+            ("encryptedKeyValue", &pk.encrypted_key_value),
+        ];
+        for (name, value) in values {
+            elem.push_attribute((name, BASE64.encode(value).as_str()));
+        }
+        writer.write_event(Event::Empty(elem))?;
+
+        writer.write_event(Event::End(BytesEnd::new("keyEncryptor")))?;
+        writer.write_event(Event::End(BytesEnd::new("keyEncryptors")))?;
+        writer.write_event(Event::End(BytesEnd::new("encryption")))?;
+        Ok(writer.into_inner())
+    }
+
+    /// Recover the secret key, failing with [`Error::WrongPassword`]
+    fn secret_key(&self, password: &str) -> Result<Vec<u8>> {
+        let pk = &self.password_key;
+        let hash = pk.password_hash(password);
+
+        let mut input = pk.decrypt(&hash, &BLOCK_VERIFIER_INPUT, &pk.encrypted_verifier_input)?;
+        input.truncate(pk.params.salt.len());
+        let mut expected =
+            pk.decrypt(&hash, &BLOCK_VERIFIER_VALUE, &pk.encrypted_verifier_value)?;
+        expected.truncate(pk.params.hash.size());
+        if pk.params.hash.digest(&[&input]) != expected {
+            return Err(Error::WrongPassword);
+        }
+
+        let mut key = pk.decrypt(&hash, &BLOCK_KEY_VALUE, &pk.encrypted_key_value)?;
+        if key.len() < self.key_data.key_bytes {
+            return Err(invalid("encrypted key is too short"));
+        }
+        key.truncate(self.key_data.key_bytes);
+        Ok(key)
+    }
+
+    /// Check the HMAC over the whole `EncryptedPackage` stream
+    fn verify_integrity(&self, secret_key: &[u8], stream: &[u8]) -> Result<()> {
+        let Some((ref encrypted_key, ref encrypted_value)) = self.integrity else {
+            return Ok(());
+        };
+        let kd = &self.key_data;
+        let mut hmac_key = aes_cbc_decrypt(secret_key, &kd.iv(&BLOCK_HMAC_KEY), encrypted_key)?;
+        hmac_key.truncate(kd.hash.size());
+        let mut expected = aes_cbc_decrypt(secret_key, &kd.iv(&BLOCK_HMAC_VALUE), encrypted_value)?;
+        expected.truncate(kd.hash.size());
+        if kd.hash.hmac(&hmac_key, stream) != expected {
+            return Err(Error::Crypto("data integrity check failed".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Decrypt the `EncryptedPackage` stream into the package ZIP bytes
+///
+/// `descriptor` is the `EncryptionInfo` stream after its version and flags.
+pub(crate) fn decrypt(descriptor: &[u8], stream: &[u8], password: &str) -> Result<Vec<u8>> {
+    let info = AgileInfo::from_xml(descriptor)?;
+    let secret_key = info.secret_key(password)?;
+    info.verify_integrity(&secret_key, stream)?;
+
+    let (size, data) = super::split_package_stream(stream)?;
+    let mut package = Vec::with_capacity(data.len());
+    for (i, segment) in data.chunks(SEGMENT_SIZE).enumerate() {
+        if segment.len() % info.key_data.block_size != 0 {
+            return Err(invalid("encrypted package is not block aligned"));
+        }
+        let iv = info.key_data.iv(&(i as u32).to_le_bytes());
+        package.extend(aes_cbc_decrypt(&secret_key, &iv, segment)?);
+    }
+    super::truncate_package(package, size)
+}
+
+/// Encrypt package ZIP bytes with SHA-512 and AES-256
+///
+/// Returns the `EncryptionInfo` and `EncryptedPackage` streams.
+pub(crate) fn encrypt(package: &[u8], password: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let hash = HashAlgorithm::Sha512;
+    let key_data = CipherParams {
+        salt: random_bytes(16)?,
+        block_size: AES_BLOCK_SIZE,
+        key_bytes: 32,
+        hash,
+    };
+    let mut password_key = PasswordKey {
+        params: CipherParams {
+            salt: random_bytes(16)?,
+            ..key_data.clone()
+        },
+        spin_count: SPIN_COUNT,
+        encrypted_verifier_input: Vec::new(),
+        encrypted_verifier_value: Vec::new(),
+        encrypted_key_value: Vec::new(),
+    };
+
+    // Secret key, protected by the password
+    let secret_key = random_bytes(key_data.key_bytes)?;
+    let password_hash = password_key.password_hash(password);
+    let verifier = random_bytes(password_key.params.salt.len())?;
+    password_key.encrypted_verifier_input =
+        password_key.encrypt(&password_hash, &BLOCK_VERIFIER_INPUT, &verifier)?;
+    password_key.encrypted_verifier_value = password_key.encrypt(
+        &password_hash,
+        &BLOCK_VERIFIER_VALUE,
+        &hash.digest(&[&verifier]),
+    )?;
+    password_key.encrypted_key_value =
+        password_key.encrypt(&password_hash, &BLOCK_KEY_VALUE, &secret_key)?;
+
+    // Package segments
+    let mut stream = (package.len() as u64).to_le_bytes().to_vec();
+    for (i, segment) in package.chunks(SEGMENT_SIZE).enumerate() {
+        let iv = key_data.iv(&(i as u32).to_le_bytes());
+        let segment = pad_to_block(segment.to_vec(), key_data.block_size);
+        stream.extend(aes_cbc_encrypt(&secret_key, &iv, &segment)?);
+    }
+
+    // Data integrity
+    let hmac_key = random_bytes(hash.size())?;
+    let hmac_value = hash.hmac(&hmac_key, &stream);
+    let integrity = (
+        aes_cbc_encrypt(
+            &secret_key,
+            &key_data.iv(&BLOCK_HMAC_KEY),
+            &pad_to_block(hmac_key, key_data.block_size),
+        )?,
+        aes_cbc_encrypt(
+            &secret_key,
+            &key_data.iv(&BLOCK_HMAC_VALUE),
+            &pad_to_block(hmac_value, key_data.block_size),
+        )?,
+    );
+
+    let info = AgileInfo {
+        key_data,
+        password_key,
+        integrity: Some(integrity),
+    };
+    let mut info_stream = Vec::new();
+    info_stream.extend(VERSION.0.to_le_bytes());
+    info_stream.extend(VERSION.1.to_le_bytes());
+    info_stream.extend(FLAGS.to_le_bytes());
+    info_stream.extend(info.to_xml()?);
+    Ok((info_stream, stream))
+}
+
+/// Zero-pad data to a whole number of cipher blocks
+fn pad_to_block(mut data: Vec<u8>, block_size: usize) -> Vec<u8> {
+    let len = (data.len() + block_size - 1) / block_size * block_size;
+    data.resize(len, 0);
+    data
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::InvalidDocument(format!("Invalid encryption info: {}", msg.into()))
+}
+
+/// Attribute value by local name
+fn attr(e: &BytesStart, name: &str) -> Option<String> {
+    e.attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.local_name().as_ref() == name.as_bytes())
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
+fn number_attr<T: std::str::FromStr>(e: &BytesStart, name: &str) -> Result<T> {
+    attr(e, name)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| invalid(format!("missing or invalid {}", name)))
+}
+
+fn base64_attr(e: &BytesStart, name: &str) -> Result<Vec<u8>> {
+    attr(e, name)
+        .and_then(|v| BASE64.decode(v.trim()).ok())
+        .ok_or_else(|| invalid(format!("missing or invalid {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agile_roundtrip() {
+        let package = b"PK\x03\x04 not really a zip, but any bytes will do".repeat(200);
+        let (info, stream) = encrypt(&package, "pässword").unwrap();
+        assert_eq!(&info[..8], &[4, 0, 4, 0, 0x40, 0, 0, 0]);
+        assert_eq!(decrypt(&info[8..], &stream, "pässword").unwrap(), package);
+        assert!(matches!(
+            decrypt(&info[8..], &stream, "password"),
+            Err(Error::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_agile_rejects_huge_spin_count() {
+        let (info, stream) = encrypt(b"hello", "pw").unwrap();
+        let xml = String::from_utf8(info[8..].to_vec()).unwrap();
+        let xml = xml.replace(r#"spinCount="100000""#, r#"spinCount="4294967295""#);
+        let err = decrypt(xml.as_bytes(), &stream, "pw").unwrap_err();
+        assert!(err.to_string().contains("spinCount"));
+    }
+
+    #[test]
+    fn test_agile_detects_tampering() {
+        let (info, mut stream) = encrypt(b"hello", "pw").unwrap();
+        let last = stream.len() - 1;
+        stream[last] ^= 1;
+        assert!(matches!(
+            decrypt(&info[8..], &stream, "pw"),
+            Err(Error::Crypto(_))
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+//! Hash and cipher primitives used by Office encryption
+
+use crate::error::{Error, Result};
+use aes::{Aes128, Aes192, Aes256};
+use cbc::cipher::block_padding::NoPadding;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyInit, KeyIvInit};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// AES block size in bytes
+pub(crate) const AES_BLOCK_SIZE: usize = 16;
+
+/// Hash algorithm named in the encryption info
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Parse an agile `hashAlgorithm` attribute value
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "SHA1" | "SHA-1" => Some(Self::Sha1),
+            "SHA256" => Some(Self::Sha256),
+            "SHA384" => Some(Self::Sha384),
+            "SHA512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Value of the agile `hashAlgorithm` attribute
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha384 => "SHA384",
+            Self::Sha512 => "SHA512",
+        }
+    }
+
+    /// Digest size in bytes
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 => 64,
+        }
+    }
+
+    /// Hash the concatenation of `parts`
+    pub(crate) fn digest(&self, parts: &[&[u8]]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => digest_with::<Sha1>(parts),
+            Self::Sha256 => digest_with::<Sha256>(parts),
+            Self::Sha384 => digest_with::<Sha384>(parts),
+            Self::Sha512 => digest_with::<Sha512>(parts),
+        }
+    }
+
+    /// HMAC of `data` keyed with `key`
+    pub(crate) fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        macro_rules! hmac_with {
+            ($hash:ty) => {{
+                let mut mac = <Hmac<$hash> as Mac>::new_from_slice(key)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }};
+        }
+        match self {
+            Self::Sha1 => hmac_with!(Sha1),
+            Self::Sha256 => hmac_with!(Sha256),
+            Self::Sha384 => hmac_with!(Sha384),
+            Self::Sha512 => hmac_with!(Sha512),
+        }
+    }
+}
+
+fn digest_with<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Truncate `data` to `len` bytes, or pad it with `pad`
+pub(crate) fn fit(mut data: Vec<u8>, len: usize, pad: u8) -> Vec<u8> {
+    data.resize(len, pad);
+    data
+}
+
+/// Password as UTF-16LE bytes
+pub(crate) fn utf16le(password: &str) -> Vec<u8> {
+    password
+        .encode_utf16()
+        .flat_map(|u| u.to_le_bytes())
+        .collect()
+}
+
+/// Random bytes from the operating system
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| Error::Crypto(e.to_string()))?;
+    Ok(bytes)
+}
+
+fn block_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::Crypto(e.to_string())
+}
+
+macro_rules! with_aes {
+    ($key:expr, $aes:ident => $body:expr) => {
+        match $key.len() {
+            16 => {
+                type $aes = Aes128;
+                $body
+            }
+            24 => {
+                type $aes = Aes192;
+                $body
+            }
+            32 => {
+                type $aes = Aes256;
+                $body
+            }
+            n => Err(Error::Crypto(format!(
+                "invalid AES key length: {} bytes",
+                n
+            ))),
+        }
+    };
+}
+
+/// AES-CBC decryption of whole blocks
+pub(crate) fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    with_aes!(key, A => cbc::Decryptor::<A>::new_from_slices(key, iv)
+        .map_err(block_error)?
+        .decrypt_padded_vec_mut::<NoPadding>(data)
+        .map_err(block_error))
+}
+
+/// AES-CBC encryption of whole blocks
+pub(crate) fn aes_cbc_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    with_aes!(key, A => Ok(cbc::Encryptor::<A>::new_from_slices(key, iv)
+        .map_err(block_error)?
+        .encrypt_padded_vec_mut::<NoPadding>(data)))
+}
+
+/// AES-ECB decryption of whole blocks
+pub(crate) fn aes_ecb_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    with_aes!(key, A => ecb::Decryptor::<A>::new_from_slice(key)
+        .map_err(block_error)?
+        .decrypt_padded_vec_mut::<NoPadding>(data)
+        .map_err(block_error))
+}
+
+/// AES-ECB encryption of whole blocks
+#[cfg(test)]
+pub(crate) fn aes_ecb_encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    with_aes!(key, A => Ok(ecb::Encryptor::<A>::new_from_slice(key)
+        .map_err(block_error)?
+        .encrypt_padded_vec_mut::<NoPadding>(data)))
+}
+
+/// RC4 stream cipher
+pub(crate) struct Rc4 {
+    state: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    pub(crate) fn new(key: &[u8]) -> Self {
+        let mut state = [0u8; 256];
+        for (i, s) in state.iter_mut().enumerate() {
+            *s = i as u8;
+        }
+        let mut j: u8 = 0;
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
+        }
+        Self { state, i: 0, j: 0 }
+    }
+
+    /// Encrypt or decrypt `data` in place
+    pub(crate) fn apply(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.i = self.i.wrapping_add(1);
+            self.j = self.j.wrapping_add(self.state[self.i as usize]);
+            self.state.swap(self.i as usize, self.j as usize);
+            let k = self.state
+                [self.state[self.i as usize].wrapping_add(self.state[self.j as usize]) as usize];
+            *byte ^= k;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rc4_known_vector() {
+        let mut data = *b"Plaintext";
+        Rc4::new(b"Key").apply(&mut data);
+        assert_eq!(data, [0xBB, 0xF3, 0x16, 0xE8, 0xD9, 0x40, 0xAF, 0x0A, 0xD3]);
+    }
+
+    #[test]
+    fn test_aes_cbc_roundtrip() {
+        let key = [7u8; 32];
+        let iv = [9u8; 16];
+        let data = [1u8; 48];
+        let encrypted = aes_cbc_encrypt(&key, &iv, &data).unwrap();
+        assert_ne!(encrypted, data);
+        assert_eq!(aes_cbc_decrypt(&key, &iv, &encrypted).unwrap(), data);
+        assert!(aes_cbc_decrypt(&key[..5], &iv, &encrypted).is_err());
+    }
+}
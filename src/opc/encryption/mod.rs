@@ -0,0 +1,234 @@
+//! Password-encrypted packages (MS-OFFCRYPTO)
+//!
+//! An encrypted DOCX is not a ZIP file but an OLE compound file holding an
+//! `EncryptionInfo` stream, which describes the cipher and password
+//! verifier, and an `EncryptedPackage` stream with the encrypted ZIP.
+//! Agile and standard (including RC4 CryptoAPI) encryption can be read;
+//! files are always written with agile encryption.
+
+mod agile;
+mod cipher;
+mod standard;
+
+use crate::error::{Error, Result};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+/// Signature at the start of every OLE compound file
+const COMPOUND_FILE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+const ENCRYPTION_INFO: &str = "/EncryptionInfo";
+const ENCRYPTED_PACKAGE: &str = "/EncryptedPackage";
+const DATA_SPACES: &str = "/\u{6}DataSpaces";
+
+/// Whether the reader holds an OLE compound file rather than a ZIP archive
+///
+/// The reader is left at its original position.
+pub(crate) fn is_compound_file<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+    let start = reader.stream_position()?;
+    let mut signature = [0u8; 8];
+    let is_compound = match reader.read_exact(&mut signature) {
+        Ok(()) => signature == COMPOUND_FILE_SIGNATURE,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(e.into()),
+    };
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(is_compound)
+}
+
+/// Fail with a clear error when the reader is not a ZIP package
+///
+/// Encrypted documents give [`Error::Encrypted`]; other compound files
+/// (legacy binary `.doc` files) are unsupported.
+pub(crate) fn check_not_encrypted<R: Read + Seek>(reader: &mut R) -> Result<()> {
+    if !is_compound_file(reader)? {
+        return Ok(());
+    }
+    let compound = cfb::CompoundFile::open(reader)?;
+    if compound.is_stream(ENCRYPTION_INFO) {
+        Err(Error::Encrypted)
+    } else {
+        Err(Error::Unsupported(
+            "OLE compound file without an encrypted package (legacy binary format?)".into(),
+        ))
+    }
+}
+
+/// Decrypt an encrypted package, returning the ZIP bytes
+pub(crate) fn decrypt<R: Read + Seek>(reader: R, password: &str) -> Result<Vec<u8>> {
+    let mut compound = cfb::CompoundFile::open(reader)?;
+    if !compound.is_stream(ENCRYPTION_INFO) || !compound.is_stream(ENCRYPTED_PACKAGE) {
+        return Err(Error::Unsupported(
+            "OLE compound file without an encrypted package (legacy binary format?)".into(),
+        ));
+    }
+    let mut info = Vec::new();
+    compound
+        .open_stream(ENCRYPTION_INFO)?
+        .read_to_end(&mut info)?;
+    let mut stream = Vec::new();
+    compound
+        .open_stream(ENCRYPTED_PACKAGE)?
+        .read_to_end(&mut stream)?;
+
+    if info.len() < 8 {
+        return Err(Error::InvalidDocument(
+            "Invalid encryption info: truncated header".into(),
+        ));
+    }
+    let major = u16::from_le_bytes([info[0], info[1]]);
+    let minor = u16::from_le_bytes([info[2], info[3]]);
+    match (major, minor) {
+        agile::VERSION => agile::decrypt(&info[8..], &stream, password),
+        (2..=4, 2) => standard::decrypt(&info[8..], &stream, password),
+        (3 | 4, 3) => Err(Error::Unsupported("extensible encryption".into())),
+        _ => Err(Error::Unsupported(format!(
+            "encryption version {}.{}",
+            major, minor
+        ))),
+    }
+}
+
+/// Encrypt ZIP package bytes with agile encryption (AES-256, SHA-512)
+pub(crate) fn encrypt(package: &[u8], password: &str) -> Result<Vec<u8>> {
+    let (info, stream) = agile::encrypt(package, password)?;
+
+    let mut compound = cfb::CompoundFile::create(Cursor::new(Vec::new()))?;
+    write_data_spaces(&mut compound)?;
+    compound.create_stream(ENCRYPTION_INFO)?.write_all(&info)?;
+    compound
+        .create_stream(ENCRYPTED_PACKAGE)?
+        .write_all(&stream)?;
+    compound.flush()?;
+    Ok(compound.into_inner().into_inner())
+}
+
+/// Split the `EncryptedPackage` stream into its declared size and data
+fn split_package_stream(stream: &[u8]) -> Result<(u64, &[u8])> {
+    if stream.len() < 8 {
+        return Err(Error::InvalidDocument(
+            "Encrypted package stream is truncated".into(),
+        ));
+    }
+    let (size, data) = stream.split_at(8);
+    let size = u64::from_le_bytes(size.try_into().expect("8-byte slice"));
+    Ok((size, data))
+}
+
+/// Drop the cipher padding after the declared package size
+fn truncate_package(mut package: Vec<u8>, size: u64) -> Result<Vec<u8>> {
+    if size > package.len() as u64 {
+        return Err(Error::InvalidDocument(
+            "Encrypted package is shorter than its declared size".into(),
+        ));
+    }
+    package.truncate(size as usize);
+    Ok(package)
+}
+
+/// Write the `\x06DataSpaces` storage that marks the package as encrypted
+/// (MS-OFFCRYPTO 2.1)
+fn write_data_spaces<F: Read + Write + Seek>(compound: &mut cfb::CompoundFile<F>) -> Result<()> {
+    let transform_dir = format!("{}/TransformInfo/StrongEncryptionTransform", DATA_SPACES);
+    compound.create_storage_all(&transform_dir)?;
+    compound.create_storage(format!("{}/DataSpaceInfo", DATA_SPACES))?;
+
+    // DataSpaceVersionInfo
+    let mut version = Vec::new();
+    push_unicode_lp_p4(&mut version, "Microsoft.Container.DataSpaces");
+    push_versions(&mut version);
+    compound
+        .create_stream(format!("{}/Version", DATA_SPACES))?
+        .write_all(&version)?;
+
+    // DataSpaceMap with one entry mapping EncryptedPackage to the data space
+    let mut entry = Vec::new();
+    entry.extend(1u32.to_le_bytes());
+    entry.extend(0u32.to_le_bytes());
+    push_unicode_lp_p4(&mut entry, "EncryptedPackage");
+    push_unicode_lp_p4(&mut entry, "StrongEncryptionDataSpace");
+    let mut map = Vec::new();
+    map.extend(8u32.to_le_bytes());
+    map.extend(1u32.to_le_bytes());
+    map.extend((entry.len() as u32 + 4).to_le_bytes());
+    map.extend(entry);
+    compound
+        .create_stream(format!("{}/DataSpaceMap", DATA_SPACES))?
+        .write_all(&map)?;
+
+    // DataSpaceDefinition referencing the transform
+    let mut definition = Vec::new();
+    definition.extend(8u32.to_le_bytes());
+    definition.extend(1u32.to_le_bytes());
+    push_unicode_lp_p4(&mut definition, "StrongEncryptionTransform");
+    compound
+        .create_stream(format!(
+            "{}/DataSpaceInfo/StrongEncryptionDataSpace",
+            DATA_SPACES
+        ))?
+        .write_all(&definition)?;
+
+    // TransformInfoHeader of the encryption transform
+    let mut id = Vec::new();
+    push_unicode_lp_p4(&mut id, "{FF9A3F03-56EF-4613-BDD5-5A41C1D07246}");
+    let mut transform = Vec::new();
+    transform.extend((id.len() as u32 + 8).to_le_bytes());
+    transform.extend(1u32.to_le_bytes());
+    transform.extend(id);
+    push_unicode_lp_p4(&mut transform, "Microsoft.Container.EncryptionTransform");
+    push_versions(&mut transform);
+    // EncryptionTransformInfo with an empty name
+    transform.extend(0u32.to_le_bytes());
+    transform.extend(0u32.to_le_bytes());
+    compound
+        .create_stream(format!("{}/\u{6}Primary", transform_dir))?
+        .write_all(&transform)?;
+
+    Ok(())
+}
+
+/// Length-prefixed UTF-16LE string padded to 4 bytes
+fn push_unicode_lp_p4(buf: &mut Vec<u8>, s: &str) {
+    let bytes = cipher::utf16le(s);
+    buf.extend((bytes.len() as u32).to_le_bytes());
+    buf.extend(&bytes);
+    buf.resize(buf.len() + (4 - bytes.len() % 4) % 4, 0);
+}
+
+/// Reader, updater and writer versions, all 1.0
+fn push_versions(buf: &mut Vec<u8>) {
+    for _ in 0..3 {
+        buf.extend(1u16.to_le_bytes());
+        buf.extend(0u16.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_container() {
+        let package = b"PK\x03\x04package bytes".to_vec();
+        let encrypted = encrypt(&package, "pw").unwrap();
+        assert_eq!(&encrypted[..8], &COMPOUND_FILE_SIGNATURE);
+
+        let mut reader = Cursor::new(&encrypted);
+        assert!(is_compound_file(&mut reader).unwrap());
+        assert!(matches!(
+            check_not_encrypted(&mut reader),
+            Err(Error::Encrypted)
+        ));
+
+        let compound = cfb::CompoundFile::open(Cursor::new(&encrypted)).unwrap();
+        assert!(compound.is_stream("/\u{6}DataSpaces/DataSpaceMap"));
+        assert_eq!(decrypt(Cursor::new(&encrypted), "pw").unwrap(), package);
+    }
+
+    #[test]
+    fn test_zip_is_not_compound_file() {
+        let mut reader = Cursor::new(b"PK\x03\x04".to_vec());
+        assert!(!is_compound_file(&mut reader).unwrap());
+        assert!(check_not_encrypted(&mut reader).is_ok());
+        assert_eq!(reader.position(), 0);
+    }
+}
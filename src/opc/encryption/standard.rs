@@ -0,0 +1,267 @@
+//! Standard encryption (MS-OFFCRYPTO 2.3.4.5) and RC4 CryptoAPI (2.3.5.2)
+//!
+//! Written by Office 2007; only decryption is supported.
+
+use super::cipher::{aes_ecb_decrypt, utf16le, HashAlgorithm, Rc4};
+use crate::error::{Error, Result};
+
+/// Spin count of standard encryption key derivation
+const SPIN_COUNT: u32 = 50_000;
+
+/// RC4 CryptoAPI re-keys the cipher for every block of this size
+const RC4_BLOCK_SIZE: usize = 512;
+
+const ALG_RC4: u32 = 0x6801;
+const ALG_AES_128: u32 = 0x660E;
+const ALG_AES_192: u32 = 0x660F;
+const ALG_AES_256: u32 = 0x6610;
+
+/// Parsed `EncryptionHeader` and `EncryptionVerifier`
+#[derive(Clone, Debug)]
+struct StandardInfo {
+    alg_id: u32,
+    key_bytes: usize,
+    salt: Vec<u8>,
+    encrypted_verifier: Vec<u8>,
+    verifier_hash_size: usize,
+    encrypted_verifier_hash: Vec<u8>,
+}
+
+impl StandardInfo {
+    /// Parse the `EncryptionInfo` stream after its version and flags
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut cursor = ByteReader { data, pos: 0 };
+        let header_size = cursor.u32()? as usize;
+        let header = cursor.bytes(header_size)?;
+
+        let mut header = ByteReader {
+            data: header,
+            pos: 0,
+        };
+        let _flags = header.u32()?;
+        let _size_extra = header.u32()?;
+        let alg_id = header.u32()?;
+        let _alg_id_hash = header.u32()?;
+        let key_bits = match header.u32()? {
+            // 0 means the 40-bit default of RC4
+            0 => 40,
+            bits => bits as usize,
+        };
+
+        let salt_size = cursor.u32()? as usize;
+        let salt = cursor.bytes(salt_size)?.to_vec();
+        let encrypted_verifier = cursor.bytes(16)?.to_vec();
+        let verifier_hash_size = cursor.u32()? as usize;
+        let encrypted_verifier_hash = cursor.rest().to_vec();
+
+        Ok(Self {
+            alg_id,
+            key_bytes: key_bits / 8,
+            salt,
+            encrypted_verifier,
+            verifier_hash_size,
+            encrypted_verifier_hash,
+        })
+    }
+
+    /// AES key for the password (2.3.4.7)
+    fn aes_key(&self, password: &str) -> Vec<u8> {
+        let sha1 = HashAlgorithm::Sha1;
+        let mut h = sha1.digest(&[&self.salt, &utf16le(password)]);
+        for i in 0..SPIN_COUNT {
+            h = sha1.digest(&[&i.to_le_bytes(), &h]);
+        }
+        let h = sha1.digest(&[&h, &0u32.to_le_bytes()]);
+
+        let derive = |fill: u8| {
+            let mut buf = [fill; 64];
+            for (b, x) in buf.iter_mut().zip(&h) {
+                *b ^= x;
+            }
+            sha1.digest(&[&buf])
+        };
+        let mut key = derive(0x36);
+        key.extend(derive(0x5C));
+        key.truncate(self.key_bytes);
+        key
+    }
+
+    /// RC4 key for the password and block number (2.3.5.2)
+    fn rc4_key(&self, password_hash: &[u8], block: u32) -> Vec<u8> {
+        let mut key = HashAlgorithm::Sha1.digest(&[password_hash, &block.to_le_bytes()]);
+        key.truncate(self.key_bytes);
+        // 40-bit keys are padded to 128 bits
+        if self.key_bytes == 5 {
+            key.resize(16, 0);
+        }
+        key
+    }
+
+    /// Compare the decrypted verifier with its decrypted hash
+    fn check_verifier(&self, verifier: &[u8], verifier_hash: &[u8]) -> Result<()> {
+        let size = self.verifier_hash_size.min(verifier_hash.len());
+        if HashAlgorithm::Sha1.digest(&[verifier]).get(..size) != Some(&verifier_hash[..size]) {
+            return Err(Error::WrongPassword);
+        }
+        Ok(())
+    }
+}
+
+/// Decrypt the `EncryptedPackage` stream into the package ZIP bytes
+///
+/// `descriptor` is the `EncryptionInfo` stream after its version and flags.
+pub(crate) fn decrypt(descriptor: &[u8], stream: &[u8], password: &str) -> Result<Vec<u8>> {
+    let info = StandardInfo::parse(descriptor)?;
+    let (size, data) = super::split_package_stream(stream)?;
+
+    let package = match info.alg_id {
+        ALG_AES_128 | ALG_AES_192 | ALG_AES_256 => {
+            let key = info.aes_key(password);
+            let verifier = aes_ecb_decrypt(&key, &info.encrypted_verifier)?;
+            let verifier_hash = aes_ecb_decrypt(&key, &info.encrypted_verifier_hash)?;
+            info.check_verifier(&verifier, &verifier_hash)?;
+
+            let aligned = data.len() / 16 * 16;
+            aes_ecb_decrypt(&key, &data[..aligned])?
+        }
+        ALG_RC4 => {
+            let password_hash = HashAlgorithm::Sha1.digest(&[&info.salt, &utf16le(password)]);
+            let mut rc4 = Rc4::new(&info.rc4_key(&password_hash, 0));
+            let mut verifier = info.encrypted_verifier.clone();
+            let mut verifier_hash = info.encrypted_verifier_hash.clone();
+            rc4.apply(&mut verifier);
+            rc4.apply(&mut verifier_hash);
+            info.check_verifier(&verifier, &verifier_hash)?;
+
+            let mut package = data.to_vec();
+            for (block, chunk) in package.chunks_mut(RC4_BLOCK_SIZE).enumerate() {
+                Rc4::new(&info.rc4_key(&password_hash, block as u32)).apply(chunk);
+            }
+            package
+        }
+        alg_id => {
+            return Err(Error::Unsupported(format!(
+                "standard encryption algorithm 0x{:04X}",
+                alg_id
+            )))
+        }
+    };
+    super::truncate_package(package, size)
+}
+
+/// Little-endian reader over a byte slice
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len());
+        let end = end.ok_or_else(|| {
+            Error::InvalidDocument("Invalid encryption info: truncated header".into())
+        })?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.pos..];
+        self.pos = self.data.len();
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cipher::aes_ecb_encrypt;
+    use super::*;
+
+    /// Build an `EncryptionInfo` descriptor for the given algorithm
+    fn descriptor(alg_id: u32, key_bits: u32, verifier: &[u8], verifier_hash: &[u8]) -> Vec<u8> {
+        let mut header = Vec::new();
+        for value in [0x24, 0, alg_id, 0x8004, key_bits, 0x18, 0, 0] {
+            header.extend(u32::to_le_bytes(value));
+        }
+        header.extend(utf16le(
+            "Microsoft Enhanced RSA and AES Cryptographic Provider\0",
+        ));
+
+        let mut data = (header.len() as u32).to_le_bytes().to_vec();
+        data.extend(header);
+        data.extend(16u32.to_le_bytes());
+        data.extend([0x11u8; 16]);
+        data.extend(verifier);
+        data.extend(20u32.to_le_bytes());
+        data.extend(verifier_hash);
+        data
+    }
+
+    fn package_stream(size: usize, data: Vec<u8>) -> Vec<u8> {
+        let mut stream = (size as u64).to_le_bytes().to_vec();
+        stream.extend(data);
+        stream
+    }
+
+    #[test]
+    fn test_standard_aes() {
+        let plain = b"standard encryption test package".repeat(3);
+        let template =
+            StandardInfo::parse(&descriptor(ALG_AES_128, 128, &[0; 16], &[0; 32])).unwrap();
+        let key = template.aes_key("Password1");
+
+        let verifier = [0x42u8; 16];
+        let mut hash = HashAlgorithm::Sha1.digest(&[&verifier]);
+        hash.resize(32, 0);
+        let info = descriptor(
+            ALG_AES_128,
+            128,
+            &aes_ecb_encrypt(&key, &verifier).unwrap(),
+            &aes_ecb_encrypt(&key, &hash).unwrap(),
+        );
+        let mut padded = plain.clone();
+        padded.resize(112, 0);
+        let stream = package_stream(plain.len(), aes_ecb_encrypt(&key, &padded).unwrap());
+
+        assert_eq!(decrypt(&info, &stream, "Password1").unwrap(), plain);
+        assert!(matches!(
+            decrypt(&info, &stream, "password1"),
+            Err(Error::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_rc4_cryptoapi() {
+        let plain = b"rc4 package".repeat(100);
+        let template = StandardInfo::parse(&descriptor(ALG_RC4, 128, &[0; 16], &[0; 20])).unwrap();
+        let password_hash = HashAlgorithm::Sha1.digest(&[&template.salt, &utf16le("secret")]);
+
+        let mut verifier = [0x24u8; 16].to_vec();
+        let mut hash = HashAlgorithm::Sha1.digest(&[&verifier]);
+        let mut rc4 = Rc4::new(&template.rc4_key(&password_hash, 0));
+        rc4.apply(&mut verifier);
+        rc4.apply(&mut hash);
+        let info = descriptor(ALG_RC4, 128, &verifier, &hash);
+
+        let mut data = plain.clone();
+        for (block, chunk) in data.chunks_mut(RC4_BLOCK_SIZE).enumerate() {
+            Rc4::new(&template.rc4_key(&password_hash, block as u32)).apply(chunk);
+        }
+        let stream = package_stream(plain.len(), data);
+
+        assert_eq!(decrypt(&info, &stream, "secret").unwrap(), plain);
+        assert!(matches!(
+            decrypt(&info, &stream, "Secret"),
+            Err(Error::WrongPassword)
+        ));
+    }
+}
@@ -0,0 +1,214 @@
+//! Resource limits applied while loading a package
+//!
+//! Protects against zip bombs, huge entry counts and deeply nested XML
+//! when opening untrusted documents.
+
+use crate::error::{Error, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::Read;
+
+/// Limits for loading a package. `None` means unlimited.
+///
+/// # Example
+/// ```rust,ignore
+/// use linch_docx_rs::{Document, LoadOptions};
+///
+/// let options = LoadOptions {
+///     max_total_size: Some(100 * 1024 * 1024),
+///     max_entries: Some(1000),
+///     ..LoadOptions::strict()
+/// };
+/// let doc = Document::open_with("upload.docx", &options)?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    /// Maximum total uncompressed size of all entries, in bytes
+    pub max_total_size: Option<u64>,
+    /// Maximum uncompressed size of a single entry, in bytes
+    pub max_part_size: Option<u64>,
+    /// Maximum number of ZIP entries
+    pub max_entries: Option<usize>,
+    /// Maximum ratio of uncompressed to compressed size of a single entry
+    pub max_compression_ratio: Option<u64>,
+    /// Maximum element nesting depth of XML parts
+    pub max_xml_depth: Option<usize>,
+    /// Read part data on first access instead of up front
+    pub lazy: bool,
+}
+
+impl LoadOptions {
+    /// No limits (same as `Default`)
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Conservative limits for untrusted input
+    pub fn strict() -> Self {
+        Self {
+            max_total_size: Some(256 * 1024 * 1024),
+            max_part_size: Some(64 * 1024 * 1024),
+            max_entries: Some(4096),
+            max_compression_ratio: Some(200),
+            max_xml_depth: Some(256),
+            lazy: false,
+        }
+    }
+
+    /// Check the number of entries in the archive
+    pub(crate) fn check_entry_count(&self, count: usize) -> Result<()> {
+        match self.max_entries {
+            Some(limit) if count > limit => Err(Error::TooManyEntries { count, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Read one entry, failing as soon as a size or ratio limit is crossed.
+    ///
+    /// `total` is the number of bytes read from earlier entries and is
+    /// advanced by the size of this one.
+    pub(crate) fn read_entry<R: Read>(
+        &self,
+        reader: &mut R,
+        name: &str,
+        declared_size: u64,
+        compressed_size: u64,
+        total: &mut u64,
+    ) -> Result<Vec<u8>> {
+        // Fail early on the declared size; the actual size is checked below
+        self.check_entry_size(name, declared_size, compressed_size, *total)?;
+
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..n]);
+            self.check_entry_size(name, data.len() as u64, compressed_size, *total)?;
+        }
+
+        *total += data.len() as u64;
+        Ok(data)
+    }
+
+    /// Check an entry's size against the size and ratio limits
+    pub(crate) fn check_entry_size(
+        &self,
+        name: &str,
+        size: u64,
+        compressed: u64,
+        total: u64,
+    ) -> Result<()> {
+        if let Some(limit) = self.max_part_size {
+            if size > limit {
+                return Err(Error::PartTooLarge {
+                    name: name.to_string(),
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.max_total_size {
+            if total.saturating_add(size) > limit {
+                return Err(Error::PackageTooLarge { limit });
+            }
+        }
+        if let Some(limit) = self.max_compression_ratio {
+            if size > compressed.max(1).saturating_mul(limit) {
+                return Err(Error::CompressionRatioExceeded {
+                    name: name.to_string(),
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the element nesting depth of an XML entry
+    pub(crate) fn check_xml_depth(&self, name: &str, data: &[u8]) -> Result<()> {
+        let Some(limit) = self.max_xml_depth else {
+            return Ok(());
+        };
+
+        let mut reader = Reader::from_reader(data);
+        let mut buf = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(_) => {
+                    depth += 1;
+                    if depth > limit {
+                        return Err(Error::XmlTooDeep {
+                            name: name.to_string(),
+                            limit,
+                        });
+                    }
+                }
+                Event::End(_) => depth = depth.saturating_sub(1),
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_entry_limits() {
+        let data = vec![b'a'; 10_000];
+        let mut total = 0;
+
+        let options = LoadOptions {
+            max_part_size: Some(5_000),
+            ..Default::default()
+        };
+        let err = options
+            .read_entry(&mut data.as_slice(), "big.xml", 0, 10_000, &mut total)
+            .unwrap_err();
+        assert!(matches!(err, Error::PartTooLarge { limit: 5_000, .. }));
+
+        let options = LoadOptions {
+            max_compression_ratio: Some(100),
+            ..Default::default()
+        };
+        let err = options
+            .read_entry(&mut data.as_slice(), "bomb.xml", 0, 20, &mut total)
+            .unwrap_err();
+        assert!(matches!(err, Error::CompressionRatioExceeded { .. }));
+
+        let options = LoadOptions {
+            max_total_size: Some(15_000),
+            ..Default::default()
+        };
+        options
+            .read_entry(&mut data.as_slice(), "a.xml", 0, 10_000, &mut total)
+            .unwrap();
+        assert_eq!(total, 10_000);
+        let err = options
+            .read_entry(&mut data.as_slice(), "b.xml", 0, 10_000, &mut total)
+            .unwrap_err();
+        assert!(matches!(err, Error::PackageTooLarge { limit: 15_000 }));
+    }
+
+    #[test]
+    fn test_xml_depth() {
+        let xml = format!("<a>{}{}</a>", "<b>".repeat(20), "</b>".repeat(20));
+        let options = LoadOptions {
+            max_xml_depth: Some(10),
+            ..Default::default()
+        };
+        assert!(matches!(
+            options.check_xml_depth("deep.xml", xml.as_bytes()),
+            Err(Error::XmlTooDeep { limit: 10, .. })
+        ));
+        assert!(LoadOptions::strict()
+            .check_xml_depth("deep.xml", xml.as_bytes())
+            .is_ok());
+    }
+}
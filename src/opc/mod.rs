@@ -3,15 +3,21 @@
 //! This module handles the ZIP-based package format used by DOCX files.
 
 mod content_types;
+mod custom_xml;
+mod encryption;
+mod load_options;
 mod package;
 mod part;
 mod part_uri;
 mod relationships;
 
 pub use content_types::{
-    ContentTypes, COMMENTS, CORE_PROPERTIES, ENDNOTES, FOOTER, FOOTNOTES, HEADER, MAIN_DOCUMENT,
-    NUMBERING, RELATIONSHIPS, STYLES, XML,
+    ContentTypes, COMMENTS, COMMENTS_EXTENDED, COMMENTS_IDS, CORE_PROPERTIES, CUSTOM_PROPERTIES,
+    CUSTOM_XML_PROPERTIES, ENDNOTES, EXTENDED_PROPERTIES, FOOTER, FOOTNOTES, HEADER, MAIN_DOCUMENT,
+    NUMBERING, PEOPLE, RELATIONSHIPS, SETTINGS, STYLES, XML,
 };
+pub use custom_xml::CustomXmlPart;
+pub use load_options::LoadOptions;
 pub use package::Package;
 pub use part::Part;
 pub use part_uri::{well_known, PartUri};
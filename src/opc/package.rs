@@ -3,15 +3,21 @@
 //! Handles reading and writing DOCX files as ZIP packages
 
 use crate::error::{Error, Result};
+use crate::opc::encryption;
 use crate::opc::relationships::rel_types;
-use crate::opc::{ContentTypes, Part, PartUri, Relationships};
-use std::collections::HashMap;
+use crate::opc::{ContentTypes, LoadOptions, Part, PartUri, Relationships};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read, Seek, Write};
 use std::path::Path;
+use std::sync::Mutex;
 use zip::read::ZipArchive;
 use zip::write::{FileOptions, ZipWriter};
-use zip::CompressionMethod;
+use zip::{CompressionMethod, DateTime};
+
+const CONTENT_TYPES_ENTRY: &str = "[Content_Types].xml";
+const PACKAGE_RELS_ENTRY: &str = "_rels/.rels";
 
 /// An OPC package (ZIP-based container for DOCX, XLSX, PPTX, etc.)
 #[derive(Debug)]
@@ -22,6 +28,22 @@ pub struct Package {
     relationships: Relationships,
     /// Content types ([Content_Types].xml)
     content_types: ContentTypes,
+    /// ZIP entries of the source package, in their original order
+    entries: Vec<SourceEntry>,
+    /// Source archive kept open when parts are loaded lazily
+    source: Option<LazySource>,
+}
+
+/// A ZIP entry as found in the source package
+#[derive(Clone, Debug)]
+struct SourceEntry {
+    /// Index in the source archive
+    index: usize,
+    name: String,
+    compression: CompressionMethod,
+    last_modified: DateTime,
+    /// Original bytes of entries not stored as parts (content types, relationships)
+    raw: Option<Vec<u8>>,
 }
 
 impl Package {
@@ -31,40 +53,197 @@ impl Package {
             parts: HashMap::new(),
             relationships: Relationships::new(),
             content_types: ContentTypes::new(),
+            entries: Vec::new(),
+            source: None,
         }
     }
 
     /// Open a package from a file path
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, &LoadOptions::default())
+    }
+
+    /// Open a package from a file path, enforcing resource limits
+    ///
+    /// With [`LoadOptions::lazy`] set, the file stays open and part data is
+    /// read on first access.
+    pub fn open_with<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Self> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        Self::from_reader(reader)
+        if options.lazy {
+            Self::from_source(Box::new(reader), options)
+        } else {
+            Self::from_reader_with(reader, options)
+        }
     }
 
     /// Open a package from bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let cursor = Cursor::new(bytes);
-        Self::from_reader(cursor)
+        Self::from_bytes_with(bytes, &LoadOptions::default())
+    }
+
+    /// Open a package from bytes, enforcing resource limits
+    ///
+    /// With [`LoadOptions::lazy`] set, the compressed bytes are copied and
+    /// parts are decompressed on first access.
+    pub fn from_bytes_with(bytes: &[u8], options: &LoadOptions) -> Result<Self> {
+        if options.lazy {
+            Self::from_source(Box::new(Cursor::new(bytes.to_vec())), options)
+        } else {
+            Self::from_reader_with(Cursor::new(bytes), options)
+        }
     }
 
     /// Open a package from a reader
     pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        Self::from_reader_with(reader, &LoadOptions::default())
+    }
+
+    /// Open a package from a reader, enforcing resource limits
+    ///
+    /// All parts are read up front; [`LoadOptions::lazy`] is ignored.
+    pub fn from_reader_with<R: Read + Seek>(mut reader: R, options: &LoadOptions) -> Result<Self> {
+        encryption::check_not_encrypted(&mut reader)?;
         let mut archive = ZipArchive::new(reader)?;
-        let mut package = Self::new();
+        let (package, _) = Self::load(&mut archive, options, false)?;
+        Ok(package)
+    }
 
-        // Step 1: Read [Content_Types].xml
-        package.content_types = Self::read_content_types(&mut archive)?;
+    /// Open a package whose parts are read from `reader` on first access
+    fn from_source(mut reader: Box<dyn ReadSeek>, options: &LoadOptions) -> Result<Self> {
+        encryption::check_not_encrypted(&mut reader)?;
+        let mut archive = ZipArchive::new(reader)?;
+        let (mut package, total) = Self::load(&mut archive, options, true)?;
+        package.source = Some(LazySource {
+            state: Mutex::new(LazyState { archive, total }),
+            options: options.clone(),
+        });
+        Ok(package)
+    }
+
+    /// Open a password-encrypted package from a file path
+    ///
+    /// Packages that are not encrypted are opened as usual.
+    pub fn open_with_password<P: AsRef<Path>>(path: P, password: &str) -> Result<Self> {
+        Self::open_with_password_and_options(path, password, &LoadOptions::default())
+    }
 
-        // Step 2: Read package relationships (/_rels/.rels)
-        package.relationships = Self::read_package_rels(&mut archive)?;
+    /// Open a password-encrypted package from a file path, enforcing resource limits
+    pub fn open_with_password_and_options<P: AsRef<Path>>(
+        path: P,
+        password: &str,
+        options: &LoadOptions,
+    ) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::from_reader_with_password_and_options(BufReader::new(file), password, options)
+    }
 
-        // Step 3: Read all parts
-        package.read_parts(&mut archive)?;
+    /// Open a password-encrypted package from bytes
+    pub fn from_bytes_with_password(bytes: &[u8], password: &str) -> Result<Self> {
+        Self::from_bytes_with_password_and_options(bytes, password, &LoadOptions::default())
+    }
 
-        // Step 4: Read part relationships
-        package.read_part_relationships(&mut archive)?;
+    /// Open a password-encrypted package from bytes, enforcing resource limits
+    pub fn from_bytes_with_password_and_options(
+        bytes: &[u8],
+        password: &str,
+        options: &LoadOptions,
+    ) -> Result<Self> {
+        Self::from_reader_with_password_and_options(Cursor::new(bytes), password, options)
+    }
 
-        Ok(package)
+    /// Open a password-encrypted package from a reader
+    pub fn from_reader_with_password<R: Read + Seek>(reader: R, password: &str) -> Result<Self> {
+        Self::from_reader_with_password_and_options(reader, password, &LoadOptions::default())
+    }
+
+    /// Open a password-encrypted package from a reader, enforcing resource limits
+    ///
+    /// The limits apply to the decrypted package. With [`LoadOptions::lazy`]
+    /// set, the decrypted ZIP is kept in memory and parts are decompressed on
+    /// first access.
+    pub fn from_reader_with_password_and_options<R: Read + Seek>(
+        mut reader: R,
+        password: &str,
+        options: &LoadOptions,
+    ) -> Result<Self> {
+        if !encryption::is_compound_file(&mut reader)? {
+            return Self::from_reader_with(reader, options);
+        }
+        let bytes = encryption::decrypt(reader, password)?;
+        if options.lazy {
+            Self::from_source(Box::new(Cursor::new(bytes)), options)
+        } else {
+            Self::from_reader_with(Cursor::new(bytes), options)
+        }
+    }
+
+    /// Read the package structure; part data is skipped when `lazy` is set.
+    ///
+    /// Returns the package and the number of uncompressed bytes read.
+    fn load<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
+        options: &LoadOptions,
+        lazy: bool,
+    ) -> Result<(Self, u64)> {
+        options.check_entry_count(archive.len())?;
+        let mut package = Self::new();
+
+        // Step 1: Read entries within the limits
+        let (contents, total) = Self::read_entries(archive, options, lazy)?;
+
+        // Step 2: Parse [Content_Types].xml
+        let ct_data = contents
+            .iter()
+            .find(|(entry, _)| entry.name == CONTENT_TYPES_ENTRY)
+            .and_then(|(_, data)| data.as_ref())
+            .ok_or_else(|| Error::MissingPart(CONTENT_TYPES_ENTRY.into()))?;
+        options.check_xml_depth(CONTENT_TYPES_ENTRY, ct_data)?;
+        package.content_types = ContentTypes::from_xml(std::str::from_utf8(ct_data)?)?;
+
+        // Step 3: Package relationships, parts and part relationships
+        let mut part_rels = HashMap::new();
+        for (mut entry, data) in contents {
+            if entry.name == CONTENT_TYPES_ENTRY {
+                entry.raw = data;
+            } else if is_rels_entry(&entry.name) {
+                let data = data.unwrap_or_default();
+                options.check_xml_depth(&entry.name, &data)?;
+                let rels = Relationships::from_xml(std::str::from_utf8(&data)?)?;
+                if entry.name == PACKAGE_RELS_ENTRY {
+                    package.relationships = rels;
+                } else if let Some(owner) = rels_owner(&entry.name) {
+                    part_rels.insert(owner, rels);
+                }
+                entry.raw = Some(data);
+            } else {
+                let uri = PartUri::new(&format!("/{}", entry.name))?;
+                let content_type = package
+                    .content_types
+                    .get(&uri)
+                    .unwrap_or("application/octet-stream")
+                    .to_string();
+                let part = match data {
+                    Some(data) => {
+                        if content_type.ends_with("xml") {
+                            options.check_xml_depth(&entry.name, &data)?;
+                        }
+                        Part::new(uri.clone(), content_type, data)
+                    }
+                    None => Part::unloaded(uri.clone(), content_type),
+                };
+                package.parts.insert(uri, part);
+            }
+            package.entries.push(entry);
+        }
+
+        for (uri, rels) in part_rels {
+            if let Some(part) = package.parts.get_mut(&uri) {
+                part.set_relationships(rels);
+            }
+        }
+
+        Ok((package, total))
     }
 
     /// Save the package to a file
@@ -81,50 +260,127 @@ impl Package {
         Ok(buf)
     }
 
+    /// Save the package to a file, encrypted with a password
+    ///
+    /// Uses agile encryption (AES-256, SHA-512) like current Office versions.
+    pub fn save_with_password<P: AsRef<Path>>(&self, path: P, password: &str) -> Result<()> {
+        std::fs::write(path, self.to_bytes_with_password(password)?)?;
+        Ok(())
+    }
+
+    /// Save the package to bytes, encrypted with a password
+    pub fn to_bytes_with_password(&self, password: &str) -> Result<Vec<u8>> {
+        encryption::encrypt(&self.to_bytes()?, password)
+    }
+
     /// Write the package to a writer
+    ///
+    /// Entries are written in the order of the source package with
+    /// `[Content_Types].xml` first; new parts follow, sorted by name.
+    /// Relationship and content type entries that were not modified are
+    /// copied unchanged, and all timestamps are fixed, so saving the same
+    /// package twice gives identical bytes.
     pub fn write_to<W: Write + Seek>(&self, writer: W) -> Result<()> {
         let mut zip = ZipWriter::new(writer);
-        let options: FileOptions<()> =
-            FileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.write_entries(&mut zip, HashSet::new())?;
+        zip.finish()?;
+        Ok(())
+    }
 
-        // Write [Content_Types].xml
-        zip.start_file("[Content_Types].xml", options)?;
-        self.content_types.write_to(&mut zip)?;
+    /// Write `[Content_Types].xml` to an open archive
+    pub(crate) fn write_content_types<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        written: &mut HashSet<String>,
+    ) -> Result<()> {
+        let source = self.source_entry(CONTENT_TYPES_ENTRY);
+        zip.start_file(CONTENT_TYPES_ENTRY, entry_options(source))?;
+        match source.and_then(|e| e.raw.as_ref()) {
+            Some(raw) if !self.content_types.is_modified() => zip.write_all(raw)?,
+            _ => self.content_types.write_to(&mut *zip)?,
+        }
+        written.insert(CONTENT_TYPES_ENTRY.to_string());
+        Ok(())
+    }
 
-        // Write package relationships
-        if !self.relationships.is_empty() {
-            zip.start_file("_rels/.rels", options)?;
-            self.relationships.write_to(&mut zip)?;
+    /// Write all entries not listed in `written` to an open archive
+    ///
+    /// `[Content_Types].xml` is the first entry written here unless the
+    /// caller has already written it.
+    pub(crate) fn write_entries<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        mut written: HashSet<String>,
+    ) -> Result<()> {
+        // [Content_Types].xml always comes first
+        if !written.contains(CONTENT_TYPES_ENTRY) {
+            self.write_content_types(zip, &mut written)?;
         }
 
-        // Write all parts
-        for (uri, part) in &self.parts {
-            let path = &uri.as_str()[1..]; // Remove leading '/'
-            zip.start_file(path, options)?;
-            zip.write_all(part.data())?;
-
-            // Write part relationships if any
-            if let Some(rels) = part.relationships() {
-                if !rels.is_empty() {
-                    let rels_uri = uri.relationships_uri();
-                    let rels_path = &rels_uri.as_str()[1..];
-                    zip.start_file(rels_path, options)?;
-                    rels.write_to(&mut zip)?;
-                }
+        // Package relationships go next unless the source placed them elsewhere
+        if self.source_entry(PACKAGE_RELS_ENTRY).is_none() {
+            self.write_package_rels(zip, &mut written)?;
+        }
+
+        for entry in &self.entries {
+            if entry.name == PACKAGE_RELS_ENTRY {
+                self.write_package_rels(zip, &mut written)?;
+            } else if let Some(owner) = rels_owner(&entry.name) {
+                self.write_part_rels(zip, &owner, &mut written)?;
+            } else if let Ok(uri) = PartUri::new(&format!("/{}", entry.name)) {
+                self.write_part(zip, &uri, &mut written)?;
             }
         }
 
-        zip.finish()?;
+        // Parts added since the package was read
+        let mut remaining: Vec<&PartUri> = self
+            .parts
+            .keys()
+            .filter(|uri| !written.contains(&uri.as_str()[1..]))
+            .collect();
+        remaining.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for uri in remaining {
+            self.write_part(zip, uri, &mut written)?;
+        }
         Ok(())
     }
 
     /// Get a part by URI
+    ///
+    /// Returns `None` if the part does not exist or, for lazily loaded
+    /// packages, its data cannot be read; use [`Package::try_part`] to
+    /// see the error.
     pub fn part(&self, uri: &PartUri) -> Option<&Part> {
+        self.try_part(uri).ok().flatten()
+    }
+
+    /// Get a part by URI, reading its data from the source archive if needed
+    pub fn try_part(&self, uri: &PartUri) -> Result<Option<&Part>> {
+        match self.parts.get(uri) {
+            Some(part) => {
+                self.ensure_loaded(part)?;
+                Ok(Some(part))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get a part without reading its data from the source archive
+    ///
+    /// For lazily loaded packages the data may not be read yet; only the
+    /// URI, content type and relationships are meant to be used.
+    pub(crate) fn part_entry(&self, uri: &PartUri) -> Option<&Part> {
         self.parts.get(uri)
     }
 
+    /// Get a mutable part without reading its data, for replacing it
+    pub(crate) fn part_entry_mut(&mut self, uri: &PartUri) -> Option<&mut Part> {
+        self.parts.get_mut(uri)
+    }
+
     /// Get a mutable part by URI
     pub fn part_mut(&mut self, uri: &PartUri) -> Option<&mut Part> {
+        self.part(uri)?;
         self.parts.get_mut(uri)
     }
 
@@ -147,8 +403,18 @@ impl Package {
     }
 
     /// Get all parts
+    ///
+    /// For lazily loaded packages this reads every part; parts that cannot
+    /// be read are skipped.
     pub fn parts(&self) -> impl Iterator<Item = (&PartUri, &Part)> {
-        self.parts.iter()
+        self.parts
+            .iter()
+            .filter(|(_, part)| self.ensure_loaded(part).is_ok())
+    }
+
+    /// Check if a part's data has been read (always true unless loaded lazily)
+    pub fn is_part_loaded(&self, uri: &PartUri) -> bool {
+        self.parts.get(uri).is_some_and(Part::is_loaded)
     }
 
     /// Get package-level relationships
@@ -175,7 +441,7 @@ impl Package {
     pub fn part_by_rel_type(&self, rel_type: &str) -> Option<&Part> {
         let rel = self.relationships.by_type(rel_type)?;
         let uri = PartUri::new(&rel.target).ok()?;
-        self.parts.get(&uri)
+        self.part(&uri)
     }
 
     /// Get the main document part
@@ -187,7 +453,7 @@ impl Package {
     pub fn main_document_part_mut(&mut self) -> Option<&mut Part> {
         let rel = self.relationships.by_type(rel_types::OFFICE_DOCUMENT)?;
         let uri = PartUri::new(&rel.target).ok()?;
-        self.parts.get_mut(&uri)
+        self.part_mut(&uri)
     }
 
     /// Add a package-level relationship
@@ -197,29 +463,126 @@ impl Package {
 
     // === Private methods ===
 
-    fn read_content_types<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<ContentTypes> {
-        let mut file = archive
-            .by_name("[Content_Types].xml")
-            .map_err(|_| Error::MissingPart("[Content_Types].xml".into()))?;
+    /// Read a lazily loaded part's data from the source archive
+    fn ensure_loaded(&self, part: &Part) -> Result<()> {
+        let Some(source) = self.source.as_ref().filter(|_| !part.is_loaded()) else {
+            return Ok(());
+        };
+        let name = &part.uri().as_str()[1..];
+        let mut state = source.state.lock().unwrap_or_else(|e| e.into_inner());
+        let LazyState { archive, total } = &mut *state;
+
+        let mut file = archive.by_name(name)?;
+        let (size, compressed_size) = (file.size(), file.compressed_size());
+        let data = source
+            .options
+            .read_entry(&mut file, name, size, compressed_size, total)?;
+        if part.content_type().ends_with("xml") {
+            source.options.check_xml_depth(name, &data)?;
+        }
+        part.load(data);
+        Ok(())
+    }
 
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
+    fn source_entry(&self, name: &str) -> Option<&SourceEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
 
-        ContentTypes::from_xml(&content)
+    fn write_package_rels<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        written: &mut HashSet<String>,
+    ) -> Result<()> {
+        if self.relationships.is_empty() || written.contains(PACKAGE_RELS_ENTRY) {
+            return Ok(());
+        }
+        let source = self.source_entry(PACKAGE_RELS_ENTRY);
+        zip.start_file(PACKAGE_RELS_ENTRY, entry_options(source))?;
+        match source.and_then(|e| e.raw.as_ref()) {
+            Some(raw) if !self.relationships.is_modified() => zip.write_all(raw)?,
+            _ => self.relationships.write_to(&mut *zip)?,
+        }
+        written.insert(PACKAGE_RELS_ENTRY.to_string());
+        Ok(())
     }
 
-    fn read_package_rels<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Relationships> {
-        match archive.by_name("_rels/.rels") {
-            Ok(mut file) => {
-                let mut content = String::new();
-                file.read_to_string(&mut content)?;
-                Relationships::from_xml(&content)
+    /// Write a part, followed by its relationships if the source had none
+    fn write_part<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        uri: &PartUri,
+        written: &mut HashSet<String>,
+    ) -> Result<()> {
+        let path = &uri.as_str()[1..]; // Remove leading '/'
+        let Some(part) = self.parts.get(uri) else {
+            return Ok(());
+        };
+        if written.contains(path) {
+            return Ok(());
+        }
+
+        let source_entry = self.source_entry(path);
+        match (&self.source, source_entry) {
+            // Untouched parts of a lazily loaded package are copied still compressed
+            (Some(source), Some(entry)) if !part.is_modified() => {
+                let mut state = source.state.lock().unwrap_or_else(|e| e.into_inner());
+                let file = state.archive.by_index_raw(entry.index)?;
+                zip.raw_copy_file(file)?;
+            }
+            _ => {
+                zip.start_file(path, entry_options(source_entry))?;
+                zip.write_all(part.data())?;
             }
-            Err(_) => Ok(Relationships::new()),
         }
+        written.insert(path.to_string());
+
+        let rels_uri = uri.relationships_uri();
+        if self.source_entry(&rels_uri.as_str()[1..]).is_none() {
+            self.write_part_rels(zip, uri, written)?;
+        }
+        Ok(())
     }
 
-    fn read_parts<R: Read + Seek>(&mut self, archive: &mut ZipArchive<R>) -> Result<()> {
+    /// Write the relationships of `owner`, unless already written
+    pub(crate) fn write_part_rels<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        owner: &PartUri,
+        written: &mut HashSet<String>,
+    ) -> Result<()> {
+        let Some(rels) = self.parts.get(owner).and_then(|p| p.relationships()) else {
+            return Ok(());
+        };
+        let rels_uri = owner.relationships_uri();
+        let rels_path = &rels_uri.as_str()[1..];
+        if rels.is_empty() || written.contains(rels_path) {
+            return Ok(());
+        }
+
+        let source = self.source_entry(rels_path);
+        zip.start_file(rels_path, entry_options(source))?;
+        match source.and_then(|e| e.raw.as_ref()) {
+            Some(raw) if !rels.is_modified() => zip.write_all(raw)?,
+            _ => rels.write_to(&mut *zip)?,
+        }
+        written.insert(rels_path.to_string());
+        Ok(())
+    }
+
+    /// Read file entries, recording their order, compression and timestamp.
+    ///
+    /// When `lazy` is set only content types and relationships are read;
+    /// other entries are checked against the limits by their declared size.
+    /// Returns the entries and the number of uncompressed bytes read.
+    #[allow(clippy::type_complexity)]
+    fn read_entries<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
+        options: &LoadOptions,
+        lazy: bool,
+    ) -> Result<(Vec<(SourceEntry, Option<Vec<u8>>)>, u64)> {
+        let mut contents = Vec::with_capacity(archive.len());
+        let mut total = 0;
+        let mut declared_total: u64 = 0;
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
             let name = file.name().to_string();
@@ -229,60 +592,84 @@ impl Package {
                 continue;
             }
 
-            // Skip special files
-            if name == "[Content_Types].xml" {
-                continue;
-            }
-
-            // Skip relationship files (handle separately)
-            if name.contains("_rels/") && name.ends_with(".rels") {
-                continue;
-            }
+            let (size, compressed_size) = (file.size(), file.compressed_size());
+            let data = if !lazy || name == CONTENT_TYPES_ENTRY || is_rels_entry(&name) {
+                let data =
+                    options.read_entry(&mut file, &name, size, compressed_size, &mut total)?;
+                Some(data)
+            } else {
+                let read_so_far = total.saturating_add(declared_total);
+                options.check_entry_size(&name, size, compressed_size, read_so_far)?;
+                declared_total = declared_total.saturating_add(size);
+                None
+            };
+
+            let entry = SourceEntry {
+                index: i,
+                name,
+                compression: file.compression(),
+                last_modified: file.last_modified().unwrap_or_default(),
+                raw: None,
+            };
+            contents.push((entry, data));
+        }
+        Ok((contents, total))
+    }
+}
 
-            let uri = PartUri::new(&format!("/{}", name))?;
+/// Object-safe reader for a source archive kept open after loading
+trait ReadSeek: Read + Seek + Send {}
 
-            // Get content type
-            let content_type = self
-                .content_types
-                .get(&uri)
-                .unwrap_or("application/octet-stream")
-                .to_string();
+impl<T: Read + Seek + Send> ReadSeek for T {}
 
-            // Read data
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)?;
+/// Source archive of a lazily loaded package
+struct LazySource {
+    state: Mutex<LazyState>,
+    options: LoadOptions,
+}
 
-            let part = Part::new(uri.clone(), content_type, data);
-            self.parts.insert(uri, part);
-        }
+struct LazyState {
+    archive: ZipArchive<Box<dyn ReadSeek>>,
+    /// Uncompressed bytes read so far, checked against `max_total_size`
+    total: u64,
+}
 
-        Ok(())
+impl fmt::Debug for LazySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazySource")
+            .field("options", &self.options)
+            .finish_non_exhaustive()
     }
+}
 
-    fn read_part_relationships<R: Read + Seek>(
-        &mut self,
-        archive: &mut ZipArchive<R>,
-    ) -> Result<()> {
-        // Collect all part URIs first to avoid borrow issues
-        let part_uris: Vec<PartUri> = self.parts.keys().cloned().collect();
-
-        for uri in part_uris {
-            let rels_path = uri.relationships_uri();
-            let rels_zip_path = rels_path.as_str()[1..].to_string(); // Remove leading '/'
-
-            if let Ok(mut file) = archive.by_name(&rels_zip_path) {
-                let mut content = String::new();
-                file.read_to_string(&mut content)?;
-                let rels = Relationships::from_xml(&content)?;
-
-                if let Some(part) = self.parts.get_mut(&uri) {
-                    part.set_relationships(rels);
-                }
-            }
+/// ZIP options for an entry, keeping the source compression and timestamp
+fn entry_options(source: Option<&SourceEntry>) -> FileOptions<'static, ()> {
+    let (compression, last_modified) = match source {
+        Some(entry) if entry.compression == CompressionMethod::Stored => {
+            (CompressionMethod::Stored, entry.last_modified)
         }
+        Some(entry) => (CompressionMethod::Deflated, entry.last_modified),
+        None => (CompressionMethod::Deflated, DateTime::default()),
+    };
+    FileOptions::default()
+        .compression_method(compression)
+        .last_modified_time(last_modified)
+}
 
-        Ok(())
+/// Whether a ZIP entry holds relationships rather than a part
+fn is_rels_entry(name: &str) -> bool {
+    name.contains("_rels/") && name.ends_with(".rels")
+}
+
+/// Part URI owning a relationships entry such as `word/_rels/document.xml.rels`
+fn rels_owner(name: &str) -> Option<PartUri> {
+    if name == PACKAGE_RELS_ENTRY {
+        return None;
     }
+    let file = name.strip_suffix(".rels")?;
+    let (dir, file) = file.rsplit_once('/')?;
+    let parent = dir.strip_suffix("_rels")?;
+    PartUri::new(&format!("/{}{}", parent, file)).ok()
 }
 
 impl Default for Package {
@@ -314,6 +701,26 @@ mod tests {
         assert_eq!(pkg.part(&uri).unwrap().data(), b"<doc/>");
     }
 
+    #[test]
+    fn test_lazy_load() {
+        let mut pkg = Package::new();
+        let uri = PartUri::new("/word/media/image1.png").unwrap();
+        pkg.add_part(Part::new(uri.clone(), "image/png", vec![1, 2, 3]));
+        let bytes = pkg.to_bytes().unwrap();
+
+        let options = LoadOptions {
+            lazy: true,
+            ..Default::default()
+        };
+        let pkg = Package::from_bytes_with(&bytes, &options).unwrap();
+        assert!(!pkg.is_part_loaded(&uri));
+        assert_eq!(pkg.to_bytes().unwrap(), bytes);
+        assert!(!pkg.is_part_loaded(&uri));
+
+        assert_eq!(pkg.part(&uri).unwrap().data(), &[1, 2, 3]);
+        assert!(pkg.is_part_loaded(&uri));
+    }
+
     #[test]
     fn test_roundtrip_empty() {
         let pkg = Package::new();
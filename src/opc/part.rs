@@ -1,6 +1,7 @@
 //! Part representation for OPC packages
 
 use crate::opc::{PartUri, Relationships};
+use std::sync::OnceLock;
 
 /// A part within an OPC package
 #[derive(Clone, Debug)]
@@ -9,8 +10,8 @@ pub struct Part {
     uri: PartUri,
     /// Content type
     content_type: String,
-    /// Part data
-    data: Vec<u8>,
+    /// Part data (unset until read when the package is loaded lazily)
+    data: OnceLock<Vec<u8>>,
     /// Part relationships (if any)
     relationships: Option<Relationships>,
     /// Whether this part has been modified
@@ -23,7 +24,18 @@ impl Part {
         Self {
             uri,
             content_type: content_type.into(),
-            data,
+            data: OnceLock::from(data),
+            relationships: None,
+            modified: false,
+        }
+    }
+
+    /// Create a part whose data is read from the source archive on first access
+    pub(crate) fn unloaded(uri: PartUri, content_type: impl Into<String>) -> Self {
+        Self {
+            uri,
+            content_type: content_type.into(),
+            data: OnceLock::new(),
             relationships: None,
             modified: false,
         }
@@ -41,20 +53,30 @@ impl Part {
 
     /// Get the raw data
     pub fn data(&self) -> &[u8] {
-        &self.data
+        self.data.get().map(Vec::as_slice).unwrap_or_default()
     }
 
     /// Get data as UTF-8 string
     pub fn data_as_str(&self) -> Result<&str, std::str::Utf8Error> {
-        std::str::from_utf8(&self.data)
+        std::str::from_utf8(self.data())
     }
 
     /// Set the data
     pub fn set_data(&mut self, data: Vec<u8>) {
-        self.data = data;
+        self.data = OnceLock::from(data);
         self.modified = true;
     }
 
+    /// Check if the data has been read
+    pub(crate) fn is_loaded(&self) -> bool {
+        self.data.get().is_some()
+    }
+
+    /// Fill in data read from the source archive
+    pub(crate) fn load(&self, data: Vec<u8>) {
+        let _ = self.data.set(data);
+    }
+
     /// Get relationships
     pub fn relationships(&self) -> Option<&Relationships> {
         self.relationships.as_ref()
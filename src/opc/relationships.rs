@@ -5,23 +5,25 @@
 use crate::error::{Error, Result};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::{Reader, Writer};
-use std::collections::HashMap;
 use std::io::{BufRead, Write};
 
 /// Collection of relationships
 #[derive(Clone, Debug)]
 pub struct Relationships {
-    /// Relationships by ID
-    items: HashMap<String, Relationship>,
+    /// Relationships in document order
+    items: Vec<Relationship>,
     /// Next auto-generated ID number
     next_id: u32,
+    /// Whether relationships were added or removed since parsing
+    modified: bool,
 }
 
 impl Default for Relationships {
     fn default() -> Self {
         Self {
-            items: HashMap::new(),
+            items: Vec::new(),
             next_id: 1, // Start from 1, not 0
+            modified: false,
         }
     }
 }
@@ -74,7 +76,7 @@ impl Relationships {
                     let name = e.name();
                     if name.local_name().as_ref() == b"Relationship" {
                         let rel = parse_relationship(&e)?;
-                        rels.items.insert(rel.id.clone(), rel);
+                        rels.items.push(rel);
                     }
                 }
                 Event::Eof => break,
@@ -112,7 +114,7 @@ impl Relationships {
         xml.write_event(Event::Start(rels_elem))?;
 
         // Relationship elements
-        for rel in self.items.values() {
+        for rel in &self.items {
             let mut rel_elem = BytesStart::new("Relationship");
             rel_elem.push_attribute(("Id", rel.id.as_str()));
             rel_elem.push_attribute(("Type", rel.rel_type.as_str()));
@@ -132,18 +134,18 @@ impl Relationships {
 
     /// Get a relationship by ID
     pub fn get(&self, id: &str) -> Option<&Relationship> {
-        self.items.get(id)
+        self.items.iter().find(|r| r.id == id)
     }
 
     /// Get a relationship by type (returns first match)
     pub fn by_type(&self, rel_type: &str) -> Option<&Relationship> {
-        self.items.values().find(|r| r.rel_type == rel_type)
+        self.items.iter().find(|r| r.rel_type == rel_type)
     }
 
     /// Get all relationships of a given type
     pub fn all_by_type(&self, rel_type: &str) -> Vec<&Relationship> {
         self.items
-            .values()
+            .iter()
             .filter(|r| r.rel_type == rel_type)
             .collect()
     }
@@ -170,17 +172,23 @@ impl Relationships {
             target: target.to_string(),
             target_mode: mode,
         };
-        self.items.insert(id.to_string(), rel);
+        match self.items.iter_mut().find(|r| r.id == id) {
+            Some(existing) => *existing = rel,
+            None => self.items.push(rel),
+        }
+        self.modified = true;
     }
 
     /// Remove a relationship by ID
     pub fn remove(&mut self, id: &str) -> Option<Relationship> {
-        self.items.remove(id)
+        let index = self.items.iter().position(|r| r.id == id)?;
+        self.modified = true;
+        Some(self.items.remove(index))
     }
 
     /// Iterate over all relationships
     pub fn iter(&self) -> impl Iterator<Item = &Relationship> {
-        self.items.values()
+        self.items.iter()
     }
 
     /// Number of relationships
@@ -193,6 +201,11 @@ impl Relationships {
         self.items.is_empty()
     }
 
+    /// Check if relationships were added or removed since parsing
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
     /// Generate a new unique ID
     fn generate_id(&mut self) -> String {
         let id = format!("rId{}", self.next_id);
@@ -204,8 +217,8 @@ impl Relationships {
     fn update_next_id(&mut self) {
         let max_id = self
             .items
-            .keys()
-            .filter_map(|id| id.strip_prefix("rId").and_then(|n| n.parse::<u32>().ok()))
+            .iter()
+            .filter_map(|r| r.id.strip_prefix("rId").and_then(|n| n.parse::<u32>().ok()))
             .max()
             .unwrap_or(0);
 
@@ -229,10 +242,8 @@ fn parse_relationship(element: &BytesStart) -> Result<Relationship> {
             b"Id" => id = Some(value),
             b"Type" => rel_type = Some(value),
             b"Target" => target = Some(value),
-            b"TargetMode" => {
-                if value == "External" {
-                    target_mode = TargetMode::External;
-                }
+            b"TargetMode" if value == "External" => {
+                target_mode = TargetMode::External;
             }
             _ => {}
         }
@@ -288,8 +299,19 @@ pub mod rel_types {
         "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties";
     pub const EXTENDED_PROPERTIES: &str =
         "http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties";
+    pub const CUSTOM_PROPERTIES: &str =
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships/custom-properties";
     pub const COMMENTS: &str =
         "http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments";
+    pub const COMMENTS_EXTENDED: &str =
+        "http://schemas.microsoft.com/office/2011/relationships/commentsExtended";
+    pub const COMMENTS_IDS: &str =
+        "http://schemas.microsoft.com/office/2016/09/relationships/commentsIds";
+    pub const PEOPLE: &str = "http://schemas.microsoft.com/office/2011/relationships/people";
+    pub const CUSTOM_XML: &str =
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships/customXml";
+    pub const CUSTOM_XML_PROPS: &str =
+        "http://schemas.openxmlformats.org/officeDocument/2006/relationships/customXmlProps";
 }
 
 #[cfg(test)]
@@ -352,4 +374,21 @@ mod tests {
         assert_eq!(id1, "rId1");
         assert_eq!(id2, "rId2");
     }
+
+    #[test]
+    fn test_document_order_and_modified() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId9" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="settings.xml"/>
+</Relationships>"#;
+
+        let mut rels = Relationships::from_xml(xml).unwrap();
+        assert!(!rels.is_modified());
+        let ids: Vec<_> = rels.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["rId9", "rId2"]);
+
+        assert_eq!(rels.add(rel_types::NUMBERING, "numbering.xml"), "rId10");
+        assert!(rels.is_modified());
+    }
 }
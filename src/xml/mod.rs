@@ -2,19 +2,25 @@
 
 mod namespace;
 mod raw;
+pub(crate) mod schema;
 
 pub use namespace::*;
+use raw::attribute_value;
+pub(crate) use raw::read_attributes;
 pub use raw::{RawXmlElement, RawXmlNode};
 
 use quick_xml::events::BytesStart;
 
 /// Helper to get attribute value from BytesStart
+///
+/// Entity references are resolved, as in [`RawXmlElement`] attributes, so
+/// the value can be handed straight back to `push_attribute` when writing.
 pub fn get_attr(element: &BytesStart, name: &str) -> Option<String> {
     element
         .attributes()
         .filter_map(|a| a.ok())
         .find(|a| a.key.as_ref() == name.as_bytes())
-        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+        .map(|a| attribute_value(&a))
 }
 
 /// Helper to get w:val attribute (common in OOXML)
@@ -52,6 +58,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_raw_element_attribute_escaping() {
+        let xml = r#"<w:tag w:val="R&amp;D &quot;x&quot;"/>"#;
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        if let Event::Empty(e) = reader.read_event_into(&mut buf).unwrap() {
+            let elem = RawXmlElement::from_empty(&e);
+            assert_eq!(elem.attributes[0].1, r#"R&D "x""#);
+
+            let mut writer = quick_xml::Writer::new(Vec::new());
+            elem.write_to(&mut writer).unwrap();
+            assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), xml);
+        }
+    }
+
+    #[test]
+    fn test_unknown_element_attribute_roundtrip() {
+        let xml = r#"<w:customXml w:element="a&amp;b"><w:attr w:name="q" w:val="&quot;R&amp;D&quot;"/></w:customXml>"#;
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        if let Event::Start(e) = reader.read_event_into(&mut buf).unwrap() {
+            let elem = RawXmlElement::from_reader(&mut reader, &e).unwrap();
+            assert_eq!(elem.attributes[0].1, "a&b");
+            match &elem.children[0] {
+                RawXmlNode::Element(child) => assert_eq!(child.attributes[1].1, r#""R&D""#),
+                other => panic!("unexpected child {:?}", other),
+            }
+
+            // Written back as read, however often it is saved
+            let mut writer = quick_xml::Writer::new(Vec::new());
+            elem.write_to(&mut writer).unwrap();
+            assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), xml);
+        }
+    }
+
+    #[test]
+    fn test_get_attr_unescapes() {
+        let mut reader = Reader::from_str(r#"<w:pStyle w:val="R&amp;D &quot;1&quot;"/>"#);
+        if let Event::Empty(e) = reader.read_event().unwrap() {
+            assert_eq!(get_w_val(&e).as_deref(), Some(r#"R&D "1""#));
+            let raw = RawXmlElement::from_empty(&e);
+            assert_eq!(get_attr(&e, "w:val").unwrap(), raw.attributes[0].1);
+        }
+    }
+
     #[test]
     fn test_namespace_constants() {
         assert!(W.contains("wordprocessingml"));
@@ -10,6 +10,10 @@ pub const WP: &str = "http://schemas.openxmlformats.org/drawingml/2006/wordproce
 pub const A: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
 /// Pictures namespace
 pub const PIC: &str = "http://schemas.openxmlformats.org/drawingml/2006/picture";
+/// Word 2010 shapes namespace (text boxes and other `wps:wsp` shapes)
+pub const WPS: &str = "http://schemas.microsoft.com/office/word/2010/wordprocessingShape";
+/// Office 2016 SVG image namespace
+pub const ASVG: &str = "http://schemas.microsoft.com/office/drawing/2016/SVG/main";
 /// Content Types namespace
 pub const CT: &str = "http://schemas.openxmlformats.org/package/2006/content-types";
 /// Package Relationships namespace
@@ -20,6 +24,21 @@ pub const CP: &str = "http://schemas.openxmlformats.org/package/2006/metadata/co
 pub const DC: &str = "http://purl.org/dc/elements/1.1/";
 /// Dublin Core Terms namespace
 pub const DCTERMS: &str = "http://purl.org/dc/terms/";
+/// Extended (application) properties namespace
+pub const EP: &str = "http://schemas.openxmlformats.org/officeDocument/2006/extended-properties";
+/// Custom properties namespace
+pub const CUSTOM_PROPS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/custom-properties";
+/// Variant types namespace used by extended and custom properties
+pub const VT: &str = "http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes";
+/// Markup compatibility namespace
+pub const MC: &str = "http://schemas.openxmlformats.org/markup-compatibility/2006";
+/// Word 2010 extensions namespace
+pub const W14: &str = "http://schemas.microsoft.com/office/word/2010/wordml";
+/// Word 2012 extensions namespace
+pub const W15: &str = "http://schemas.microsoft.com/office/word/2012/wordml";
+/// Word 2016 comment ID namespace
+pub const W16CID: &str = "http://schemas.microsoft.com/office/word/2016/wordml/cid";
 
 /// Standard namespace declarations for document.xml
 pub fn document_namespaces() -> Vec<(&'static str, &'static str)> {
@@ -29,6 +48,7 @@ pub fn document_namespaces() -> Vec<(&'static str, &'static str)> {
         ("xmlns:wp", WP),
         ("xmlns:a", A),
         ("xmlns:pic", PIC),
+        ("xmlns:w14", W14),
     ]
 }
 
@@ -1,5 +1,6 @@
 //! Raw XML node types for round-trip preservation
 
+use quick_xml::events::attributes::Attribute;
 use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::{Reader, Writer};
 use std::io::BufRead;
@@ -7,7 +8,7 @@ use std::io::BufRead;
 use crate::error::{Error, Result};
 
 /// Raw XML node for preserving unknown elements during round-trip
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RawXmlNode {
     /// Element node
     Element(RawXmlElement),
@@ -18,7 +19,7 @@ pub enum RawXmlNode {
 }
 
 /// Raw XML element with attributes and children
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RawXmlElement {
     /// Full element name (with prefix, e.g., "w:customXml")
     pub name: String,
@@ -45,16 +46,7 @@ impl RawXmlElement {
     pub fn from_reader<R: BufRead>(reader: &mut Reader<R>, start: &BytesStart) -> Result<Self> {
         let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
 
-        let attributes = start
-            .attributes()
-            .filter_map(|a| a.ok())
-            .map(|a| {
-                (
-                    String::from_utf8_lossy(a.key.as_ref()).to_string(),
-                    String::from_utf8_lossy(&a.value).to_string(),
-                )
-            })
-            .collect();
+        let attributes = read_attributes(start);
 
         let mut children = Vec::new();
         let mut buf = Vec::new();
@@ -68,16 +60,7 @@ impl RawXmlElement {
                 Event::Empty(e) => {
                     let elem = Self {
                         name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
-                        attributes: e
-                            .attributes()
-                            .filter_map(|a| a.ok())
-                            .map(|a| {
-                                (
-                                    String::from_utf8_lossy(a.key.as_ref()).to_string(),
-                                    String::from_utf8_lossy(&a.value).to_string(),
-                                )
-                            })
-                            .collect(),
+                        attributes: read_attributes(&e),
                         children: Vec::new(),
                         self_closing: true,
                     };
@@ -85,7 +68,8 @@ impl RawXmlElement {
                 }
                 Event::Text(t) => {
                     let text = t.unescape()?.to_string();
-                    if !text.is_empty() {
+                    // Whitespace only matters inside text elements; elsewhere it is indentation
+                    if !text.trim().is_empty() || (!text.is_empty() && holds_text(&name)) {
                         children.push(RawXmlNode::Text(text));
                     }
                 }
@@ -116,16 +100,7 @@ impl RawXmlElement {
     pub fn from_empty(e: &BytesStart) -> Self {
         Self {
             name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
-            attributes: e
-                .attributes()
-                .filter_map(|a| a.ok())
-                .map(|a| {
-                    (
-                        String::from_utf8_lossy(a.key.as_ref()).to_string(),
-                        String::from_utf8_lossy(&a.value).to_string(),
-                    )
-                })
-                .collect(),
+            attributes: read_attributes(e),
             children: Vec::new(),
             self_closing: true,
         }
@@ -170,6 +145,36 @@ impl RawXmlElement {
     }
 }
 
+/// Attributes of a start tag with entity references resolved
+///
+/// Values are escaped again when written, so they are kept unescaped here.
+pub(crate) fn read_attributes(e: &BytesStart) -> Vec<(String, String)> {
+    e.attributes()
+        .filter_map(|a| a.ok())
+        .map(|a| {
+            (
+                String::from_utf8_lossy(a.key.as_ref()).into_owned(),
+                attribute_value(&a),
+            )
+        })
+        .collect()
+}
+
+/// An attribute value with entity references resolved, or as written if
+/// they cannot be
+pub(crate) fn attribute_value(a: &Attribute) -> String {
+    match a.unescape_value() {
+        Ok(value) => value.into_owned(),
+        Err(_) => String::from_utf8_lossy(&a.value).into_owned(),
+    }
+}
+
+/// Whether an element holds text whose whitespace is significant
+fn holds_text(name: &str) -> bool {
+    let local = name.rsplit(':').next().unwrap_or(name);
+    matches!(local, "t" | "delText" | "instrText" | "delInstrText")
+}
+
 impl RawXmlNode {
     /// Write node to XML writer
     pub fn write_to<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
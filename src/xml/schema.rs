@@ -0,0 +1,404 @@
+//! Child element sequences from the OOXML schema
+//!
+//! Property containers such as `w:pPr` are `xsd:sequence` types: Word rejects a
+//! document when their children appear out of order. The tables below list the
+//! local names of each sequence so that parsed fields and preserved unknown
+//! children can be written back interleaved in schema order.
+
+use crate::error::Result;
+use crate::xml::RawXmlNode;
+use quick_xml::Writer;
+
+/// CT_PPr child sequence (`w:pPr`)
+pub const PPR: &[&str] = &[
+    "pStyle",
+    "keepNext",
+    "keepLines",
+    "pageBreakBefore",
+    "framePr",
+    "widowControl",
+    "numPr",
+    "suppressLineNumbers",
+    "pBdr",
+    "shd",
+    "tabs",
+    "suppressAutoHyphens",
+    "kinsoku",
+    "wordWrap",
+    "overflowPunct",
+    "topLinePunct",
+    "autoSpaceDE",
+    "autoSpaceDN",
+    "bidi",
+    "adjustRightInd",
+    "snapToGrid",
+    "spacing",
+    "ind",
+    "contextualSpacing",
+    "mirrorIndents",
+    "suppressOverlap",
+    "jc",
+    "textDirection",
+    "textAlignment",
+    "textboxTightWrap",
+    "outlineLvl",
+    "divId",
+    "cnfStyle",
+    "rPr",
+    "sectPr",
+    "pPrChange",
+];
+
+/// CT_RPr / CT_ParaRPr child sequence (`w:rPr`)
+///
+/// The revision markers at the start only occur in paragraph mark run properties.
+pub const RPR: &[&str] = &[
+    "ins",
+    "del",
+    "moveFrom",
+    "moveTo",
+    "rStyle",
+    "rFonts",
+    "b",
+    "bCs",
+    "i",
+    "iCs",
+    "caps",
+    "smallCaps",
+    "strike",
+    "dstrike",
+    "outline",
+    "shadow",
+    "emboss",
+    "imprint",
+    "noProof",
+    "snapToGrid",
+    "vanish",
+    "webHidden",
+    "color",
+    "spacing",
+    "w",
+    "kern",
+    "position",
+    "sz",
+    "szCs",
+    "highlight",
+    "u",
+    "effect",
+    "bdr",
+    "shd",
+    "fitText",
+    "vertAlign",
+    "rtl",
+    "cs",
+    "em",
+    "lang",
+    "eastAsianLayout",
+    "specVanish",
+    "oMath",
+    "rPrChange",
+];
+
+/// CT_TcPr child sequence (`w:tcPr`)
+pub const TCPR: &[&str] = &[
+    "cnfStyle",
+    "tcW",
+    "gridSpan",
+    "hMerge",
+    "vMerge",
+    "tcBorders",
+    "shd",
+    "noWrap",
+    "tcMar",
+    "textDirection",
+    "tcFitText",
+    "vAlign",
+    "hideMark",
+    "headers",
+    "cellIns",
+    "cellDel",
+    "cellMerge",
+    "tcPrChange",
+];
+
+/// CT_SectPr child sequence (`w:sectPr`)
+///
+/// Header and footer references form a choice group and may interleave.
+pub const SECTPR: &[&str] = &[
+    "headerReference",
+    "footerReference",
+    "footnotePr",
+    "endnotePr",
+    "type",
+    "pgSz",
+    "pgMar",
+    "paperSrc",
+    "pgBorders",
+    "lnNumType",
+    "pgNumType",
+    "cols",
+    "formProt",
+    "vAlign",
+    "noEndnote",
+    "titlePg",
+    "textDirection",
+    "bidi",
+    "rtlGutter",
+    "docGrid",
+    "printerSettings",
+    "sectPrChange",
+];
+
+/// CT_SdtPr child sequence (`w:sdtPr`)
+///
+/// The control type elements from `equation` on form a choice group.
+pub const SDTPR: &[&str] = &[
+    "rPr",
+    "alias",
+    "tag",
+    "id",
+    "lock",
+    "placeholder",
+    "temporary",
+    "showingPlcHdr",
+    "dataBinding",
+    "label",
+    "tabIndex",
+    "equation",
+    "comboBox",
+    "date",
+    "docPartObj",
+    "docPartList",
+    "dropDownList",
+    "picture",
+    "richText",
+    "text",
+    "citation",
+    "group",
+    "bibliography",
+];
+
+/// CT_Settings child sequence (`w:settings`)
+pub const SETTINGS: &[&str] = &[
+    "writeProtection",
+    "view",
+    "zoom",
+    "removePersonalInformation",
+    "removeDateAndTime",
+    "doNotDisplayPageBoundaries",
+    "displayBackgroundShape",
+    "printPostScriptOverText",
+    "printFractionalCharacterWidth",
+    "printFormsData",
+    "embedTrueTypeFonts",
+    "embedSystemFonts",
+    "saveSubsetFonts",
+    "saveFormsData",
+    "mirrorMargins",
+    "alignBordersAndEdges",
+    "bordersDoNotSurroundHeader",
+    "bordersDoNotSurroundFooter",
+    "gutterAtTop",
+    "hideSpellingErrors",
+    "hideGrammaticalErrors",
+    "activeWritingStyle",
+    "proofState",
+    "formsDesign",
+    "attachedTemplate",
+    "linkStyles",
+    "stylePaneFormatFilter",
+    "stylePaneSortMethod",
+    "documentType",
+    "mailMerge",
+    "revisionView",
+    "trackRevisions",
+    "doNotTrackMoves",
+    "doNotTrackFormatting",
+    "documentProtection",
+    "autoFormatOverride",
+    "styleLockTheme",
+    "styleLockQFSet",
+    "defaultTabStop",
+    "autoHyphenation",
+    "consecutiveHyphenLimit",
+    "hyphenationZone",
+    "doNotHyphenateCaps",
+    "showEnvelope",
+    "summaryLength",
+    "clickAndTypeStyle",
+    "defaultTableStyle",
+    "evenAndOddHeaders",
+    "bookFoldRevPrinting",
+    "bookFoldPrinting",
+    "bookFoldPrintingSheets",
+    "drawingGridHorizontalSpacing",
+    "drawingGridVerticalSpacing",
+    "displayHorizontalDrawingGridEvery",
+    "displayVerticalDrawingGridEvery",
+    "doNotUseMarginsForDrawingGridOrigin",
+    "drawingGridHorizontalOrigin",
+    "drawingGridVerticalOrigin",
+    "doNotShadeFormData",
+    "noPunctuationKerning",
+    "characterSpacingControl",
+    "printTwoOnOne",
+    "strictFirstAndLastChars",
+    "noLineBreaksAfter",
+    "noLineBreaksBefore",
+    "savePreviewPicture",
+    "doNotValidateAgainstSchema",
+    "saveInvalidXml",
+    "ignoreMixedContent",
+    "alwaysShowPlaceholderText",
+    "doNotDemarcateInvalidXml",
+    "saveXmlDataOnly",
+    "useXSLTWhenSaving",
+    "saveThroughXslt",
+    "showXMLTags",
+    "alwaysMergeEmptyNamespace",
+    "updateFields",
+    "hdrShapeDefaults",
+    "footnotePr",
+    "endnotePr",
+    "compat",
+    "docVars",
+    "rsids",
+    "attachedSchema",
+    "themeFontLang",
+    "clrSchemeMapping",
+    "doNotIncludeSubdocsInStats",
+    "doNotAutoCompressPictures",
+    "forceUpgrade",
+    "captions",
+    "readModeInkLockDown",
+    "smartTagType",
+    "shapeDefaults",
+    "doNotEmbedSmartTags",
+    "decimalSymbol",
+    "listSeparator",
+];
+
+/// Extended properties children (`Properties` in app.xml), in the order Word writes them
+pub const APP_PROPERTIES: &[&str] = &[
+    "Template",
+    "TotalTime",
+    "Pages",
+    "Words",
+    "Characters",
+    "Application",
+    "DocSecurity",
+    "Lines",
+    "Paragraphs",
+    "ScaleCrop",
+    "HeadingPairs",
+    "TitlesOfParts",
+    "Manager",
+    "Company",
+    "LinksUpToDate",
+    "CharactersWithSpaces",
+    "SharedDoc",
+    "HyperlinkBase",
+    "HLinks",
+    "HyperlinksChanged",
+    "DigSig",
+    "AppVersion",
+];
+
+/// Write known fields and preserved children interleaved in schema order.
+///
+/// For each name in `order`, `write_known` is called first, followed by any
+/// preserved children with that local name (in their original order).
+/// Preserved children whose names are not in the sequence (e.g. vendor
+/// extensions) stay attached to the preserved child preceding them, or go
+/// last if none does.
+pub fn write_in_order<W, F>(
+    writer: &mut Writer<W>,
+    order: &[&str],
+    unknown: &[RawXmlNode],
+    mut write_known: F,
+) -> Result<()>
+where
+    W: std::io::Write,
+    F: FnMut(&mut Writer<W>, &str) -> Result<()>,
+{
+    let ranks = rank_children(order, unknown);
+
+    for (idx, name) in order.iter().enumerate() {
+        write_known(writer, name)?;
+        for (child, rank) in unknown.iter().zip(&ranks) {
+            if *rank == Some(idx) {
+                child.write_to(writer)?;
+            }
+        }
+    }
+
+    for (child, rank) in unknown.iter().zip(&ranks) {
+        if rank.is_none() {
+            child.write_to(writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Position of each preserved child within the sequence
+fn rank_children(order: &[&str], children: &[RawXmlNode]) -> Vec<Option<usize>> {
+    let mut ranks = Vec::with_capacity(children.len());
+    let mut previous = None;
+
+    for child in children {
+        let own = match child {
+            RawXmlNode::Element(e) => {
+                let local = e.name.rsplit(':').next().unwrap_or(&e.name);
+                // Only the `w:` vocabulary is governed by the sequence
+                let in_w = !e.name.contains(':') || e.name.starts_with("w:");
+                if in_w {
+                    order.iter().position(|n| *n == local)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let rank = own.or(previous);
+        ranks.push(rank);
+        previous = rank;
+    }
+
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::RawXmlElement;
+
+    fn elem(name: &str) -> RawXmlNode {
+        let mut e = RawXmlElement::new(name);
+        e.self_closing = true;
+        RawXmlNode::Element(e)
+    }
+
+    #[test]
+    fn test_interleave_known_and_unknown() {
+        let unknown = vec![elem("w:tabs"), elem("w:widowControl"), elem("w14:ext")];
+
+        let mut buf = Vec::new();
+        let mut writer = Writer::new(&mut buf);
+        write_in_order(&mut writer, PPR, &unknown, |w, name| {
+            if name == "pStyle" || name == "jc" {
+                RawXmlNode::Element(RawXmlElement::new(format!("w:{}", name))).write_to(w)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        let xml = String::from_utf8(buf).unwrap();
+        let pos = |s: &str| xml.find(s).unwrap();
+        assert!(pos("<w:pStyle") < pos("<w:widowControl"));
+        // The extension follows the preserved child it was read after
+        assert!(pos("<w:widowControl") < pos("<w14:ext"));
+        assert!(pos("<w14:ext") < pos("<w:tabs"));
+        assert!(pos("<w:tabs") < pos("<w:jc"));
+    }
+}
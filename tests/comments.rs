@@ -0,0 +1,165 @@
+//! Integration tests for anchored comments
+
+use linch_docx_rs::opc::PartUri;
+use linch_docx_rs::{CommentRange, Document, Error, Package, Run};
+
+#[test]
+fn test_comment_on_paragraph() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Intro");
+    doc.add_paragraph("Needs review");
+
+    let id = doc
+        .add_comment(CommentRange::Paragraph(1), "Ann", "Check this")
+        .unwrap();
+    assert_eq!(id, 1);
+    // Comment markers add no text
+    assert_eq!(doc.paragraph(1).unwrap().text(), "Needs review");
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let comment = doc2.comments().unwrap().get(id).unwrap();
+    assert_eq!(comment.author, "Ann");
+    assert_eq!(comment.text(), "Check this");
+    assert_eq!(
+        comment.anchored_text(&doc2).as_deref(),
+        Some("Needs review")
+    );
+}
+
+#[test]
+fn test_comment_on_runs_and_text() {
+    let mut doc = Document::new();
+    let para = doc.add_paragraph("The ");
+    para.add_run(Run::new("quick"));
+    para.add_run(Run::new(" brown fox"));
+
+    let first = doc
+        .add_comment(
+            CommentRange::Runs {
+                paragraph: 0,
+                start: 1,
+                end: 2,
+            },
+            "Ann",
+            "Adjective",
+        )
+        .unwrap();
+    let location = doc.find_text("brown").remove(0);
+    let second = doc
+        .add_comment(CommentRange::Text { location, len: 5 }, "Bob", "Colour")
+        .unwrap();
+    assert_eq!(second, first + 1);
+    assert_eq!(doc.text(), "The quick brown fox");
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let comments = doc2.comments().unwrap();
+    assert_eq!(
+        comments.get(first).unwrap().anchored_text(&doc2).as_deref(),
+        Some("quick")
+    );
+    assert_eq!(
+        comments
+            .get(second)
+            .unwrap()
+            .anchored_text(&doc2)
+            .as_deref(),
+        Some("brown")
+    );
+}
+
+#[test]
+fn test_comment_range_errors() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Short");
+
+    assert!(matches!(
+        doc.add_comment(CommentRange::Paragraph(3), "Ann", "x"),
+        Err(Error::IndexOutOfBounds { index: 3, max: 1 })
+    ));
+    let location = doc.find_text("Short").remove(0);
+    assert!(doc
+        .add_comment(CommentRange::Text { location, len: 10 }, "Ann", "x")
+        .is_err());
+    assert!(doc.comments().is_none());
+}
+
+#[test]
+fn test_remove_comment() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Keep");
+    doc.add_paragraph("Discuss");
+    let keep = doc
+        .add_comment(CommentRange::Paragraph(0), "Ann", "Fine")
+        .unwrap();
+    let remove = doc
+        .add_comment(CommentRange::Paragraph(1), "Ann", "Drop")
+        .unwrap();
+
+    assert!(doc.remove_comment(remove));
+    assert!(!doc.remove_comment(remove));
+    assert_eq!(doc.paragraph(1).unwrap().content.len(), 1);
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let comments = doc2.comments().unwrap();
+    assert!(comments.get(remove).is_none());
+    assert_eq!(
+        comments.get(keep).unwrap().anchored_text(&doc2).as_deref(),
+        Some("Keep")
+    );
+}
+
+#[test]
+fn test_comment_threads() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Ship it?");
+    let question = doc
+        .add_comment(CommentRange::Paragraph(0), "Ann", "Is this final?")
+        .unwrap();
+    let answer = doc.reply_to_comment(question, "Bot", "Yes").unwrap();
+    // Replies to a reply stay in the same thread
+    let thanks = doc.reply_to_comment(answer, "Ann", "Thanks").unwrap();
+    doc.comments_mut()
+        .get_mut(question)
+        .unwrap()
+        .set_resolved(true);
+    assert!(doc.reply_to_comment(42, "Bot", "?").is_err());
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    for name in ["commentsExtended.xml", "commentsIds.xml", "people.xml"] {
+        let uri = PartUri::new(&format!("/word/{}", name)).unwrap();
+        assert!(pkg.part(&uri).is_some(), "{} missing", name);
+    }
+
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    let comments = doc2.comments().unwrap();
+    let root = comments.get(question).unwrap();
+    assert!(root.is_resolved());
+    let replies: Vec<_> = root.replies(comments).iter().map(|c| c.id).collect();
+    assert_eq!(replies, [answer, thanks]);
+    assert_eq!(comments.get(thanks).unwrap().parent(), Some(question));
+    assert_eq!(
+        comments
+            .get(answer)
+            .unwrap()
+            .anchored_text(&doc2)
+            .as_deref(),
+        Some("Ship it?")
+    );
+    let authors: Vec<_> = comments.people.iter().map(|p| p.author.as_str()).collect();
+    assert_eq!(authors, ["Ann", "Bot"]);
+}
+
+#[test]
+fn test_remove_comment_thread() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Text");
+    let id = doc
+        .add_comment(CommentRange::Paragraph(0), "Ann", "Note")
+        .unwrap();
+    doc.reply_to_comment(id, "Bob", "Reply").unwrap();
+
+    assert!(doc.remove_comment(id));
+    assert!(doc.comments().unwrap().comments.is_empty());
+    assert_eq!(doc.paragraph(0).unwrap().content.len(), 1);
+}
@@ -0,0 +1,184 @@
+//! Integration tests for content controls
+
+use linch_docx_rs::document::{ListItem, SdtLevel, SdtLock};
+use linch_docx_rs::{Document, Package, Sdt, SdtKind};
+
+/// A document whose body is the given WordprocessingML
+fn docx_with_body(body: &str) -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+fn sdt_pr(tag: &str, extra: &str) -> String {
+    format!(
+        r#"<w:sdtPr><w:tag w:val="{}"/><w:id w:val="{}"/>{}</w:sdtPr>"#,
+        tag,
+        tag.len(),
+        extra
+    )
+}
+
+/// Controls at every level: block, run, table rows, cells and cell paragraphs
+fn body() -> String {
+    [
+        format!(
+            r#"<w:sdt>{}<w:sdtContent><w:p><w:r><w:t>Acme Corp</w:t></w:r></w:p></w:sdtContent></w:sdt>"#,
+            sdt_pr("company", "<w:text/>")
+        ),
+        format!(
+            r#"<w:p><w:r><w:t xml:space="preserve">Signed: </w:t></w:r><w:sdt>{}<w:sdtContent><w:r><w:t>☐</w:t></w:r></w:sdtContent></w:sdt></w:p>"#,
+            sdt_pr(
+                "signed",
+                r#"<w14:checkbox><w14:checked w14:val="0"/></w14:checkbox>"#
+            )
+        ),
+        "<w:tbl><w:tblGrid><w:gridCol/><w:gridCol/></w:tblGrid>".to_string(),
+        r#"<w:tr><w:tc><w:p><w:r><w:t>Item</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Qty</w:t></w:r></w:p></w:tc></w:tr>"#.to_string(),
+        format!(
+            r#"<w:sdt>{}<w:sdtContent><w:tr><w:tc><w:p><w:r><w:t>Bolts</w:t></w:r></w:p></w:tc>"#,
+            sdt_pr("items", "")
+        ),
+        format!(
+            r#"<w:sdt>{}<w:sdtContent><w:tc><w:sdt>{}<w:sdtContent><w:p><w:r><w:t>10</w:t></w:r></w:p></w:sdtContent></w:sdt></w:tc></w:sdtContent></w:sdt>"#,
+            sdt_pr("qty-cell", ""),
+            sdt_pr("qty", "")
+        ),
+        "</w:tr></w:sdtContent></w:sdt></w:tbl>".to_string(),
+        r#"<w:p><w:r><w:t>End</w:t></w:r></w:p>"#.to_string(),
+    ]
+    .concat()
+}
+
+#[test]
+fn test_content_controls() {
+    let doc = Document::from_bytes(&docx_with_body(&body())).unwrap();
+    let controls = doc.content_controls();
+    let summary: Vec<_> = controls
+        .iter()
+        .map(|c| (c.tag().unwrap(), c.level, c.text.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("company", SdtLevel::Block, "Acme Corp"),
+            ("signed", SdtLevel::Inline, "☐"),
+            ("items", SdtLevel::Row, "Bolts\t10"),
+            ("qty-cell", SdtLevel::Cell, "10"),
+            ("qty", SdtLevel::Block, "10"),
+        ]
+    );
+    assert!(matches!(
+        controls[0].properties.kind,
+        SdtKind::PlainText { multi_line: false }
+    ));
+    assert_eq!(controls[1].value(), "false");
+
+    // Content stays reachable through the normal model
+    assert_eq!(doc.text(), "Acme Corp\nSigned: ☐\nEnd");
+    let table = doc.table(0).unwrap();
+    assert_eq!(table.row_count(), 2);
+    assert_eq!(table.cell(1, 1).unwrap().text(), "10");
+}
+
+#[test]
+fn test_content_controls_roundtrip() {
+    let mut doc = Document::from_bytes(&docx_with_body(&body())).unwrap();
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    assert!(xml.contains(r#"<w:sdtContent><w:tr><w:tc><w:p><w:r><w:t>Bolts</w:t>"#));
+    assert!(xml.contains(r#"<w:sdtContent><w:tc><w:sdt><w:sdtPr><w:tag w:val="qty"/>"#));
+
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    let tags: Vec<_> = doc2
+        .content_controls()
+        .iter()
+        .map(|c| c.tag().unwrap().to_string())
+        .collect();
+    assert_eq!(tags, ["company", "signed", "items", "qty-cell", "qty"]);
+    assert_eq!(doc2.text(), doc.text());
+}
+
+#[test]
+fn test_set_content_control_value() {
+    let mut doc = Document::from_bytes(&docx_with_body(&body())).unwrap();
+    assert_eq!(
+        doc.set_content_control_value("company", "Globex").unwrap(),
+        1
+    );
+    assert_eq!(doc.set_content_control_value("signed", "true").unwrap(), 1);
+    assert_eq!(doc.set_content_control_value("qty", "25").unwrap(), 1);
+    assert_eq!(doc.set_content_control_value("missing", "x").unwrap(), 0);
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(doc2.text(), "Globex\nSigned: ☒\nEnd");
+    assert_eq!(
+        doc2.find_content_control_by_tag("signed").unwrap().value(),
+        "true"
+    );
+    assert_eq!(doc2.find_content_control_by_tag("qty").unwrap().text, "25");
+    assert_eq!(doc2.table(0).unwrap().cell(1, 1).unwrap().text(), "25");
+}
+
+#[test]
+fn test_create_content_controls() {
+    let mut doc = Document::new();
+    doc.add_content_control(
+        Sdt::plain_text("")
+            .with_placeholder_text("Enter a name")
+            .with_tag("name")
+            .with_alias("Name")
+            .with_lock(SdtLock::SdtLocked),
+    );
+    let para = doc.add_paragraph("Status: ");
+    para.add_content_control(
+        Sdt::dropdown(
+            vec![
+                ListItem::new("Open", "open"),
+                ListItem::new("Closed", "closed"),
+            ],
+            Some("open"),
+        )
+        .with_tag("status"),
+    );
+    doc.add_paragraph("Due: ")
+        .add_content_control(Sdt::date("2024-05-01", "yyyy-MM-dd").with_tag("due"));
+
+    let mut doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let name = doc.find_content_control_by_tag("name").unwrap();
+    assert_eq!(name.alias(), Some("Name"));
+    assert_eq!(name.properties.id, Some(1));
+    assert_eq!(name.properties.lock, Some(SdtLock::SdtLocked));
+    assert!(name.properties.showing_placeholder);
+    assert_eq!(name.value(), "");
+    assert_eq!(name.text, "Enter a name");
+    assert_eq!(
+        doc.find_content_control_by_tag("status").unwrap().value(),
+        "open"
+    );
+    assert_eq!(
+        doc.find_content_control_by_tag("due").unwrap().value(),
+        "2024-05-01T00:00:00Z"
+    );
+    assert_eq!(doc.text(), "Enter a name\nStatus: Open\nDue: 2024-05-01");
+
+    doc.set_content_control_value("name", "Alice").unwrap();
+    doc.set_content_control_value("status", "Closed").unwrap();
+    assert!(doc.set_content_control_value("status", "Pending").is_err());
+    assert_eq!(doc.text(), "Alice\nStatus: Closed\nDue: 2024-05-01");
+    assert!(
+        !doc.find_content_control_by_tag("name")
+            .unwrap()
+            .properties
+            .showing_placeholder
+    );
+}
@@ -0,0 +1,173 @@
+//! Integration tests for custom XML parts and content control data binding
+
+use linch_docx_rs::opc::rel_types;
+use linch_docx_rs::{Document, Package};
+
+const CRM_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><crm:customer xmlns:crm="urn:crm"><crm:name>Acme Corp</crm:name><crm:vip>true</crm:vip><crm:since>2021-03-15</crm:since><crm:tier code="gold"/></crm:customer>"#;
+
+fn bound_sdt(tag: &str, xpath: &str, store: &str, pr: &str, content: &str) -> String {
+    format!(
+        r#"<w:sdt><w:sdtPr><w:tag w:val="{tag}"/><w:dataBinding w:prefixMappings="xmlns:ns0='urn:crm'" w:xpath="{xpath}" w:storeItemID="{store}"/>{pr}</w:sdtPr><w:sdtContent><w:r><w:t>{content}</w:t></w:r></w:sdtContent></w:sdt>"#
+    )
+}
+
+/// A form bound to a CRM custom XML part, plus the part's item ID
+fn crm_form() -> (Vec<u8>, String) {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let part = pkg.add_custom_xml_part(CRM_XML, &["urn:crm"]).unwrap();
+    let id = part.item_id.unwrap();
+
+    let body = [
+        format!(
+            "<w:p><w:r><w:t xml:space=\"preserve\">Customer: </w:t></w:r>{}</w:p>",
+            bound_sdt("name", "/ns0:customer[1]/ns0:name[1]", &id, "<w:text/>", "Name")
+        ),
+        format!(
+            "<w:p><w:r><w:t xml:space=\"preserve\">VIP: </w:t></w:r>{}</w:p>",
+            bound_sdt(
+                "vip",
+                "/ns0:customer[1]/ns0:vip[1]",
+                &id,
+                r#"<w14:checkbox><w14:checked w14:val="0"/></w14:checkbox>"#,
+                "☐"
+            )
+        ),
+        format!(
+            "<w:p><w:r><w:t xml:space=\"preserve\">Since: </w:t></w:r>{}</w:p>",
+            bound_sdt(
+                "since",
+                "/ns0:customer[1]/ns0:since[1]",
+                &id,
+                r#"<w:date><w:dateFormat w:val="yyyy-MM-dd"/></w:date>"#,
+                "Date"
+            )
+        ),
+        format!(
+            "<w:p><w:r><w:t xml:space=\"preserve\">Tier: </w:t></w:r>{}</w:p>",
+            bound_sdt(
+                "tier",
+                "/ns0:customer[1]/ns0:tier[1]/@code",
+                &id,
+                r#"<w:dropDownList><w:listItem w:displayText="Gold" w:value="gold"/><w:listItem w:displayText="Silver" w:value="silver"/></w:dropDownList>"#,
+                "Silver"
+            )
+        ),
+    ]
+    .concat();
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    (pkg.to_bytes().unwrap(), id)
+}
+
+#[test]
+fn test_custom_xml_parts() {
+    let (bytes, id) = crm_form();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let doc_rels = pkg.main_document_part().unwrap().relationships().unwrap();
+    assert_eq!(
+        doc_rels.by_type(rel_types::CUSTOM_XML).unwrap().target,
+        "../customXml/item1.xml"
+    );
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    let parts = doc.custom_xml_parts();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].uri.as_str(), "/customXml/item1.xml");
+    assert_eq!(
+        parts[0].props_uri.as_ref().unwrap().as_str(),
+        "/customXml/itemProps1.xml"
+    );
+    assert_eq!(parts[0].item_id.as_deref(), Some(id.as_str()));
+    assert_eq!(parts[0].schema_refs, ["urn:crm"]);
+    assert_eq!(parts[0].xml, CRM_XML);
+}
+
+#[test]
+fn test_add_custom_xml_part_to_new_document() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Hello");
+    let part = doc.add_custom_xml_part(CRM_XML, &["urn:crm"]).unwrap();
+    let bytes = doc.to_bytes().unwrap();
+
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let doc_rels = pkg.main_document_part().unwrap().relationships().unwrap();
+    assert_eq!(
+        doc_rels.by_type(rel_types::CUSTOM_XML).unwrap().target,
+        "../customXml/item1.xml"
+    );
+    let doc = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc.custom_xml_parts(), [part]);
+    assert_eq!(doc.paragraph(0).unwrap().text(), "Hello");
+}
+
+#[test]
+fn test_refresh_bindings() {
+    let (bytes, id) = crm_form();
+    let mut doc = Document::from_bytes(&bytes).unwrap();
+    let binding = doc
+        .find_content_control_by_tag("name")
+        .unwrap()
+        .properties
+        .data_binding
+        .unwrap();
+    assert_eq!(
+        binding.prefix_mappings.as_deref(),
+        Some("xmlns:ns0='urn:crm'")
+    );
+
+    assert_eq!(doc.refresh_bindings().unwrap(), 4);
+    assert_eq!(
+        doc.text(),
+        "Customer: Acme Corp\nVIP: ☒\nSince: 2021-03-15\nTier: Gold"
+    );
+    // Nothing left to update
+    assert_eq!(doc.refresh_bindings().unwrap(), 0);
+
+    // The CRM rewrites the XML and the document follows
+    let updated = CRM_XML
+        .replace("Acme Corp", "Globex")
+        .replace("true", "false");
+    doc.replace_custom_xml_part(&id, &updated).unwrap();
+    assert_eq!(doc.refresh_bindings().unwrap(), 2);
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        doc.text(),
+        "Customer: Globex\nVIP: ☐\nSince: 2021-03-15\nTier: Gold"
+    );
+    assert_eq!(doc.custom_xml_parts()[0].xml, updated);
+}
+
+#[test]
+fn test_collect_bindings() {
+    let (bytes, id) = crm_form();
+    let mut doc = Document::from_bytes(&bytes).unwrap();
+    doc.refresh_bindings().unwrap();
+    assert_eq!(doc.collect_bindings().unwrap(), 0);
+
+    doc.set_content_control_value("name", "Initech & Co")
+        .unwrap();
+    doc.set_content_control_value("tier", "Silver").unwrap();
+    doc.set_content_control_value("since", "2024-01-02")
+        .unwrap();
+    assert_eq!(doc.collect_bindings().unwrap(), 3);
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let part = doc.package().custom_xml_part(&id).unwrap();
+    assert!(part
+        .xml
+        .starts_with(r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#));
+    assert!(part.xml.contains("<crm:name>Initech &amp; Co</crm:name>"));
+    assert!(part.xml.contains(r#"<crm:tier code="silver"/>"#));
+    assert!(part
+        .xml
+        .contains("<crm:since>2024-01-02T00:00:00Z</crm:since>"));
+    assert!(part.xml.contains("<crm:vip>true</crm:vip>"));
+}
@@ -0,0 +1,116 @@
+//! Integration tests for the streaming DocumentWriter
+
+use linch_docx_rs::{
+    AnchoredImage, DocumentWriter, ImageData, InlineImage, PageOrientation, Paragraph, Run,
+    SectionProperties, Style, StyleType, Table,
+};
+use linch_docx_rs::{Document, Package, PartUri};
+use std::io::Cursor;
+
+#[test]
+fn test_stream_large_document() {
+    let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+    for i in 0..5_000 {
+        writer.write_text(&format!("Row {}", i)).unwrap();
+    }
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc.paragraph_count(), 5_000);
+    assert_eq!(doc.paragraph(4_999).unwrap().text(), "Row 4999");
+}
+
+#[test]
+fn test_stream_with_styles_numbering_and_image() {
+    let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+    writer.styles_mut().add(Style {
+        style_type: Some(StyleType::Paragraph),
+        style_id: "Report".into(),
+        name: Some("Report".into()),
+        ..Default::default()
+    });
+    let num_id = writer.numbering_mut().add_bullet_list();
+    writer.core_properties_mut().title = Some("Report".into());
+
+    let mut heading = Paragraph::new("Summary");
+    heading.set_style("Report");
+    writer.write_paragraph(&heading).unwrap();
+
+    let mut item = Paragraph::new("First point");
+    item.set_numbering(num_id, 0);
+    writer.write_paragraph(&item).unwrap();
+
+    let r_id = writer.add_image(ImageData::png(vec![0x89, b'P', b'N', b'G']));
+    let mut run = Run::new("");
+    run.add_image(InlineImage::from_cm(&r_id, 2.0, 1.0));
+    let mut para = Paragraph::default();
+    para.add_run(run);
+    writer.write_paragraph(&para).unwrap();
+
+    writer.write_table(&Table::new(3, 2)).unwrap();
+
+    let mut sect = SectionProperties::default();
+    sect.set_a4_landscape();
+    writer.set_section_properties(sect);
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc.paragraph(0).unwrap().style(), Some("Report"));
+    assert!(doc.style("Report").is_some());
+    assert!(doc.is_bullet_list_item(doc.paragraph(1).unwrap()));
+    assert_eq!(
+        doc.core_properties().unwrap().title.as_deref(),
+        Some("Report")
+    );
+    assert_eq!(doc.table_count(), 1);
+    assert_eq!(
+        doc.section_properties().unwrap().orientation(),
+        Some(&PageOrientation::Landscape)
+    );
+
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let image = PartUri::new("/word/media/image1.png").unwrap();
+    assert_eq!(pkg.part(&image).unwrap().content_type(), "image/png");
+    let rels = pkg.main_document_part().unwrap().relationships().unwrap();
+    assert_eq!(rels.get(&r_id).unwrap().target, "media/image1.png");
+}
+
+#[test]
+fn test_stream_output_is_deterministic() {
+    let write = || {
+        let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.write_text("Hello").unwrap();
+        writer.finish().unwrap().into_inner()
+    };
+    assert_eq!(write(), write());
+}
+
+#[test]
+fn test_stream_content_types_first() {
+    let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+    writer.write_text("Hello").unwrap();
+    writer.add_image(ImageData::png(vec![0x89, b'P', b'N', b'G']));
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.name_for_index(0), Some("[Content_Types].xml"));
+    assert_eq!(archive.name_for_index(1), Some("word/document.xml"));
+}
+
+#[test]
+fn test_stream_drawing_ids() {
+    let mut writer = DocumentWriter::new(Cursor::new(Vec::new())).unwrap();
+    for _ in 0..2 {
+        let mut run = Run::default();
+        run.add_anchored_image(AnchoredImage::new("rId9", 914400, 914400));
+        let mut para = Paragraph::default();
+        para.add_run(run);
+        writer.write_paragraph(&para).unwrap();
+    }
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    assert!(xml.contains(r#"<wp:docPr id="2""#));
+    assert!(xml.contains(r#"<wp:docPr id="3""#));
+}
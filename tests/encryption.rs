@@ -0,0 +1,110 @@
+//! Integration tests for password-encrypted documents
+
+use linch_docx_rs::{Document, Error, LoadOptions};
+use std::path::Path;
+
+#[test]
+fn test_encrypted_roundtrip() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Top secret");
+    doc.core_properties_mut().title = Some("Encrypted".into());
+    let bytes = doc.to_bytes_with_password("correct horse").unwrap();
+
+    let doc2 = Document::from_bytes_with_password(&bytes, "correct horse").unwrap();
+    assert_eq!(doc2.text(), "Top secret");
+    assert_eq!(
+        doc2.core_properties().unwrap().title.as_deref(),
+        Some("Encrypted")
+    );
+}
+
+#[test]
+fn test_encrypted_errors() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Hidden");
+    let bytes = doc.to_bytes_with_password("pw").unwrap();
+
+    assert!(matches!(
+        Document::from_bytes(&bytes),
+        Err(Error::Encrypted)
+    ));
+    assert!(matches!(
+        Document::from_bytes_with_password(&bytes, "wrong"),
+        Err(Error::WrongPassword)
+    ));
+}
+
+#[test]
+fn test_encrypted_file() {
+    let path = Path::new("target/test_encrypted.docx");
+
+    let mut doc = Document::new();
+    doc.add_paragraph("On disk");
+    doc.save_with_password(path, "pw").unwrap();
+
+    assert!(matches!(Document::open(path), Err(Error::Encrypted)));
+    let doc2 = Document::open_with_password(path, "pw").unwrap();
+    assert_eq!(doc2.text(), "On disk");
+
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_password_ignored_for_plain_document() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Plain");
+    let bytes = doc.to_bytes().unwrap();
+
+    let doc2 = Document::from_bytes_with_password(&bytes, "unused").unwrap();
+    assert_eq!(doc2.text(), "Plain");
+}
+
+/// Files from tests/fixtures/create_encrypted_docx.py, which encrypts
+/// independently of this crate
+#[test]
+fn test_independently_encrypted_fixtures() {
+    for name in [
+        "encrypted_agile_sha512.docx",
+        "encrypted_agile_sha1.docx",
+        "encrypted_standard.docx",
+        "encrypted_rc4_cryptoapi.docx",
+        "encrypted_rc4_cryptoapi_40bit.docx",
+    ] {
+        let path = Path::new("tests/fixtures").join(name);
+        assert!(matches!(Document::open(&path), Err(Error::Encrypted)));
+        assert!(matches!(
+            Document::open_with_password(&path, "password1234_"),
+            Err(Error::WrongPassword)
+        ));
+
+        let doc = Document::open_with_password(&path, "Password1234_").unwrap();
+        assert_eq!(doc.paragraph_count(), 300, "{}", name);
+        assert_eq!(
+            doc.paragraph(299).unwrap().text(),
+            "Paragraph 300 of the encrypted fixture"
+        );
+    }
+}
+
+#[test]
+fn test_encrypted_load_options() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Limited");
+    let bytes = doc.to_bytes_with_password("pw").unwrap();
+
+    let strict = LoadOptions {
+        max_entries: Some(2),
+        ..LoadOptions::default()
+    };
+    assert!(matches!(
+        Document::from_bytes_with_password_and_options(&bytes, "pw", &strict),
+        Err(Error::TooManyEntries { limit: 2, .. })
+    ));
+
+    let lazy = LoadOptions {
+        lazy: true,
+        ..LoadOptions::strict()
+    };
+    let doc = Document::from_bytes_with_password_and_options(&bytes, "pw", &lazy).unwrap();
+    assert_eq!(doc.text(), "Limited");
+}
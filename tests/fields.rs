@@ -0,0 +1,127 @@
+//! Integration tests for fields
+
+use linch_docx_rs::{Document, FieldContext, FieldDisplay, FieldInstruction, Package};
+
+/// A document whose body is the given WordprocessingML
+fn docx_with_body(body: &str) -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+/// A TOC field whose result spans two paragraphs
+const TOC: &str = concat!(
+    r#"<w:p><w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+    r#"<w:r><w:instrText xml:space="preserve"> TOC \o "1-3" \h \z \u </w:instrText></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>Introduction</w:t></w:r></w:p>"#,
+    r#"<w:p><w:r><w:t>Summary</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+    r#"<w:p><w:r><w:t xml:space="preserve">Printed </w:t></w:r>"#,
+    r#"<w:fldSimple w:instr=" DATE \@ &quot;yyyy-MM-dd&quot; "><w:r><w:t>2024-05-01</w:t></w:r></w:fldSimple></w:p>"#,
+);
+
+#[test]
+fn test_document_fields() {
+    let doc = Document::from_bytes(&docx_with_body(TOC)).unwrap();
+    let fields = doc.fields();
+    assert_eq!(fields.len(), 2);
+
+    assert_eq!(fields[0].field_type(), "TOC");
+    assert_eq!(fields[0].instruction.switch_value("o"), Some("1-3"));
+    assert_eq!(fields[0].result, "Introduction\nSummary");
+
+    assert!(fields[1].is_simple);
+    assert_eq!(fields[1].instruction.date_format(), Some("yyyy-MM-dd"));
+    assert_eq!(fields[1].result, "2024-05-01");
+
+    let para = doc.paragraph(2).unwrap();
+    assert_eq!(para.text(), "Printed 2024-05-01");
+    assert_eq!(
+        para.text_with(FieldDisplay::Codes),
+        r#"Printed { DATE \@ "yyyy-MM-dd" }"#
+    );
+}
+
+#[test]
+fn test_insert_fields_roundtrip() {
+    let mut doc = Document::new();
+    let para = doc.add_paragraph("Page ");
+    para.add_field("PAGE", "1");
+    para.add_run(linch_docx_rs::Run::new(" of "));
+    para.add_field("NUMPAGES", "1");
+    let date = FieldInstruction::new("DATE").with_switch_value("@", "d MMMM yyyy");
+    doc.add_empty_paragraph()
+        .add_simple_field(date.to_string(), "1 May 2024");
+
+    let bytes = doc.to_bytes().unwrap();
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc2.text(), "Page 1 of 1\n1 May 2024");
+
+    let fields = doc2.fields();
+    let types: Vec<_> = fields.iter().map(|f| f.field_type()).collect();
+    assert_eq!(types, ["PAGE", "NUMPAGES", "DATE"]);
+    assert_eq!(fields[2].instruction, date);
+}
+
+/// Fields that can be evaluated offline, plus a layout-dependent PAGE field
+const UPDATABLE: &str = concat!(
+    r#"<w:p><w:bookmarkStart w:id="0" w:name="Client"/><w:r><w:t>Acme Corp</w:t></w:r><w:bookmarkEnd w:id="0"/></w:p>"#,
+    r#"<w:p><w:fldSimple w:instr=" DATE \@ &quot;d MMMM yyyy&quot; "><w:r><w:t>old</w:t></w:r></w:fldSimple></w:p>"#,
+    r#"<w:p><w:fldSimple w:instr=" MERGEFIELD FirstName \* Upper "><w:r><w:t>«FirstName»</w:t></w:r></w:fldSimple></w:p>"#,
+    r#"<w:p><w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText> REF Client \h </w:instrText></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>Old</w:t></w:r><w:r><w:t> name</w:t></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+    r#"<w:p><w:fldSimple w:instr=" SEQ Figure "><w:r><w:t>9</w:t></w:r></w:fldSimple>"#,
+    r#"<w:r><w:t>, </w:t></w:r><w:fldSimple w:instr=" SEQ Figure \* ROMAN "><w:r><w:t>9</w:t></w:r></w:fldSimple></w:p>"#,
+    r#"<w:p><w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText xml:space="preserve"> IF </w:instrText></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText> MERGEFIELD Total </w:instrText></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>0</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+    r#"<w:r><w:instrText xml:space="preserve"> &gt; 100 "Large order" "Small order" </w:instrText></w:r>"#,
+    r#"<w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>"#,
+    r#"<w:p><w:fldSimple w:instr=" AUTHOR "/><w:r><w:t xml:space="preserve"> / </w:t></w:r>"#,
+    r#"<w:fldSimple w:instr=" DOCPROPERTY Project "/></w:p>"#,
+    r#"<w:p><w:fldSimple w:instr=" PAGE "><w:r><w:t>1</w:t></w:r></w:fldSimple></w:p>"#,
+);
+
+#[test]
+fn test_update_fields() {
+    let mut doc = Document::from_bytes(&docx_with_body(UPDATABLE)).unwrap();
+    doc.core_properties_mut().creator = Some("Jane Doe".into());
+    doc.custom_properties_mut().set("Project", "Apollo");
+
+    let context = FieldContext::new()
+        .with_now("2024-05-01T09:30:00Z")
+        .with_merge_value("FirstName", "Ada")
+        .with_merge_value("Total", "250");
+    assert_eq!(doc.update_fields(&context), 9);
+
+    let bytes = doc.to_bytes().unwrap();
+    let doc = Document::from_bytes(&bytes).unwrap();
+    let text: Vec<_> = doc.paragraphs().map(|p| p.text()).collect();
+    assert_eq!(
+        text,
+        [
+            "Acme Corp",
+            "1 May 2024",
+            "ADA",
+            "Acme Corp",
+            "1, II",
+            "Large order",
+            "Jane Doe / Apollo",
+            "1",
+        ]
+    );
+
+    let fields = doc.fields();
+    let page = fields.iter().find(|f| f.field_type() == "PAGE").unwrap();
+    assert!(page.dirty);
+    let date = fields.iter().find(|f| f.field_type() == "DATE").unwrap();
+    assert!(!date.dirty);
+}
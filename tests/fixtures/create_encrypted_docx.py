@@ -0,0 +1,299 @@
+#!/usr/bin/env python3
+"""Create password-encrypted DOCX fixtures independently of the crate
+
+The encryption follows MS-OFFCRYPTO directly (agile, standard AES and RC4
+CryptoAPI), using the `cryptography` package for AES and a plain RC4, and the
+OLE compound file is written by hand, so a bug the crate makes symmetrically
+on encrypt and decrypt does not carry over into these files. All use the
+password "Password1234_".
+"""
+import base64
+import hashlib
+import hmac
+import io
+import os
+import struct
+import zipfile
+
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+
+PASSWORD = 'Password1234_'
+PARAGRAPHS = 300
+
+
+def create_plain_docx():
+    """A stored (uncompressed) package spanning several 4096-byte segments"""
+    body = ''.join(
+        '<w:p><w:r><w:t>Paragraph %d of the encrypted fixture</w:t></w:r></w:p>' % i
+        for i in range(1, PARAGRAPHS + 1)
+    )
+    files = {
+        '[Content_Types].xml': '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>'
+        '<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">'
+        '<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>'
+        '<Default Extension="xml" ContentType="application/xml"/>'
+        '<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>'
+        '</Types>',
+        '_rels/.rels': '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>'
+        '<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">'
+        '<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>'
+        '</Relationships>',
+        'word/document.xml': '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>'
+        '<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">'
+        '<w:body>%s</w:body></w:document>' % body,
+    }
+    buf = io.BytesIO()
+    with zipfile.ZipFile(buf, 'w', zipfile.ZIP_STORED) as zf:
+        for name, data in files.items():
+            zf.writestr(zipfile.ZipInfo(name, (2024, 1, 1, 0, 0, 0)), data)
+    return buf.getvalue()
+
+
+def aes(key, data, iv=None):
+    """AES encryption, CBC with an IV or ECB without"""
+    mode = modes.CBC(iv) if iv is not None else modes.ECB()
+    encryptor = Cipher(algorithms.AES(key), mode).encryptor()
+    return encryptor.update(data) + encryptor.finalize()
+
+
+def pad(data, size=16):
+    return data + b'\0' * (-len(data) % size)
+
+
+def fit(data, size, fill):
+    return data[:size] + bytes([fill]) * (size - len(data))
+
+
+# Agile encryption (MS-OFFCRYPTO 2.3.4.10 - 2.3.4.15)
+
+BLOCK_VERIFIER_INPUT = bytes.fromhex('fea7d2763b4b9e79')
+BLOCK_VERIFIER_VALUE = bytes.fromhex('d7aa0f6d3061344e')
+BLOCK_KEY_VALUE = bytes.fromhex('146e0be7abacd0d6')
+BLOCK_HMAC_KEY = bytes.fromhex('5fb2ad010cb9e1f6')
+BLOCK_HMAC_VALUE = bytes.fromhex('a0677f02b22c8433')
+
+
+def agile(package, hash_name, key_bits, spin_count=100000):
+    h = lambda *parts: hashlib.new(hash_name, b''.join(parts)).digest()
+    hash_size = hashlib.new(hash_name).digest_size
+    key_bytes = key_bits // 8
+    key_salt = bytes(range(16))
+    password_salt = bytes(range(16, 32))
+    secret_key = bytes(range(0x40, 0x40 + key_bytes))
+
+    # Password key encryptor
+    hashed = h(password_salt, PASSWORD.encode('utf-16-le'))
+    for i in range(spin_count):
+        hashed = h(struct.pack('<I', i), hashed)
+    derive = lambda block: fit(h(hashed, block), key_bytes, 0x36)
+    password_iv = password_salt
+    verifier_input = bytes(range(0x80, 0x90))
+    encrypted_verifier_input = aes(derive(BLOCK_VERIFIER_INPUT), verifier_input, password_iv)
+    encrypted_verifier_value = aes(
+        derive(BLOCK_VERIFIER_VALUE), pad(h(verifier_input)), password_iv
+    )
+    encrypted_key_value = aes(derive(BLOCK_KEY_VALUE), pad(secret_key), password_iv)
+
+    # Package, in segments with their own IVs
+    stream = struct.pack('<Q', len(package))
+    for index in range(0, len(package), 4096):
+        segment = package[index:index + 4096]
+        iv = h(key_salt, struct.pack('<I', index // 4096))[:16]
+        stream += aes(secret_key, pad(segment), iv)
+
+    # Data integrity
+    hmac_key = bytes(range(0xA0, 0xA0 + hash_size))
+    hmac_value = hmac.new(hmac_key, stream, hash_name).digest()
+    encrypted_hmac_key = aes(secret_key, pad(hmac_key), h(key_salt, BLOCK_HMAC_KEY)[:16])
+    encrypted_hmac_value = aes(
+        secret_key, pad(hmac_value), h(key_salt, BLOCK_HMAC_VALUE)[:16]
+    )
+
+    b64 = lambda data: base64.b64encode(data).decode()
+    params = (
+        'saltSize="16" blockSize="16" keyBits="%d" hashSize="%d" cipherAlgorithm="AES" '
+        'cipherChaining="ChainingModeCBC" hashAlgorithm="%s"'
+        % (key_bits, hash_size, hash_name.upper())
+    )
+    xml = (
+        '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>\r\n'
+        '<encryption xmlns="http://schemas.microsoft.com/office/2006/encryption" '
+        'xmlns:p="http://schemas.microsoft.com/office/2006/keyEncryptor/password" '
+        'xmlns:c="http://schemas.microsoft.com/office/2006/keyEncryptor/certificate">'
+        '<keyData %s saltValue="%s"/>'
+        '<dataIntegrity encryptedHmacKey="%s" encryptedHmacValue="%s"/>'
+        '<keyEncryptors><keyEncryptor uri="http://schemas.microsoft.com/office/2006/keyEncryptor/password">'
+        '<p:encryptedKey spinCount="%d" %s saltValue="%s" encryptedVerifierHashInput="%s" '
+        'encryptedVerifierHashValue="%s" encryptedKeyValue="%s"/>'
+        '</keyEncryptor></keyEncryptors></encryption>'
+        % (
+            params, b64(key_salt), b64(encrypted_hmac_key), b64(encrypted_hmac_value),
+            spin_count, params, b64(password_salt), b64(encrypted_verifier_input),
+            b64(encrypted_verifier_value), b64(encrypted_key_value),
+        )
+    )
+    info = struct.pack('<HHI', 4, 4, 0x40) + xml.encode()
+    return info, stream
+
+
+# Standard encryption, AES-128 with SHA-1 (MS-OFFCRYPTO 2.3.4.5 - 2.3.4.9)
+
+def standard(package):
+    salt = bytes(range(0x10, 0x20))
+    hashed = hashlib.sha1(salt + PASSWORD.encode('utf-16-le')).digest()
+    for i in range(50000):
+        hashed = hashlib.sha1(struct.pack('<I', i) + hashed).digest()
+    final = hashlib.sha1(hashed + struct.pack('<I', 0)).digest()
+    x1 = hashlib.sha1(bytes(b ^ 0x36 for b in fit(final, 64, 0))).digest()
+    x2 = hashlib.sha1(bytes(b ^ 0x5C for b in fit(final, 64, 0))).digest()
+    key = (x1 + x2)[:16]
+
+    verifier = bytes(range(0x30, 0x40))
+    encrypted_verifier = aes(key, verifier)
+    encrypted_verifier_hash = aes(key, pad(hashlib.sha1(verifier).digest(), 32))
+
+    csp = 'Microsoft Enhanced RSA and AES Cryptographic Provider\0'.encode('utf-16-le')
+    flags = 0x24  # fCryptoAPI | fAES
+    header = struct.pack('<IIIIIIII', flags, 0, 0x660E, 0x8004, 128, 0x18, 0, 0) + csp
+    info = struct.pack('<HHI', 4, 2, flags) + struct.pack('<I', len(header)) + header
+    info += struct.pack('<I', 16) + salt + encrypted_verifier
+    info += struct.pack('<I', 20) + encrypted_verifier_hash
+
+    stream = struct.pack('<Q', len(package)) + aes(key, pad(package))
+    return info, stream
+
+
+# RC4 CryptoAPI (MS-OFFCRYPTO 2.3.5.1 - 2.3.5.6), re-keyed every 512 bytes
+
+def rc4(key, data):
+    state = list(range(256))
+    j = 0
+    for i in range(256):
+        j = (j + state[i] + key[i % len(key)]) % 256
+        state[i], state[j] = state[j], state[i]
+    out, i, j = bytearray(), 0, 0
+    for byte in data:
+        i = (i + 1) % 256
+        j = (j + state[i]) % 256
+        state[i], state[j] = state[j], state[i]
+        out.append(byte ^ state[(state[i] + state[j]) % 256])
+    return bytes(out)
+
+
+def rc4_cryptoapi(package, key_bits):
+    salt = bytes(range(0x50, 0x60))
+    hashed = hashlib.sha1(salt + PASSWORD.encode('utf-16-le')).digest()
+
+    def block_key(block):
+        key = hashlib.sha1(hashed + struct.pack('<I', block)).digest()[:key_bits // 8]
+        # 40-bit keys are zero-padded to 128 bits
+        return key.ljust(16, b'\0') if key_bits == 40 else key
+
+    # Verifier and its hash are one RC4 stream under the block 0 key
+    verifier = bytes(range(0x60, 0x70))
+    encrypted = rc4(block_key(0), verifier + hashlib.sha1(verifier).digest())
+
+    csp = 'Microsoft Enhanced Cryptographic Provider v1.0\0'.encode('utf-16-le')
+    flags = 0x04  # fCryptoAPI
+    header = struct.pack('<IIIIIIII', flags, 0, 0x6801, 0x8004, key_bits, 0x01, 0, 0) + csp
+    info = struct.pack('<HHI', 4, 2, flags) + struct.pack('<I', len(header)) + header
+    info += struct.pack('<I', 16) + salt + encrypted[:16]
+    info += struct.pack('<I', 20) + encrypted[16:]
+
+    stream = struct.pack('<Q', len(package))
+    for index in range(0, len(package), 512):
+        stream += rc4(block_key(index // 512), package[index:index + 512])
+    return info, stream
+
+
+# OLE compound file, version 3 (MS-CFB)
+
+FREESECT, ENDOFCHAIN, FATSECT, NOSTREAM = 0xFFFFFFFF, 0xFFFFFFFE, 0xFFFFFFFD, 0xFFFFFFFF
+
+
+def compound_file(streams):
+    sectors, fat = [], []
+
+    def chain(data, size, table, store):
+        if not data:
+            return ENDOFCHAIN
+        start = len(store)
+        count = (len(data) + size - 1) // size
+        for i in range(count):
+            store.append(data[i * size:(i + 1) * size].ljust(size, b'\0'))
+            table.append(start + i + 1 if i < count - 1 else ENDOFCHAIN)
+        return start
+
+    mini_sectors, mini_fat, starts = [], [], {}
+    for name, data in streams:
+        if len(data) < 4096:
+            starts[name] = chain(data, 64, mini_fat, mini_sectors)
+        else:
+            starts[name] = chain(data, 512, fat, sectors)
+    mini_stream = b''.join(mini_sectors)
+    root_start = chain(mini_stream, 512, fat, sectors)
+    mini_fat_data = b''.join(struct.pack('<I', e) for e in mini_fat)
+    mini_fat_data += b'\xff' * (-len(mini_fat_data) % 512)
+    mini_fat_start = chain(mini_fat_data, 512, fat, sectors)
+
+    # Red-black tree of the streams: the middle one black, its siblings red
+    order = sorted(range(len(streams)), key=lambda i: (len(streams[i][0]), streams[i][0].upper()))
+    assert len(order) <= 3
+    middle = order[len(order) // 2]
+
+    def entry(name, kind, color, left, right, child, start, size):
+        encoded = (name + '\0').encode('utf-16-le')
+        return (
+            encoded.ljust(64, b'\0')
+            + struct.pack('<HBBIII', len(encoded), kind, color, left, right, child)
+            + b'\0' * 16 + struct.pack('<IQQIQ', 0, 0, 0, start, size)
+        )
+
+    directory = entry('Root Entry', 5, 1, NOSTREAM, NOSTREAM, middle + 1, root_start, len(mini_stream))
+    for i, (name, data) in enumerate(streams):
+        left = order[0] + 1 if i == middle and len(order) > 1 else NOSTREAM
+        right = order[2] + 1 if i == middle and len(order) > 2 else NOSTREAM
+        color = 1 if i == middle else 0
+        directory += entry(name, 2, color, left, right, NOSTREAM, starts[name], len(data))
+    unused = b'\0' * 68 + struct.pack('<III', NOSTREAM, NOSTREAM, NOSTREAM) + b'\0' * 48
+    directory += unused * (-len(directory) % 512 // 128)
+    directory_start = chain(directory, 512, fat, sectors)
+
+    # FAT sectors go last and are marked in the FAT itself
+    fat_count = 1
+    while (len(fat) + fat_count) > fat_count * 128:
+        fat_count += 1
+    fat_start = len(sectors)
+    fat += [FATSECT] * fat_count
+    fat += [FREESECT] * (fat_count * 128 - len(fat))
+    fat_data = b''.join(struct.pack('<I', e) for e in fat)
+    for i in range(fat_count):
+        sectors.append(fat_data[i * 512:(i + 1) * 512])
+
+    difat = [fat_start + i for i in range(fat_count)] + [FREESECT] * (109 - fat_count)
+    header = bytes.fromhex('d0cf11e0a1b11ae1') + b'\0' * 16
+    header += struct.pack('<HHHHH', 0x3E, 3, 0xFFFE, 9, 6) + b'\0' * 6
+    header += struct.pack(
+        '<IIIIIIIII', 0, fat_count, directory_start, 0, 4096,
+        mini_fat_start if mini_fat else ENDOFCHAIN,
+        len(mini_fat_data) // 512 if mini_fat else 0, ENDOFCHAIN, 0,
+    )
+    header += b''.join(struct.pack('<I', e) for e in difat)
+    return header + b''.join(sectors)
+
+
+if __name__ == '__main__':
+    os.makedirs('tests/fixtures', exist_ok=True)
+    package = create_plain_docx()
+    fixtures = {
+        'encrypted_agile_sha512.docx': agile(package, 'sha512', 256),
+        'encrypted_agile_sha1.docx': agile(package, 'sha1', 128),
+        'encrypted_standard.docx': standard(package),
+        'encrypted_rc4_cryptoapi.docx': rc4_cryptoapi(package, 128),
+        'encrypted_rc4_cryptoapi_40bit.docx': rc4_cryptoapi(package, 40),
+    }
+    for name, (info, stream) in fixtures.items():
+        data = compound_file([('EncryptionInfo', info), ('EncryptedPackage', stream)])
+        with open(os.path.join('tests/fixtures', name), 'wb') as f:
+            f.write(data)
+        print('Created tests/fixtures/%s' % name)
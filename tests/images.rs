@@ -0,0 +1,330 @@
+//! Integration tests for the image inventory and image replacement
+
+use linch_docx_rs::opc::{rel_types, TargetMode, HEADER};
+use linch_docx_rs::{
+    AnchorPosition, AnchoredImage, Document, ImageData, ImageLocation, InlineImage, LoadOptions,
+    Package, Paragraph, Part, PartUri, PositionAlign, RelativeFrom, Run, RunContent, WrapMode,
+};
+
+const NS: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture" xmlns:wps="http://schemas.microsoft.com/office/word/2010/wordprocessingShape" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006""#;
+
+/// A PNG header with a pixel size
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    data.extend(width.to_be_bytes());
+    data.extend(height.to_be_bytes());
+    data.extend([8, 6, 0, 0, 0]);
+    data
+}
+
+/// An SVG with a nominal pixel size
+fn svg(width: u32, height: u32) -> Vec<u8> {
+    format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"/>"#)
+        .into_bytes()
+}
+
+/// An inline picture
+fn inline(r_id: &str, cx: i64, cy: i64, doc_pr: &str) -> String {
+    format!(
+        r#"<w:drawing><wp:inline><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="1" {doc_pr}/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="0" name="image.png"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{r_id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"/></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing>"#
+    )
+}
+
+/// A product-catalog template: a placeholder picture in the body, the same
+/// media in a floating text box, and a logo in the header
+fn catalog() -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    pkg.add_part(Part::new(
+        PartUri::new("/word/media/image1.png").unwrap(),
+        "image/png",
+        png(400, 200),
+    ));
+    pkg.add_part(Part::new(
+        PartUri::new("/word/media/image2.png").unwrap(),
+        "image/png",
+        png(100, 100),
+    ));
+
+    let mut header = Part::new(
+        PartUri::new("/word/header1.xml").unwrap(),
+        HEADER,
+        format!(
+            r#"<w:hdr {NS}><w:p><w:r>{}</w:r></w:p></w:hdr>"#,
+            inline("rId1", 914400, 914400, r#"name="Logo""#)
+        )
+        .into_bytes(),
+    );
+    header
+        .ensure_relationships()
+        .add(rel_types::IMAGE, "media/image2.png");
+    pkg.add_part(header);
+
+    let main = pkg.main_document_part_mut().unwrap();
+    let rels = main.ensure_relationships();
+    rels.add_with_id(
+        "rIdImg",
+        rel_types::IMAGE,
+        "media/image1.png",
+        TargetMode::Internal,
+    );
+    rels.add_with_id(
+        "rIdHdr",
+        rel_types::HEADER,
+        "header1.xml",
+        TargetMode::Internal,
+    );
+
+    let text_box = format!(
+        r#"<mc:AlternateContent><mc:Choice Requires="wps"><w:drawing><wp:anchor behindDoc="0"><wp:simplePos x="0" y="0"/><wp:positionH relativeFrom="page"><wp:posOffset>0</wp:posOffset></wp:positionH><wp:positionV relativeFrom="page"><wp:posOffset>0</wp:posOffset></wp:positionV><wp:extent cx="2000000" cy="1000000"/><wp:wrapNone/><wp:docPr id="2" name="Text Box 1"/><a:graphic><a:graphicData uri="http://schemas.microsoft.com/office/word/2010/wordprocessingShape"><wps:wsp><wps:txbx><w:txbxContent><w:p><w:r>{}</w:r></w:p></w:txbxContent></wps:txbx><wps:bodyPr/></wps:wsp></a:graphicData></a:graphic></wp:anchor></w:drawing></mc:Choice><mc:Fallback><w:pict/></mc:Fallback></mc:AlternateContent>"#,
+        inline("rIdImg", 500000, 250000, r#"name="Thumb""#)
+    );
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document {NS}><w:body><w:p><w:r>{}</w:r></w:p><w:p><w:r>{}</w:r></w:p><w:sectPr><w:headerReference w:type="default" r:id="rIdHdr"/></w:sectPr></w:body></w:document>"#,
+        inline(
+            "rIdImg",
+            1905000,
+            952500,
+            r#"name="Placeholder" descr="SKU photo" title="Product""#
+        ),
+        text_box
+    );
+    main.set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+#[test]
+fn test_images_inventory() {
+    let doc = Document::from_bytes(&catalog()).unwrap();
+    let images = doc.images();
+    assert_eq!(images.len(), 3);
+
+    let product = &images[0];
+    assert_eq!(product.id, 0);
+    assert_eq!(product.location, ImageLocation::Body);
+    assert_eq!(product.r_id, "rIdImg");
+    assert_eq!(
+        product.part_uri.as_ref().unwrap().as_str(),
+        "/word/media/image1.png"
+    );
+    assert_eq!(product.data, png(400, 200));
+    assert_eq!(product.content_type, "image/png");
+    assert_eq!((product.width_emu, product.height_emu), (1905000, 952500));
+    assert_eq!(product.description, "SKU photo");
+    assert_eq!(product.title, "Product");
+    assert!(!product.in_text_box && !product.floating);
+
+    let thumb = &images[1];
+    assert!(thumb.in_text_box);
+    assert_eq!(thumb.name, "Thumb");
+    assert_eq!(thumb.part_uri, product.part_uri);
+    assert_eq!((thumb.width_emu, thumb.height_emu), (500000, 250000));
+
+    let logo = &images[2];
+    assert_eq!(logo.location, ImageLocation::Header("rIdHdr".into()));
+    assert_eq!(
+        logo.part_uri.as_ref().unwrap().as_str(),
+        "/word/media/image2.png"
+    );
+    assert_eq!(logo.data, png(100, 100));
+}
+
+#[test]
+fn test_replace_image_keeps_size() {
+    let mut doc = Document::from_bytes(&catalog()).unwrap();
+    // The logo's media is not shared, so the part is swapped in place
+    doc.replace_image(2, ImageData::jpeg(vec![0xFF, 0xD8, 0xFF, 0xD9]))
+        .unwrap();
+    // The body picture shares its media with the text box
+    doc.replace_image(0, ImageData::png(png(300, 300))).unwrap();
+    assert!(doc.replace_image(3, ImageData::png(png(1, 1))).is_err());
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert_eq!(images[0].data, png(300, 300));
+    assert_eq!(
+        (images[0].width_emu, images[0].height_emu),
+        (1905000, 952500)
+    );
+    assert_ne!(images[0].part_uri, images[1].part_uri);
+    assert_eq!(images[1].data, png(400, 200));
+
+    assert_eq!(images[2].r_id, "rId1");
+    assert_eq!(images[2].content_type, "image/jpeg");
+    assert_eq!(images[2].data, [0xFF, 0xD8, 0xFF, 0xD9]);
+    assert_eq!(images[2].width_emu, 914400);
+    let pkg = doc.package();
+    assert!(pkg
+        .part(&PartUri::new("/word/media/image2.png").unwrap())
+        .is_none());
+}
+
+#[test]
+fn test_replace_image_leaves_other_media_unread() {
+    let options = LoadOptions {
+        lazy: true,
+        ..Default::default()
+    };
+    let mut doc = Document::from_bytes_with(&catalog(), &options).unwrap();
+    let product = PartUri::new("/word/media/image1.png").unwrap();
+    let logo = PartUri::new("/word/media/image2.png").unwrap();
+
+    doc.replace_image(2, ImageData::png(png(50, 50))).unwrap();
+    doc.replace_image(0, ImageData::png(png(300, 300))).unwrap();
+    assert!(!doc.package().is_part_loaded(&product));
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert_eq!(images[0].data, png(300, 300));
+    assert_eq!(images[1].data, png(400, 200));
+    assert_eq!(images[2].part_uri.as_ref(), Some(&logo));
+    assert_eq!(images[2].data, png(50, 50));
+}
+
+#[test]
+fn test_replace_image_keep_aspect() {
+    let mut doc = Document::from_bytes(&catalog()).unwrap();
+    doc.replace_image_keep_aspect(0, ImageData::png(png(200, 200)))
+        .unwrap();
+    doc.replace_image_keep_aspect(1, ImageData::png(png(100, 400)))
+        .unwrap();
+    assert!(doc
+        .replace_image_keep_aspect(2, ImageData::png(vec![1, 2, 3]))
+        .is_err());
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert_eq!(
+        (images[0].width_emu, images[0].height_emu),
+        (952500, 952500)
+    );
+    assert_eq!((images[1].width_emu, images[1].height_emu), (62500, 250000));
+    assert_eq!(images[1].data, png(100, 400));
+    // Only the text box now uses the original media, so it was replaced in place
+    assert_eq!(
+        images[1].part_uri.as_ref().unwrap().as_str(),
+        "/word/media/image1.png"
+    );
+}
+
+#[test]
+fn test_add_picture_from_file() {
+    let dir = std::env::temp_dir().join(format!("linch-docx-images-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // A PNG saved with the wrong extension
+    let path = dir.join("logo.bin");
+    std::fs::write(&path, png(192, 96)).unwrap();
+
+    let image = ImageData::from_file(&path).unwrap();
+    assert_eq!(image.content_type, "image/png");
+    assert_eq!(image.natural_size(), Some((1828800, 914400)));
+
+    let mut doc = Document::new();
+    let mut para = Paragraph::default();
+    para.add_picture(&mut doc, &path, None).unwrap();
+    para.add_picture(&mut doc, &path, Some(914400)).unwrap();
+    doc.body_mut().add_paragraph(para);
+
+    let bad = dir.join("notes.txt");
+    std::fs::write(&bad, "hello").unwrap();
+    assert!(ImageData::from_file(&bad).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(
+        (images[0].width_emu, images[0].height_emu),
+        (1828800, 914400)
+    );
+    assert_eq!(
+        (images[1].width_emu, images[1].height_emu),
+        (914400, 457200)
+    );
+    assert_eq!(images[0].name, "logo.bin");
+    assert_eq!(images[1].content_type, "image/png");
+    assert_ne!(images[0].part_uri, images[1].part_uri);
+}
+
+#[test]
+fn test_letterhead_logo_behind_text() {
+    let mut doc = Document::new();
+    let r_id = doc.add_image(ImageData::png(png(192, 96)));
+    let logo = AnchoredImage::builder(r_id, 1828800, 914400)
+        .position_h(AnchorPosition::align(
+            RelativeFrom::Margin,
+            PositionAlign::Right,
+        ))
+        .position_v(AnchorPosition::offset(RelativeFrom::Page, 457200))
+        .wrap(WrapMode::None)
+        .behind_text(true)
+        .name("Letterhead logo")
+        .build();
+    let mut run = Run::default();
+    run.add_anchored_image(logo);
+    let mut para = Paragraph::default();
+    para.add_run(run);
+    doc.body_mut().add_paragraph(para);
+    doc.add_paragraph("Dear customer,");
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let run = doc.paragraph(0).unwrap().runs().next().unwrap();
+    let RunContent::AnchoredDrawing(logo) = &run.content[0] else {
+        panic!("expected a floating image");
+    };
+    assert_eq!(logo.position_h.relative_from, RelativeFrom::Margin);
+    assert_eq!(
+        logo.position_v,
+        AnchorPosition::offset(RelativeFrom::Page, 457200)
+    );
+    assert_eq!(logo.wrap, WrapMode::None);
+    assert!(logo.behind_text);
+
+    let images = doc.images();
+    assert_eq!(images.len(), 1);
+    assert!(images[0].floating && !images[0].in_text_box);
+    assert_eq!(images[0].name, "Letterhead logo");
+    assert_eq!(images[0].data, png(192, 96));
+}
+
+#[test]
+fn test_svg_image_with_fallback() {
+    let mut doc = Document::new();
+    let chart = ImageData::svg(svg(192, 96), png(192, 96));
+    let size = chart.natural_size().unwrap();
+    let (png_r_id, svg_r_id) = doc.add_svg_image(chart).unwrap();
+    assert!(doc.add_svg_image(ImageData::png(png(1, 1))).is_err());
+    for _ in 0..2 {
+        let image = InlineImage::new(&png_r_id, size.0, size.1).with_svg(&svg_r_id);
+        let mut run = Run::default();
+        run.add_image(image);
+        let mut para = Paragraph::default();
+        para.add_run(run);
+        doc.body_mut().add_paragraph(para);
+    }
+
+    let mut doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[0].content_type, "image/png");
+    assert_eq!(images[0].data, png(192, 96));
+    let blip = images[0].svg.as_ref().unwrap();
+    assert_eq!(blip.r_id, svg_r_id);
+    assert_eq!(blip.data, svg(192, 96));
+    assert!(blip.part_uri.as_ref().unwrap().as_str().ends_with(".svg"));
+
+    // A raster replacement drops the SVG; an SVG replacement swaps both blips
+    doc.replace_image(0, ImageData::png(png(10, 10))).unwrap();
+    doc.replace_image(1, ImageData::svg(svg(50, 50), png(50, 50)))
+        .unwrap();
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let images = doc.images();
+    assert!(images[0].svg.is_none());
+    assert_eq!(images[0].data, png(10, 10));
+    assert_eq!(images[1].data, png(50, 50));
+    let blip = images[1].svg.as_ref().unwrap();
+    assert_eq!(blip.r_id, svg_r_id);
+    assert_eq!(blip.data, svg(50, 50));
+}
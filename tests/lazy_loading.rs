@@ -0,0 +1,104 @@
+//! Integration tests for lazy part loading
+
+use linch_docx_rs::{Document, Error, LoadOptions, Package, Part, PartUri};
+
+fn lazy() -> LoadOptions {
+    LoadOptions {
+        lazy: true,
+        ..Default::default()
+    }
+}
+
+/// A styled document with a header and one media part
+fn docx_with_media(data: Vec<u8>) -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("Hello");
+    doc.styles_mut();
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    pkg.add_part(Part::new(
+        PartUri::new("/word/media/image1.png").unwrap(),
+        "image/png",
+        data,
+    ));
+    pkg.to_bytes().unwrap()
+}
+
+fn media_uri() -> PartUri {
+    PartUri::new("/word/media/image1.png").unwrap()
+}
+
+#[test]
+fn test_text_does_not_read_media() {
+    let bytes = docx_with_media(vec![0x89, b'P', b'N', b'G']);
+
+    let doc = Document::from_bytes_with(&bytes, &lazy()).unwrap();
+    assert_eq!(doc.text(), "Hello");
+    assert!(!doc.package().is_part_loaded(&media_uri()));
+    assert!(!doc
+        .package()
+        .is_part_loaded(&PartUri::new("/word/styles.xml").unwrap()));
+
+    assert!(doc.styles().is_some());
+    assert!(doc
+        .package()
+        .is_part_loaded(&PartUri::new("/word/styles.xml").unwrap()));
+}
+
+#[test]
+fn test_lazy_save_matches_eager_save() {
+    let bytes = docx_with_media((0..=255u8).cycle().take(100_000).collect());
+
+    let mut eager = Document::from_bytes(&bytes).unwrap();
+    let mut lazy_doc = Document::from_bytes_with(&bytes, &lazy()).unwrap();
+    assert_eq!(lazy_doc.to_bytes().unwrap(), eager.to_bytes().unwrap());
+    assert!(!lazy_doc.package().is_part_loaded(&media_uri()));
+
+    let saved = Package::from_bytes(&lazy_doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        saved.part(&media_uri()).unwrap().data(),
+        Package::from_bytes(&bytes)
+            .unwrap()
+            .part(&media_uri())
+            .unwrap()
+            .data()
+    );
+}
+
+#[test]
+fn test_lazy_edit_roundtrip() {
+    let bytes = docx_with_media(vec![1, 2, 3]);
+
+    let mut doc = Document::from_bytes_with(&bytes, &lazy()).unwrap();
+    doc.add_paragraph("World");
+    let reopened = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(reopened.text(), "Hello\nWorld");
+    assert!(reopened.styles().is_some());
+}
+
+#[test]
+fn test_limits_apply_to_lazy_parts() {
+    // Declared sizes are checked up front
+    let bytes = docx_with_media(vec![0u8; 1024 * 1024]);
+    let options = LoadOptions {
+        max_compression_ratio: Some(200),
+        ..lazy()
+    };
+    let err = Package::from_bytes_with(&bytes, &options).unwrap_err();
+    assert!(matches!(err, Error::CompressionRatioExceeded { .. }));
+
+    // Content checks run when the part is first read
+    let mut pkg = Package::from_bytes(&docx_with_media(vec![1])).unwrap();
+    let uri = PartUri::new("/customXml/item1.xml").unwrap();
+    let deep = format!("<r>{}{}</r>", "<a>".repeat(500), "</a>".repeat(500));
+    pkg.add_part(Part::new(uri.clone(), "application/xml", deep.into_bytes()));
+    let bytes = pkg.to_bytes().unwrap();
+
+    let options = LoadOptions {
+        max_xml_depth: Some(256),
+        ..lazy()
+    };
+    let pkg = Package::from_bytes_with(&bytes, &options).unwrap();
+    let err = pkg.try_part(&uri).unwrap_err();
+    assert!(matches!(err, Error::XmlTooDeep { .. }));
+    assert!(pkg.part(&uri).is_none());
+}
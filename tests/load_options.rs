@@ -0,0 +1,83 @@
+//! Integration tests for resource-limited package loading
+
+use linch_docx_rs::{Document, Error, LoadOptions, Package, Part, PartUri};
+
+/// A valid document with one extra part
+fn docx_with_part(name: &str, data: Vec<u8>) -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("Hello");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    pkg.add_part(Part::new(
+        PartUri::new(name).unwrap(),
+        "application/xml",
+        data,
+    ));
+    pkg.to_bytes().unwrap()
+}
+
+#[test]
+fn test_default_and_strict_accept_normal_document() {
+    let bytes = docx_with_part("/customXml/item1.xml", b"<root><a/></root>".to_vec());
+
+    assert!(Document::from_bytes(&bytes).is_ok());
+    let doc = Document::from_bytes_with(&bytes, &LoadOptions::strict()).unwrap();
+    assert_eq!(doc.text(), "Hello");
+}
+
+#[test]
+fn test_compression_ratio_limit() {
+    let bytes = docx_with_part("/word/media/blank.bin", vec![0u8; 4 * 1024 * 1024]);
+
+    let err = Document::from_bytes_with(&bytes, &LoadOptions::strict()).unwrap_err();
+    assert!(
+        matches!(err, Error::CompressionRatioExceeded { ref name, .. } if name == "word/media/blank.bin")
+    );
+}
+
+#[test]
+fn test_size_limits() {
+    let bytes = docx_with_part("/word/media/data.bin", vec![7u8; 64 * 1024]);
+
+    let options = LoadOptions {
+        max_part_size: Some(16 * 1024),
+        ..Default::default()
+    };
+    let err = Package::from_bytes_with(&bytes, &options).unwrap_err();
+    assert!(matches!(err, Error::PartTooLarge { .. }));
+
+    let options = LoadOptions {
+        max_total_size: Some(32 * 1024),
+        ..Default::default()
+    };
+    let err = Package::from_bytes_with(&bytes, &options).unwrap_err();
+    assert!(matches!(err, Error::PackageTooLarge { limit } if limit == 32 * 1024));
+}
+
+#[test]
+fn test_entry_count_limit() {
+    let bytes = docx_with_part("/customXml/item1.xml", b"<root/>".to_vec());
+
+    let options = LoadOptions {
+        max_entries: Some(2),
+        ..Default::default()
+    };
+    let err = Document::from_bytes_with(&bytes, &options).unwrap_err();
+    assert!(matches!(err, Error::TooManyEntries { limit: 2, .. }));
+}
+
+#[test]
+fn test_xml_depth_limit() {
+    let deep = format!(
+        "<root>{}{}</root>",
+        "<a>".repeat(10_000),
+        "</a>".repeat(10_000)
+    );
+    let bytes = docx_with_part("/customXml/item1.xml", deep.into_bytes());
+
+    let options = LoadOptions {
+        max_xml_depth: Some(256),
+        ..Default::default()
+    };
+    let err = Document::from_bytes_with(&bytes, &options).unwrap_err();
+    assert!(matches!(err, Error::XmlTooDeep { ref name, .. } if name == "customXml/item1.xml"));
+}
@@ -1,7 +1,11 @@
 //! Integration tests for new features:
 //! styles, properties, section, header/footer, footnotes, text ops, paragraph/run enhancements
 
-use linch_docx_rs::{Alignment, Document, Indentation, LineSpacing, Run, Style, StyleType, Table};
+use linch_docx_rs::document::ParagraphContent;
+use linch_docx_rs::{
+    Alignment, Document, EditRestriction, Indentation, LineSpacing, Package, Paragraph, PartUri,
+    PermStart, PropertyValue, Run, Style, StyleType, Table,
+};
 use std::path::Path;
 
 // ============================================================
@@ -67,6 +71,168 @@ fn test_core_properties_create() {
     assert_eq!(props2.description.as_deref(), Some("Test Description"));
 }
 
+// ============================================================
+// Extended & Custom Properties
+// ============================================================
+
+#[test]
+fn test_extended_properties_create() {
+    let mut doc = Document::new();
+
+    let props = doc.extended_properties_mut();
+    props.company = Some("Acme".into());
+    props.manager = Some("Jane".into());
+    props.pages = Some(4);
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let app = PartUri::new("/docProps/app.xml").unwrap();
+    assert_eq!(
+        pkg.part(&app).unwrap().content_type(),
+        linch_docx_rs::opc::EXTENDED_PROPERTIES
+    );
+
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    let props2 = doc2.extended_properties().unwrap();
+    assert_eq!(props2.company.as_deref(), Some("Acme"));
+    assert_eq!(props2.manager.as_deref(), Some("Jane"));
+    assert_eq!(props2.pages, Some(4));
+}
+
+#[test]
+fn test_custom_properties_roundtrip() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Case file");
+
+    let props = doc.custom_properties_mut();
+    props.set("CaseNumber", "C-2024-0042");
+    props.set("Classification", "Confidential");
+    props.set("Retention", 7);
+
+    let bytes = doc.to_bytes().unwrap();
+    let mut doc2 = Document::from_bytes(&bytes).unwrap();
+    let props2 = doc2.custom_properties().unwrap();
+    assert_eq!(
+        props2.get("CaseNumber").and_then(|v| v.as_text()),
+        Some("C-2024-0042")
+    );
+    assert_eq!(props2.get("Retention"), Some(&PropertyValue::Integer(7)));
+
+    // Editing updates the existing part rather than adding another
+    doc2.custom_properties_mut().set("Classification", "Public");
+    let bytes = doc2.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let custom_rels: Vec<_> = pkg
+        .relationships()
+        .iter()
+        .filter(|r| r.rel_type == linch_docx_rs::opc::rel_types::CUSTOM_PROPERTIES)
+        .collect();
+    assert_eq!(custom_rels.len(), 1);
+    assert_eq!(custom_rels[0].target, "docProps/custom.xml");
+
+    let doc3 = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        doc3.custom_properties()
+            .unwrap()
+            .get("Classification")
+            .and_then(|v| v.as_text()),
+        Some("Public")
+    );
+}
+
+// ============================================================
+// Settings
+// ============================================================
+
+#[test]
+fn test_new_document_settings() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Hello");
+
+    let bytes = doc.to_bytes().unwrap();
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+
+    let settings = doc2.settings().unwrap();
+    assert_eq!(
+        settings.compatibility_mode(),
+        Some(linch_docx_rs::document::CURRENT_COMPATIBILITY_MODE)
+    );
+    assert_eq!(settings.default_tab_stop, Some(720));
+}
+
+#[test]
+fn test_settings_roundtrip() {
+    let mut doc = Document::new();
+    let settings = doc.settings_mut();
+    settings.even_and_odd_headers = true;
+    settings.update_fields = true;
+    settings.default_tab_stop = Some(567);
+
+    let bytes = doc.to_bytes().unwrap();
+    let mut doc2 = Document::from_bytes(&bytes).unwrap();
+    let settings2 = doc2.settings().unwrap();
+    assert!(settings2.even_and_odd_headers);
+    assert!(settings2.update_fields);
+    assert_eq!(settings2.default_tab_stop, Some(567));
+
+    doc2.settings_mut().set_compatibility_mode(14);
+    let doc3 = Document::from_bytes(&doc2.to_bytes().unwrap()).unwrap();
+    assert_eq!(doc3.settings().unwrap().compatibility_mode(), Some(14));
+    assert!(doc3.settings().unwrap().even_and_odd_headers);
+}
+
+// ============================================================
+// Document Protection
+// ============================================================
+
+#[test]
+fn test_protect_with_password() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Locked");
+    doc.protect(EditRestriction::ReadOnly, Some("s3cret"))
+        .unwrap();
+
+    let bytes = doc.to_bytes().unwrap();
+    let mut doc2 = Document::from_bytes(&bytes).unwrap();
+    let protection = doc2.protection().unwrap();
+    assert_eq!(protection.edit, EditRestriction::ReadOnly);
+    assert!(protection.enforcement);
+    assert!(protection.verify_password("s3cret"));
+    assert!(!protection.verify_password("secret"));
+    assert_eq!(doc2.settings().unwrap().default_tab_stop, Some(720));
+
+    doc2.unprotect();
+    let doc3 = Document::from_bytes(&doc2.to_bytes().unwrap()).unwrap();
+    assert!(doc3.protection().is_none());
+}
+
+#[test]
+fn test_editable_ranges_roundtrip() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Fixed text");
+    let mut para = Paragraph::new("Fill in here");
+    para.add_editable_range(PermStart::group("1", "everyone"));
+    doc.body_mut().add_paragraph(para);
+    let mut table = Table::new(1, 1);
+    table
+        .cell_mut(0, 0)
+        .unwrap()
+        .paragraphs_mut()
+        .next()
+        .unwrap()
+        .add_editable_range(PermStart::user("2", "jane@example.com"));
+    doc.add_table(table);
+    doc.protect(EditRestriction::ReadOnly, None).unwrap();
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let ranges = doc2.editable_ranges();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0].ed_group.as_deref(), Some("everyone"));
+    assert_eq!(ranges[1].ed.as_deref(), Some("jane@example.com"));
+    assert_eq!(doc2.paragraph(1).unwrap().text(), "Fill in here");
+    assert!(doc2.protection().unwrap().password.is_none());
+}
+
 // ============================================================
 // Section Properties
 // ============================================================
@@ -309,6 +475,26 @@ fn test_add_hyperlink_and_bookmark() {
     assert!(text.contains("Go to section 1"));
 }
 
+#[test]
+fn test_edit_hyperlink_runs() {
+    let mut doc = Document::new();
+    doc.add_empty_paragraph()
+        .add_internal_link("section1", "Go to section 1");
+
+    let para = doc.paragraph_mut(0).unwrap();
+    let Some(ParagraphContent::Hyperlink(link)) = para.content.first_mut() else {
+        panic!("expected a hyperlink");
+    };
+    link.runs_mut()
+        .next()
+        .unwrap()
+        .set_text("Back to section 1");
+    assert_eq!(link.runs().count(), 1);
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(doc.paragraph(0).unwrap().text(), "Back to section 1");
+}
+
 // ============================================================
 // Footnotes & Endnotes
 // ============================================================
@@ -343,6 +529,42 @@ fn test_endnotes_create() {
     assert_eq!(en.regular_notes().count(), 2);
 }
 
+#[test]
+fn test_note_references() {
+    let mut doc = Document::new();
+    doc.add_paragraph("First claim");
+    doc.add_paragraph("Second claim");
+
+    let footnote = doc.add_footnote(1, "Source A").unwrap();
+    let endnote = doc.add_endnote(0, "Source B").unwrap();
+    assert_eq!((footnote, endnote), (1, 1));
+    assert!(doc.add_footnote(5, "Nowhere").is_err());
+
+    let bytes = doc.to_bytes().unwrap();
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc2.footnote_references(), [(1, footnote)]);
+    assert_eq!(doc2.endnote_references(), [(0, endnote)]);
+    // Reference marks add no text
+    assert_eq!(doc2.text(), "First claim\nSecond claim");
+
+    let reference = doc2.paragraph(1).unwrap().runs().last().unwrap();
+    assert_eq!(reference.footnote_id(), Some(footnote));
+    let style = reference
+        .properties
+        .as_ref()
+        .and_then(|p| p.style.as_deref());
+    assert_eq!(style, Some("FootnoteReference"));
+    assert!(doc2.style("FootnoteReference").is_some());
+
+    let notes = doc2.footnotes().unwrap();
+    let types: Vec<_> = notes.notes.iter().map(|n| n.note_type.as_deref()).collect();
+    assert_eq!(
+        types,
+        [Some("separator"), Some("continuationSeparator"), None]
+    );
+    assert_eq!(notes.get(footnote).unwrap().text(), " Source A");
+}
+
 // ============================================================
 // Units
 // ============================================================
@@ -0,0 +1,156 @@
+//! Integration tests for byte-exact passthrough and deterministic package output
+
+use linch_docx_rs::{Document, Package, PartUri};
+use std::io::{Cursor, Read, Write};
+use zip::write::{FileOptions, ZipWriter};
+use zip::{CompressionMethod, ZipArchive};
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/header1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId7" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+  <w:body>
+    <w:p><w:r><w:t>Hello {{name}}</w:t></w:r></w:p>
+    <w:sectPr><w:headerReference w:type="default" r:id="rId7"/></w:sectPr>
+  </w:body>
+</w:document>"#;
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+
+    <!-- formatting that a re-serialization would not keep -->
+    <w:style w:type="paragraph"   w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+</w:styles>"#;
+
+const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:p><w:r><w:t>Header {{name}}</w:t></w:r></w:p></w:hdr>"#;
+
+/// Build a source package with a non-alphabetical entry order and mixed compression
+fn source_docx() -> Vec<u8> {
+    let mut buf = Vec::new();
+    {
+        let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+        let deflated: FileOptions<()> =
+            FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let stored: FileOptions<()> =
+            FileOptions::default().compression_method(CompressionMethod::Stored);
+
+        let entries = [
+            ("[Content_Types].xml", CONTENT_TYPES, deflated),
+            ("_rels/.rels", PACKAGE_RELS, deflated),
+            ("word/styles.xml", STYLES, stored),
+            ("word/document.xml", DOCUMENT, deflated),
+            ("word/header1.xml", HEADER, deflated),
+            ("word/_rels/document.xml.rels", DOCUMENT_RELS, deflated),
+        ];
+        for (name, data, options) in entries {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf
+}
+
+fn entry_names(bytes: &[u8]) -> Vec<String> {
+    let archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    (0..archive.len())
+        .map(|i| archive.name_for_index(i).unwrap().to_string())
+        .collect()
+}
+
+fn entry(bytes: &[u8], name: &str) -> (Vec<u8>, CompressionMethod) {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut file = archive.by_name(name).unwrap();
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).unwrap();
+    (data, file.compression())
+}
+
+#[test]
+fn test_open_save_is_noop() {
+    let source = source_docx();
+    let mut doc = Document::from_bytes(&source).unwrap();
+    let saved = doc.to_bytes().unwrap();
+
+    assert_eq!(entry_names(&saved), entry_names(&source));
+    for name in entry_names(&source) {
+        assert_eq!(entry(&saved, &name), entry(&source, &name), "{}", name);
+    }
+}
+
+#[test]
+fn test_save_is_deterministic() {
+    let source = source_docx();
+    let mut first = Document::from_bytes(&source).unwrap();
+    first.replace_text("{{name}}", "Alice");
+    let mut second = Document::from_bytes(&source).unwrap();
+    second.replace_text("{{name}}", "Alice");
+
+    assert_eq!(first.to_bytes().unwrap(), second.to_bytes().unwrap());
+    assert_eq!(first.to_bytes().unwrap(), first.to_bytes().unwrap());
+
+    let generate = || {
+        let mut doc = Document::new();
+        doc.add_paragraph("Generated");
+        doc.styles_mut();
+        doc.core_properties_mut().title = Some("Report".into());
+        doc.to_bytes().unwrap()
+    };
+    let bytes = generate();
+    assert_eq!(entry_names(&bytes)[0], "[Content_Types].xml");
+    assert_eq!(bytes, generate());
+}
+
+#[test]
+fn test_only_modified_parts_are_rewritten() {
+    let source = source_docx();
+    let mut doc = Document::from_bytes(&source).unwrap();
+    doc.replace_text("{{name}}", "Alice");
+    let saved = doc.to_bytes().unwrap();
+
+    assert_eq!(entry_names(&saved), entry_names(&source));
+    assert_eq!(
+        entry(&saved, "word/styles.xml"),
+        entry(&source, "word/styles.xml")
+    );
+    assert_eq!(
+        entry(&saved, "word/header1.xml"),
+        entry(&source, "word/header1.xml")
+    );
+    assert_eq!(
+        entry(&saved, "word/_rels/document.xml.rels"),
+        entry(&source, "word/_rels/document.xml.rels")
+    );
+    assert_ne!(
+        entry(&saved, "word/document.xml"),
+        entry(&source, "word/document.xml")
+    );
+
+    let doc2 = Document::from_bytes(&saved).unwrap();
+    assert_eq!(doc2.text(), "Hello Alice");
+    assert_eq!(doc2.default_header().unwrap().text(), "Header {{name}}");
+}
+
+#[test]
+fn test_header_edit_keeps_part_name() {
+    let mut doc = Document::from_bytes(&source_docx()).unwrap();
+    doc.default_header_mut()
+        .unwrap()
+        .add_paragraph("Second line");
+    let saved = doc.to_bytes().unwrap();
+
+    let pkg = Package::from_bytes(&saved).unwrap();
+    let header = pkg
+        .part(&PartUri::new("/word/header1.xml").unwrap())
+        .unwrap();
+    assert!(header.data_as_str().unwrap().contains("Second line"));
+    assert!(!entry_names(&saved).iter().any(|n| n.contains("header_")));
+}
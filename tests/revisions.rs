@@ -0,0 +1,191 @@
+//! Integration tests for tracked changes
+
+use linch_docx_rs::{Document, Package, RevisionKind, Run, TemplateContext};
+
+/// A document whose body is the given WordprocessingML
+fn docx_with_body(body: &str) -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+const REDLINED: &str = concat!(
+    r#"<w:p><w:r><w:t>Hello,</w:t></w:r>"#,
+    r#"<w:del w:id="1" w:author="Ann" w:date="2024-03-01T10:00:00Z"><w:r><w:delText>cruel,</w:delText></w:r></w:del>"#,
+    r#"<w:ins w:id="2" w:author="Bob" w:date="2024-03-02T10:00:00Z"><w:r><w:t>brave,</w:t></w:r></w:ins>"#,
+    r#"<w:r><w:t>world</w:t></w:r></w:p>"#,
+    r#"<w:p><w:pPr><w:rPr><w:ins w:id="3" w:author="Bob"/></w:rPr></w:pPr><w:r><w:t>First</w:t></w:r></w:p>"#,
+    r#"<w:p><w:r><w:rPr><w:b/><w:rPrChange w:id="4" w:author="Ann"><w:rPr/></w:rPrChange></w:rPr><w:t>Second</w:t></w:r></w:p>"#,
+    r#"<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Kept row</w:t></w:r></w:p></w:tc></w:tr>"#,
+    r#"<w:tr><w:trPr><w:del w:id="5" w:author="Ann"/></w:trPr><w:tc><w:p><w:r><w:t>Deleted row</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"#,
+);
+
+#[test]
+fn test_list_revisions() {
+    let doc = Document::from_bytes(&docx_with_body(REDLINED)).unwrap();
+    assert_eq!(doc.paragraph(0).unwrap().text(), "Hello,brave,world");
+
+    let revisions = doc.revisions();
+    let kinds: Vec<_> = revisions.iter().map(|r| r.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            RevisionKind::Deletion,
+            RevisionKind::Insertion,
+            RevisionKind::Insertion,
+            RevisionKind::Formatting,
+            RevisionKind::Deletion,
+        ]
+    );
+    assert_eq!(revisions[0].text, "cruel,");
+    assert_eq!(revisions[0].info.author.as_deref(), Some("Ann"));
+    assert_eq!(
+        revisions[1].info.date.as_deref(),
+        Some("2024-03-02T10:00:00Z")
+    );
+}
+
+#[test]
+fn test_revisions_survive_roundtrip() {
+    let mut doc = Document::from_bytes(&docx_with_body(REDLINED)).unwrap();
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert_eq!(doc2.revisions(), doc.revisions());
+}
+
+#[test]
+fn test_accept_all_revisions() {
+    let mut doc = Document::from_bytes(&docx_with_body(REDLINED)).unwrap();
+    assert_eq!(doc.accept_all_revisions(), 5);
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert!(doc2.revisions().is_empty());
+    assert_eq!(doc2.paragraph(0).unwrap().text(), "Hello,brave,world");
+    assert_eq!(doc2.paragraph(1).unwrap().text(), "First");
+    assert!(doc2.paragraph(2).unwrap().runs().next().unwrap().bold());
+    assert_eq!(doc2.table(0).unwrap().row_count(), 1);
+}
+
+#[test]
+fn test_reject_all_revisions() {
+    let mut doc = Document::from_bytes(&docx_with_body(REDLINED)).unwrap();
+    assert_eq!(doc.reject_all_revisions(), 5);
+
+    let doc2 = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    assert!(doc2.revisions().is_empty());
+    assert_eq!(doc2.paragraph(0).unwrap().text(), "Hello,cruel,world");
+    // The inserted paragraph mark is removed, joining "First" to the next paragraph
+    assert_eq!(doc2.paragraph_count(), 2);
+    assert_eq!(doc2.paragraph(1).unwrap().text(), "FirstSecond");
+    assert!(!doc2.paragraph(1).unwrap().runs().nth(1).unwrap().bold());
+    assert_eq!(doc2.table(0).unwrap().row_count(), 2);
+}
+
+#[test]
+fn test_resolve_by_author_and_id() {
+    let mut doc = Document::from_bytes(&docx_with_body(REDLINED)).unwrap();
+    assert_eq!(doc.reject_revisions_by("Bob"), 2);
+    assert_eq!(doc.paragraph(0).unwrap().text(), "Hello,world");
+    assert!(doc
+        .revisions()
+        .iter()
+        .all(|r| r.info.author.as_deref() == Some("Ann")));
+
+    assert!(doc.accept_revision("5"));
+    assert!(!doc.accept_revision("5"));
+    assert_eq!(doc.table(0).unwrap().row_count(), 1);
+
+    assert_eq!(doc.accept_revisions_by("Ann"), 2);
+    assert!(doc.revisions().is_empty());
+}
+
+#[test]
+fn test_moves() {
+    let body = concat!(
+        r#"<w:p><w:moveFromRangeStart w:id="10" w:author="Ann" w:name="move1"/>"#,
+        r#"<w:moveFrom w:id="11" w:author="Ann"><w:r><w:t>Moved</w:t></w:r></w:moveFrom>"#,
+        r#"<w:moveFromRangeEnd w:id="10"/><w:r><w:t>Stay</w:t></w:r></w:p>"#,
+        r#"<w:p><w:moveToRangeStart w:id="12" w:author="Ann" w:name="move1"/>"#,
+        r#"<w:moveTo w:id="13" w:author="Ann"><w:r><w:t>Moved</w:t></w:r></w:moveTo>"#,
+        r#"<w:moveToRangeEnd w:id="12"/></w:p>"#,
+    );
+    let bytes = docx_with_body(body);
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc.text(), "Stay\nMoved");
+
+    let mut accepted = Document::from_bytes(&bytes).unwrap();
+    accepted.accept_all_revisions();
+    assert!(accepted.revisions().is_empty());
+    // Move range markers go with the move
+    assert_eq!(accepted.paragraph(0).unwrap().content.len(), 1);
+    assert_eq!(accepted.text(), "Stay\nMoved");
+
+    let mut rejected = Document::from_bytes(&bytes).unwrap();
+    rejected.reject_all_revisions();
+    assert_eq!(rejected.text(), "MovedStay\n");
+}
+
+#[test]
+fn test_tracked_replace_and_fill() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Dear {{name}},");
+    doc.add_paragraph("Thanks,old-signature");
+    doc.track_changes("Bot", Some("2024-05-01T09:00:00Z"));
+
+    let mut ctx = TemplateContext::new();
+    ctx.insert("name".into(), "Ann".into());
+    assert_eq!(doc.fill_template(&ctx), 1);
+    assert_eq!(doc.replace_text("old-signature", "Bot"), 1);
+
+    let bytes = doc.to_bytes().unwrap();
+    let mut doc2 = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc2.text(), "Dear Ann,\nThanks,Bot");
+    let revisions = doc2.revisions();
+    assert_eq!(revisions.len(), 4);
+    assert!(revisions
+        .iter()
+        .all(|r| r.info.author.as_deref() == Some("Bot")
+            && r.info.date.as_deref() == Some("2024-05-01T09:00:00Z")));
+    let mut ids: Vec<_> = revisions.iter().map(|r| r.info.id.clone()).collect();
+    ids.dedup();
+    assert_eq!(ids.len(), 4);
+
+    assert_eq!(doc2.reject_all_revisions(), 4);
+    assert_eq!(doc2.text(), "Dear {{name}},\nThanks,old-signature");
+}
+
+#[test]
+fn test_tracked_paragraph_edits() {
+    let mut doc = Document::new();
+    doc.add_paragraph("One");
+    doc.add_paragraph("Two");
+    doc.add_paragraph("Three");
+
+    doc.track_changes("Bot", None);
+    doc.add_paragraph("Four");
+    assert!(doc.remove_paragraph(1));
+    assert!(doc.set_paragraph_text(0, "First"));
+    let (para, tracker) = doc.paragraph_mut_tracked(2).unwrap();
+    para.add_run_tracked(Run::new("!"), tracker);
+    assert!(doc.stop_tracking_changes().is_some());
+
+    let bytes = doc.to_bytes().unwrap();
+    let doc2 = Document::from_bytes(&bytes).unwrap();
+    assert_eq!(doc2.text(), "First\n\nThree!\nFour");
+
+    let mut accepted = Document::from_bytes(&bytes).unwrap();
+    accepted.accept_all_revisions();
+    assert_eq!(accepted.text(), "First\nThree!\nFour");
+
+    let mut rejected = Document::from_bytes(&bytes).unwrap();
+    rejected.reject_all_revisions();
+    assert_eq!(rejected.text(), "One\nTwo\nThree");
+}
@@ -0,0 +1,183 @@
+//! Round-trip corpus checking that property containers keep OOXML schema order
+
+use linch_docx_rs::{Alignment, Document, Indentation, Package};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Subsets of the CT_PPr / CT_RPr / CT_TcPr / CT_SectPr sequences used by the corpus
+const PPR: &[&str] = &[
+    "pStyle",
+    "keepNext",
+    "keepLines",
+    "pageBreakBefore",
+    "widowControl",
+    "numPr",
+    "pBdr",
+    "shd",
+    "tabs",
+    "bidi",
+    "spacing",
+    "ind",
+    "contextualSpacing",
+    "jc",
+    "outlineLvl",
+    "rPr",
+];
+const RPR: &[&str] = &[
+    "rStyle",
+    "rFonts",
+    "b",
+    "bCs",
+    "i",
+    "iCs",
+    "caps",
+    "strike",
+    "noProof",
+    "color",
+    "spacing",
+    "sz",
+    "szCs",
+    "highlight",
+    "u",
+    "vertAlign",
+    "lang",
+];
+const TCPR: &[&str] = &[
+    "tcW",
+    "gridSpan",
+    "vMerge",
+    "tcBorders",
+    "shd",
+    "noWrap",
+    "tcMar",
+    "vAlign",
+    "hideMark",
+];
+const SECTPR: &[&str] = &[
+    "headerReference",
+    "footerReference",
+    "footnotePr",
+    "type",
+    "pgSz",
+    "pgMar",
+    "pgNumType",
+    "cols",
+    "titlePg",
+    "docGrid",
+];
+
+const CORPUS: &[&str] = &[
+    // Unknown children ahead of known ones
+    r#"<w:p><w:pPr><w:jc w:val="center"/><w:widowControl/><w:pStyle w:val="Heading1"/><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs><w:spacing w:after="120"/><w:contextualSpacing/></w:pPr><w:r><w:t>A</w:t></w:r></w:p>"#,
+    // Unknown children behind the paragraph mark rPr
+    r#"<w:p><w:pPr><w:rPr><w:b/></w:rPr><w:shd w:val="clear" w:fill="FFFF00"/><w:ind w:left="720"/><w:keepNext/></w:pPr></w:p>"#,
+    // Run properties with complex-script toggles and language
+    r#"<w:p><w:r><w:rPr><w:lang w:val="en-US"/><w:szCs w:val="28"/><w:sz w:val="28"/><w:noProof/><w:iCs/><w:i/><w:bCs/><w:b/><w:rFonts w:ascii="Arial"/></w:rPr><w:t>B</w:t></w:r></w:p>"#,
+    // Table cell properties
+    r#"<w:tbl><w:tblPr/><w:tblGrid><w:gridCol w:w="2000"/></w:tblGrid><w:tr><w:tc><w:tcPr><w:vAlign w:val="center"/><w:shd w:val="clear" w:fill="EEEEEE"/><w:tcBorders><w:top w:val="single"/></w:tcBorders><w:gridSpan w:val="2"/><w:tcW w:w="2000" w:type="dxa"/><w:noWrap/></w:tcPr><w:p/></w:tc></w:tr></w:tbl>"#,
+    // Section properties with unknown children before page size
+    r#"<w:sectPr><w:docGrid w:linePitch="360"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440"/><w:titlePg/><w:pgSz w:w="12240" w:h="15840"/><w:type w:val="nextPage"/><w:cols w:space="720"/><w:pgNumType w:start="1"/></w:sectPr>"#,
+];
+
+fn docx_with_body(body: &str) -> Vec<u8> {
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    let bytes = Document::new().to_bytes().unwrap();
+    let mut pkg = Package::from_bytes(&bytes).unwrap();
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+fn saved_document_xml(bytes: &[u8]) -> String {
+    let pkg = Package::from_bytes(bytes).unwrap();
+    pkg.main_document_part()
+        .unwrap()
+        .data_as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Assert that the `w:` children of every container follow `order`
+fn assert_schema_order(xml: &str, container: &str, order: &[&str]) {
+    let mut reader = Reader::from_str(xml);
+    // Stack of (element name, last rank seen) for open elements
+    let mut stack: Vec<(String, usize)> = Vec::new();
+
+    loop {
+        let event = reader.read_event().unwrap();
+        let (name, is_start) = match &event {
+            Event::Start(e) => (String::from_utf8_lossy(e.name().as_ref()).to_string(), true),
+            Event::Empty(e) => (
+                String::from_utf8_lossy(e.name().as_ref()).to_string(),
+                false,
+            ),
+            Event::End(_) => {
+                stack.pop();
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        if let Some((parent, last)) = stack.last_mut() {
+            if parent.as_str() == container {
+                let local = name.trim_start_matches("w:");
+                let rank = order
+                    .iter()
+                    .position(|n| *n == local)
+                    .unwrap_or_else(|| panic!("{} not in {} table", local, container));
+                assert!(
+                    rank >= *last,
+                    "<{}> written after a later sibling in <{}>:\n{}",
+                    name,
+                    container,
+                    xml
+                );
+                *last = rank;
+            }
+        }
+
+        if is_start {
+            stack.push((name, 0));
+        }
+    }
+}
+
+#[test]
+fn test_schema_order_corpus() {
+    for body in CORPUS {
+        let mut doc = Document::from_bytes(&docx_with_body(body)).unwrap();
+        // Untouched parts are passed through, so force re-serialization
+        doc.body_mut();
+        let xml = saved_document_xml(&doc.to_bytes().unwrap());
+
+        assert_schema_order(&xml, "w:pPr", PPR);
+        assert_schema_order(&xml, "w:rPr", RPR);
+        assert_schema_order(&xml, "w:tcPr", TCPR);
+        assert_schema_order(&xml, "w:sectPr", SECTPR);
+    }
+}
+
+#[test]
+fn test_schema_order_after_edit() {
+    let mut doc = Document::from_bytes(&docx_with_body(CORPUS[0])).unwrap();
+    {
+        let para = doc.paragraph_mut(0).unwrap();
+        para.set_alignment(Alignment::Right);
+        para.set_indentation(Indentation {
+            left: Some(360),
+            ..Default::default()
+        });
+        para.set_keep_next(true);
+    }
+    let xml = saved_document_xml(&doc.to_bytes().unwrap());
+
+    assert_schema_order(&xml, "w:pPr", PPR);
+    assert!(xml.contains("<w:tabs>"));
+    assert!(xml.contains("<w:widowControl/>"));
+    assert!(xml.contains("<w:contextualSpacing/>"));
+}
@@ -0,0 +1,161 @@
+//! Integration tests for text boxes and their content
+
+use linch_docx_rs::{
+    AnchorPosition, AnchoredImage, Document, Package, Paragraph, PositionAlign, RelativeFrom, Run,
+    RunContent, Table, TemplateContext, TextBox, WrapMode,
+};
+
+const NS: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:wps="http://schemas.microsoft.com/office/word/2010/wordprocessingShape" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" xmlns:v="urn:schemas-microsoft-com:vml""#;
+
+fn context(pairs: &[(&str, &str)]) -> TemplateContext {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn document_xml(bytes: &[u8]) -> String {
+    let pkg = Package::from_bytes(bytes).unwrap();
+    String::from_utf8(pkg.main_document_part().unwrap().data().to_vec()).unwrap()
+}
+
+/// A letter template as Word saves it: an address block in a text box, with
+/// its VML copy in the fallback
+fn letter() -> Vec<u8> {
+    let mut doc = Document::new();
+    doc.add_paragraph("placeholder");
+    let mut pkg = Package::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let content = r#"<w:txbxContent><w:p><w:r><w:t>{{name}}</w:t></w:r></w:p><w:p><w:r><w:t>{{</w:t></w:r><w:r><w:t>street}}</w:t></w:r></w:p></w:txbxContent>"#;
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document {NS}><w:body><w:p><w:r><w:t xml:space="preserve">Dear {{{{name}}}}, </w:t></w:r><w:r><mc:AlternateContent><mc:Choice Requires="wps"><w:drawing><wp:anchor behindDoc="0"><wp:simplePos x="0" y="0"/><wp:positionH relativeFrom="page"><wp:posOffset>0</wp:posOffset></wp:positionH><wp:positionV relativeFrom="page"><wp:posOffset>0</wp:posOffset></wp:positionV><wp:extent cx="2000000" cy="1000000"/><wp:wrapNone/><wp:docPr id="2" name="Address"/><a:graphic><a:graphicData uri="http://schemas.microsoft.com/office/word/2010/wordprocessingShape"><wps:wsp><wps:txbx>{content}</wps:txbx><wps:bodyPr/></wps:wsp></a:graphicData></a:graphic></wp:anchor></w:drawing></mc:Choice><mc:Fallback><w:pict><v:shape><v:textbox>{content}</v:textbox></v:shape></w:pict></mc:Fallback></mc:AlternateContent></w:r></w:p><w:sectPr/></w:body></w:document>"#
+    );
+    pkg.main_document_part_mut()
+        .unwrap()
+        .set_data(xml.into_bytes());
+    pkg.to_bytes().unwrap()
+}
+
+#[test]
+fn test_text_box_content_is_parsed() {
+    let doc = Document::from_bytes(&letter()).unwrap();
+    let para = doc.paragraph(0).unwrap();
+    let text_boxes: Vec<_> = para.text_boxes().collect();
+    assert_eq!(text_boxes.len(), 1);
+    assert_eq!(text_boxes[0].paragraphs().count(), 2);
+    assert_eq!(text_boxes[0].text(), "{{name}}\n{{street}}");
+
+    assert!(para.text().starts_with("Dear {{name}}, "));
+    assert!(para.text().contains("{{street}}"));
+    assert_eq!(doc.find_text("{{name}}").len(), 2);
+    assert!(doc.template_placeholders().contains(&"street".to_string()));
+}
+
+#[test]
+fn test_fill_template_in_text_box() {
+    let mut doc = Document::from_bytes(&letter()).unwrap();
+    let count = doc.fill_template(&context(&[
+        ("name", "Ada Lovelace"),
+        ("street", "12 Baker Street"),
+    ]));
+    assert_eq!(count, 3);
+
+    let bytes = doc.to_bytes().unwrap();
+    let xml = document_xml(&bytes);
+    assert!(!xml.contains("{{"));
+    // Both the shape and its VML fallback are filled in
+    assert_eq!(xml.matches(">Ada Lovelace<").count(), 2);
+    assert_eq!(xml.matches(">12 Baker Street<").count(), 2);
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    let para = doc.paragraph(0).unwrap();
+    assert_eq!(para.runs().next().unwrap().text(), "Dear Ada Lovelace, ");
+    let text_box = para.text_boxes().next().unwrap();
+    assert_eq!(text_box.text(), "Ada Lovelace\n12 Baker Street");
+}
+
+#[test]
+fn test_replace_text_in_text_box() {
+    let mut doc = Document::from_bytes(&letter()).unwrap();
+    assert_eq!(doc.replace_text("{{name}}", "Grace"), 2);
+
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let para = doc.paragraph(0).unwrap();
+    assert_eq!(
+        para.text_boxes().next().unwrap().text(),
+        "Grace\n{{street}}"
+    );
+}
+
+#[test]
+fn test_add_text_box() {
+    let mut table = Table::new(1, 2);
+    table.cell_mut(0, 0).unwrap().set_text("Total");
+    table.cell_mut(0, 1).unwrap().set_text("{{total}}");
+    let text_box = TextBox::builder(2743200, 914400)
+        .position_h(AnchorPosition::align(
+            RelativeFrom::Margin,
+            PositionAlign::Right,
+        ))
+        .position_v(AnchorPosition::offset(RelativeFrom::Paragraph, 0))
+        .wrap(WrapMode::Square)
+        .fill("FFF2CC")
+        .border("C00000", 12700)
+        .text("Invoice {{number}}")
+        .table(table)
+        .build();
+
+    let mut doc = Document::new();
+    let para = doc.add_paragraph("Summary");
+    let mut run = Run::default();
+    run.add_text_box(text_box);
+    para.add_run(run);
+
+    let mut doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    {
+        let para = doc.paragraph(0).unwrap();
+        let text_box = para.text_boxes().next().unwrap();
+        assert_eq!(text_box.paragraphs().count(), 1);
+        assert_eq!(text_box.tables().count(), 1);
+        assert_eq!(text_box.text(), "Invoice {{number}}\nTotal\n{{total}}");
+    }
+
+    let count = doc.fill_template(&context(&[("number", "42"), ("total", "$10")]));
+    assert_eq!(count, 2);
+    let mut doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let para = doc.paragraph_mut(0).unwrap();
+    assert_eq!(para.runs().next().unwrap().text(), "Summary");
+    let text_box = para.text_boxes_mut().next().unwrap();
+    assert_eq!(text_box.text(), "Invoice 42\nTotal\n$10");
+
+    text_box.add_paragraph(Paragraph::new("Paid"));
+    let doc = Document::from_bytes(&doc.to_bytes().unwrap()).unwrap();
+    let run = doc.paragraph(0).unwrap().runs().nth(1).unwrap();
+    assert!(matches!(
+        &run.content[0],
+        RunContent::TextBox(text_box) if text_box.text().ends_with("\nPaid")
+    ));
+}
+
+#[test]
+fn test_new_drawings_get_unique_ids() {
+    let mut doc = Document::from_bytes(&letter()).unwrap();
+    let para = doc.add_paragraph("Floating");
+    let mut run = Run::default();
+    run.add_anchored_image(AnchoredImage::new("rId9", 914400, 914400));
+    run.add_text_box(TextBox::builder(914400, 914400).text("Note").build());
+    run.add_anchored_image(AnchoredImage::new("rId9", 914400, 914400));
+    para.add_run(run);
+
+    let bytes = doc.to_bytes().unwrap();
+    let xml = document_xml(&bytes);
+    for id in ["2", "3", "4", "5"] {
+        let doc_pr = format!(r#"<wp:docPr id="{}""#, id);
+        assert_eq!(xml.matches(&doc_pr).count(), 1, "{}", doc_pr);
+    }
+
+    // The IDs stick to the drawings
+    assert_eq!(doc.to_bytes().unwrap(), bytes);
+    let doc = Document::from_bytes(&bytes).unwrap();
+    let run = doc.paragraph(1).unwrap().runs().nth(1).unwrap();
+    assert!(matches!(&run.content[1], RunContent::TextBox(text_box) if text_box.id() == Some(4)));
+}
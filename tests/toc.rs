@@ -0,0 +1,138 @@
+//! Integration tests for table of contents generation
+
+use linch_docx_rs::document::{BlockContent, ParagraphContent, SdtContent};
+use linch_docx_rs::{Document, Package, Paragraph, Sdt, Table, TocOptions};
+
+fn report() -> Document {
+    let mut doc = Document::new();
+    doc.add_paragraph("Title page");
+    for (style, text) in [
+        ("Heading1", "Introduction"),
+        ("Heading2", "Background"),
+        ("Heading4", "Too deep"),
+        ("Heading1", "Results"),
+    ] {
+        doc.add_paragraph(text).set_style(style);
+        doc.add_paragraph("Body text");
+    }
+    doc
+}
+
+#[test]
+fn test_insert_toc() {
+    let mut doc = report();
+    assert_eq!(doc.insert_toc(1, &TocOptions::default()).unwrap(), 3);
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    assert!(xml.contains(r#"<w:docPartGallery w:val="Table of Contents"/>"#));
+    assert!(xml.contains(r#"TOC \o &quot;1-3&quot; \h \z \u"#));
+    assert!(xml.contains(r#"<w:hyperlink w:anchor="_Toc000000000">"#));
+    assert!(xml.contains(r#"PAGEREF _Toc000000002 \h"#));
+    assert!(!xml.contains("_Toc000000003"));
+    // The TOC comes after the title page
+    assert!(xml.find("Title page").unwrap() < xml.find("docPartGallery").unwrap());
+
+    let doc = Document::from_bytes(&bytes).unwrap();
+    assert!(doc.settings().unwrap().update_fields);
+    assert!(doc.style("TOC1").is_some());
+    assert!(doc.style("TOC2").is_some());
+    assert!(doc.style("TOC3").is_none());
+    assert!(doc.style("TOCHeading").is_some());
+
+    // Headings keep their text, now wrapped in _Toc bookmarks
+    let intro = doc
+        .paragraphs()
+        .find(|p| p.text() == "Introduction")
+        .unwrap();
+    assert!(intro.content.iter().any(|c| matches!(
+        c,
+        linch_docx_rs::document::ParagraphContent::BookmarkStart { name, .. } if name == "_Toc000000000"
+    )));
+}
+
+#[test]
+fn test_insert_toc_reuses_bookmarks() {
+    let mut doc = report();
+    doc.insert_toc(0, &TocOptions::default()).unwrap();
+    let options = TocOptions {
+        levels: 1..=1,
+        hyperlinks: false,
+        title: None,
+        ..Default::default()
+    };
+    assert_eq!(doc.insert_toc(0, &options).unwrap(), 2);
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    assert!(xml.contains(r#"TOC \o &quot;1-1&quot; \z \u"#));
+    assert_eq!(xml.matches("<w:bookmarkStart").count(), 3);
+}
+
+#[test]
+fn test_insert_toc_without_headings() {
+    let mut doc = Document::new();
+    doc.add_paragraph("No headings here");
+    assert_eq!(doc.insert_toc(0, &TocOptions::default()).unwrap(), 0);
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    assert!(xml.contains("No table of contents entries found."));
+}
+
+#[test]
+fn test_insert_toc_nested_headings() {
+    let mut doc = Document::new();
+    doc.add_paragraph("Overview").set_style("Heading1");
+
+    // A bookmark inside a hyperlink in a table cell holds the highest id
+    let mut table = Table::new(1, 1);
+    let cell = table.cell_mut(0, 0).unwrap();
+    let mut heading = Paragraph::new("In a table");
+    heading.set_style("Heading2");
+    cell.paragraphs = vec![heading];
+    let mut link = Paragraph::default();
+    link.add_internal_link("Overview", "see above");
+    if let Some(ParagraphContent::Hyperlink(link)) = link.content.last_mut() {
+        link.content.insert(
+            0,
+            ParagraphContent::BookmarkStart {
+                id: "41".into(),
+                name: "linked".into(),
+            },
+        );
+        link.content
+            .push(ParagraphContent::BookmarkEnd { id: "41".into() });
+    }
+    cell.paragraphs.push(link);
+    doc.add_table(table);
+
+    let mut sdt = Sdt::rich_text("");
+    let mut heading = Paragraph::new("In a control");
+    heading.set_style("Heading1");
+    sdt.content = SdtContent::Blocks(vec![BlockContent::Paragraph(Box::new(heading))]);
+    doc.add_content_control(sdt);
+
+    assert_eq!(doc.insert_toc(0, &TocOptions::default()).unwrap(), 3);
+
+    let bytes = doc.to_bytes().unwrap();
+    let pkg = Package::from_bytes(&bytes).unwrap();
+    let xml = pkg.main_document_part().unwrap().data_as_str().unwrap();
+    for (id, name) in [
+        ("42", "_Toc000000042"),
+        ("43", "_Toc000000043"),
+        ("44", "_Toc000000044"),
+    ] {
+        let start = format!(r#"<w:bookmarkStart w:id="{}" w:name="{}"/>"#, id, name);
+        assert!(xml.contains(&start), "{}", start);
+    }
+    assert_eq!(xml.matches(r#"w:id="41""#).count(), 2);
+    // Entries follow document order
+    let overview = xml.find(r#"PAGEREF _Toc000000042 \h"#).unwrap();
+    let table = xml.find(r#"PAGEREF _Toc000000043 \h"#).unwrap();
+    let control = xml.find(r#"PAGEREF _Toc000000044 \h"#).unwrap();
+    assert!(overview < table && table < control);
+}